@@ -36,8 +36,9 @@ const connection = new Connection(DEVNET_URL, "confirmed");
 // Anchor discriminator for "Escrow" account = sha256("account:Escrow")[0..8]
 import { createHash } from "crypto";
 const ESCROW_DISCRIMINATOR = createHash("sha256").update("account:Escrow").digest().subarray(0, 8);
+const ESCROW_LAYOUT_VERSION = 20;
 
-// State enum mapping (byte 168)
+// State enum mapping (byte 153)
 const STATE_MAP: Record<number, string> = {
   0: "created",
   1: "accepted",
@@ -47,6 +48,12 @@ const STATE_MAP: Record<number, string> = {
   5: "resolved_buyer",
   6: "resolved_seller",
   7: "cancelled",
+  8: "timed_out",
+  9: "ruling_pending",
+  10: "ruling_frozen",
+  11: "resolved_split",
+  12: "vesting",
+  13: "draft",
 };
 
 // In-memory job store for metadata not on-chain (description, fileId, etc.)
@@ -63,9 +70,11 @@ interface Job {
   buyer: string;
   seller?: string;
   arbitrator?: string;
+  mint?: string;
   paymentAmount: number;
   buyerCollateral: number;
   sellerCollateral: number;
+  decimals: number;
   state: string;
   createdAt: number;
   deliveryHash?: string;
@@ -76,25 +85,28 @@ interface Job {
 
 const jobMeta: Map<number, JobMeta> = new Map();
 
-// Parse on-chain escrow account data (699 bytes) into a Job
+// Parse on-chain escrow account data into a Job
 function parseEscrowAccount(data: Buffer, meta?: JobMeta): Job {
-  // Layout: 8 disc + 8 id(@8) + 32 buyer(@16) + 32 seller(@48) + 32 arb(@80)
-  // + 8 pay(@112) + 8 buyCol(@120) + 8 selCol(@128) + 8 deadline(@136)
-  // + 4+500 desc(@144) + 1 state(@648) + 32 hash(@649)
-  // + 8 created(@681) + 8 delivered(@689) + 1 bump(@697) + 1 vaultBump(@698)
-  const escrowId = Number(data.readBigUInt64LE(8));
-  const buyer = new PublicKey(data.subarray(16, 48)).toBase58();
-  const seller = new PublicKey(data.subarray(48, 80)).toBase58();
-  const arbitrator = new PublicKey(data.subarray(80, 112)).toBase58();
-  const paymentAmount = Number(data.readBigUInt64LE(112));
-  const buyerCollateral = Number(data.readBigUInt64LE(120));
-  const sellerCollateral = Number(data.readBigUInt64LE(128));
-  const descLen = Math.min(data.readUInt32LE(144), 500);
-  const description = data.subarray(148, 148 + descLen).toString("utf-8");
-  // Borsh serializes string at actual length, state follows dynamically
-  let off = 148 + descLen;
-  const stateVal = data[off]; off += 1;
+  // Layout v20 (offsets mirror the program's `layout` module):
+  // 8 disc + 1 version(@8) + 8 genesis slot(@9) + 8 id(@17) + 32 buyer(@25)
+  // + 32 seller(@57) + 32 arb(@89) + 32 mint(@121) + 1 state(@153) + 8 pay(@154)
+  // + 8 buyCol(@162) + 8 selCol(@170) + 1 decimals(@178) + 8 deadline(@179)
+  // + 4+2000 desc(@187) + 32 hash + 8 created + 8 delivered + ...
+  const escrowId = Number(data.readBigUInt64LE(17));
+  const buyer = new PublicKey(data.subarray(25, 57)).toBase58();
+  const seller = new PublicKey(data.subarray(57, 89)).toBase58();
+  const arbitrator = new PublicKey(data.subarray(89, 121)).toBase58();
+  const mint = new PublicKey(data.subarray(121, 153)).toBase58();
+  const stateVal = data[153];
   const state = STATE_MAP[stateVal] || `unknown(${stateVal})`;
+  const paymentAmount = Number(data.readBigUInt64LE(154));
+  const buyerCollateral = Number(data.readBigUInt64LE(162));
+  const sellerCollateral = Number(data.readBigUInt64LE(170));
+  const decimals = data[178];
+  const descLen = Math.min(data.readUInt32LE(187), 2000);
+  const description = data.subarray(191, 191 + descLen).toString("utf-8");
+  // Borsh serializes string at actual length, the rest follows dynamically
+  let off = 191 + descLen;
   off += 32; // delivery_hash
   const createdAt = Number(data.readBigInt64LE(off)) * 1000; off += 8;
   const deliveredAt = Number(data.readBigInt64LE(off)) * 1000; off += 8;
@@ -107,9 +119,11 @@ function parseEscrowAccount(data: Buffer, meta?: JobMeta): Job {
     buyer,
     seller: sellerStr,
     arbitrator,
+    mint,
     paymentAmount,
     buyerCollateral,
     sellerCollateral,
+    decimals,
     state,
     createdAt: createdAt || meta?.createdAt || 0,
     deliveredAt,
@@ -123,29 +137,33 @@ async function fetchAllEscrows(): Promise<Job[]> {
   const accounts = await connection.getProgramAccounts(PROGRAM_PUBKEY, {
     filters: [
       { memcmp: { offset: 0, bytes: anchor.utils.bytes.bs58.encode(ESCROW_DISCRIMINATOR) } },
+      // Skip accounts left over from earlier layouts
+      { memcmp: { offset: 8, bytes: anchor.utils.bytes.bs58.encode(Buffer.from([ESCROW_LAYOUT_VERSION])) } },
     ],
   });
   
   return accounts.map(({ account }) => {
     const data = account.data;
-    const escrowId = Number(data.readBigUInt64LE(8));
+    const escrowId = Number(data.readBigUInt64LE(17));
     return parseEscrowAccount(data, jobMeta.get(escrowId));
   });
 }
 
-// Fetch single escrow by ID from chain
+// Fetch single escrow by ID from chain. IDs are assigned per buyer, so the
+// PDA can't be derived from the ID alone; match on the stored escrow_id instead.
 async function fetchEscrowById(escrowId: number): Promise<Job | null> {
   const escrowIdBuf = Buffer.alloc(8);
   escrowIdBuf.writeBigUInt64LE(BigInt(escrowId));
-  const [escrowPda] = PublicKey.findProgramAddressSync(
-    [Buffer.from("escrow"), escrowIdBuf],
-    PROGRAM_PUBKEY
-  );
-  
-  const accountInfo = await connection.getAccountInfo(escrowPda);
-  if (!accountInfo) return null;
+  const accounts = await connection.getProgramAccounts(PROGRAM_PUBKEY, {
+    filters: [
+      { memcmp: { offset: 0, bytes: anchor.utils.bytes.bs58.encode(ESCROW_DISCRIMINATOR) } },
+      { memcmp: { offset: 8, bytes: anchor.utils.bytes.bs58.encode(Buffer.from([ESCROW_LAYOUT_VERSION])) } },
+      { memcmp: { offset: 17, bytes: anchor.utils.bytes.bs58.encode(escrowIdBuf) } },
+    ],
+  });
+  if (accounts.length === 0) return null;
   
-  return parseEscrowAccount(accountInfo.data, jobMeta.get(escrowId));
+  return parseEscrowAccount(accounts[0].account.data, jobMeta.get(escrowId));
 }
 
 // Legacy in-memory jobs map (kept for backward compat during transition)
@@ -340,7 +358,7 @@ const server = createServer(async (req, res) => {
 
     if (pathname === "/api/jobs" && req.method === "POST") {
       const body = await parseBody(req);
-      const { escrowId, description, buyer, paymentAmount, buyerCollateral, sellerCollateral } = body;
+      const { escrowId, description, buyer, paymentAmount, buyerCollateral, sellerCollateral, decimals } = body;
       
       if (!escrowId || !description || !buyer) {
         return json(res, { error: "Missing required fields: escrowId, description, buyer" }, 400);
@@ -364,6 +382,7 @@ const server = createServer(async (req, res) => {
         paymentAmount: paymentAmount || 0,
         buyerCollateral: buyerCollateral || 0,
         sellerCollateral: sellerCollateral || 0,
+        decimals: decimals ?? 6,
         state: "created",
         createdAt: Date.now(),
         onChain: false,
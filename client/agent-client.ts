@@ -11,7 +11,7 @@ import {
   TransactionInstruction, Transaction, sendAndConfirmTransaction,
 } from "@solana/web3.js";
 import {
-  getAssociatedTokenAddress, getOrCreateAssociatedTokenAccount,
+  getAssociatedTokenAddress, getOrCreateAssociatedTokenAccount, getMint,
   TOKEN_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID,
 } from "@solana/spl-token";
 import * as fs from "fs";
@@ -50,50 +50,192 @@ function encodeBorshString(s: string): Buffer {
   return Buffer.concat([lenBuf, strBuf]);
 }
 
-function getEscrowPDA(escrowId: number): [PublicKey, number] {
+const MAX_DESCRIPTION_GRAPHEMES = 500;
+const MAX_DESCRIPTION_BYTES = 2000;
+
+/** NFC-normalize a description and check it against the program's grapheme and byte limits. */
+function normalizeDescription(description: string): string {
+  const normalized = description.normalize("NFC");
+  const graphemes = [...new Intl.Segmenter(undefined, { granularity: "grapheme" }).segment(normalized)].length;
+  if (graphemes > MAX_DESCRIPTION_GRAPHEMES) {
+    throw new Error(`Description is ${graphemes} characters; the limit is ${MAX_DESCRIPTION_GRAPHEMES}`);
+  }
+  if (Buffer.byteLength(normalized, "utf-8") > MAX_DESCRIPTION_BYTES) {
+    throw new Error(`Description exceeds ${MAX_DESCRIPTION_BYTES} bytes of UTF-8`);
+  }
+  return normalized;
+}
+
+/** Convert a UI amount (e.g. 1.5) into base units for a mint with `decimals`. */
+function toBaseUnits(amount: number, decimals: number): bigint {
+  return BigInt(Math.round(amount * 10 ** decimals));
+}
+
+/** Render base units using the escrow's stored mint decimals. */
+function formatAmount(amount: bigint | number, decimals: number): string {
+  const raw = BigInt(amount);
+  const scale = 10n ** BigInt(decimals);
+  const whole = raw / scale;
+  const frac = (raw % scale).toString().padStart(decimals, "0").replace(/0+$/, "");
+  return frac ? `${whole}.${frac}` : `${whole}`;
+}
+
+/** Off-chain mirror of the program's `TermsV1`; field order is the Borsh layout. */
+interface TermsV1 {
+  buyer: PublicKey;
+  seller: PublicKey | null;
+  arbitrator: PublicKey;
+  mint: PublicKey;
+  paymentAmount: bigint;
+  buyerCollateral: bigint;
+  sellerCollateral: bigint;
+  deadlineTs: number;
+  description: string;
+  milestoneAmounts: bigint[];
+  arbitrationSla: { slaSecs: number; decaySecs: number } | null;
+  platform: PublicKey | null;
+}
+
+const TERMS_V1_DOMAIN = Buffer.from("clawscrow:terms:v1");
+
+function encodeI64(n: number | bigint): Buffer {
+  const buf = Buffer.alloc(8);
+  buf.writeBigInt64LE(BigInt(n));
+  return buf;
+}
+
+function encodeTermsV1(t: TermsV1): Buffer {
+  const milestonesLen = Buffer.alloc(4);
+  milestonesLen.writeUInt32LE(t.milestoneAmounts.length);
+  return Buffer.concat([
+    t.buyer.toBuffer(),
+    (t.seller ?? PublicKey.default).toBuffer(),
+    t.arbitrator.toBuffer(),
+    t.mint.toBuffer(),
+    encodeU64(t.paymentAmount),
+    encodeU64(t.buyerCollateral),
+    encodeU64(t.sellerCollateral),
+    encodeI64(t.deadlineTs),
+    createHash("sha256").update(t.description, "utf-8").digest(),
+    milestonesLen,
+    ...t.milestoneAmounts.map(encodeU64),
+    t.arbitrationSla
+      ? Buffer.concat([Buffer.from([1]), encodeI64(t.arbitrationSla.slaSecs), encodeI64(t.arbitrationSla.decaySecs)])
+      : Buffer.from([0]),
+    t.platform ? Buffer.concat([Buffer.from([1]), t.platform.toBuffer()]) : Buffer.from([0]),
+  ]);
+}
+
+/** Hash agents agree on off-chain and pass as `terms_hash` to `create_escrow`. */
+function hashTermsV1(t: TermsV1): Buffer {
+  return createHash("sha256").update(TERMS_V1_DOMAIN).update(encodeTermsV1(t)).digest();
+}
+
+function getCounterPDA(buyer: PublicKey): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("counter"), buyer.toBuffer()],
+    PROGRAM_ID
+  );
+}
+
+/** Escrow IDs are only unique per buyer, so the PDA needs both. */
+function getEscrowPDA(buyer: PublicKey, escrowId: number): [PublicKey, number] {
   return PublicKey.findProgramAddressSync(
-    [Buffer.from("escrow"), encodeU64(escrowId)],
+    [Buffer.from("escrow"), buyer.toBuffer(), encodeU64(escrowId)],
     PROGRAM_ID
   );
 }
 
-function getVaultPDA(escrowId: number): [PublicKey, number] {
+function getVaultPDA(escrowPda: PublicKey): [PublicKey, number] {
   return PublicKey.findProgramAddressSync(
-    [Buffer.from("vault"), encodeU64(escrowId)],
+    [Buffer.from("vault"), escrowPda.toBuffer()],
     PROGRAM_ID
   );
 }
 
+/** ID the program will assign to the buyer's next escrow (0 before their first). */
+async function readNextEscrowId(connection: Connection, buyer: PublicKey): Promise<number> {
+  const info = await connection.getAccountInfo(getCounterPDA(buyer)[0]);
+  if (!info) return 0;
+  return Number(info.data.readBigUInt64LE(8 + 32));
+}
+
+async function fetchJob(escrowId: number): Promise<any> {
+  const res = await fetch(`${BACKEND_URL}/api/jobs/${escrowId}`);
+  const jobData: any = await res.json();
+  return jobData.job || jobData;
+}
+
+const ESCROW_DISCRIMINATOR = createHash("sha256").update("account:Escrow").digest().subarray(0, 8);
+/** Must match `ESCROW_LAYOUT_VERSION` in the program. */
+const ESCROW_LAYOUT_VERSION = 20;
+
+/** Read the generation stamp every escrow carries right after its discriminator. */
+function readEscrowGeneration(data: Buffer): { layoutVersion: number; genesisSlot: bigint } | null {
+  if (data.length < 17 || !data.subarray(0, 8).equals(ESCROW_DISCRIMINATOR)) return null;
+  return { layoutVersion: data[8], genesisSlot: data.readBigUInt64LE(9) };
+}
+
+/**
+ * True when an escrow account was written by the current program layout and,
+ * if `deployedAtSlot` is given, created after that deployment.
+ */
+function isCurrentGeneration(data: Buffer, deployedAtSlot?: bigint): boolean {
+  const gen = readEscrowGeneration(data);
+  if (!gen || gen.layoutVersion !== ESCROW_LAYOUT_VERSION) return false;
+  return deployedAtSlot === undefined || gen.genesisSlot >= deployedAtSlot;
+}
+
 // ─────────────────── BUYER ACTIONS ───────────────────
 
 async function createEscrow(
   keypairPath: string,
-  description: string,
+  rawDescription: string,
   paymentUsdc: number,
   buyerCollUsdc: number,
   sellerCollUsdc: number,
 ) {
+  const description = normalizeDescription(rawDescription);
   const buyer = loadKeypair(keypairPath);
   const connection = new Connection(DEVNET_URL, "confirmed");
-  const escrowId = Date.now();
+  const escrowId = await readNextEscrowId(connection, buyer.publicKey);
   const deadline = Math.floor(Date.now() / 1000) + 7 * 86400;
 
-  const [escrowPda] = getEscrowPDA(escrowId);
-  const [vaultPda] = getVaultPDA(escrowId);
+  const [counterPda] = getCounterPDA(buyer.publicKey);
+  const [escrowPda] = getEscrowPDA(buyer.publicKey, escrowId);
+  const [vaultPda] = getVaultPDA(escrowPda);
   const buyerToken = await getOrCreateAssociatedTokenAccount(connection, buyer, USDC_MINT, buyer.publicKey);
+  const { decimals } = await getMint(connection, USDC_MINT);
+  const payment = toBaseUnits(paymentUsdc, decimals);
+  const buyerCollateral = toBaseUnits(buyerCollUsdc, decimals);
+  const sellerCollateral = toBaseUnits(sellerCollUsdc, decimals);
+  const termsHash = hashTermsV1({
+    buyer: buyer.publicKey,
+    seller: null,
+    arbitrator: ARBITRATOR,
+    mint: USDC_MINT,
+    paymentAmount: payment,
+    buyerCollateral,
+    sellerCollateral,
+    deadlineTs: deadline,
+    description,
+    milestoneAmounts: [],
+    arbitrationSla: null,
+    platform: null,
+  });
 
   const data = Buffer.concat([
     anchorDisc("create_escrow"),
-    encodeU64(escrowId),
     encodeBorshString(description),
-    encodeU64(Math.round(paymentUsdc * 1e6)),
-    encodeU64(Math.round(buyerCollUsdc * 1e6)),
-    encodeU64(Math.round(sellerCollUsdc * 1e6)),
+    encodeU64(payment),
+    encodeU64(buyerCollateral),
+    encodeU64(sellerCollateral),
     encodeU64(deadline),
   ]);
 
   const keys = [
     { pubkey: buyer.publicKey, isSigner: true, isWritable: true },
+    { pubkey: counterPda, isSigner: false, isWritable: true },
     { pubkey: escrowPda, isSigner: false, isWritable: true },
     { pubkey: vaultPda, isSigner: false, isWritable: true },
     { pubkey: buyerToken.address, isSigner: false, isWritable: true },
@@ -109,7 +251,8 @@ async function createEscrow(
 
   console.log(`Creating escrow #${escrowId}...`);
   console.log(`  Description: ${description}`);
-  console.log(`  Payment: ${paymentUsdc} USDC | Buyer collateral: ${buyerCollUsdc} USDC | Seller collateral: ${sellerCollUsdc} USDC`);
+  console.log(`  Terms hash: ${termsHash.toString("hex")}`);
+  console.log(`  Payment: ${formatAmount(payment, decimals)} USDC | Buyer collateral: ${formatAmount(buyerCollateral, decimals)} USDC | Seller collateral: ${formatAmount(sellerCollateral, decimals)} USDC`);
 
   const sig = await sendAndConfirmTransaction(connection, tx, [buyer]);
   console.log(`✅ Escrow created!`);
@@ -125,9 +268,10 @@ async function createEscrow(
       escrowId,
       buyer: buyer.publicKey.toBase58(),
       description,
-      payment: Math.round(paymentUsdc * 1e6),
-      buyerCollateral: Math.round(buyerCollUsdc * 1e6),
-      sellerCollateral: Math.round(sellerCollUsdc * 1e6),
+      payment: payment.toString(),
+      buyerCollateral: buyerCollateral.toString(),
+      sellerCollateral: sellerCollateral.toString(),
+      decimals,
     }),
   });
   console.log(`  Backend registered: ${res.status}`);
@@ -139,8 +283,13 @@ async function acceptEscrow(keypairPath: string, escrowId: number) {
   const seller = loadKeypair(keypairPath);
   const connection = new Connection(DEVNET_URL, "confirmed");
 
-  const [escrowPda] = getEscrowPDA(escrowId);
-  const [vaultPda] = getVaultPDA(escrowId);
+  const job = await fetchJob(escrowId);
+  const [escrowPda] = getEscrowPDA(new PublicKey(job.buyer), escrowId);
+  const [vaultPda] = getVaultPDA(escrowPda);
+  const escrowInfo = await connection.getAccountInfo(escrowPda);
+  if (!escrowInfo || !isCurrentGeneration(escrowInfo.data)) {
+    throw new Error(`Escrow #${escrowId} was not created by the current program layout`);
+  }
   const sellerToken = await getOrCreateAssociatedTokenAccount(connection, seller, USDC_MINT, seller.publicKey);
 
   const data = Buffer.concat([
@@ -205,7 +354,8 @@ async function deliver(keypairPath: string, escrowId: number, filePath: string)
   const uploadData: any = await uploadRes.json();
 
   // Submit delivery hash on-chain
-  const [escrowPda] = getEscrowPDA(escrowId);
+  const job = await fetchJob(escrowId);
+  const [escrowPda] = getEscrowPDA(new PublicKey(job.buyer), escrowId);
 
   // deliver instruction: disc + content_hash (32 bytes as [u8; 32])
   const data = Buffer.concat([
@@ -247,13 +397,11 @@ async function approve(keypairPath: string, escrowId: number) {
   const buyer = loadKeypair(keypairPath);
   const connection = new Connection(DEVNET_URL, "confirmed");
 
-  const [escrowPda] = getEscrowPDA(escrowId);
-  const [vaultPda] = getVaultPDA(escrowId);
+  const [escrowPda] = getEscrowPDA(buyer.publicKey, escrowId);
+  const [vaultPda] = getVaultPDA(escrowPda);
 
   // Get seller from backend
-  const res = await fetch(`${BACKEND_URL}/api/jobs/${escrowId}`);
-  const jobData: any = await res.json();
-  const job = jobData.job || jobData;
+  const job = await fetchJob(escrowId);
   const seller = new PublicKey(job.seller);
 
   const buyerToken = await getAssociatedTokenAddress(USDC_MINT, buyer.publicKey);
@@ -286,7 +434,7 @@ async function raiseDispute(keypairPath: string, escrowId: number, reason: strin
   const buyer = loadKeypair(keypairPath);
   const connection = new Connection(DEVNET_URL, "confirmed");
 
-  const [escrowPda] = getEscrowPDA(escrowId);
+  const [escrowPda] = getEscrowPDA(buyer.publicKey, escrowId);
 
   const data = Buffer.concat([
     anchorDisc("raise_dispute"),
@@ -0,0 +1,154 @@
+//! Parsing and printing of amounts, times, rulings and escrow state.
+
+use anyhow::{anyhow, bail, Context, Result};
+use clawscrow_client::clawscrow::{Escrow, EscrowState, Ruling};
+
+/// A decimal token amount such as `12.5`, in base units of a mint with `decimals`.
+pub fn parse_amount(text: &str, decimals: u8) -> Result<u64> {
+    let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+    if fraction.len() > decimals as usize {
+        bail!("{text} has more than {decimals} decimal places");
+    }
+    let digits = format!("{whole}{fraction:0<width$}", width = decimals as usize);
+    digits.parse().with_context(|| format!("invalid amount {text}"))
+}
+
+pub fn format_amount(amount: u64, decimals: u8) -> String {
+    let scale = 10u64.pow(decimals as u32);
+    let fraction = format!("{:0width$}", amount % scale, width = decimals as usize);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        (amount / scale).to_string()
+    } else {
+        format!("{}.{fraction}", amount / scale)
+    }
+}
+
+/// A unix timestamp, or `+<n>` followed by `s`, `m`, `h` or `d` from `now`.
+pub fn parse_deadline(text: &str, now: i64) -> Result<i64> {
+    let Some(relative) = text.strip_prefix('+') else {
+        return text.parse().with_context(|| format!("invalid timestamp {text}"));
+    };
+    let (count, unit) = relative.split_at(relative.len().saturating_sub(1));
+    let unit_secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => bail!("deadline offset {text} needs a unit: s, m, h or d"),
+    };
+    let count: i64 = count.parse().with_context(|| format!("invalid deadline offset {text}"))?;
+    Ok(now + count * unit_secs)
+}
+
+/// `buyer`, `seller`, or `split:<buyer bps>`.
+pub fn parse_ruling(text: &str) -> Result<Ruling> {
+    match text {
+        "buyer" => Ok(Ruling::BuyerWins),
+        "seller" => Ok(Ruling::SellerWins),
+        _ => {
+            let bps = text.strip_prefix("split:").ok_or_else(|| anyhow!("ruling must be buyer, seller or split:<bps>"))?;
+            Ok(Ruling::Split { buyer_bps: bps.parse().with_context(|| format!("invalid split {bps}"))? })
+        }
+    }
+}
+
+/// A 32-byte hash as 64 hex digits.
+pub fn parse_hash(text: &str) -> Result<[u8; 32]> {
+    let bytes = hex::decode(text).with_context(|| format!("invalid hex {text}"))?;
+    bytes.try_into().map_err(|_| anyhow!("hash must be 32 bytes"))
+}
+
+pub fn state_name(state: &EscrowState) -> &'static str {
+    match state {
+        EscrowState::Created => "Created (awaiting seller)",
+        EscrowState::Accepted => "Accepted (work in progress)",
+        EscrowState::Delivered => "Delivered (in review)",
+        EscrowState::Approved => "Approved",
+        EscrowState::Disputed => "Disputed",
+        EscrowState::ResolvedBuyer => "Resolved for buyer",
+        EscrowState::ResolvedSeller => "Resolved for seller",
+        EscrowState::Cancelled => "Cancelled",
+        EscrowState::TimedOut => "Timed out",
+        EscrowState::RulingPending => "Ruling pending execution",
+        EscrowState::RulingFrozen => "Ruling frozen",
+        EscrowState::ResolvedSplit => "Resolved by split",
+        EscrowState::Vesting => "Approved, vesting",
+        EscrowState::Draft => "Draft (unfunded)",
+        EscrowState::Escalated => "Escalated to DAO vote",
+    }
+}
+
+/// `ts` relative to `now`, e.g. `in 2d 3h` or `5m ago`.
+pub fn relative_time(ts: i64, now: i64) -> String {
+    let delta = (ts - now).abs();
+    let span = match (delta / 86_400, delta % 86_400 / 3_600, delta % 3_600 / 60) {
+        (0, 0, m) => format!("{m}m"),
+        (0, h, m) => format!("{h}h {m}m"),
+        (d, h, _) => format!("{d}d {h}h"),
+    };
+    if ts >= now { format!("in {span}") } else { format!("{span} ago") }
+}
+
+pub fn print_escrow(address: &str, escrow: &Escrow, now: i64) {
+    let amount = |value| format_amount(value, escrow.decimals);
+    println!("Escrow #{} {address}", escrow.escrow_id);
+    println!("  State:        {}", state_name(&escrow.state));
+    println!("  Buyer:        {}", escrow.buyer);
+    if escrow.seller == Default::default() {
+        println!("  Seller:       (open)");
+    } else {
+        println!("  Seller:       {}", escrow.seller);
+    }
+    println!("  Arbitrator:   {}", escrow.arbitrator);
+    println!("  Mint:         {}", escrow.mint);
+    println!("  Payment:      {} ({} released)", amount(escrow.payment_amount), amount(escrow.released_amount));
+    println!(
+        "  Collateral:   buyer {}, seller {}",
+        amount(escrow.buyer_collateral),
+        amount(escrow.seller_collateral)
+    );
+    println!("  Deadline:     {} ({})", escrow.deadline_ts, relative_time(escrow.deadline_ts, now));
+    if escrow.delivered_at > 0 {
+        println!(
+            "  Delivered:    {} ({}), hash {}",
+            escrow.delivered_at,
+            relative_time(escrow.delivered_at, now),
+            hex::encode(escrow.delivery_hash)
+        );
+    }
+    println!("  Description:  {}", escrow.description);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn amounts_round_trip_through_decimals() {
+        assert_eq!(parse_amount("12.5", 6).unwrap(), 12_500_000);
+        assert_eq!(parse_amount("3", 6).unwrap(), 3_000_000);
+        assert!(parse_amount("0.0000001", 6).is_err());
+        assert!(parse_amount("1.2.3", 6).is_err());
+        assert_eq!(format_amount(12_500_000, 6), "12.5");
+        assert_eq!(format_amount(3_000_000, 6), "3");
+        assert_eq!(format_amount(7, 0), "7");
+    }
+
+    #[test]
+    fn deadlines_are_absolute_or_relative() {
+        assert_eq!(parse_deadline("1700000000", 5).unwrap(), 1_700_000_000);
+        assert_eq!(parse_deadline("+2d", 100).unwrap(), 100 + 2 * 86_400);
+        assert_eq!(parse_deadline("+90m", 0).unwrap(), 5_400);
+        assert!(parse_deadline("+2w", 0).is_err());
+        assert_eq!(relative_time(100 + 2 * 86_400 + 3 * 3_600, 100), "in 2d 3h");
+        assert_eq!(relative_time(0, 300), "5m ago");
+    }
+
+    #[test]
+    fn rulings_parse() {
+        assert!(matches!(parse_ruling("buyer").unwrap(), Ruling::BuyerWins));
+        assert!(matches!(parse_ruling("split:2500").unwrap(), Ruling::Split { buyer_bps: 2_500 }));
+        assert!(parse_ruling("draw").is_err());
+    }
+}
@@ -0,0 +1,228 @@
+//! `clawscrow-cli`: drive an escrow through its lifecycle from the shell.
+//!
+//! Escrow IDs are per buyer, so commands on an existing escrow take
+//! `--buyer` (the creating buyer), defaulting to the signing keypair.
+
+mod format;
+
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::token::Mint;
+use anyhow::{Context, Result};
+use clap::{Args, Parser, Subcommand};
+use clawscrow_client::clawscrow::{Config, Escrow};
+use clawscrow_client::{accounts, instructions, pda};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+
+#[derive(Parser)]
+#[command(version, about = "Create and settle clawscrow escrows")]
+struct Cli {
+    /// JSON-RPC endpoint.
+    #[arg(long, env = "CLAWSCROW_RPC_URL", default_value = "https://api.devnet.solana.com")]
+    url: String,
+
+    /// Keypair that signs and pays for transactions.
+    #[arg(long, env = "CLAWSCROW_KEYPAIR", default_value = "~/.config/solana/id.json")]
+    keypair: String,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Args)]
+struct EscrowRef {
+    escrow_id: u64,
+
+    /// Buyer that created the escrow; defaults to the signer.
+    #[arg(long)]
+    buyer: Option<Pubkey>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create and fund an escrow as the buyer.
+    Create {
+        #[arg(long)]
+        arbitrator: Pubkey,
+        #[arg(long)]
+        mint: Pubkey,
+        /// Payment in whole tokens, e.g. 12.5.
+        #[arg(long)]
+        amount: String,
+        #[arg(long, default_value = "0")]
+        buyer_collateral: String,
+        #[arg(long, default_value = "0")]
+        seller_collateral: String,
+        /// Unix timestamp, or an offset such as +3d.
+        #[arg(long)]
+        deadline: String,
+        #[arg(long)]
+        description: String,
+        /// Only this seller may accept.
+        #[arg(long)]
+        seller: Option<Pubkey>,
+    },
+    /// Accept an escrow as the seller, locking seller collateral.
+    Accept(EscrowRef),
+    /// Record a delivery as the seller.
+    Deliver {
+        #[command(flatten)]
+        escrow: EscrowRef,
+        /// File whose SHA-256 is recorded as the delivery hash.
+        #[arg(long, conflicts_with = "hash", required_unless_present = "hash")]
+        file: Option<PathBuf>,
+        /// Delivery hash as 64 hex digits.
+        #[arg(long)]
+        hash: Option<String>,
+        #[arg(long)]
+        uri: Option<String>,
+    },
+    /// Approve the delivery as the buyer and release payment.
+    Approve {
+        #[command(flatten)]
+        escrow: EscrowRef,
+        /// 1 (worst) to 5 (best).
+        #[arg(long)]
+        satisfaction: Option<u8>,
+    },
+    /// Dispute the delivery as the buyer.
+    Dispute(EscrowRef),
+    /// Rule on a dispute as the arbitrator.
+    Arbitrate {
+        #[command(flatten)]
+        escrow: EscrowRef,
+        /// buyer, seller, or split:<buyer bps>.
+        #[arg(long)]
+        ruling: String,
+    },
+    /// Release payment once the review window has passed.
+    AutoApprove(EscrowRef),
+    /// Print an escrow's state.
+    Show(EscrowRef),
+}
+
+struct Session {
+    rpc: RpcClient,
+    signer: Keypair,
+}
+
+impl Session {
+    fn escrow(&self, escrow: &EscrowRef) -> Result<(Pubkey, Escrow)> {
+        let buyer = escrow.buyer.unwrap_or(self.signer.pubkey());
+        let address = pda::escrow(&buyer, escrow.escrow_id);
+        let account = accounts::fetch(&self.rpc, &address)
+            .map_err(anyhow::Error::from_boxed)
+            .with_context(|| format!("escrow #{} of buyer {buyer} not found", escrow.escrow_id))?;
+        Ok((address, account))
+    }
+
+    fn treasury(&self) -> Result<Pubkey> {
+        let config: Config = accounts::fetch(&self.rpc, &pda::config()).map_err(anyhow::Error::from_boxed)?;
+        Ok(config.treasury)
+    }
+
+    fn send(&self, instruction: Instruction) -> Result<()> {
+        let blockhash = self.rpc.get_latest_blockhash()?;
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&self.signer.pubkey()),
+            &[&self.signer],
+            blockhash,
+        );
+        let signature = self.rpc.send_and_confirm_transaction(&transaction)?;
+        println!("Signature: {signature}");
+        Ok(())
+    }
+}
+
+fn now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() as i64)
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let keypair_path = expand_home(&cli.keypair);
+    let signer = read_keypair_file(&keypair_path)
+        .map_err(|e| anyhow::anyhow!("{e}"))
+        .with_context(|| format!("reading keypair {}", keypair_path.display()))?;
+    let session = Session { rpc: RpcClient::new_with_commitment(cli.url, CommitmentConfig::confirmed()), signer };
+    let me = session.signer.pubkey();
+
+    match cli.command {
+        Command::Create {
+            arbitrator,
+            mint,
+            amount,
+            buyer_collateral,
+            seller_collateral,
+            deadline,
+            description,
+            seller,
+        } => {
+            let decimals = accounts::fetch::<Mint>(&session.rpc, &mint).map_err(anyhow::Error::from_boxed)?.decimals;
+            let mut params = instructions::create_escrow_params(
+                description,
+                format::parse_amount(&amount, decimals)?,
+                format::parse_deadline(&deadline, now())?,
+            );
+            params.buyer_collateral = format::parse_amount(&buyer_collateral, decimals)?;
+            params.seller_collateral = format::parse_amount(&seller_collateral, decimals)?;
+            params.intended_seller = seller;
+            let escrow_id = accounts::fetch_next_escrow_id(&session.rpc, &me).map_err(anyhow::Error::from_boxed)?;
+            session.send(instructions::create_escrow(&me, &me, &arbitrator, &mint, escrow_id, params))?;
+            println!("Created escrow #{escrow_id} {}", pda::escrow(&me, escrow_id));
+        }
+        Command::Accept(escrow) => {
+            let (_, escrow) = session.escrow(&escrow)?;
+            session.send(instructions::accept_escrow(&escrow, &me, &me, None))?;
+        }
+        Command::Deliver { escrow, file, hash, uri } => {
+            let delivery_hash = match (file, hash) {
+                (Some(file), _) => {
+                    let bytes = std::fs::read(&file).with_context(|| format!("reading {}", file.display()))?;
+                    solana_sdk::hash::hash(&bytes).to_bytes()
+                }
+                (None, Some(hash)) => format::parse_hash(&hash)?,
+                (None, None) => unreachable!("clap requires --file or --hash"),
+            };
+            let (_, escrow) = session.escrow(&escrow)?;
+            session.send(instructions::deliver(&escrow, &me, delivery_hash, uri))?;
+            println!("Delivery hash: {}", hex::encode(delivery_hash));
+        }
+        Command::Approve { escrow, satisfaction } => {
+            let (_, escrow) = session.escrow(&escrow)?;
+            session.send(instructions::approve(&escrow, &me, &session.treasury()?, None, satisfaction))?;
+        }
+        Command::Dispute(escrow) => {
+            let (_, escrow) = session.escrow(&escrow)?;
+            session.send(instructions::raise_dispute(&escrow, &me))?;
+        }
+        Command::Arbitrate { escrow, ruling } => {
+            let ruling = format::parse_ruling(&ruling)?;
+            let (_, escrow) = session.escrow(&escrow)?;
+            session.send(instructions::arbitrate(&escrow, &me, ruling))?;
+        }
+        Command::AutoApprove(escrow) => {
+            let (_, escrow) = session.escrow(&escrow)?;
+            session.send(instructions::auto_approve(&escrow, &me, &session.treasury()?, None))?;
+        }
+        Command::Show(escrow) => {
+            let (address, escrow) = session.escrow(&escrow)?;
+            format::print_escrow(&address.to_string(), &escrow, now());
+        }
+    }
+    Ok(())
+}
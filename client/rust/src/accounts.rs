@@ -0,0 +1,73 @@
+//! Decoding program accounts.
+//!
+//! Accounts are Anchor accounts: an 8-byte discriminator, then Borsh. An
+//! `Escrow` only decodes at the current `ESCROW_LAYOUT_VERSION`; older ones
+//! need `migrate_escrow` first (see `clawscrow::layout` for the fields that
+//! can be read at fixed offsets from any version). Likewise a `Counter`
+//! from before open-escrow tracking needs `migrate_counter`.
+
+use anchor_lang::{AccountDeserialize, Result};
+use clawscrow::{Counter, Escrow, EscrowState};
+
+/// Decode account data as `T`, checking its discriminator.
+pub fn decode<T: AccountDeserialize>(data: &[u8]) -> Result<T> {
+    T::try_deserialize(&mut &data[..])
+}
+
+pub fn decode_escrow(data: &[u8]) -> Result<Escrow> {
+    decode(data)
+}
+
+/// The ID `create_escrow` will give the buyer's next escrow, from their
+/// `Counter` account's data, or `None` if it does not exist yet.
+pub fn next_escrow_id(counter: Option<&[u8]>) -> Result<u64> {
+    match counter {
+        Some(data) => Ok(decode::<Counter>(data)?.count),
+        None => Ok(0),
+    }
+}
+
+/// The state's variant name, as it appears in the IDL.
+pub fn state_name(state: &EscrowState) -> &'static str {
+    match state {
+        EscrowState::Created => "Created",
+        EscrowState::Accepted => "Accepted",
+        EscrowState::Delivered => "Delivered",
+        EscrowState::Approved => "Approved",
+        EscrowState::Disputed => "Disputed",
+        EscrowState::ResolvedBuyer => "ResolvedBuyer",
+        EscrowState::ResolvedSeller => "ResolvedSeller",
+        EscrowState::Cancelled => "Cancelled",
+        EscrowState::TimedOut => "TimedOut",
+        EscrowState::RulingPending => "RulingPending",
+        EscrowState::RulingFrozen => "RulingFrozen",
+        EscrowState::ResolvedSplit => "ResolvedSplit",
+        EscrowState::Vesting => "Vesting",
+        EscrowState::Draft => "Draft",
+        EscrowState::Escalated => "Escalated",
+    }
+}
+
+#[cfg(feature = "rpc")]
+pub use rpc::*;
+
+#[cfg(feature = "rpc")]
+mod rpc {
+    use anchor_lang::prelude::Pubkey;
+    use anchor_lang::AccountDeserialize;
+    use solana_client::rpc_client::RpcClient;
+
+    use super::decode;
+
+    /// Fetch and decode one account.
+    pub fn fetch<T: AccountDeserialize>(rpc: &RpcClient, address: &Pubkey) -> Result<T, Box<dyn std::error::Error + Send + Sync>> {
+        let data = rpc.get_account_data(address)?;
+        Ok(decode(&data)?)
+    }
+
+    /// The buyer's next escrow ID, read from their `Counter`.
+    pub fn fetch_next_escrow_id(rpc: &RpcClient, buyer: &Pubkey) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+        let account = rpc.get_account_with_commitment(&crate::pda::counter(buyer), rpc.commitment())?.value;
+        Ok(super::next_escrow_id(account.as_ref().map(|a| a.data.as_slice()))?)
+    }
+}
@@ -0,0 +1,98 @@
+//! Decoding program events.
+//!
+//! `emit!` events arrive base64-encoded in `Program data:` log lines;
+//! `emit_cpi!` events arrive as the data of a self-invocation, behind
+//! Anchor's event instruction tag. Both carry the event's discriminator and
+//! then its Borsh encoding.
+
+use anchor_lang::event::EVENT_IX_TAG_LE;
+use anchor_lang::Event;
+use base64::Engine;
+
+use crate::ID;
+
+/// Decode raw event bytes as `T`, or `None` if they are another event.
+pub fn decode<T: Event>(data: &[u8]) -> Option<T> {
+    let body = data.strip_prefix(&T::DISCRIMINATOR)?;
+    T::deserialize(&mut &body[..]).ok()
+}
+
+/// Decode an `emit_cpi!` event from the data of an inner instruction to this program.
+pub fn from_cpi_data<T: Event>(ix_data: &[u8]) -> Option<T> {
+    decode(ix_data.strip_prefix(&EVENT_IX_TAG_LE)?)
+}
+
+/// Every `T` logged with `emit!` by this program, in order. Data logged by
+/// other programs the transaction invokes is skipped.
+pub fn from_logs<T: Event>(logs: &[String]) -> Vec<T> {
+    let program = ID.to_string();
+    let mut stack: Vec<&str> = Vec::new();
+    let mut events = Vec::new();
+    for line in logs {
+        let Some(rest) = line.strip_prefix("Program ") else {
+            continue;
+        };
+        if let Some(data) = rest.strip_prefix("data: ") {
+            if stack.last() != Some(&program.as_str()) {
+                continue;
+            }
+            let Ok(bytes) = base64::engine::general_purpose::STANDARD.decode(data) else {
+                continue;
+            };
+            events.extend(decode::<T>(&bytes));
+        } else if let Some((id, tail)) = rest.split_once(' ') {
+            if tail.starts_with("invoke [") {
+                stack.push(id);
+            } else if tail == "success" || tail.starts_with("failed") {
+                stack.pop();
+            }
+        }
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::prelude::Pubkey;
+    use clawscrow::{TemplateClosed, TemplateCreated};
+
+    use super::*;
+
+    fn closed() -> TemplateClosed {
+        TemplateClosed { template: Pubkey::new_unique(), owner: Pubkey::new_unique(), template_id: 7, timestamp: 1_000 }
+    }
+
+    fn data_line(event: &TemplateClosed) -> String {
+        format!("Program data: {}", base64::engine::general_purpose::STANDARD.encode(event.data()))
+    }
+
+    #[test]
+    fn decodes_only_the_requested_event() {
+        let event = closed();
+        let decoded = decode::<TemplateClosed>(&event.data()).unwrap();
+        assert_eq!((decoded.template, decoded.template_id), (event.template, 7));
+        assert!(decode::<TemplateCreated>(&event.data()).is_none());
+
+        let mut cpi = EVENT_IX_TAG_LE.to_vec();
+        cpi.extend(event.data());
+        assert_eq!(from_cpi_data::<TemplateClosed>(&cpi).unwrap().owner, event.owner);
+        assert!(from_cpi_data::<TemplateClosed>(&event.data()).is_none());
+    }
+
+    #[test]
+    fn reads_logs_of_this_program_only() {
+        let (ours, theirs) = (closed(), closed());
+        let other = Pubkey::new_unique();
+        let logs = vec![
+            format!("Program {ID} invoke [1]"),
+            format!("Program {other} invoke [2]"),
+            data_line(&theirs),
+            format!("Program {other} success"),
+            data_line(&ours),
+            format!("Program {ID} success"),
+        ];
+        let events = from_logs::<TemplateClosed>(&logs);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].template, ours.template);
+    }
+}
@@ -0,0 +1,369 @@
+//! Instruction builders.
+//!
+//! Anchor generates typed accounts and arguments for every instruction in
+//! `clawscrow::accounts` and `clawscrow::instruction`; [`build`] turns any
+//! such pair into an `Instruction`. The functions below cover the main
+//! escrow lifecycle and fill in every PDA, associated token account and the
+//! event authority themselves, so callers only name the signers. Optional
+//! accounts are left out; set them on the generated structs and use
+//! [`build`] when an escrow needs them.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::{self, get_associated_token_address};
+use anchor_spl::token;
+use clawscrow::{accounts, instruction, CreateEscrowParams, Escrow, Ruling};
+
+use crate::{pda, ID};
+
+/// Any clawscrow instruction from its generated accounts and arguments.
+pub fn build(accounts: impl ToAccountMetas, args: impl InstructionData) -> Instruction {
+    Instruction { program_id: ID, accounts: accounts.to_account_metas(None), data: args.data() }
+}
+
+fn escrow_address(escrow: &Escrow) -> Pubkey {
+    pda::escrow(&escrow.original_buyer, escrow.escrow_id)
+}
+
+fn ata(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    get_associated_token_address(owner, mint)
+}
+
+/// The buyer's counter, for instructions that settle an escrow still holding
+/// an open-escrow slot.
+fn open_slot_counter(escrow: &Escrow) -> Option<Pubkey> {
+    escrow.holds_open_slot.then(|| pda::counter(&escrow.original_buyer))
+}
+
+/// `create_escrow` parameters with every optional term unset. Adjust the
+/// fields before passing them to [`create_escrow`].
+pub fn create_escrow_params(description: String, payment_amount: u64, deadline_ts: i64) -> CreateEscrowParams {
+    CreateEscrowParams { description, payment_amount, deadline_ts, ..Default::default() }
+}
+
+/// `escrow_id` is the buyer's next ID; see `accounts::next_escrow_id`. The
+/// buyer pays from their associated token account. A platform's
+/// `fee_override` is not passed, so it is charged the default fee.
+pub fn create_escrow(
+    buyer: &Pubkey,
+    payer: &Pubkey,
+    arbitrator: &Pubkey,
+    mint: &Pubkey,
+    escrow_id: u64,
+    params: CreateEscrowParams,
+) -> Instruction {
+    let escrow = pda::escrow(buyer, escrow_id);
+    let accounts = accounts::CreateEscrow {
+        buyer: *buyer,
+        payer: *payer,
+        counter: pda::counter(buyer),
+        escrow,
+        vault: pda::vault(&escrow),
+        bonus_vault: params.early_bonus.is_some().then(|| pda::bonus_vault(&escrow)),
+        buyer_token: ata(buyer, mint),
+        mint: *mint,
+        arbitrator: *arbitrator,
+        arbitrator_profile: None,
+        config: pda::config(),
+        buyer_credential: None,
+        stats: pda::stats(),
+        buyer_index: pda::user_index(buyer),
+        fee_override: None,
+        arbitrator_index: pda::arbitrator_index(arbitrator),
+        token_program: token::ID,
+        system_program: system_program::ID,
+        rent: anchor_lang::solana_program::sysvar::rent::ID,
+        event_authority: pda::event_authority(),
+        program: ID,
+    };
+    build(accounts, instruction::CreateEscrow { params })
+}
+
+/// Collateral comes from the seller's associated token account.
+/// `seller_payout` sends payouts to another wallet's associated token account.
+pub fn accept_escrow(escrow: &Escrow, seller: &Pubkey, payer: &Pubkey, seller_payout: Option<Pubkey>) -> Instruction {
+    let address = escrow_address(escrow);
+    let accounts = accounts::AcceptEscrow {
+        seller: *seller,
+        payer: *payer,
+        escrow: address,
+        vault: pda::vault(&address),
+        seller_token: ata(seller, &escrow.mint),
+        config: pda::config(),
+        seller_credential: None,
+        seller_index: pda::user_index(seller),
+        token_program: token::ID,
+        system_program: system_program::ID,
+        event_authority: pda::event_authority(),
+        program: ID,
+    };
+    build(accounts, instruction::AcceptEscrow { escrow_id: escrow.escrow_id, seller_payout })
+}
+
+pub fn deliver(escrow: &Escrow, seller: &Pubkey, delivery_hash: [u8; 32], delivery_uri: Option<String>) -> Instruction {
+    let accounts = accounts::Deliver {
+        seller: *seller,
+        escrow: escrow_address(escrow),
+        operator_key: None,
+        event_authority: pda::event_authority(),
+        program: ID,
+    };
+    build(accounts, instruction::Deliver { delivery_hash, delivery_uri })
+}
+
+/// Accounts shared by `approve` and `auto_approve`. `treasury` is
+/// `Config::treasury`; its fee is paid to its associated token account.
+/// Optional accounts are unset; fill them in and use [`build`] for escrows
+/// with a payout split, deliverable or USD peg.
+pub fn resolve_accounts(escrow: &Escrow, signer: &Pubkey, treasury: &Pubkey) -> accounts::Resolve {
+    let address = escrow_address(escrow);
+    let buyer_payout_owner = escrow.buyer_payout.unwrap_or(escrow.buyer);
+    let seller_payout_owner = escrow.seller_payout.unwrap_or(escrow.seller);
+    accounts::Resolve {
+        signer: *signer,
+        escrow: address,
+        vault: pda::vault(&address),
+        counter: open_slot_counter(escrow),
+        buyer_payout_owner,
+        seller_payout_owner,
+        mint: escrow.mint,
+        buyer_token: ata(&buyer_payout_owner, &escrow.mint),
+        seller_token: ata(&seller_payout_owner, &escrow.mint),
+        unwrap_token: None,
+        payout_split: None,
+        operator_key: None,
+        asset_vault: None,
+        buyer_asset_token: None,
+        price_update: None,
+        tip_source: None,
+        config: pda::config(),
+        stats: pda::stats(),
+        treasury_token: ata(treasury, &escrow.mint),
+        buyer_reputation: pda::reputation(&escrow.buyer),
+        seller_reputation: pda::reputation(&escrow.seller),
+        arbitrator_index: pda::arbitrator_index(&escrow.arbitrator),
+        crank_token: None,
+        referrer_token: escrow.referral.as_ref().map(|r| ata(&r.referrer, &escrow.mint)),
+        token_program: token::ID,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+        event_authority: pda::event_authority(),
+        program: ID,
+    }
+}
+
+pub fn approve(
+    escrow: &Escrow,
+    buyer: &Pubkey,
+    treasury: &Pubkey,
+    feedback_hash: Option<[u8; 32]>,
+    satisfaction: Option<u8>,
+) -> Instruction {
+    build(
+        resolve_accounts(escrow, buyer, treasury),
+        instruction::Approve { escrow_id: escrow.escrow_id, feedback_hash, satisfaction },
+    )
+}
+
+/// Permissionless. The escrow's crank tip is paid to `crank_token`, which
+/// must be a token account of the signer's in the escrow's mint; without
+/// one the tip stays with the seller.
+pub fn auto_approve(escrow: &Escrow, signer: &Pubkey, treasury: &Pubkey, crank_token: Option<Pubkey>) -> Instruction {
+    let accounts = accounts::Resolve { crank_token, ..resolve_accounts(escrow, signer, treasury) };
+    build(accounts, instruction::AutoApprove { escrow_id: escrow.escrow_id })
+}
+
+/// The dispute bond comes from the buyer's associated token account.
+pub fn raise_dispute(escrow: &Escrow, buyer: &Pubkey) -> Instruction {
+    let address = escrow_address(escrow);
+    let accounts = accounts::DisputeCtx {
+        buyer: *buyer,
+        escrow: address,
+        vault: pda::vault(&address),
+        buyer_token: ata(buyer, &escrow.mint),
+        operator_key: None,
+        config: pda::config(),
+        stats: pda::stats(),
+        reputation: pda::reputation(&escrow.buyer),
+        case_queue: pda::case_queue(&escrow.arbitrator),
+        token_program: token::ID,
+        system_program: system_program::ID,
+        event_authority: pda::event_authority(),
+        program: ID,
+    };
+    build(accounts, instruction::RaiseDispute {})
+}
+
+pub fn arbitrate(escrow: &Escrow, arbitrator: &Pubkey, ruling: Ruling) -> Instruction {
+    let accounts = accounts::Arbitrate {
+        arbitrator: *arbitrator,
+        escrow: escrow_address(escrow),
+        config: pda::config(),
+        case_queue: pda::case_queue(&escrow.arbitrator),
+    };
+    build(accounts, instruction::Arbitrate { escrow_id: escrow.escrow_id, ruling })
+}
+
+/// Permissionless once the ruling is final. The arbitration fee goes to the
+/// ruling arbitrator's associated token account.
+pub fn execute_ruling(escrow: &Escrow, signer: &Pubkey, treasury: &Pubkey) -> Instruction {
+    let address = escrow_address(escrow);
+    let buyer_payout_owner = escrow.buyer_payout.unwrap_or(escrow.buyer);
+    let seller_payout_owner = escrow.seller_payout.unwrap_or(escrow.seller);
+    let accounts = accounts::ExecuteRuling {
+        signer: *signer,
+        escrow: address,
+        vault: pda::vault(&address),
+        counter: open_slot_counter(escrow),
+        buyer_payout_owner,
+        seller_payout_owner,
+        mint: escrow.mint,
+        buyer_token: ata(&buyer_payout_owner, &escrow.mint),
+        seller_token: ata(&seller_payout_owner, &escrow.mint),
+        unwrap_token: None,
+        payout_split: None,
+        referrer_token: escrow.referral.as_ref().map(|r| ata(&r.referrer, &escrow.mint)),
+        price_update: None,
+        escalation: None,
+        governance_proposal: None,
+        arbitrator_token: ata(&escrow.ruling_arbitrator(), &escrow.mint),
+        config: pda::config(),
+        stats: pda::stats(),
+        treasury_token: ata(treasury, &escrow.mint),
+        arbitrator_index: pda::arbitrator_index(&escrow.arbitrator),
+        buyer_reputation: pda::reputation(&escrow.buyer),
+        seller_reputation: pda::reputation(&escrow.seller),
+        token_program: token::ID,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+        event_authority: pda::event_authority(),
+        program: ID,
+    };
+    build(accounts, instruction::ExecuteRuling { escrow_id: escrow.escrow_id })
+}
+
+pub fn cancel_escrow(escrow: &Escrow, buyer: &Pubkey) -> Instruction {
+    let address = escrow_address(escrow);
+    let accounts = accounts::CancelEscrow {
+        buyer: *buyer,
+        escrow: address,
+        vault: pda::vault(&address),
+        counter: open_slot_counter(escrow),
+        buyer_token: escrow.buyer_payout_ata(),
+        second_vault: None,
+        buyer_second_token: None,
+        arbitrator_index: pda::arbitrator_index(&escrow.arbitrator),
+        token_program: token::ID,
+        event_authority: pda::event_authority(),
+        program: ID,
+    };
+    build(accounts, instruction::CancelEscrow { escrow_id: escrow.escrow_id })
+}
+
+/// Refunds the buyer of an accepted escrow whose deadline passed without a delivery.
+pub fn claim_timeout(escrow: &Escrow, buyer: &Pubkey) -> Instruction {
+    let address = escrow_address(escrow);
+    let accounts = accounts::ClaimTimeout {
+        buyer: *buyer,
+        escrow: address,
+        vault: pda::vault(&address),
+        counter: open_slot_counter(escrow),
+        buyer_token: escrow.buyer_payout_ata(),
+        arbitrator_index: pda::arbitrator_index(&escrow.arbitrator),
+        token_program: token::ID,
+        event_authority: pda::event_authority(),
+        program: ID,
+    };
+    build(accounts, instruction::ClaimTimeout { escrow_id: escrow.escrow_id })
+}
+
+/// Permissionless once `accept_by_ts` has passed without a seller accepting.
+pub fn expire_unaccepted(escrow: &Escrow, signer: &Pubkey) -> Instruction {
+    let address = escrow_address(escrow);
+    let accounts = accounts::ExpireUnaccepted {
+        signer: *signer,
+        escrow: address,
+        vault: pda::vault(&address),
+        counter: open_slot_counter(escrow),
+        buyer_token: escrow.buyer_payout_ata(),
+        arbitrator_index: pda::arbitrator_index(&escrow.arbitrator),
+        token_program: token::ID,
+        event_authority: pda::event_authority(),
+        program: ID,
+    };
+    build(accounts, instruction::ExpireUnaccepted { escrow_id: escrow.escrow_id })
+}
+
+pub fn close_escrow(escrow: &Escrow, signer: &Pubkey) -> Instruction {
+    let address = escrow_address(escrow);
+    let accounts = accounts::CloseEscrow {
+        signer: *signer,
+        buyer: escrow.buyer,
+        escrow: address,
+        vault: pda::vault(&address),
+        asset_vault: escrow.deliverable.as_ref().map(|_| pda::asset_vault(&address)),
+        token_program: token::ID,
+        event_authority: pda::event_authority(),
+        program: ID,
+    };
+    build(accounts, instruction::CloseEscrow { escrow_id: escrow.escrow_id })
+}
+
+/// Grows a buyer's counter from before open-escrow tracking; `create_escrow`
+/// fails on it until this runs. Anyone can pay.
+pub fn migrate_counter(buyer: &Pubkey, payer: &Pubkey) -> Instruction {
+    let accounts = accounts::MigrateCounter {
+        payer: *payer,
+        buyer: *buyer,
+        counter: pda::counter(buyer),
+        system_program: system_program::ID,
+    };
+    build(accounts, instruction::MigrateCounter {})
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{AccountDeserialize, Discriminator};
+
+    use super::*;
+
+    /// A decoded escrow with every field empty, as a fresh account reads.
+    fn escrow() -> Escrow {
+        let data = vec![0; 8 + <Escrow as anchor_lang::Space>::INIT_SPACE];
+        let mut escrow = Escrow::try_deserialize_unchecked(&mut &data[..]).unwrap();
+        escrow.original_buyer = Pubkey::new_unique();
+        escrow.buyer = escrow.original_buyer;
+        escrow.seller = Pubkey::new_unique();
+        escrow.arbitrator = Pubkey::new_unique();
+        escrow.mint = Pubkey::new_unique();
+        escrow.escrow_id = 3;
+        escrow
+    }
+
+    #[test]
+    fn create_escrow_derives_the_escrow_and_vault() {
+        let (buyer, arbitrator, mint) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let ix = create_escrow(&buyer, &buyer, &arbitrator, &mint, 4, create_escrow_params("job".into(), 10, 100));
+        let escrow = pda::escrow(&buyer, 4);
+        assert_eq!(ix.accounts[3].pubkey, escrow);
+        assert_eq!(ix.accounts[4].pubkey, pda::vault(&escrow));
+        // Unset optional accounts are passed as the program ID.
+        assert_eq!(ix.accounts[5].pubkey, ID);
+        assert_eq!(ix.data[..8], instruction::CreateEscrow::DISCRIMINATOR);
+    }
+
+    #[test]
+    fn approve_pays_the_bound_payout_owners() {
+        let mut escrow = escrow();
+        let payout = Pubkey::new_unique();
+        escrow.seller_payout = Some(payout);
+        escrow.holds_open_slot = true;
+        let ix = approve(&escrow, &escrow.buyer, &Pubkey::new_unique(), None, Some(5));
+        assert_eq!(ix.accounts[1].pubkey, escrow_address(&escrow));
+        assert_eq!(ix.accounts[3].pubkey, pda::counter(&escrow.original_buyer));
+        assert_eq!(ix.accounts[5].pubkey, payout);
+        assert_eq!(ix.accounts[8].pubkey, ata(&payout, &escrow.mint));
+        assert!(ix.accounts[0].is_signer);
+    }
+}
@@ -0,0 +1,22 @@
+//! Rust client for the clawscrow program.
+//!
+//! Off-chain services use this crate instead of hand-rolling account metas
+//! against the IDL:
+//!
+//! - [`pda`] derives every program address from its seeds.
+//! - [`instructions`] builds instructions, deriving PDAs, associated token
+//!   accounts and the event authority for the common escrow lifecycle.
+//! - [`accounts`] decodes program accounts, and fetches them with the `rpc`
+//!   feature.
+//! - [`events`] decodes events from transaction logs and self-CPI data.
+//!
+//! The program's own types (`Escrow`, `Ruling`, every event and the typed
+//! `clawscrow::accounts` / `clawscrow::instruction` structs) are re-exported
+//! through [`clawscrow`].
+
+pub mod accounts;
+pub mod events;
+pub mod instructions;
+pub mod pda;
+
+pub use clawscrow::{self, ID};
@@ -0,0 +1,123 @@
+//! Program-derived addresses, one function per seed scheme in the README.
+//! `clawscrow::pda` has the same functions returning the bump as well.
+
+use anchor_lang::prelude::Pubkey;
+
+pub fn config() -> Pubkey {
+    clawscrow::pda::config().0
+}
+
+pub fn stats() -> Pubkey {
+    clawscrow::pda::stats().0
+}
+
+/// Signs `emit_cpi!` event self-invocations; every `#[event_cpi]` instruction takes it.
+pub fn event_authority() -> Pubkey {
+    clawscrow::pda::event_authority().0
+}
+
+pub fn receipt_authority() -> Pubkey {
+    clawscrow::pda::receipt_authority().0
+}
+
+/// The buyer's escrow ID counter; its `count` is the next escrow's ID.
+pub fn counter(buyer: &Pubkey) -> Pubkey {
+    clawscrow::pda::counter(buyer).0
+}
+
+/// Keyed by the creating buyer, which `transfer_buyer_role` does not change.
+pub fn escrow(original_buyer: &Pubkey, escrow_id: u64) -> Pubkey {
+    clawscrow::pda::escrow(original_buyer, escrow_id).0
+}
+
+pub fn vault(escrow: &Pubkey) -> Pubkey {
+    clawscrow::pda::vault(escrow).0
+}
+
+pub fn asset_vault(escrow: &Pubkey) -> Pubkey {
+    clawscrow::pda::asset_vault(escrow).0
+}
+
+pub fn second_vault(escrow: &Pubkey) -> Pubkey {
+    clawscrow::pda::second_vault(escrow).0
+}
+
+pub fn bonus_vault(escrow: &Pubkey) -> Pubkey {
+    clawscrow::pda::bonus_vault(escrow).0
+}
+
+pub fn unwrap(escrow: &Pubkey) -> Pubkey {
+    clawscrow::pda::unwrap(escrow).0
+}
+
+pub fn payout_split(escrow: &Pubkey) -> Pubkey {
+    clawscrow::pda::payout_split(escrow).0
+}
+
+pub fn badge(escrow: &Pubkey) -> Pubkey {
+    clawscrow::pda::badge(escrow).0
+}
+
+pub fn badge_mint(escrow: &Pubkey) -> Pubkey {
+    clawscrow::pda::badge_mint(escrow).0
+}
+
+pub fn contribution(escrow: &Pubkey, contributor: &Pubkey) -> Pubkey {
+    clawscrow::pda::contribution(escrow, contributor).0
+}
+
+pub fn bid(escrow: &Pubkey, bidder: &Pubkey) -> Pubkey {
+    clawscrow::pda::bid(escrow, bidder).0
+}
+
+pub fn operator(owner: &Pubkey) -> Pubkey {
+    clawscrow::pda::operator(owner).0
+}
+
+pub fn offer(seller: &Pubkey, offer_id: u64) -> Pubkey {
+    clawscrow::pda::offer(seller, offer_id).0
+}
+
+pub fn offer_vault(offer: &Pubkey) -> Pubkey {
+    clawscrow::pda::offer_vault(offer).0
+}
+
+pub fn recurring_agreement(buyer: &Pubkey, agreement_id: u64) -> Pubkey {
+    clawscrow::pda::recurring_agreement(buyer, agreement_id).0
+}
+
+pub fn template(owner: &Pubkey, template_id: u64) -> Pubkey {
+    clawscrow::pda::template(owner, template_id).0
+}
+
+pub fn user_index(wallet: &Pubkey) -> Pubkey {
+    clawscrow::pda::user_index(wallet).0
+}
+
+pub fn reputation(wallet: &Pubkey) -> Pubkey {
+    clawscrow::pda::reputation(wallet).0
+}
+
+pub fn arbitrator_index(arbitrator: &Pubkey) -> Pubkey {
+    clawscrow::pda::arbitrator_index(arbitrator).0
+}
+
+pub fn arbitrator_profile(arbitrator: &Pubkey) -> Pubkey {
+    clawscrow::pda::arbitrator_profile(arbitrator).0
+}
+
+pub fn case_queue(arbitrator: &Pubkey) -> Pubkey {
+    clawscrow::pda::case_queue(arbitrator).0
+}
+
+pub fn fee_override(platform: &Pubkey) -> Pubkey {
+    clawscrow::pda::fee_override(platform).0
+}
+
+pub fn listing_index(platform: &Pubkey) -> Pubkey {
+    clawscrow::pda::listing_index(platform).0
+}
+
+pub fn insurance_vault(mint: &Pubkey) -> Pubkey {
+    clawscrow::pda::insurance_vault(mint).0
+}
@@ -0,0 +1,131 @@
+//! Read-only HTTP/JSON API over the mirror.
+//!
+//! - `GET /health`
+//! - `GET /escrows?buyer=&seller=&arbitrator=&state=&platform=&closed=true&limit=`
+//! - `GET /escrows/<address>`
+//! - `GET /escrows/<address>/transitions`
+//! - `GET /escrows/<address>/activity`
+//!
+//! Responses allow any origin so marketplace frontends can call it directly.
+
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::store::{EscrowFilter, Store};
+
+const DEFAULT_LIMIT: u32 = 100;
+const MAX_LIMIT: u32 = 1_000;
+
+#[derive(Debug, PartialEq)]
+enum Route {
+    Health,
+    Escrows(Vec<(String, String)>),
+    Escrow(String),
+    Transitions(String),
+    Activity(String),
+    NotFound,
+}
+
+fn route(url: &str) -> Route {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    match segments.as_slice() {
+        ["health"] => Route::Health,
+        ["escrows"] => Route::Escrows(
+            query
+                .split('&')
+                .filter_map(|pair| pair.split_once('='))
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        ),
+        ["escrows", address] => Route::Escrow(address.to_string()),
+        ["escrows", address, "transitions"] => Route::Transitions(address.to_string()),
+        ["escrows", address, "activity"] => Route::Activity(address.to_string()),
+        _ => Route::NotFound,
+    }
+}
+
+fn filter(params: &[(String, String)]) -> EscrowFilter {
+    let get = |name: &str| params.iter().find(|(k, _)| k == name).map(|(_, v)| v.clone());
+    EscrowFilter {
+        buyer: get("buyer"),
+        seller: get("seller"),
+        arbitrator: get("arbitrator"),
+        state: get("state"),
+        platform: get("platform"),
+        include_closed: get("closed").as_deref() == Some("true"),
+        limit: get("limit").and_then(|l| l.parse().ok()).unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT),
+    }
+}
+
+fn handle(store: &Store, url: &str) -> Result<(u16, Value)> {
+    let not_found = (404, json!({ "error": "not found" }));
+    Ok(match route(url) {
+        Route::Health => (200, json!({ "ok": true })),
+        Route::Escrows(params) => (200, json!(store.list_escrows(&filter(&params))?)),
+        Route::Escrow(address) => store.get_escrow(&address)?.map_or(not_found, |escrow| (200, escrow)),
+        Route::Transitions(address) => (200, json!(store.transitions(&address)?)),
+        Route::Activity(address) => (200, json!(store.activity(&address)?)),
+        Route::NotFound => not_found,
+    })
+}
+
+fn respond(request: Request, status: u16, body: &Value) {
+    let response = Response::from_string(body.to_string())
+        .with_status_code(status)
+        .with_header(Header::from_bytes("Content-Type", "application/json").expect("static header"))
+        .with_header(Header::from_bytes("Access-Control-Allow-Origin", "*").expect("static header"));
+    if let Err(e) = request.respond(response) {
+        eprintln!("failed to respond: {e}");
+    }
+}
+
+pub fn serve(listen: &str, store: Arc<Mutex<Store>>) -> Result<()> {
+    let server = Server::http(listen).map_err(|e| anyhow::anyhow!("cannot listen on {listen}: {e}"))?;
+    println!("serving on http://{listen}");
+    for request in server.incoming_requests() {
+        if request.method() != &Method::Get {
+            respond(request, 405, &json!({ "error": "method not allowed" }));
+            continue;
+        }
+        let result = {
+            let store = store.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            handle(&store, request.url())
+        };
+        match result {
+            Ok((status, body)) => respond(request, status, &body),
+            Err(e) => respond(request, 500, &json!({ "error": e.to_string() })),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routes_paths_and_queries() {
+        assert_eq!(route("/health"), Route::Health);
+        assert_eq!(route("/escrows/abc"), Route::Escrow("abc".into()));
+        assert_eq!(route("/escrows/abc/transitions"), Route::Transitions("abc".into()));
+        assert_eq!(route("/escrows/abc/other"), Route::NotFound);
+        let Route::Escrows(params) = route("/escrows?state=Delivered&limit=5000&closed=true") else {
+            panic!("expected a listing");
+        };
+        let filter = filter(&params);
+        assert_eq!(filter.state.as_deref(), Some("Delivered"));
+        assert_eq!(filter.limit, MAX_LIMIT);
+        assert!(filter.include_closed);
+    }
+
+    #[test]
+    fn unknown_escrows_are_404() {
+        let store = Store::open(":memory:").unwrap();
+        assert_eq!(handle(&store, "/escrows/missing").unwrap().0, 404);
+        assert_eq!(handle(&store, "/escrows").unwrap(), (200, json!([])));
+    }
+}
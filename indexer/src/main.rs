@@ -0,0 +1,71 @@
+//! `clawscrow-indexer`: mirrors every escrow into SQLite from the program's
+//! websocket logs and serves the mirror over HTTP for marketplace frontends.
+
+mod api;
+mod store;
+mod watcher;
+
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use anyhow::Result;
+use clap::Parser;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+
+use crate::store::Store;
+use crate::watcher::Watcher;
+
+#[derive(Parser)]
+#[command(version, about = "Index clawscrow escrows and serve them over HTTP")]
+struct Cli {
+    /// JSON-RPC endpoint.
+    #[arg(long, env = "CLAWSCROW_RPC_URL", default_value = "https://api.devnet.solana.com")]
+    rpc_url: String,
+
+    /// Websocket endpoint; derived from `--rpc-url` when unset.
+    #[arg(long, env = "CLAWSCROW_WS_URL")]
+    ws_url: Option<String>,
+
+    /// SQLite database file.
+    #[arg(long, env = "CLAWSCROW_INDEXER_DB", default_value = "clawscrow-index.db")]
+    db: String,
+
+    /// Address for the HTTP API.
+    #[arg(long, env = "CLAWSCROW_INDEXER_LISTEN", default_value = "127.0.0.1:8787")]
+    listen: String,
+}
+
+/// `https://host` to `wss://host`, `http://host:8899` to `ws://host:8900`,
+/// following the validator's convention of websockets one port up.
+fn default_ws_url(rpc_url: &str) -> String {
+    let ws = rpc_url.replacen("https://", "wss://", 1).replacen("http://", "ws://", 1);
+    match ws.rsplit_once(':').map(|(host, port)| (host, port.trim_end_matches('/').parse::<u16>())) {
+        Some((host, Ok(port))) => format!("{host}:{}", port + 1),
+        _ => ws,
+    }
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let store = Arc::new(Mutex::new(Store::open(&cli.db)?));
+    let watcher = Watcher {
+        rpc: RpcClient::new_with_commitment(cli.rpc_url.clone(), CommitmentConfig::confirmed()),
+        ws_url: cli.ws_url.unwrap_or_else(|| default_ws_url(&cli.rpc_url)),
+        store: store.clone(),
+    };
+    println!("indexed {} escrows", watcher.backfill()?);
+    thread::spawn(move || watcher.watch());
+    api::serve(&cli.listen, store)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn websocket_url_follows_the_rpc_url() {
+        assert_eq!(default_ws_url("https://api.devnet.solana.com"), "wss://api.devnet.solana.com");
+        assert_eq!(default_ws_url("http://127.0.0.1:8899"), "ws://127.0.0.1:8900");
+    }
+}
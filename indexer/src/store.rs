@@ -0,0 +1,325 @@
+//! The SQLite mirror.
+//!
+//! `escrows` holds the latest decoded state of every escrow account, with
+//! `closed` set once the account is gone. `transitions` records each state
+//! change as the indexer observed it, and `activity` the transactions that
+//! touched each escrow. Token amounts are stored as SQLite integers, which
+//! are signed 64-bit; real balances stay far below that.
+
+use anyhow::Result;
+use clawscrow_client::accounts::state_name;
+use clawscrow_client::clawscrow::Escrow;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde_json::{json, Value};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS escrows (
+    address TEXT PRIMARY KEY,
+    escrow_id INTEGER NOT NULL,
+    buyer TEXT NOT NULL,
+    seller TEXT NOT NULL,
+    arbitrator TEXT NOT NULL,
+    mint TEXT NOT NULL,
+    platform TEXT,
+    state TEXT NOT NULL,
+    payment_amount INTEGER NOT NULL,
+    released_amount INTEGER NOT NULL,
+    buyer_collateral INTEGER NOT NULL,
+    seller_collateral INTEGER NOT NULL,
+    decimals INTEGER NOT NULL,
+    deadline_ts INTEGER NOT NULL,
+    created_at INTEGER NOT NULL,
+    delivered_at INTEGER NOT NULL,
+    description TEXT NOT NULL,
+    closed INTEGER NOT NULL DEFAULT 0,
+    updated_slot INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS escrows_buyer ON escrows (buyer);
+CREATE INDEX IF NOT EXISTS escrows_seller ON escrows (seller);
+CREATE INDEX IF NOT EXISTS escrows_state ON escrows (state);
+CREATE TABLE IF NOT EXISTS transitions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    escrow TEXT NOT NULL,
+    from_state TEXT,
+    to_state TEXT NOT NULL,
+    slot INTEGER NOT NULL,
+    signature TEXT
+);
+CREATE INDEX IF NOT EXISTS transitions_escrow ON transitions (escrow);
+CREATE TABLE IF NOT EXISTS activity (
+    signature TEXT NOT NULL,
+    escrow TEXT NOT NULL,
+    slot INTEGER NOT NULL,
+    PRIMARY KEY (signature, escrow)
+);
+";
+
+/// `to_state` recorded when an escrow account is closed.
+pub const CLOSED: &str = "Closed";
+
+/// Filters for [`Store::list_escrows`]; unset fields match everything.
+#[derive(Default)]
+pub struct EscrowFilter {
+    pub buyer: Option<String>,
+    pub seller: Option<String>,
+    pub arbitrator: Option<String>,
+    pub state: Option<String>,
+    pub platform: Option<String>,
+    pub include_closed: bool,
+    pub limit: u32,
+}
+
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    pub fn open(path: &str) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    /// Write the escrow's latest state, recording a transition if the state
+    /// differs from the mirrored one. Older slots never overwrite newer ones.
+    pub fn upsert_escrow(&self, address: &str, escrow: &Escrow, slot: u64, signature: Option<&str>) -> Result<()> {
+        let previous: Option<(String, i64, bool)> = self
+            .conn
+            .query_row(
+                "SELECT state, updated_slot, closed FROM escrows WHERE address = ?1",
+                [address],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()?;
+        if previous.as_ref().is_some_and(|(_, updated, _)| *updated > slot as i64) {
+            return Ok(());
+        }
+        let state = state_name(&escrow.state);
+        self.conn.execute(
+            "INSERT INTO escrows (address, escrow_id, buyer, seller, arbitrator, mint, platform, state,
+                payment_amount, released_amount, buyer_collateral, seller_collateral, decimals,
+                deadline_ts, created_at, delivered_at, description, closed, updated_slot)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, 0, ?18)
+             ON CONFLICT (address) DO UPDATE SET
+                buyer = excluded.buyer, seller = excluded.seller, arbitrator = excluded.arbitrator,
+                platform = excluded.platform, state = excluded.state,
+                payment_amount = excluded.payment_amount, released_amount = excluded.released_amount,
+                buyer_collateral = excluded.buyer_collateral, seller_collateral = excluded.seller_collateral,
+                deadline_ts = excluded.deadline_ts, delivered_at = excluded.delivered_at,
+                description = excluded.description, closed = 0, updated_slot = excluded.updated_slot",
+            params![
+                address,
+                escrow.escrow_id as i64,
+                escrow.buyer.to_string(),
+                escrow.seller.to_string(),
+                escrow.arbitrator.to_string(),
+                escrow.mint.to_string(),
+                escrow.platform.map(|p| p.to_string()),
+                state,
+                escrow.payment_amount as i64,
+                escrow.released_amount as i64,
+                escrow.buyer_collateral as i64,
+                escrow.seller_collateral as i64,
+                escrow.decimals,
+                escrow.deadline_ts,
+                escrow.created_at,
+                escrow.delivered_at,
+                escrow.description,
+                slot as i64,
+            ],
+        )?;
+        match previous {
+            Some((from, _, false)) if from == state => {}
+            previous => self.record_transition(address, previous.map(|(from, _, _)| from), state, slot, signature)?,
+        }
+        Ok(())
+    }
+
+    /// Mark an escrow whose account no longer exists. Unknown addresses are ignored.
+    pub fn mark_closed(&self, address: &str, slot: u64, signature: Option<&str>) -> Result<()> {
+        let state: Option<String> = self
+            .conn
+            .query_row("SELECT state FROM escrows WHERE address = ?1 AND closed = 0", [address], |row| row.get(0))
+            .optional()?;
+        if let Some(state) = state {
+            self.conn.execute(
+                "UPDATE escrows SET closed = 1, updated_slot = ?2 WHERE address = ?1",
+                params![address, slot as i64],
+            )?;
+            self.record_transition(address, Some(state), CLOSED, slot, signature)?;
+        }
+        Ok(())
+    }
+
+    fn record_transition(&self, address: &str, from: Option<String>, to: &str, slot: u64, signature: Option<&str>) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO transitions (escrow, from_state, to_state, slot, signature) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![address, from, to, slot as i64, signature],
+        )?;
+        Ok(())
+    }
+
+    pub fn record_activity(&self, signature: &str, address: &str, slot: u64) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO activity (signature, escrow, slot) VALUES (?1, ?2, ?3)",
+            params![signature, address, slot as i64],
+        )?;
+        Ok(())
+    }
+
+    pub fn is_open(&self, address: &str) -> Result<bool> {
+        Ok(self
+            .conn
+            .query_row("SELECT 1 FROM escrows WHERE address = ?1 AND closed = 0", [address], |_| Ok(()))
+            .optional()?
+            .is_some())
+    }
+
+    pub fn open_addresses(&self) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare("SELECT address FROM escrows WHERE closed = 0")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    pub fn list_escrows(&self, filter: &EscrowFilter) -> Result<Vec<Value>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT * FROM escrows
+             WHERE (?1 IS NULL OR buyer = ?1) AND (?2 IS NULL OR seller = ?2)
+               AND (?3 IS NULL OR arbitrator = ?3) AND (?4 IS NULL OR state = ?4)
+               AND (?5 IS NULL OR platform = ?5) AND (?6 OR closed = 0)
+             ORDER BY created_at DESC LIMIT ?7",
+        )?;
+        let rows = stmt.query_map(
+            params![
+                filter.buyer,
+                filter.seller,
+                filter.arbitrator,
+                filter.state,
+                filter.platform,
+                filter.include_closed,
+                filter.limit
+            ],
+            escrow_json,
+        )?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    pub fn get_escrow(&self, address: &str) -> Result<Option<Value>> {
+        Ok(self.conn.query_row("SELECT * FROM escrows WHERE address = ?1", [address], escrow_json).optional()?)
+    }
+
+    pub fn transitions(&self, address: &str) -> Result<Vec<Value>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT from_state, to_state, slot, signature FROM transitions WHERE escrow = ?1 ORDER BY id",
+        )?;
+        let rows = stmt.query_map([address], |row| {
+            Ok(json!({
+                "from": row.get::<_, Option<String>>(0)?,
+                "to": row.get::<_, String>(1)?,
+                "slot": row.get::<_, i64>(2)?,
+                "signature": row.get::<_, Option<String>>(3)?,
+            }))
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    pub fn activity(&self, address: &str) -> Result<Vec<Value>> {
+        let mut stmt = self.conn.prepare("SELECT signature, slot FROM activity WHERE escrow = ?1 ORDER BY slot")?;
+        let rows = stmt.query_map([address], |row| {
+            Ok(json!({ "signature": row.get::<_, String>(0)?, "slot": row.get::<_, i64>(1)? }))
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+}
+
+fn escrow_json(row: &Row) -> rusqlite::Result<Value> {
+    Ok(json!({
+        "address": row.get::<_, String>("address")?,
+        "escrowId": row.get::<_, i64>("escrow_id")?,
+        "buyer": row.get::<_, String>("buyer")?,
+        "seller": row.get::<_, String>("seller")?,
+        "arbitrator": row.get::<_, String>("arbitrator")?,
+        "mint": row.get::<_, String>("mint")?,
+        "platform": row.get::<_, Option<String>>("platform")?,
+        "state": row.get::<_, String>("state")?,
+        "paymentAmount": row.get::<_, i64>("payment_amount")?,
+        "releasedAmount": row.get::<_, i64>("released_amount")?,
+        "buyerCollateral": row.get::<_, i64>("buyer_collateral")?,
+        "sellerCollateral": row.get::<_, i64>("seller_collateral")?,
+        "decimals": row.get::<_, u8>("decimals")?,
+        "deadlineTs": row.get::<_, i64>("deadline_ts")?,
+        "createdAt": row.get::<_, i64>("created_at")?,
+        "deliveredAt": row.get::<_, i64>("delivered_at")?,
+        "description": row.get::<_, String>("description")?,
+        "closed": row.get::<_, bool>("closed")?,
+        "updatedSlot": row.get::<_, i64>("updated_slot")?,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::prelude::Pubkey;
+    use anchor_lang::{AccountDeserialize, Space};
+    use clawscrow_client::clawscrow::EscrowState;
+
+    use super::*;
+
+    fn escrow() -> Escrow {
+        let data = vec![0; 8 + Escrow::INIT_SPACE];
+        let mut escrow = Escrow::try_deserialize_unchecked(&mut &data[..]).unwrap();
+        escrow.buyer = Pubkey::new_unique();
+        escrow.payment_amount = 5_000_000;
+        escrow
+    }
+
+    fn states(store: &Store, address: &str) -> Vec<(Option<String>, String)> {
+        store
+            .transitions(address)
+            .unwrap()
+            .iter()
+            .map(|t| (t["from"].as_str().map(String::from), t["to"].as_str().unwrap().to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn records_each_state_change_once() {
+        let store = Store::open(":memory:").unwrap();
+        let mut escrow = escrow();
+        store.upsert_escrow("e1", &escrow, 10, Some("sig1")).unwrap();
+        store.upsert_escrow("e1", &escrow, 11, None).unwrap();
+        escrow.state = EscrowState::Accepted;
+        store.upsert_escrow("e1", &escrow, 12, Some("sig2")).unwrap();
+        // A stale update from an earlier slot changes nothing.
+        escrow.state = EscrowState::Created;
+        store.upsert_escrow("e1", &escrow, 11, None).unwrap();
+        store.mark_closed("e1", 13, Some("sig3")).unwrap();
+        store.mark_closed("e1", 14, None).unwrap();
+
+        assert_eq!(
+            states(&store, "e1"),
+            [
+                (None, "Created".to_string()),
+                (Some("Created".to_string()), "Accepted".to_string()),
+                (Some("Accepted".to_string()), CLOSED.to_string()),
+            ]
+        );
+        let row = store.get_escrow("e1").unwrap().unwrap();
+        assert_eq!((row["state"].as_str(), row["closed"].as_bool()), (Some("Accepted"), Some(true)));
+    }
+
+    #[test]
+    fn filters_listings() {
+        let store = Store::open(":memory:").unwrap();
+        let (a, b) = (escrow(), escrow());
+        store.upsert_escrow("a", &a, 1, None).unwrap();
+        store.upsert_escrow("b", &b, 1, None).unwrap();
+        store.mark_closed("b", 2, None).unwrap();
+
+        let open = EscrowFilter { limit: 10, ..Default::default() };
+        assert_eq!(store.list_escrows(&open).unwrap().len(), 1);
+        let all = EscrowFilter { include_closed: true, limit: 10, ..Default::default() };
+        assert_eq!(store.list_escrows(&all).unwrap().len(), 2);
+        let by_buyer = EscrowFilter { buyer: Some(b.buyer.to_string()), include_closed: true, limit: 10, ..Default::default() };
+        assert_eq!(store.list_escrows(&by_buyer).unwrap()[0]["address"], "b");
+    }
+}
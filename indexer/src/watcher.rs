@@ -0,0 +1,171 @@
+//! Keeps the store in step with the chain.
+//!
+//! On start, and after every websocket reconnect, [`backfill`] loads every
+//! escrow account with `getProgramAccounts` and closes rows whose account
+//! is gone. In between, [`watch`] subscribes to the program's logs; for each
+//! successful transaction it re-reads every account the transaction could
+//! write, so the mirror follows the accounts themselves rather than any one
+//! event. Closed accounts stop being owned by the program, which is how
+//! closes and cancellations are detected.
+
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::Discriminator;
+use anyhow::{anyhow, Result};
+use clawscrow_client::clawscrow::Escrow;
+use clawscrow_client::{accounts, ID};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{
+    RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcTransactionConfig, RpcTransactionLogsConfig,
+    RpcTransactionLogsFilter,
+};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_pubsub_client::pubsub_client::PubsubClient;
+use solana_sdk::account::Account;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::option_serializer::OptionSerializer;
+use solana_transaction_status::UiTransactionEncoding;
+
+use crate::store::Store;
+
+/// Attempts at fetching a transaction the logs subscription just reported.
+const FETCH_ATTEMPTS: u32 = 5;
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+
+pub struct Watcher {
+    pub rpc: RpcClient,
+    pub ws_url: String,
+    pub store: Arc<Mutex<Store>>,
+}
+
+impl Watcher {
+    fn store(&self) -> std::sync::MutexGuard<'_, Store> {
+        self.store.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Mirror every escrow account, and close rows for accounts that no longer exist.
+    pub fn backfill(&self) -> Result<usize> {
+        let slot = self.rpc.get_slot()?;
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, &Escrow::DISCRIMINATOR))]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                commitment: Some(self.rpc.commitment()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let found = self.rpc.get_program_accounts_with_config(&ID, config)?;
+        let store = self.store();
+        let mut live = std::collections::HashSet::new();
+        for (address, account) in &found {
+            live.insert(address.to_string());
+            if let Err(e) = self.mirror(&store, address, Some(account), slot, None) {
+                eprintln!("skipping {address}: {e}");
+            }
+        }
+        for address in store.open_addresses()? {
+            if !live.contains(&address) {
+                store.mark_closed(&address, slot, None)?;
+            }
+        }
+        Ok(found.len())
+    }
+
+    /// Follow the program's logs forever, backfilling after each reconnect.
+    pub fn watch(&self) -> ! {
+        loop {
+            if let Err(e) = self.watch_once() {
+                eprintln!("log subscription ended: {e}");
+            }
+            thread::sleep(RETRY_DELAY);
+            match self.backfill() {
+                Ok(count) => println!("resynced {count} escrows"),
+                Err(e) => eprintln!("backfill failed: {e}"),
+            }
+        }
+    }
+
+    fn watch_once(&self) -> Result<()> {
+        let (_subscription, notifications) = PubsubClient::logs_subscribe(
+            &self.ws_url,
+            RpcTransactionLogsFilter::Mentions(vec![ID.to_string()]),
+            RpcTransactionLogsConfig { commitment: Some(CommitmentConfig::confirmed()) },
+        )?;
+        for notification in notifications.iter() {
+            if notification.value.err.is_some() {
+                continue;
+            }
+            let signature = notification.value.signature;
+            if let Err(e) = self.process(&signature) {
+                eprintln!("failed to index {signature}: {e}");
+            }
+        }
+        Err(anyhow!("websocket closed"))
+    }
+
+    /// Re-read every account the transaction could have written.
+    fn process(&self, signature: &str) -> Result<()> {
+        let parsed: Signature = signature.parse()?;
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(CommitmentConfig::confirmed()),
+            max_supported_transaction_version: Some(0),
+        };
+        let mut attempt = 1;
+        let confirmed = loop {
+            match self.rpc.get_transaction_with_config(&parsed, config) {
+                Ok(confirmed) => break confirmed,
+                Err(_) if attempt < FETCH_ATTEMPTS => {
+                    attempt += 1;
+                    thread::sleep(RETRY_DELAY);
+                }
+                Err(e) => return Err(e.into()),
+            }
+        };
+        let transaction = confirmed.transaction.transaction.decode().ok_or_else(|| anyhow!("undecodable transaction"))?;
+        let message = &transaction.message;
+        let mut keys: Vec<Pubkey> = message
+            .static_account_keys()
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| message.is_maybe_writable(*i))
+            .map(|(_, key)| *key)
+            .collect();
+        if let Some(meta) = confirmed.transaction.meta {
+            if let OptionSerializer::Some(loaded) = meta.loaded_addresses {
+                keys.extend(loaded.writable.iter().filter_map(|key| key.parse::<Pubkey>().ok()));
+            }
+        }
+
+        let fetched = self.rpc.get_multiple_accounts(&keys)?;
+        let store = self.store();
+        for (address, account) in keys.iter().zip(&fetched) {
+            self.mirror(&store, address, account.as_ref(), confirmed.slot, Some(signature))?;
+        }
+        Ok(())
+    }
+
+    /// Upsert `address` if it holds an escrow, or close its row if it used to.
+    fn mirror(&self, store: &Store, address: &Pubkey, account: Option<&Account>, slot: u64, signature: Option<&str>) -> Result<()> {
+        let key = address.to_string();
+        let escrow = account
+            .filter(|a| a.owner == ID && a.data.starts_with(&Escrow::DISCRIMINATOR))
+            .map(|a| accounts::decode_escrow(&a.data))
+            .transpose()?;
+        match escrow {
+            Some(escrow) => store.upsert_escrow(&key, &escrow, slot, signature)?,
+            None if store.is_open(&key)? => store.mark_closed(&key, slot, signature)?,
+            None => return Ok(()),
+        }
+        if let Some(signature) = signature {
+            store.record_activity(signature, &key, slot)?;
+        }
+        Ok(())
+    }
+}
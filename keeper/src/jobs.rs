@@ -0,0 +1,106 @@
+//! Which escrows are due for a keeper transaction, decided from the escrow
+//! alone so a pass needs no further reads for escrows that are not.
+
+use anchor_lang::prelude::Pubkey;
+use clawscrow_client::clawscrow::{Escrow, EscrowState};
+
+/// A transaction the keeper can submit for an escrow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Job {
+    /// Review period over without approval or dispute; releases payment.
+    AutoApprove,
+    /// Deadline passed without delivery; refunds the buyer, who must sign.
+    ClaimTimeout,
+    /// `accept_by_ts` passed without a seller; refunds the buyer.
+    ExpireUnaccepted,
+}
+
+impl Job {
+    pub fn name(self) -> &'static str {
+        match self {
+            Job::AutoApprove => "auto_approve",
+            Job::ClaimTimeout => "claim_timeout",
+            Job::ExpireUnaccepted => "expire_unaccepted",
+        }
+    }
+}
+
+/// States worth scanning for, each filtered server-side on the state byte.
+pub const SCANNED_STATES: [EscrowState; 4] =
+    [EscrowState::Delivered, EscrowState::Accepted, EscrowState::Created, EscrowState::Draft];
+
+/// The job `escrow` is due for at cluster time `now`, if the keeper can
+/// submit it. `claim_timeout` is only due when `keeper` is the buyer.
+pub fn due(escrow: &Escrow, now: i64, keeper: &Pubkey) -> Option<Job> {
+    match escrow.state {
+        EscrowState::Delivered if now >= escrow.review_started_at().saturating_add(escrow.review_period_secs) => {
+            Some(Job::AutoApprove)
+        }
+        EscrowState::Accepted if now > escrow.deadline_ts && escrow.buyer == *keeper => Some(Job::ClaimTimeout),
+        EscrowState::Created | EscrowState::Draft if escrow.accept_by_ts.is_some_and(|by| now > by) => {
+            Some(Job::ExpireUnaccepted)
+        }
+        _ => None,
+    }
+}
+
+/// Why the keeper cannot submit `job` for `escrow` on its own, if it can't.
+/// An unpriced USD peg needs a fresh oracle update posted alongside, and an
+/// asset deliverable needs the buyer's token account for that asset.
+pub fn blocker(escrow: &Escrow, job: Job) -> Option<&'static str> {
+    if job != Job::AutoApprove {
+        return None;
+    }
+    if escrow.usd_peg.as_ref().is_some_and(|peg| !peg.priced) {
+        return Some("needs a SOL/USD price update");
+    }
+    if escrow.deliverable.is_some() {
+        return Some("releases an asset deliverable");
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::AccountDeserialize;
+
+    use super::*;
+
+    fn escrow(state: EscrowState) -> Escrow {
+        let data = vec![0; 8 + <Escrow as anchor_lang::Space>::INIT_SPACE];
+        let mut escrow = Escrow::try_deserialize_unchecked(&mut &data[..]).unwrap();
+        escrow.buyer = Pubkey::new_unique();
+        escrow.state = state;
+        escrow
+    }
+
+    #[test]
+    fn auto_approve_waits_for_the_review_period() {
+        let mut escrow = escrow(EscrowState::Delivered);
+        escrow.delivered_at = 1_000;
+        escrow.acknowledged_at = 1_000;
+        escrow.review_period_secs = 500;
+        let keeper = Pubkey::new_unique();
+        assert_eq!(due(&escrow, 1_499, &keeper), None);
+        assert_eq!(due(&escrow, 1_500, &keeper), Some(Job::AutoApprove));
+    }
+
+    #[test]
+    fn timeouts_are_only_claimed_for_the_keepers_own_escrows() {
+        let mut escrow = escrow(EscrowState::Accepted);
+        escrow.deadline_ts = 100;
+        assert_eq!(due(&escrow, 101, &Pubkey::new_unique()), None);
+        assert_eq!(due(&escrow, 100, &escrow.buyer), None);
+        assert_eq!(due(&escrow, 101, &escrow.buyer), Some(Job::ClaimTimeout));
+    }
+
+    #[test]
+    fn unaccepted_escrows_expire_after_accept_by() {
+        let keeper = Pubkey::new_unique();
+        let mut escrow = escrow(EscrowState::Draft);
+        assert_eq!(due(&escrow, i64::MAX, &keeper), None);
+        escrow.accept_by_ts = Some(50);
+        assert_eq!(due(&escrow, 50, &keeper), None);
+        assert_eq!(due(&escrow, 51, &keeper), Some(Job::ExpireUnaccepted));
+    }
+}
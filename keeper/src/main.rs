@@ -0,0 +1,237 @@
+//! `clawscrow-keeper`: finds escrows whose review period, deadline or
+//! accept-by time has passed and submits the transaction that moves them on.
+//!
+//! `auto_approve` and `expire_unaccepted` are permissionless, so one keeper
+//! serves every escrow; `auto_approve` also pays the escrow's crank tip to
+//! the keeper's associated token account, created on first use.
+//! `claim_timeout` must be signed by the buyer, so it is only submitted for
+//! escrows the keeper's own keypair bought.
+
+mod jobs;
+
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::Discriminator;
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::associated_token::spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use anchor_spl::token;
+use anyhow::{anyhow, Context, Result};
+use clap::Parser;
+use clawscrow_client::clawscrow::{layout, Config, Escrow, PayoutSplit};
+use clawscrow_client::{accounts, instructions, pda, ID};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::clock::Clock;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use solana_sdk::sysvar;
+use solana_sdk::transaction::Transaction;
+
+use crate::jobs::Job;
+
+#[derive(Parser)]
+#[command(version, about = "Crank due clawscrow escrows")]
+struct Cli {
+    /// JSON-RPC endpoint.
+    #[arg(long, env = "CLAWSCROW_RPC_URL", default_value = "https://api.devnet.solana.com")]
+    url: String,
+
+    /// Keypair that signs, pays fees and collects crank tips.
+    #[arg(long, env = "CLAWSCROW_KEYPAIR", default_value = "~/.config/solana/id.json")]
+    keypair: String,
+
+    /// Priority fee in micro-lamports per compute unit; 0 sends none.
+    #[arg(long, env = "CLAWSCROW_PRIORITY_FEE", default_value_t = 0)]
+    priority_fee: u64,
+
+    /// Compute unit limit requested alongside a priority fee.
+    #[arg(long, default_value_t = 300_000)]
+    compute_units: u32,
+
+    /// Seconds between scans.
+    #[arg(long, default_value_t = 30)]
+    interval: u64,
+
+    /// Scan once and exit.
+    #[arg(long)]
+    once: bool,
+
+    /// Simulate the transactions instead of sending them.
+    #[arg(long)]
+    dry_run: bool,
+}
+
+struct Keeper {
+    rpc: RpcClient,
+    signer: Keypair,
+    priority_fee: u64,
+    compute_units: u32,
+    dry_run: bool,
+}
+
+impl Keeper {
+    /// Cluster time, which is what the program compares deadlines against.
+    fn now(&self) -> Result<i64> {
+        let account = self.rpc.get_account(&sysvar::clock::ID)?;
+        let clock: Clock = solana_sdk::account::from_account(&account).ok_or_else(|| anyhow!("undecodable clock"))?;
+        Ok(clock.unix_timestamp)
+    }
+
+    /// Every escrow in one of [`jobs::SCANNED_STATES`], skipping accounts
+    /// that fail to decode.
+    fn scan(&self) -> Result<Vec<(Pubkey, Escrow)>> {
+        let mut found = Vec::new();
+        for state in jobs::SCANNED_STATES {
+            let config = RpcProgramAccountsConfig {
+                filters: Some(vec![
+                    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, &Escrow::DISCRIMINATOR)),
+                    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(layout::STATE_OFFSET, &[state as u8])),
+                ]),
+                account_config: RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    commitment: Some(self.rpc.commitment()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            for (address, account) in self.rpc.get_program_accounts_with_config(&ID, config)? {
+                match accounts::decode_escrow(&account.data) {
+                    Ok(escrow) => found.push((address, escrow)),
+                    Err(e) => eprintln!("skipping {address}: {e}"),
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    /// Scan and submit every due job; returns how many succeeded.
+    fn run_pass(&self) -> Result<usize> {
+        let now = self.now()?;
+        let me = self.signer.pubkey();
+        let mut treasury = None;
+        let mut done = 0;
+        for (address, escrow) in self.scan()? {
+            let Some(job) = jobs::due(&escrow, now, &me) else { continue };
+            let label = format!("{} #{} {address}", job.name(), escrow.escrow_id);
+            if let Some(reason) = jobs::blocker(&escrow, job) {
+                println!("skip {label}: {reason}");
+                continue;
+            }
+            let result = match job {
+                Job::AutoApprove => {
+                    let treasury = match treasury {
+                        Some(treasury) => treasury,
+                        None => {
+                            let config: Config =
+                                accounts::fetch(&self.rpc, &pda::config()).map_err(anyhow::Error::from_boxed)?;
+                            *treasury.insert(config.treasury)
+                        }
+                    };
+                    self.auto_approve(&address, &escrow, &treasury)
+                }
+                Job::ClaimTimeout => self.submit(vec![instructions::claim_timeout(&escrow, &me)]),
+                Job::ExpireUnaccepted => self.submit(vec![instructions::expire_unaccepted(&escrow, &me)]),
+            };
+            match result {
+                Ok(outcome) => {
+                    println!("{label}: {outcome}");
+                    done += 1;
+                }
+                Err(e) => eprintln!("{label} failed: {e:#}"),
+            }
+        }
+        Ok(done)
+    }
+
+    /// Claims the crank tip when the escrow offers one, and pays out the
+    /// seller's payout split when they registered one.
+    fn auto_approve(&self, address: &Pubkey, escrow: &Escrow, treasury: &Pubkey) -> Result<String> {
+        let me = self.signer.pubkey();
+        let mut ixs = Vec::new();
+        let mut accounts = instructions::resolve_accounts(escrow, &me, treasury);
+        if escrow.crank_tip_bps > 0 {
+            ixs.push(create_associated_token_account_idempotent(&me, &me, &escrow.mint, &token::ID));
+            accounts.crank_token = Some(get_associated_token_address(&me, &escrow.mint));
+        }
+        let mut recipients = Vec::new();
+        if escrow.has_payout_split {
+            let split_address = pda::payout_split(address);
+            let split: PayoutSplit = accounts::fetch(&self.rpc, &split_address).map_err(anyhow::Error::from_boxed)?;
+            accounts.payout_split = Some(split_address);
+            recipients = split
+                .recipients
+                .iter()
+                .map(|r| AccountMeta::new(get_associated_token_address(&r.wallet, &escrow.mint), false))
+                .collect();
+        }
+        let mut ix = instructions::build(
+            accounts,
+            clawscrow_client::clawscrow::instruction::AutoApprove { escrow_id: escrow.escrow_id },
+        );
+        ix.accounts.extend(recipients);
+        ixs.push(ix);
+        self.submit(ixs)
+    }
+
+    /// Send `ixs` behind the compute budget instructions, or simulate them
+    /// under `--dry-run`.
+    fn submit(&self, ixs: Vec<Instruction>) -> Result<String> {
+        let mut all = Vec::with_capacity(ixs.len() + 2);
+        if self.priority_fee > 0 {
+            all.push(ComputeBudgetInstruction::set_compute_unit_limit(self.compute_units));
+            all.push(ComputeBudgetInstruction::set_compute_unit_price(self.priority_fee));
+        }
+        all.extend(ixs);
+        let blockhash = self.rpc.get_latest_blockhash()?;
+        let transaction =
+            Transaction::new_signed_with_payer(&all, Some(&self.signer.pubkey()), &[&self.signer], blockhash);
+        if !self.dry_run {
+            return Ok(self.rpc.send_and_confirm_transaction(&transaction)?.to_string());
+        }
+        let simulated = self.rpc.simulate_transaction(&transaction)?.value;
+        match simulated.err {
+            Some(err) => Err(anyhow!("simulation failed: {err}; logs: {:?}", simulated.logs.unwrap_or_default())),
+            None => Ok(format!("simulated, {} compute units", simulated.units_consumed.unwrap_or(0))),
+        }
+    }
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let keypair_path = expand_home(&cli.keypair);
+    let signer = read_keypair_file(&keypair_path)
+        .map_err(|e| anyhow!("{e}"))
+        .with_context(|| format!("reading keypair {}", keypair_path.display()))?;
+    let keeper = Keeper {
+        rpc: RpcClient::new_with_commitment(cli.url, CommitmentConfig::confirmed()),
+        signer,
+        priority_fee: cli.priority_fee,
+        compute_units: cli.compute_units,
+        dry_run: cli.dry_run,
+    };
+    println!("keeper {}{}", keeper.signer.pubkey(), if keeper.dry_run { " (dry run)" } else { "" });
+    loop {
+        match keeper.run_pass() {
+            Ok(done) => println!("pass complete, {done} submitted"),
+            Err(e) => eprintln!("pass failed: {e:#}"),
+        }
+        if cli.once {
+            return Ok(());
+        }
+        thread::sleep(Duration::from_secs(cli.interval));
+    }
+}
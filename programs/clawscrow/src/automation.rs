@@ -0,0 +1,135 @@
+//! Registers Clockwork threads that run `auto_approve` for a delivered escrow.
+//!
+//! A thread is owned by the Clockwork thread program at a PDA of its
+//! authority and id; ours are all authorised by the program's
+//! `thread_authority` PDA and take the escrow address as their id. Each holds
+//! one `auto_approve` instruction behind a timestamp trigger, which
+//! Clockwork's workers execute once the trigger time passes. The Clockwork
+//! SDK pins an older `solana-program` than Anchor 0.30 does, so the two
+//! instructions are encoded by hand: both are plain Anchor types.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+
+/// Clockwork thread program, the owner of every thread.
+pub const CLOCKWORK_THREAD_ID: Pubkey = pubkey!("CLoCKyJ6DXBJqqu2VWx9RLbgnwwR6BMHHuyasVmfMzBh");
+/// Stands in for the executing worker in a thread's instructions; Clockwork
+/// swaps in the worker's key and reimburses what it spends from the thread.
+pub const PAYER_PUBKEY: Pubkey = pubkey!("C1ockworkPayer11111111111111111111111111111");
+pub const THREAD_SEED: &[u8] = b"thread";
+pub const THREAD_AUTHORITY_SEED: &[u8] = b"thread_authority";
+
+/// `sha256("global:thread_create")[..8]`.
+const THREAD_CREATE_DISCRIMINATOR: [u8; 8] = [54, 1, 238, 224, 71, 244, 252, 173];
+/// `sha256("global:thread_delete")[..8]`.
+const THREAD_DELETE_DISCRIMINATOR: [u8; 8] = [146, 6, 95, 17, 35, 98, 44, 140];
+/// `Trigger::Timestamp`.
+const TIMESTAMP_TRIGGER: u8 = 5;
+
+/// The program PDA that authorises every thread it registers.
+pub fn thread_authority_address() -> Pubkey {
+    Pubkey::find_program_address(&[THREAD_AUTHORITY_SEED], &crate::ID).0
+}
+
+/// Where the thread for `escrow` lives.
+pub fn thread_address(escrow: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[THREAD_SEED, thread_authority_address().as_ref(), escrow.as_ref()],
+        &CLOCKWORK_THREAD_ID,
+    )
+    .0
+}
+
+/// `thread_create` for a thread that runs `instruction` once at `unix_ts`.
+/// `payer` covers the thread's rent and funds it with `amount` lamports.
+pub fn thread_create_instruction(
+    escrow: Pubkey,
+    payer: Pubkey,
+    amount: u64,
+    instruction: &Instruction,
+    unix_ts: i64,
+) -> Instruction {
+    let mut data = THREAD_CREATE_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&amount.to_le_bytes());
+    push_bytes(&mut data, escrow.as_ref());
+    // instructions: a single `SerializableInstruction`
+    data.extend_from_slice(&1u32.to_le_bytes());
+    data.extend_from_slice(instruction.program_id.as_ref());
+    data.extend_from_slice(&(instruction.accounts.len() as u32).to_le_bytes());
+    for meta in &instruction.accounts {
+        data.extend_from_slice(meta.pubkey.as_ref());
+        data.push(meta.is_signer as u8);
+        data.push(meta.is_writable as u8);
+    }
+    push_bytes(&mut data, &instruction.data);
+    data.push(TIMESTAMP_TRIGGER);
+    data.extend_from_slice(&unix_ts.to_le_bytes());
+
+    Instruction {
+        program_id: CLOCKWORK_THREAD_ID,
+        accounts: vec![
+            AccountMeta::new_readonly(thread_authority_address(), true),
+            AccountMeta::new(payer, true),
+            AccountMeta::new_readonly(anchor_lang::system_program::ID, false),
+            AccountMeta::new(thread_address(&escrow), false),
+        ],
+        data,
+    }
+}
+
+/// `thread_delete`, returning the thread's lamports to `close_to`.
+pub fn thread_delete_instruction(escrow: Pubkey, close_to: Pubkey) -> Instruction {
+    Instruction {
+        program_id: CLOCKWORK_THREAD_ID,
+        accounts: vec![
+            AccountMeta::new_readonly(thread_authority_address(), true),
+            AccountMeta::new(close_to, false),
+            AccountMeta::new(thread_address(&escrow), false),
+        ],
+        data: THREAD_DELETE_DISCRIMINATOR.to_vec(),
+    }
+}
+
+fn push_bytes(data: &mut Vec<u8>, bytes: &[u8]) {
+    data.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    data.extend_from_slice(bytes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn create_encodes_one_instruction_behind_a_timestamp() {
+        let (escrow, payer) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let inner = Instruction {
+            program_id: crate::ID,
+            accounts: vec![AccountMeta::new(PAYER_PUBKEY, true), AccountMeta::new_readonly(escrow, false)],
+            data: vec![9, 8, 7],
+        };
+        let ix = thread_create_instruction(escrow, payer, 5_000, &inner, 1_700_000_000);
+        assert_eq!(ix.program_id, CLOCKWORK_THREAD_ID);
+        assert_eq!(&ix.data[..8], &THREAD_CREATE_DISCRIMINATOR);
+        assert_eq!(&ix.data[8..16], &5_000u64.to_le_bytes());
+        assert_eq!(&ix.data[16..20], &32u32.to_le_bytes());
+        assert_eq!(&ix.data[20..52], escrow.as_ref());
+
+        let account = &ix.data[52 + 4 + 32 + 4..][..34];
+        assert_eq!(&account[..32], PAYER_PUBKEY.as_ref());
+        assert_eq!(&account[32..], &[1, 1]);
+        let tail = &ix.data[ix.data.len() - 16..];
+        assert_eq!(tail, [&3u32.to_le_bytes()[..], &[9, 8, 7, TIMESTAMP_TRIGGER], &1_700_000_000i64.to_le_bytes()].concat());
+    }
+
+    #[test]
+    fn threads_are_seeded_by_the_escrow() {
+        let escrow = Pubkey::new_unique();
+        let create = thread_create_instruction(escrow, Pubkey::new_unique(), 0, &Instruction::new_with_bytes(crate::ID, &[], vec![]), 0);
+        let delete = thread_delete_instruction(escrow, Pubkey::new_unique());
+        assert_eq!(create.accounts[3].pubkey, thread_address(&escrow));
+        assert_eq!(delete.accounts[2].pubkey, thread_address(&escrow));
+        assert_ne!(thread_address(&escrow), thread_address(&Pubkey::new_unique()));
+        assert!(create.accounts[0].is_signer && delete.accounts[0].is_signer);
+        assert_eq!(delete.data, THREAD_DELETE_DISCRIMINATOR);
+    }
+}
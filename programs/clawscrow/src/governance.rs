@@ -0,0 +1,258 @@
+//! Creates and reads SPL Governance proposals for DAO-escalated disputes.
+//!
+//! An escalated dispute becomes a two-option ("Buyer", "Seller") proposal
+//! in the configured realm, owned by a token owner record whose governance
+//! delegate is the program's `governance_authority` PDA. The
+//! `spl-governance` crate pins an older `solana-program` than Anchor 0.30
+//! does, so the two instructions and the proposal account are encoded by
+//! hand: both are plain Borsh.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+
+use crate::{ClawscrowError, DaoEscalation};
+
+pub const PROPOSAL_SEED: &[u8] = b"governance";
+pub const REALM_CONFIG_SEED: &[u8] = b"realm-config";
+pub const PROPOSAL_DEPOSIT_SEED: &[u8] = b"proposal-deposit";
+
+/// `GovernanceInstruction::CreateProposal`.
+const CREATE_PROPOSAL_TAG: u8 = 6;
+/// `GovernanceInstruction::SignOffProposal`.
+const SIGN_OFF_PROPOSAL_TAG: u8 = 12;
+/// `GovernanceAccountType::ProposalV2`.
+const PROPOSAL_V2: u8 = 14;
+/// `OptionVoteResult::Succeeded`.
+const OPTION_SUCCEEDED: u8 = 1;
+/// Option labels; the index of the winning option is the ruling.
+pub const OPTIONS: [&str; 2] = ["Buyer", "Seller"];
+
+/// Where the proposal for `escrow` lives; the escrow address is the seed.
+pub fn proposal_address(dao: &DaoEscalation, escrow: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[PROPOSAL_SEED, dao.governance.as_ref(), dao.governing_token_mint.as_ref(), escrow.as_ref()],
+        &dao.program,
+    )
+    .0
+}
+
+pub fn realm_config_address(dao: &DaoEscalation) -> Pubkey {
+    Pubkey::find_program_address(&[REALM_CONFIG_SEED, dao.realm.as_ref()], &dao.program).0
+}
+
+/// Holds the realm's proposal deposit until `payer` reclaims it.
+pub fn proposal_deposit_address(dao: &DaoEscalation, proposal: &Pubkey, payer: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[PROPOSAL_DEPOSIT_SEED, proposal.as_ref(), payer.as_ref()], &dao.program).0
+}
+
+/// `CreateProposal` for a single-choice vote between `OPTIONS`, with no deny
+/// option. `authority` is the owner record's governance delegate.
+pub fn create_proposal_instruction(
+    dao: &DaoEscalation,
+    escrow: Pubkey,
+    authority: Pubkey,
+    payer: Pubkey,
+    name: &str,
+) -> Instruction {
+    let mut data = vec![CREATE_PROPOSAL_TAG];
+    push_string(&mut data, name);
+    // description_link
+    push_string(&mut data, &escrow.to_string());
+    // VoteType::MultiChoice { FullWeight, min 1, max 1, max winning 1 }
+    data.extend_from_slice(&[1, 0, 1, 1, 1]);
+    data.extend_from_slice(&(OPTIONS.len() as u32).to_le_bytes());
+    for option in OPTIONS {
+        push_string(&mut data, option);
+    }
+    // use_deny_option
+    data.push(0);
+    data.extend_from_slice(escrow.as_ref());
+
+    let proposal = proposal_address(dao, &escrow);
+    Instruction {
+        program_id: dao.program,
+        accounts: vec![
+            AccountMeta::new_readonly(dao.realm, false),
+            AccountMeta::new(proposal, false),
+            AccountMeta::new(dao.governance, false),
+            AccountMeta::new(dao.proposal_owner_record, false),
+            AccountMeta::new_readonly(dao.governing_token_mint, false),
+            AccountMeta::new_readonly(authority, true),
+            AccountMeta::new(payer, true),
+            AccountMeta::new_readonly(anchor_lang::system_program::ID, false),
+            AccountMeta::new_readonly(realm_config_address(dao), false),
+            AccountMeta::new(proposal_deposit_address(dao, &proposal, &payer), false),
+        ],
+        data,
+    }
+}
+
+/// `SignOffProposal` by the proposal owner's delegate, which opens voting.
+pub fn sign_off_instruction(dao: &DaoEscalation, proposal: Pubkey, authority: Pubkey) -> Instruction {
+    Instruction {
+        program_id: dao.program,
+        accounts: vec![
+            AccountMeta::new_readonly(dao.realm, false),
+            AccountMeta::new_readonly(dao.governance, false),
+            AccountMeta::new(proposal, false),
+            AccountMeta::new_readonly(authority, true),
+            AccountMeta::new_readonly(dao.proposal_owner_record, false),
+        ],
+        data: vec![SIGN_OFF_PROPOSAL_TAG],
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProposalOutcome {
+    /// Still in draft or voting.
+    Pending,
+    /// Index into `OPTIONS` of the single option that passed.
+    Winner(u8),
+    /// Voting ended without exactly one passing option, or was vetoed.
+    NoDecision,
+}
+
+/// The outcome of the proposal at `info`, which must be owned by `program`.
+pub fn read_outcome(info: &AccountInfo, program: &Pubkey) -> Result<ProposalOutcome> {
+    require_keys_eq!(*info.owner, *program, ClawscrowError::InvalidGovernanceAccount);
+    let data = info.try_borrow_data()?;
+    let outcome = parse_outcome(&data).ok_or(ClawscrowError::InvalidGovernanceAccount)?;
+    require!(outcome != ProposalOutcome::Pending, ClawscrowError::ProposalNotFinal);
+    Ok(outcome)
+}
+
+/// Decodes a `ProposalV2` up to its options, or `None` for anything else.
+fn parse_outcome(data: &[u8]) -> Option<ProposalOutcome> {
+    let mut r = Reader(data);
+    if r.u8()? != PROPOSAL_V2 {
+        return None;
+    }
+    // governance, governing_token_mint
+    r.skip(64)?;
+    let state = r.u8()?;
+    // token_owner_record, signatories_count, signatories_signed_off_count
+    r.skip(34)?;
+    match r.u8()? {
+        0 => {}
+        1 => r.skip(4)?,
+        _ => return None,
+    }
+    let mut winners = Vec::new();
+    for index in 0..r.u32()? {
+        let label_len = r.u32()? as usize;
+        // label, vote_weight
+        r.skip(label_len + 8)?;
+        if r.u8()? == OPTION_SUCCEEDED {
+            winners.push(index);
+        }
+        // transactions_executed_count, transactions_count, transactions_next_index
+        r.skip(6)?;
+    }
+    Some(match state {
+        // Succeeded, Completed
+        3 | 5 => match winners[..] {
+            [index] => u8::try_from(index).map_or(ProposalOutcome::NoDecision, ProposalOutcome::Winner),
+            _ => ProposalOutcome::NoDecision,
+        },
+        // Cancelled, Defeated, Vetoed
+        6 | 7 | 9 => ProposalOutcome::NoDecision,
+        _ => ProposalOutcome::Pending,
+    })
+}
+
+fn push_string(data: &mut Vec<u8>, s: &str) {
+    data.extend_from_slice(&(s.len() as u32).to_le_bytes());
+    data.extend_from_slice(s.as_bytes());
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn skip(&mut self, n: usize) -> Option<()> {
+        self.0 = self.0.get(n..)?;
+        Some(())
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        let (first, rest) = self.0.split_first()?;
+        self.0 = rest;
+        Some(*first)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        let bytes = self.0.get(..4)?.try_into().ok()?;
+        self.0 = &self.0[4..];
+        Some(u32::from_le_bytes(bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A `ProposalV2` head as SPL Governance writes it, with one entry per
+    /// option result; the fields after the options are irrelevant here.
+    fn proposal_account(state: u8, results: &[u8]) -> Vec<u8> {
+        let mut data = vec![PROPOSAL_V2];
+        data.extend_from_slice(&[0; 64]);
+        data.push(state);
+        data.extend_from_slice(&[0; 34]);
+        data.extend_from_slice(&[1, 0, 1, 1, 1]);
+        data.extend_from_slice(&(results.len() as u32).to_le_bytes());
+        for (label, result) in OPTIONS.iter().zip(results) {
+            push_string(&mut data, label);
+            data.extend_from_slice(&7u64.to_le_bytes());
+            data.push(*result);
+            data.extend_from_slice(&[0; 6]);
+        }
+        data.extend_from_slice(&[0; 16]);
+        data
+    }
+
+    fn dao() -> DaoEscalation {
+        DaoEscalation {
+            program: Pubkey::new_unique(),
+            realm: Pubkey::new_unique(),
+            governance: Pubkey::new_unique(),
+            governing_token_mint: Pubkey::new_unique(),
+            proposal_owner_record: Pubkey::new_unique(),
+            min_pool: 1,
+        }
+    }
+
+    #[test]
+    fn reads_the_single_winning_option() {
+        assert_eq!(parse_outcome(&proposal_account(3, &[2, 1])), Some(ProposalOutcome::Winner(1)));
+        assert_eq!(parse_outcome(&proposal_account(5, &[1, 2])), Some(ProposalOutcome::Winner(0)));
+    }
+
+    #[test]
+    fn finished_votes_without_one_winner_are_undecided() {
+        assert_eq!(parse_outcome(&proposal_account(3, &[1, 1])), Some(ProposalOutcome::NoDecision));
+        assert_eq!(parse_outcome(&proposal_account(7, &[2, 2])), Some(ProposalOutcome::NoDecision));
+        assert_eq!(parse_outcome(&proposal_account(9, &[0, 0])), Some(ProposalOutcome::NoDecision));
+        assert_eq!(parse_outcome(&proposal_account(2, &[0, 0])), Some(ProposalOutcome::Pending));
+    }
+
+    #[test]
+    fn rejects_other_accounts() {
+        let mut data = proposal_account(3, &[1, 2]);
+        assert_eq!(parse_outcome(&data[..80]), None);
+        data[0] = 5;
+        assert_eq!(parse_outcome(&data), None);
+    }
+
+    #[test]
+    fn create_proposal_is_seeded_by_the_escrow() {
+        let dao = dao();
+        let (escrow, authority, payer) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let ix = create_proposal_instruction(&dao, escrow, authority, payer, "Clawscrow dispute #1");
+        let proposal = proposal_address(&dao, &escrow);
+        assert_eq!(ix.program_id, dao.program);
+        assert_eq!(ix.data[0], CREATE_PROPOSAL_TAG);
+        assert_eq!(&ix.data[ix.data.len() - 32..], escrow.as_ref());
+        assert_eq!(ix.accounts[1].pubkey, proposal);
+        assert!(ix.accounts[5].is_signer && ix.accounts[6].is_signer);
+        assert_eq!(ix.accounts[9].pubkey, proposal_deposit_address(&dao, &proposal, &payer));
+    }
+}
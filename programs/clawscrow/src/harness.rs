@@ -0,0 +1,131 @@
+//! Deterministic hooks for LiteSVM tests and the fuzzing harness.
+//!
+//! Anchor cannot conditionally compile instructions, so the accounts below
+//! always exist, but the instructions that write them fail with
+//! `HarnessDisabled` and the clock override is compiled out unless the
+//! `test-harness` feature is on. A deployed program never reads a `TestClock`.
+//!
+//! - `set_test_clock` pins "now" for any handler that is passed the
+//!   `TestClock` PDA in its remaining accounts.
+//! - `seed_escrow_counter` sets a buyer's next escrow ID.
+//! - [`funded_token_account`] builds token account data that tests can inject
+//!   directly instead of minting and transferring.
+
+use anchor_lang::prelude::*;
+#[cfg(feature = "test-harness")]
+use anchor_lang::solana_program::{program_option::COption, program_pack::Pack};
+#[cfg(feature = "test-harness")]
+use anchor_spl::token::spl_token::state::{Account as SplTokenAccount, AccountState};
+
+use crate::Counter;
+
+pub const TEST_CLOCK_SEED: &[u8] = b"test_clock";
+
+/// Overrides the sysvar clock for handlers that are passed this account.
+#[account]
+#[derive(InitSpace)]
+pub struct TestClock {
+    pub unix_timestamp: i64,
+    pub bump: u8,
+}
+
+pub fn test_clock_address() -> Pubkey {
+    Pubkey::find_program_address(&[TEST_CLOCK_SEED], &crate::ID).0
+}
+
+/// The pinned time, if a `TestClock` owned by this program is among `accounts`.
+#[cfg(feature = "test-harness")]
+pub fn clock_override(accounts: &[AccountInfo]) -> Result<Option<i64>> {
+    let address = test_clock_address();
+    let Some(info) = accounts.iter().find(|a| a.key == &address && a.owner == &crate::ID) else {
+        return Ok(None);
+    };
+    let data = info.try_borrow_data()?;
+    let clock = TestClock::try_deserialize(&mut &data[..])?;
+    Ok(Some(clock.unix_timestamp))
+}
+
+/// Packed SPL token account holding `amount` of `mint` for `owner`.
+#[cfg(feature = "test-harness")]
+pub fn funded_token_account(mint: Pubkey, owner: Pubkey, amount: u64) -> Vec<u8> {
+    let account = SplTokenAccount {
+        mint,
+        owner,
+        amount,
+        delegate: COption::None,
+        state: AccountState::Initialized,
+        is_native: COption::None,
+        delegated_amount: 0,
+        close_authority: COption::None,
+    };
+    let mut data = vec![0u8; SplTokenAccount::LEN];
+    SplTokenAccount::pack(account, &mut data).expect("buffer is LEN bytes");
+    data
+}
+
+#[derive(Accounts)]
+pub struct SetTestClock<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + TestClock::INIT_SPACE,
+        seeds = [TEST_CLOCK_SEED],
+        bump,
+    )]
+    pub test_clock: Account<'info, TestClock>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SeedEscrowCounter<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + Counter::INIT_SPACE,
+        seeds = [b"counter", buyer.key().as_ref()],
+        bump,
+    )]
+    pub counter: Account<'info, Counter>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[cfg(all(test, feature = "test-harness"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn funded_token_account_round_trips() {
+        let mint = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let data = funded_token_account(mint, owner, 1_150_000);
+        let account = SplTokenAccount::unpack(&data).unwrap();
+        assert_eq!(account.mint, mint);
+        assert_eq!(account.owner, owner);
+        assert_eq!(account.amount, 1_150_000);
+    }
+
+    #[test]
+    fn clock_override_ignores_foreign_accounts() {
+        let key = test_clock_address();
+        let other_owner = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = Vec::new();
+        TestClock { unix_timestamp: 42, bump: 0 }.try_serialize(&mut data).unwrap();
+        let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &other_owner, false, 0);
+        assert_eq!(clock_override(&[info]).unwrap(), None);
+
+        let mut lamports = 0;
+        let mut data = Vec::new();
+        TestClock { unix_timestamp: 42, bump: 0 }.try_serialize(&mut data).unwrap();
+        let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &crate::ID, false, 0);
+        assert_eq!(clock_override(&[info]).unwrap(), Some(42));
+    }
+}
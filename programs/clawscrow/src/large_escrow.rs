@@ -0,0 +1,147 @@
+//! Zero-copy escrow state for large jobs.
+//!
+//! `Escrow` is Borsh-encoded, so every instruction pays to decode and
+//! re-encode the whole account, description and milestone list included.
+//! `LargeEscrow` holds the same core terms in fixed-size arrays behind
+//! `#[account(zero_copy)]`, so handlers can load it in place and touch only
+//! the fields they need. Options become flags, enums become their variant
+//! index, and the padding is explicit so the layout is `Pod`.
+
+use anchor_lang::prelude::*;
+
+use crate::{Escrow, MilestoneStatus, MAX_DELIVERIES, MAX_DESCRIPTION_BYTES, MAX_MILESTONES};
+
+#[zero_copy]
+#[derive(Default)]
+pub struct LargeMilestone {
+    pub amount: u64,
+    pub delivery_hash: [u8; 32],
+    /// `MilestoneStatus` variant index.
+    pub status: u8,
+    pub _padding: [u8; 7],
+}
+
+#[zero_copy]
+#[derive(Default)]
+pub struct LargeDelivery {
+    pub delivery_hash: [u8; 32],
+    pub delivered_at: i64,
+}
+
+#[account(zero_copy)]
+pub struct LargeEscrow {
+    pub escrow_id: u64,
+    pub payment_amount: u64,
+    pub buyer_collateral: u64,
+    pub seller_collateral: u64,
+    pub released_amount: u64,
+    pub deadline_ts: i64,
+    pub created_at: i64,
+    pub delivered_at: i64,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub arbitrator: Pubkey,
+    pub mint: Pubkey,
+    pub delivery_hash: [u8; 32],
+    pub milestones: [LargeMilestone; MAX_MILESTONES],
+    /// Oldest first; only the first `delivery_count` entries are set.
+    pub deliveries: [LargeDelivery; MAX_DELIVERIES],
+    pub description: [u8; MAX_DESCRIPTION_BYTES],
+    pub description_len: u16,
+    /// `EscrowState` variant index, as at `layout::STATE_OFFSET` in `Escrow`.
+    pub state: u8,
+    pub milestone_count: u8,
+    pub delivery_count: u8,
+    pub bump: u8,
+    pub vault_bump: u8,
+    pub _padding: [u8; 1],
+}
+
+impl LargeEscrow {
+    /// Mirror the core terms of a Borsh escrow into this account.
+    pub fn copy_from(&mut self, escrow: &Escrow) {
+        self.escrow_id = escrow.escrow_id;
+        self.payment_amount = escrow.payment_amount;
+        self.buyer_collateral = escrow.buyer_collateral;
+        self.seller_collateral = escrow.seller_collateral;
+        self.released_amount = escrow.released_amount;
+        self.deadline_ts = escrow.deadline_ts;
+        self.created_at = escrow.created_at;
+        self.delivered_at = escrow.delivered_at;
+        self.buyer = escrow.buyer;
+        self.seller = escrow.seller;
+        self.arbitrator = escrow.arbitrator;
+        self.mint = escrow.mint;
+        self.delivery_hash = escrow.delivery_hash;
+        self.state = escrow.state.clone() as u8;
+        self.bump = escrow.bump;
+        self.vault_bump = escrow.vault_bump;
+
+        self.milestones = Default::default();
+        for (slot, milestone) in self.milestones.iter_mut().zip(&escrow.milestones) {
+            slot.amount = milestone.amount;
+            slot.delivery_hash = milestone.delivery_hash;
+            slot.status = milestone.status.clone() as u8;
+        }
+        self.milestone_count = escrow.milestones.len().min(MAX_MILESTONES) as u8;
+
+        self.deliveries = Default::default();
+        for (slot, record) in self.deliveries.iter_mut().zip(&escrow.deliveries) {
+            slot.delivery_hash = record.delivery_hash;
+            slot.delivered_at = record.delivered_at;
+        }
+        self.delivery_count = escrow.deliveries.len().min(MAX_DELIVERIES) as u8;
+
+        let bytes = escrow.description.as_bytes();
+        let len = bytes.len().min(MAX_DESCRIPTION_BYTES);
+        self.description = [0; MAX_DESCRIPTION_BYTES];
+        self.description[..len].copy_from_slice(&bytes[..len]);
+        self.description_len = len as u16;
+    }
+
+    pub fn description(&self) -> &str {
+        let bytes = &self.description[..self.description_len as usize];
+        std::str::from_utf8(bytes).unwrap_or_default()
+    }
+
+    pub fn milestones(&self) -> &[LargeMilestone] {
+        &self.milestones[..self.milestone_count as usize]
+    }
+
+    pub fn deliveries(&self) -> &[LargeDelivery] {
+        &self.deliveries[..self.delivery_count as usize]
+    }
+
+    pub fn milestone_approved(&self, index: usize) -> bool {
+        self.milestones().get(index).is_some_and(|m| m.status == MilestoneStatus::Approved as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layout_has_no_implicit_padding() {
+        assert_eq!(std::mem::size_of::<LargeMilestone>(), 48);
+        assert_eq!(std::mem::size_of::<LargeDelivery>(), 40);
+        let fields = 8 * 8
+            + 5 * 32
+            + MAX_MILESTONES * 48
+            + MAX_DELIVERIES * 40
+            + MAX_DESCRIPTION_BYTES
+            + 8;
+        assert_eq!(std::mem::size_of::<LargeEscrow>(), fields);
+    }
+
+    #[test]
+    fn description_round_trips() {
+        let mut large: LargeEscrow = bytemuck::Zeroable::zeroed();
+        let text = "Write a haiku about lobsters 🦞";
+        large.description[..text.len()].copy_from_slice(text.as_bytes());
+        large.description_len = text.len() as u16;
+        assert_eq!(large.description(), text);
+        assert!(large.milestones().is_empty());
+        assert!(!large.milestone_approved(0));
+    }
+}
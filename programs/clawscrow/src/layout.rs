@@ -0,0 +1,322 @@
+//! Byte offsets of the fixed-size `Escrow` fields, for `getProgramAccounts`
+//! memcmp filters.
+//!
+//! Every filterable field sits ahead of the variable-length description, so
+//! these offsets hold for any escrow whose `layout_version` byte equals
+//! [`ESCROW_LAYOUT_VERSION`](crate::ESCROW_LAYOUT_VERSION). Indexers should
+//! filter on that byte too; v1 accounts put `state` after the description.
+//!
+//! It also records which fields each older layout version wrote, for
+//! `migrate_escrow`.
+
+use anchor_lang::prelude::*;
+
+use crate::{
+    Appeal, ArbitrationPanel, ArbitrationSla, AssetDeliverable, ClawscrowError, DeliveryRecord, EarlyBonus,
+    EscrowState, HeartbeatPolicy, Milestone, OffchainContract, PaymentLeg, Referral, Ruling, Timesheet, UsdPeg,
+    VestingSchedule,
+};
+
+/// Anchor's account discriminator.
+pub const DISCRIMINATOR_LEN: usize = 8;
+pub const LAYOUT_VERSION_OFFSET: usize = DISCRIMINATOR_LEN;
+pub const GENESIS_SLOT_OFFSET: usize = LAYOUT_VERSION_OFFSET + 1;
+pub const ESCROW_ID_OFFSET: usize = GENESIS_SLOT_OFFSET + 8;
+pub const BUYER_OFFSET: usize = ESCROW_ID_OFFSET + 8;
+pub const SELLER_OFFSET: usize = BUYER_OFFSET + 32;
+pub const ARBITRATOR_OFFSET: usize = SELLER_OFFSET + 32;
+pub const MINT_OFFSET: usize = ARBITRATOR_OFFSET + 32;
+/// One byte: the `EscrowState` variant index.
+pub const STATE_OFFSET: usize = MINT_OFFSET + 32;
+pub const PAYMENT_AMOUNT_OFFSET: usize = STATE_OFFSET + 1;
+pub const BUYER_COLLATERAL_OFFSET: usize = PAYMENT_AMOUNT_OFFSET + 8;
+pub const SELLER_COLLATERAL_OFFSET: usize = BUYER_COLLATERAL_OFFSET + 8;
+pub const DECIMALS_OFFSET: usize = SELLER_COLLATERAL_OFFSET + 8;
+pub const DEADLINE_TS_OFFSET: usize = DECIMALS_OFFSET + 1;
+/// Start of the description's `u32` length prefix; nothing after it has a fixed offset.
+pub const DESCRIPTION_OFFSET: usize = DEADLINE_TS_OFFSET + 8;
+
+/// Where layout version 1 put the description, before `state` moved ahead of it.
+pub const V1_DESCRIPTION_OFFSET: usize = 186;
+
+/// Rewrite a version 1 escrow in place: its `state` byte followed the
+/// description, so move it back to [`STATE_OFFSET`]. Nothing else moves.
+pub fn migrate_v1(data: &mut [u8]) -> Result<()> {
+    let len_bytes = data
+        .get(V1_DESCRIPTION_OFFSET..V1_DESCRIPTION_OFFSET + 4)
+        .ok_or(ClawscrowError::UnsupportedLayout)?;
+    let description_len = u32::from_le_bytes(len_bytes.try_into().unwrap()) as usize;
+    let state_at = V1_DESCRIPTION_OFFSET + 4 + description_len;
+    let moved = data
+        .get_mut(STATE_OFFSET..=state_at)
+        .ok_or(ClawscrowError::UnsupportedLayout)?;
+    moved.rotate_right(1);
+    Ok(())
+}
+
+/// Number of `Escrow` fields, counting from `layout_version`, each layout
+/// version serialized. Since v2 a new version only appends fields, so an
+/// older escrow is a prefix of the current layout.
+const FIELD_COUNTS: [(u8, usize); 20] = [
+    (2, 57),
+    (3, 59),
+    (4, 60),
+    (5, 61),
+    (6, 62),
+    (7, 63),
+    (8, 64),
+    (9, 66),
+    (10, 68),
+    (11, 69),
+    (12, 71),
+    (13, 72),
+    (14, 73),
+    (15, 74),
+    (16, 76),
+    (17, 77),
+    (18, 79),
+    (19, 80),
+    (20, 81),
+    (21, 82),
+];
+
+type FieldReader = fn(&mut &[u8]) -> std::io::Result<()>;
+
+/// Readers for `Escrow`'s fields in declaration order, used to find where an
+/// older layout's data ends.
+const ESCROW_FIELDS: [FieldReader; 82] = [
+    // v2 (and v1 once `migrate_v1` has moved its state byte)
+    skip::<u8>, // layout_version
+    skip::<u64>, // genesis_slot
+    skip::<u64>, // escrow_id
+    skip::<Pubkey>, // buyer
+    skip::<Pubkey>, // seller
+    skip::<Pubkey>, // arbitrator
+    skip::<Pubkey>, // mint
+    skip::<EscrowState>, // state
+    skip::<u64>, // payment_amount
+    skip::<u64>, // buyer_collateral
+    skip::<u64>, // seller_collateral
+    skip::<u8>, // decimals
+    skip::<i64>, // deadline_ts
+    skip::<String>, // description
+    skip::<[u8; 32]>, // delivery_hash
+    skip::<i64>, // created_at
+    skip::<i64>, // delivered_at
+    skip::<i64>, // acknowledged_at
+    skip::<Option<[u8; 32]>>, // feedback_hash
+    skip::<Option<u8>>, // satisfaction
+    skip::<Option<Pubkey>>, // cancel_proposed_by
+    skip::<Option<u64>>, // predecessor_id
+    skip::<Option<u64>>, // successor_id
+    skip::<Vec<Milestone>>, // milestones
+    skip::<u64>, // released_amount
+    skip::<Option<ArbitrationSla>>, // arbitration_sla
+    skip::<i64>, // disputed_at
+    skip::<Option<Pubkey>>, // platform
+    skip::<u16>, // protocol_fee_bps
+    skip::<Option<Ruling>>, // pending_ruling
+    skip::<i64>, // ruled_at
+    skip::<u64>, // arbitrator_fee
+    skip::<Option<Appeal>>, // appeal
+    skip::<[u8; 32]>, // terms_hash
+    skip::<Option<HeartbeatPolicy>>, // heartbeat_policy
+    skip::<i64>, // last_heartbeat_at
+    skip::<Option<i64>>, // accept_by_ts
+    skip::<i64>, // vault_discrepancy
+    skip::<Option<Pubkey>>, // buyer_payout
+    skip::<Option<Pubkey>>, // seller_payout
+    skip::<bool>, // arbitrator_accepted
+    skip::<Option<ArbitrationPanel>>, // panel
+    skip::<i64>, // arbitration_deadline
+    skip::<u8>, // revisions
+    skip::<Option<i64>>, // vesting_secs
+    skip::<Option<VestingSchedule>>, // vesting
+    skip::<i64>, // review_period_secs
+    skip::<Pubkey>, // buyer_payout_token
+    skip::<Pubkey>, // seller_payout_token
+    skip::<u16>, // crank_tip_bps
+    skip::<Option<u64>>, // pending_amendment
+    skip::<Option<OffchainContract>>, // contract
+    skip::<Option<String>>, // delivery_uri
+    skip::<Vec<DeliveryRecord>>, // deliveries
+    skip::<u64>, // dispute_bond
+    skip::<u8>, // bump
+    skip::<u8>, // vault_bump
+    // v3
+    skip::<i64>, // accepted_at
+    skip::<i64>, // resolved_at
+    // v4
+    skip::<Option<Pubkey>>, // funding_source
+    // v5
+    skip::<u64>, // funded_amount
+    // v6
+    skip::<bool>, // has_payout_split
+    // v7
+    skip::<Option<Pubkey>>, // buyer_authority
+    // v8
+    skip::<Pubkey>, // original_buyer
+    // v9
+    skip::<Option<Pubkey>>, // backup_arbitrator
+    skip::<bool>, // ruled_by_backup
+    // v10
+    skip::<bool>, // insured
+    skip::<bool>, // insurance_claimed
+    // v11
+    skip::<bool>, // completion_badge
+    // v12
+    skip::<bool>, // compressed_receipts
+    skip::<bool>, // receipts_minted
+    // v13
+    skip::<Option<AssetDeliverable>>, // deliverable
+    // v14
+    skip::<Option<PaymentLeg>>, // second_leg
+    // v15
+    skip::<Option<UsdPeg>>, // usd_peg
+    // v16
+    skip::<Option<EarlyBonus>>, // early_bonus
+    skip::<bool>, // early_bonus_settled
+    // v17
+    skip::<Option<u64>>, // discount_offer
+    // v18
+    skip::<Option<u64>>, // hourly_rate
+    skip::<Option<Timesheet>>, // pending_timesheet
+    // v19
+    skip::<Option<Referral>>, // referral
+    // v20
+    skip::<bool>, // trusted_pair
+    // v21
+    skip::<bool>, // holds_open_slot
+];
+
+fn skip<T: AnchorDeserialize>(buf: &mut &[u8]) -> std::io::Result<()> {
+    T::deserialize(buf).map(drop)
+}
+
+/// Bytes an escrow at `version` occupies, discriminator included.
+pub fn serialized_len(data: &[u8], version: u8) -> Result<usize> {
+    let fields = FIELD_COUNTS
+        .iter()
+        .find(|(v, _)| *v == version)
+        .map(|(_, count)| *count)
+        .ok_or(ClawscrowError::UnsupportedLayout)?;
+    let mut buf = data.get(DISCRIMINATOR_LEN..).ok_or(ClawscrowError::UnsupportedLayout)?;
+    for read in &ESCROW_FIELDS[..fields] {
+        read(&mut buf).map_err(|_| ClawscrowError::UnsupportedLayout)?;
+    }
+    Ok(data.len() - buf.len())
+}
+
+/// Zero everything after an escrow's `version` data. Anchor rewrites accounts
+/// in place without clearing what follows, so a field that shrank (an
+/// `Option` set back to `None`, a shorter URI) leaves stale bytes exactly
+/// where the fields appended since `version` are read from.
+pub fn clear_tail(data: &mut [u8], version: u8) -> Result<()> {
+    let len = serialized_len(data, version)?;
+    data[len..].fill(0);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Escrow, ESCROW_LAYOUT_VERSION};
+
+    fn blank_escrow() -> Escrow {
+        let data = vec![0; 8 + Escrow::INIT_SPACE];
+        Escrow::try_deserialize_unchecked(&mut &data[..]).unwrap()
+    }
+
+    /// `escrow` as a program at `version` would have written it over `data`.
+    fn write_as(escrow: &Escrow, version: u8, data: &mut [u8]) {
+        let mut bytes = Vec::new();
+        escrow.try_serialize(&mut bytes).unwrap();
+        let len = serialized_len(&bytes, version).unwrap();
+        data[..len].copy_from_slice(&bytes[..len]);
+    }
+
+    #[test]
+    fn offsets_match_published_layout() {
+        assert_eq!(ESCROW_ID_OFFSET, 17);
+        assert_eq!(BUYER_OFFSET, 25);
+        assert_eq!(MINT_OFFSET, 121);
+        assert_eq!(STATE_OFFSET, 153);
+        assert_eq!(DESCRIPTION_OFFSET, 187);
+    }
+
+    #[test]
+    fn state_is_one_byte() {
+        assert_eq!(EscrowState::Vesting.try_to_vec().unwrap().len(), 1);
+        assert_eq!(EscrowState::Created.try_to_vec().unwrap(), vec![0]);
+    }
+
+    #[test]
+    fn migrate_v1_moves_state_ahead_of_description() {
+        let description = b"Write a haiku";
+        let mut v1 = vec![7u8; STATE_OFFSET];
+        v1.extend_from_slice(&[1; 33]); // payment through deadline
+        v1.extend_from_slice(&(description.len() as u32).to_le_bytes());
+        v1.extend_from_slice(description);
+        v1.push(4); // Disputed
+        v1.extend_from_slice(&[9; 32]); // delivery hash
+
+        let mut data = v1.clone();
+        migrate_v1(&mut data).unwrap();
+        assert_eq!(data.len(), v1.len());
+        assert_eq!(data[..STATE_OFFSET], v1[..STATE_OFFSET]);
+        assert_eq!(data[STATE_OFFSET], 4);
+        assert_eq!(data[PAYMENT_AMOUNT_OFFSET..DESCRIPTION_OFFSET], [1; 33]);
+        let text = DESCRIPTION_OFFSET + 4;
+        assert_eq!(&data[text..text + description.len()], description);
+        assert_eq!(data[text + description.len()..], [9; 32]);
+    }
+
+    #[test]
+    fn migrate_v1_rejects_truncated_accounts() {
+        let mut data = vec![0u8; V1_DESCRIPTION_OFFSET + 4];
+        data[V1_DESCRIPTION_OFFSET] = 10;
+        assert!(migrate_v1(&mut data).is_err());
+    }
+
+    #[test]
+    fn field_readers_cover_the_current_layout() {
+        let mut escrow = blank_escrow();
+        escrow.description = "Write a haiku".to_string();
+        escrow.cancel_proposed_by = Some(Pubkey::new_unique());
+        escrow.delivery_uri = Some("ar://haiku".to_string());
+        escrow.referral = Some(Referral { referrer: Pubkey::new_unique(), fee_bps: 100 });
+        escrow.trusted_pair = true;
+        let mut bytes = Vec::new();
+        escrow.try_serialize(&mut bytes).unwrap();
+        assert_eq!(FIELD_COUNTS.last(), Some(&(ESCROW_LAYOUT_VERSION, ESCROW_FIELDS.len())));
+        assert_eq!(serialized_len(&bytes, ESCROW_LAYOUT_VERSION).unwrap(), bytes.len());
+    }
+
+    #[test]
+    fn clear_tail_drops_bytes_left_by_a_cleared_option() {
+        let mut escrow = blank_escrow();
+        escrow.layout_version = 19;
+        escrow.cancel_proposed_by = Some(Pubkey::new_unique());
+        let referrer = Pubkey::new_from_array([0xff; 32]);
+        escrow.referral = Some(Referral { referrer, fee_bps: u16::MAX });
+        let mut data = vec![0u8; 8 + Escrow::INIT_SPACE];
+        write_as(&escrow, 19, &mut data);
+        // Withdrawing the cancel proposal shifts everything after it back 32 bytes.
+        escrow.cancel_proposed_by = None;
+        write_as(&escrow, 19, &mut data);
+        assert!(Escrow::try_deserialize(&mut &data[..]).is_err(), "stale referral byte read as trusted_pair");
+
+        clear_tail(&mut data, 19).unwrap();
+        let migrated = Escrow::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!(migrated.cancel_proposed_by, None);
+        assert_eq!(migrated.referral.map(|r| (r.referrer, r.fee_bps)), Some((referrer, u16::MAX)));
+        assert!(!migrated.trusted_pair);
+    }
+
+    #[test]
+    fn clear_tail_rejects_unknown_versions() {
+        let mut data = vec![0u8; 8 + Escrow::INIT_SPACE];
+        assert!(clear_tail(&mut data, 0).is_err());
+        assert!(clear_tail(&mut data, ESCROW_LAYOUT_VERSION + 1).is_err());
+    }
+}
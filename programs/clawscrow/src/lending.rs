@@ -0,0 +1,127 @@
+//! Deposits escrow vaults into SPL token-lending reserves for yield.
+//!
+//! Whitelisted reserves speak the SPL token-lending interface (as do its
+//! forks such as Solend/Save): liquidity goes in with
+//! `DepositReserveLiquidity` for reserve collateral tokens and comes back
+//! with `RedeemReserveCollateral`. Both require the reserve to have been
+//! refreshed in the same slot, so callers put the lending program's
+//! `RefreshReserve` ahead of ours in the transaction. The lending crates pin
+//! an older `solana-program` than Anchor 0.30 does, so the two instructions
+//! are encoded by hand.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::sysvar;
+
+use crate::LendingTarget;
+
+/// `LendingInstruction::DepositReserveLiquidity`.
+const DEPOSIT_RESERVE_LIQUIDITY_TAG: u8 = 4;
+/// `LendingInstruction::RedeemReserveCollateral`.
+const REDEEM_RESERVE_COLLATERAL_TAG: u8 = 5;
+
+/// The lending market's PDA authority over its reserves' token accounts.
+pub fn market_authority(target: &LendingTarget) -> Pubkey {
+    Pubkey::find_program_address(&[target.lending_market.as_ref()], &target.program).0
+}
+
+/// Deposit `amount` of liquidity from `source` for collateral tokens paid
+/// into `collateral`; `authority` must be able to move `source`.
+pub fn deposit_instruction(
+    target: &LendingTarget,
+    source: Pubkey,
+    collateral: Pubkey,
+    authority: Pubkey,
+    amount: u64,
+) -> Instruction {
+    let mut data = vec![DEPOSIT_RESERVE_LIQUIDITY_TAG];
+    data.extend_from_slice(&amount.to_le_bytes());
+    Instruction {
+        program_id: target.program,
+        accounts: vec![
+            AccountMeta::new(source, false),
+            AccountMeta::new(collateral, false),
+            AccountMeta::new(target.reserve, false),
+            AccountMeta::new(target.liquidity_supply, false),
+            AccountMeta::new(target.collateral_mint, false),
+            AccountMeta::new_readonly(target.lending_market, false),
+            AccountMeta::new_readonly(market_authority(target), false),
+            AccountMeta::new_readonly(authority, true),
+            AccountMeta::new_readonly(sysvar::clock::ID, false),
+            AccountMeta::new_readonly(anchor_spl::token::ID, false),
+        ],
+        data,
+    }
+}
+
+/// Redeem `amount` collateral tokens from `collateral` for liquidity paid
+/// into `destination`; `authority` must be able to move `collateral`.
+pub fn redeem_instruction(
+    target: &LendingTarget,
+    collateral: Pubkey,
+    destination: Pubkey,
+    authority: Pubkey,
+    amount: u64,
+) -> Instruction {
+    let mut data = vec![REDEEM_RESERVE_COLLATERAL_TAG];
+    data.extend_from_slice(&amount.to_le_bytes());
+    Instruction {
+        program_id: target.program,
+        accounts: vec![
+            AccountMeta::new(collateral, false),
+            AccountMeta::new(destination, false),
+            AccountMeta::new(target.reserve, false),
+            AccountMeta::new(target.collateral_mint, false),
+            AccountMeta::new(target.liquidity_supply, false),
+            AccountMeta::new_readonly(target.lending_market, false),
+            AccountMeta::new_readonly(market_authority(target), false),
+            AccountMeta::new_readonly(authority, true),
+            AccountMeta::new_readonly(sysvar::clock::ID, false),
+            AccountMeta::new_readonly(anchor_spl::token::ID, false),
+        ],
+        data,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target() -> LendingTarget {
+        LendingTarget {
+            program: Pubkey::new_unique(),
+            reserve: Pubkey::new_unique(),
+            lending_market: Pubkey::new_unique(),
+            liquidity_supply: Pubkey::new_unique(),
+            collateral_mint: Pubkey::new_unique(),
+        }
+    }
+
+    #[test]
+    fn deposit_moves_liquidity_into_the_reserve() {
+        let target = target();
+        let (source, collateral, authority) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let ix = deposit_instruction(&target, source, collateral, authority, 1_500);
+        assert_eq!(ix.program_id, target.program);
+        assert_eq!(ix.data, [&[DEPOSIT_RESERVE_LIQUIDITY_TAG][..], &1_500u64.to_le_bytes()].concat());
+        assert_eq!(ix.accounts[0].pubkey, source);
+        assert_eq!(ix.accounts[1].pubkey, collateral);
+        assert_eq!(ix.accounts[3].pubkey, target.liquidity_supply);
+        assert_eq!(ix.accounts[4].pubkey, target.collateral_mint);
+        assert_eq!(ix.accounts[6].pubkey, market_authority(&target));
+        assert!(ix.accounts[7].is_signer && ix.accounts[7].pubkey == authority);
+    }
+
+    #[test]
+    fn redeem_swaps_the_supply_and_mint_order() {
+        let target = target();
+        let (collateral, destination, authority) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let ix = redeem_instruction(&target, collateral, destination, authority, 7);
+        assert_eq!(ix.data[0], REDEEM_RESERVE_COLLATERAL_TAG);
+        assert_eq!(ix.accounts[0].pubkey, collateral);
+        assert_eq!(ix.accounts[1].pubkey, destination);
+        assert_eq!(ix.accounts[3].pubkey, target.collateral_mint);
+        assert_eq!(ix.accounts[4].pubkey, target.liquidity_supply);
+        assert!(ix.accounts.iter().filter(|meta| meta.is_signer).count() == 1);
+    }
+}
@@ -1,42 +1,730 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer, Mint};
+use anchor_lang::solana_program::bpf_loader_upgradeable;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program::{invoke, invoke_signed};
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::{system_program, Discriminator, InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::{self, get_associated_token_address, AssociatedToken};
+use anchor_spl::token::{self, CloseAccount, Mint, Token, TokenAccount, Transfer};
+use anchor_spl::token_2022::spl_token_2022::extension::ExtensionType;
+use anchor_spl::token_2022::spl_token_2022::instruction::AuthorityType;
+use anchor_spl::token_2022::{self, Token2022};
+use anchor_spl::token_2022_extensions;
+use mpl_bubblegum as bubblegum;
+
+pub mod automation;
+pub mod governance;
+pub mod harness;
+pub mod large_escrow;
+pub mod layout;
+pub mod lending;
+pub mod math;
+pub mod oracle;
+pub mod pda;
+pub mod sigverify;
+pub mod terms;
+pub mod text;
+pub mod vrf;
+
+use harness::*;
+
+pub use math::BPS_DENOMINATOR;
 
 declare_id!("7KGm2AoZh2HtqqLx15BXEkt8fS1y9uAS8vXRRTw9Nud7");
 
+/// Escrow account layout generation, stamped at a fixed offset so clients can
+/// tell accounts from older deployments apart without a full decode.
+/// Bump it for any change to the `Escrow` layout, record the new field count
+/// in `layout::FIELD_COUNTS` and teach `migrate_escrow` to rewrite the previous one.
+pub const ESCROW_LAYOUT_VERSION: u8 = 21;
+/// Default buyer review window before a delivery can be auto-approved.
+pub const REVIEW_PERIOD_SECS: i64 = 3 * 24 * 60 * 60;
+/// Satisfaction scores are 1 (worst) to 5 (best).
+pub const MAX_SATISFACTION_SCORE: u8 = 5;
+/// If the buyer never acknowledges a delivery, the review window starts this long after it.
+pub const ACK_FALLBACK_SECS: i64 = 24 * 60 * 60;
+/// Upper bound on any protocol fee, default or per-platform.
+pub const MAX_PROTOCOL_FEE_BPS: u16 = 1_000;
+/// User-visible length limit for descriptions, in grapheme clusters.
+pub const MAX_DESCRIPTION_GRAPHEMES: usize = 500;
+/// Storage limit for descriptions, sized for non-Latin scripts at the grapheme limit.
+pub const MAX_DESCRIPTION_BYTES: usize = 2_000;
+/// Storage limit for off-chain document URIs.
+pub const MAX_URI_BYTES: usize = 200;
+/// Maximum number of milestones a single escrow can be split into.
+pub const MAX_MILESTONES: usize = 8;
+/// Maximum number of open disputes tracked per arbitrator queue.
+pub const MAX_QUEUED_CASES: usize = 64;
+/// Maximum number of open escrows an arbitrator can be named on.
+pub const MAX_INDEXED_ESCROWS: usize = 128;
+/// Maximum number of open listings tracked per marketplace platform.
+pub const MAX_LISTINGS: usize = 128;
+/// Escrows `create_escrows_batch` opens at once; each takes two accounts of the transaction's 64.
+pub const MAX_BATCH_ESCROWS: usize = 12;
+/// Arbitrators on a panel; a ruling needs a strict majority of them.
+pub const PANEL_SIZE: usize = 3;
+/// Staked arbitrators that random arbitrator draws pick from.
+pub const MAX_REGISTERED_ARBITRATORS: usize = 32;
+/// Recipients a seller can split their payout between.
+pub const MAX_SPLIT_RECIPIENTS: usize = 5;
+/// Revisions a buyer can request on one escrow before they must approve or dispute.
+pub const MAX_REVISIONS: u8 = 3;
+/// Deliveries kept on an escrow: the first plus one per revision.
+pub const MAX_DELIVERIES: usize = MAX_REVISIONS as usize + 1;
+/// Deadline extension granted to the seller with each revision request.
+pub const REVISION_EXTENSION_SECS: i64 = 2 * 24 * 60 * 60;
+/// Upper bound on the keeper tip paid for cranking `auto_approve`.
+pub const MAX_CRANK_TIP_BPS: u16 = 100;
+/// Upper bound on the referral fee a marketplace can take from the seller's payment.
+pub const MAX_REFERRAL_FEE_BPS: u16 = 500;
+/// Upper bound on the insurance premium an escrow pays to opt in.
+pub const MAX_INSURANCE_PREMIUM_BPS: u16 = 100;
+/// Oldest Pyth price a USD-pegged escrow will settle at.
+pub const MAX_PRICE_AGE_SECS: i64 = 60;
+/// Widest Pyth confidence interval, relative to the price, a USD-pegged escrow will settle at.
+pub const MAX_PRICE_CONF_BPS: u64 = 200;
+/// Default time an arbitrator has to rule before anyone can force the fallback split.
+pub const DEFAULT_ARBITRATION_TIMEOUT_SECS: i64 = 14 * 24 * 60 * 60;
+
 #[program]
 pub mod clawscrow {
     use super::*;
 
-    pub fn create_escrow(
-        ctx: Context<CreateEscrow>,
-        escrow_id: u64,
+    /// The escrow ID comes from the buyer's `Counter` and is reported in
+    /// `EscrowCreated`; clients no longer pick it.
+    pub fn create_escrow(ctx: Context<CreateEscrow>, params: CreateEscrowParams) -> Result<()> {
+        let CreateEscrowParams {
+            description,
+            payment_amount,
+            buyer_collateral,
+            seller_collateral,
+            deadline_ts,
+            milestone_amounts,
+            arbitration_sla,
+            platform,
+            terms_hash,
+            accept_by_ts,
+            intended_seller,
+            buyer_payout,
+            panel,
+            vesting_secs,
+            review_period_secs,
+            contract,
+            deferred_funding,
+            backup_arbitrator,
+            completion_badge,
+            compressed_receipts,
+            deliverable,
+            early_bonus,
+            referral,
+            trusted_pair,
+        } = params;
+        require!(!ctx.accounts.config.paused, ClawscrowError::ProtocolPaused);
+        require_credential(&ctx.accounts.config, ctx.accounts.buyer.key(), ctx.accounts.buyer_credential.as_ref())?;
+        let now = unix_now(ctx.remaining_accounts)?;
+        if let Some(contract) = &contract {
+            text::validate_uri(&contract.uri)?;
+        }
+        if let Some(accept_by) = accept_by_ts {
+            require!(accept_by > now && accept_by <= deadline_ts, ClawscrowError::InvalidDeadline);
+        }
+        require!(milestone_amounts.len() <= MAX_MILESTONES, ClawscrowError::TooManyMilestones);
+        if !milestone_amounts.is_empty() {
+            let mut sum: u64 = 0;
+            for amount in milestone_amounts.iter() {
+                require!(*amount > 0, ClawscrowError::InvalidAmount);
+                sum = sum.checked_add(*amount).ok_or(ClawscrowError::Overflow)?;
+            }
+            require!(sum == payment_amount, ClawscrowError::MilestoneSumMismatch);
+        }
+        if let Some(sla) = &arbitration_sla {
+            require!(sla.sla_secs >= 0 && sla.decay_secs > 0, ClawscrowError::InvalidArbitrationSla);
+        }
+        if let Some(secs) = vesting_secs {
+            require!(secs > 0, ClawscrowError::InvalidVesting);
+            require!(milestone_amounts.is_empty(), ClawscrowError::InvalidVesting);
+        }
+        if let Some(asset) = &deliverable {
+            require!(asset.amount > 0 && milestone_amounts.is_empty(), ClawscrowError::InvalidDeliverable);
+        }
+        // Milestone deliveries are not recorded, so only single-delivery escrows can earn a bonus.
+        if let Some(bonus) = &early_bonus {
+            require!(
+                bonus.amount > 0
+                    && bonus.bonus_deadline_ts > now
+                    && bonus.bonus_deadline_ts <= deadline_ts
+                    && milestone_amounts.is_empty(),
+                ClawscrowError::InvalidEarlyBonus
+            );
+            require!(ctx.accounts.bonus_vault.is_some(), ClawscrowError::InvalidEarlyBonus);
+        }
+        if let Some(backup) = backup_arbitrator {
+            require!(backup != ctx.accounts.arbitrator.key() && panel.is_none(), ClawscrowError::InvalidBackupArbitrator);
+        }
+        if let Some(referral) = &referral {
+            require!(
+                referral.fee_bps > 0
+                    && referral.fee_bps <= MAX_REFERRAL_FEE_BPS
+                    && referral.referrer != ctx.accounts.buyer.key(),
+                ClawscrowError::InvalidReferral
+            );
+        }
+        // The named arbitrator chairs the panel, so opt-in, queues and indexes stay per-arbitrator.
+        if let Some(members) = &panel {
+            require!(members[0] == ctx.accounts.arbitrator.key(), ClawscrowError::InvalidPanel);
+            for (i, member) in members.iter().enumerate() {
+                require!(!members[..i].contains(member), ClawscrowError::InvalidPanel);
+            }
+        }
+
+        // Snapshot the fee so later config changes never alter agreed terms.
+        let protocol_fee_bps = match (&platform, &ctx.accounts.fee_override) {
+            (Some(p), Some(fee_override)) => {
+                require!(fee_override.platform == *p, ClawscrowError::InvalidFeeOverride);
+                fee_override.fee_bps
+            }
+            (None, Some(_)) => return err!(ClawscrowError::InvalidFeeOverride),
+            _ => ctx.accounts.config.protocol_fee_bps,
+        };
+
+        let counter = &mut ctx.accounts.counter;
+        counter.init_if_empty(ctx.accounts.buyer.key(), ctx.bumps.counter);
+        let escrow_id = counter.next_id(ctx.accounts.config.max_open_escrows)?;
+
+        let config = &ctx.accounts.config;
+        let terms = terms::TermsV1 {
+            buyer: ctx.accounts.buyer.key(),
+            seller: intended_seller.unwrap_or_default(),
+            arbitrator: ctx.accounts.arbitrator.key(),
+            mint: ctx.accounts.mint.key(),
+            payment_amount,
+            buyer_collateral,
+            seller_collateral,
+            deadline_ts,
+            description_hash: terms::description_hash(&description),
+            milestone_amounts,
+            arbitration_sla,
+            platform,
+        };
+        let escrow = &mut ctx.accounts.escrow;
+        let computed_terms_hash = open_escrow(
+            escrow,
+            config,
+            ctx.accounts.arbitrator_profile.as_ref(),
+            escrow_id,
+            terms,
+            description,
+            ctx.accounts.mint.decimals,
+            review_period_secs.unwrap_or(REVIEW_PERIOD_SECS),
+            trusted_pair,
+            now,
+        )?;
+        if let Some(expected) = terms_hash {
+            require!(expected == computed_terms_hash, ClawscrowError::TermsMismatch);
+        }
+        escrow.backup_arbitrator = backup_arbitrator;
+        escrow.completion_badge = completion_badge;
+        escrow.compressed_receipts = compressed_receipts;
+        escrow.deliverable = deliverable;
+        escrow.early_bonus = early_bonus.clone();
+        escrow.early_bonus_settled = false;
+        escrow.referral = referral;
+        if deferred_funding {
+            escrow.state = EscrowState::Draft;
+        }
+        escrow.delivery_hash = [0u8; 32];
+        escrow.delivered_at = 0;
+        escrow.acknowledged_at = 0;
+        escrow.feedback_hash = None;
+        escrow.satisfaction = None;
+        escrow.cancel_proposed_by = None;
+        escrow.predecessor_id = None;
+        escrow.successor_id = None;
+        escrow.released_amount = 0;
+        escrow.disputed_at = 0;
+        escrow.protocol_fee_bps = protocol_fee_bps;
+        escrow.accept_by_ts = accept_by_ts;
+        escrow.buyer_payout = buyer_payout;
+        escrow.buyer_payout_token = escrow.buyer_payout_ata();
+        escrow.seller_payout_token = Pubkey::default();
+        escrow.arbitrator_accepted = false;
+        escrow.panel = panel.map(ArbitrationPanel::new);
+        escrow.vesting_secs = vesting_secs;
+        escrow.vesting = None;
+        escrow.contract = contract.clone();
+        escrow.funding_source = deferred_funding.then(|| ctx.accounts.buyer_token.key());
+        escrow.bump = ctx.bumps.escrow;
+        escrow.vault_bump = ctx.bumps.vault;
+
+        let index = &mut ctx.accounts.arbitrator_index;
+        index.init_if_empty(ctx.accounts.arbitrator.key(), ctx.bumps.arbitrator_index);
+        require!(index.escrows.len() < MAX_INDEXED_ESCROWS, ClawscrowError::ArbitratorIndexFull);
+        index.escrows.push(ctx.accounts.escrow.key());
+
+        let stats = &mut ctx.accounts.stats;
+        stats.bump = ctx.bumps.stats;
+        stats.record_created(payment_amount);
+
+        let buyer_index = &mut ctx.accounts.buyer_index;
+        buyer_index.init_if_empty(ctx.accounts.buyer.key(), ctx.bumps.buyer_index);
+        buyer_index.push(ctx.accounts.escrow.key());
+
+        let total = payment_amount.checked_add(buyer_collateral)
+            .ok_or(ClawscrowError::Overflow)?;
+
+        // Drafts hold no tokens until `fund_escrow`.
+        if deferred_funding {
+            require!(
+                ctx.accounts.buyer_token.owner == ctx.accounts.buyer.key(),
+                ClawscrowError::Unauthorized
+            );
+        } else {
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.buyer_token.to_account_info(),
+                        to: ctx.accounts.vault.to_account_info(),
+                        authority: ctx.accounts.buyer.to_account_info(),
+                    },
+                ),
+                total,
+            )?;
+        }
+
+        // The bonus is held apart from the payment even for drafts, so funding never has to know about it.
+        if let (Some(bonus), Some(bonus_vault)) = (&early_bonus, &ctx.accounts.bonus_vault) {
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.buyer_token.to_account_info(),
+                        to: bonus_vault.to_account_info(),
+                        authority: ctx.accounts.buyer.to_account_info(),
+                    },
+                ),
+                bonus.amount,
+            )?;
+        }
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.buyer.key(), ctx.remaining_accounts)?;
+        emit_cpi!(EscrowCreated {
+            escrow_id,
+            meta,
+            buyer: ctx.accounts.buyer.key(),
+            mint: ctx.accounts.mint.key(),
+            payment_amount,
+            buyer_collateral,
+            seller_collateral,
+            decimals: ctx.accounts.mint.decimals,
+            terms_hash: computed_terms_hash,
+            intended_seller,
+            contract,
+        });
+
+        Ok(())
+    }
+
+    /// One-call onboarding for marketplaces: creates the escrow under the
+    /// platform's fee terms and lists it in the platform's public index.
+    /// `params` must name the platform and fund the escrow up front.
+    /// Integrators only need to watch `EscrowListed`.
+    pub fn create_and_list<'info>(
+        ctx: Context<'_, '_, '_, 'info, CreateAndList<'info>>,
+        params: CreateEscrowParams,
+    ) -> Result<()> {
+        require!(!params.deferred_funding, ClawscrowError::InvalidListing);
+        let platform = params.platform.ok_or(ClawscrowError::InvalidListing)?;
+        let Context { program_id, accounts, remaining_accounts, bumps, .. } = ctx;
+        create_escrow(Context::new(program_id, &mut accounts.create, remaining_accounts, bumps.create), params)?;
+
+        let index = &mut accounts.listing_index;
+        index.init_if_empty(platform, bumps.listing_index);
+        require!(index.escrows.len() < MAX_LISTINGS, ClawscrowError::ListingIndexFull);
+        index.escrows.push(accounts.create.escrow.key());
+
+        let escrow = &accounts.create.escrow;
+        let meta = event_meta(&accounts.create.escrow, accounts.create.buyer.key(), remaining_accounts)?;
+        emit!(EscrowListed {
+            escrow_id: escrow.escrow_id,
+            meta,
+            platform,
+            buyer: escrow.buyer,
+            mint: escrow.mint,
+            payment_amount: escrow.payment_amount,
+            buyer_collateral: escrow.buyer_collateral,
+            seller_collateral: escrow.seller_collateral,
+            deadline_ts: escrow.deadline_ts,
+            accept_by_ts: escrow.accept_by_ts,
+            protocol_fee_bps: escrow.protocol_fee_bps,
+            terms_hash: escrow.terms_hash,
+        });
+
+        Ok(())
+    }
+
+    /// Stores reusable escrow terms for `create_escrow_from_template`.
+    /// Collateral is given as a share of each escrow's payment.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_template(
+        ctx: Context<CreateTemplate>,
+        template_id: u64,
         description: String,
+        buyer_collateral_bps: u16,
+        seller_collateral_bps: u16,
+        review_period_secs: i64,
+        max_protocol_fee_bps: u16,
+        contract: Option<OffchainContract>,
+    ) -> Result<()> {
+        text::validate_description(&description)?;
+        if let Some(contract) = &contract {
+            text::validate_uri(&contract.uri)?;
+        }
+        let config = &ctx.accounts.config;
+        require!(
+            review_period_secs >= config.min_review_period_secs
+                && review_period_secs <= config.max_review_period_secs,
+            ClawscrowError::ReviewPeriodOutOfBounds
+        );
+        require!(
+            buyer_collateral_bps as u64 <= BPS_DENOMINATOR
+                && seller_collateral_bps as u64 <= BPS_DENOMINATOR
+                && max_protocol_fee_bps <= MAX_PROTOCOL_FEE_BPS,
+            ClawscrowError::InvalidTemplate
+        );
+
+        let template = &mut ctx.accounts.template;
+        template.owner = ctx.accounts.owner.key();
+        template.template_id = template_id;
+        template.arbitrator = ctx.accounts.arbitrator.key();
+        template.mint = ctx.accounts.mint.key();
+        template.buyer_collateral_bps = buyer_collateral_bps;
+        template.seller_collateral_bps = seller_collateral_bps;
+        template.review_period_secs = review_period_secs;
+        template.max_protocol_fee_bps = max_protocol_fee_bps;
+        template.description = description;
+        template.contract = contract;
+        template.bump = ctx.bumps.template;
+
+        emit!(TemplateCreated {
+            template: ctx.accounts.template.key(),
+            owner: ctx.accounts.owner.key(),
+            template_id,
+            arbitrator: ctx.accounts.arbitrator.key(),
+            mint: ctx.accounts.mint.key(),
+            timestamp: unix_now(ctx.remaining_accounts)?,
+        });
+
+        Ok(())
+    }
+
+    pub fn close_template(ctx: Context<CloseTemplate>, template_id: u64) -> Result<()> {
+        emit!(TemplateClosed {
+            template: ctx.accounts.template.key(),
+            owner: ctx.accounts.owner.key(),
+            template_id,
+            timestamp: unix_now(ctx.remaining_accounts)?,
+        });
+
+        Ok(())
+    }
+
+    /// `create_escrow` with everything but the amount and deadline taken
+    /// from a template. Fails if the protocol fee has risen above the
+    /// template's cap since it was written.
+    pub fn create_escrow_from_template<'info>(
+        ctx: Context<'_, '_, '_, 'info, CreateFromTemplate<'info>>,
+        payment_amount: u64,
+        deadline_ts: i64,
+    ) -> Result<()> {
+        let Context { program_id, accounts, remaining_accounts, bumps, .. } = ctx;
+        let template = &accounts.template;
+        let buyer_collateral = math::bps_of(payment_amount, template.buyer_collateral_bps).ok_or(ClawscrowError::Overflow)?;
+        let seller_collateral = math::bps_of(payment_amount, template.seller_collateral_bps).ok_or(ClawscrowError::Overflow)?;
+        let max_protocol_fee_bps = template.max_protocol_fee_bps;
+        create_escrow(
+            Context::new(program_id, &mut accounts.create, remaining_accounts, bumps.create),
+            CreateEscrowParams {
+                description: template.description.clone(),
+                payment_amount,
+                buyer_collateral,
+                seller_collateral,
+                deadline_ts,
+                review_period_secs: Some(template.review_period_secs),
+                contract: template.contract.clone(),
+                ..Default::default()
+            },
+        )?;
+        require!(
+            accounts.create.escrow.protocol_fee_bps <= max_protocol_fee_bps,
+            ClawscrowError::TemplateFeeExceeded
+        );
+
+        let meta = event_meta(&accounts.create.escrow, accounts.create.buyer.key(), remaining_accounts)?;
+        emit!(EscrowCreatedFromTemplate {
+            escrow_id: accounts.create.escrow.escrow_id,
+            meta,
+            template: accounts.template.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Opens one escrow per entry against the same seller, arbitrator and
+    /// mint, for buyers starting many engagements at once. Each escrow's
+    /// PDA and vault are passed, in entry order, as pairs of remaining
+    /// accounts; IDs continue from the buyer's counter. Entries get the
+    /// defaults of a plain `create_escrow`: no milestones, platform or extras.
+    pub fn create_escrows_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CreateEscrowsBatch<'info>>,
+        entries: Vec<BatchEscrow>,
+        intended_seller: Option<Pubkey>,
+    ) -> Result<()> {
+        require!(!ctx.accounts.config.paused, ClawscrowError::ProtocolPaused);
+        require_credential(&ctx.accounts.config, ctx.accounts.buyer.key(), ctx.accounts.buyer_credential.as_ref())?;
+        require!(
+            !entries.is_empty()
+                && entries.len() <= MAX_BATCH_ESCROWS
+                && ctx.remaining_accounts.len() >= 2 * entries.len(),
+            ClawscrowError::InvalidBatch
+        );
+        require!(
+            ctx.accounts.buyer_token.owner == ctx.accounts.buyer.key(),
+            ClawscrowError::PayoutAccountMismatch
+        );
+        let now = unix_now(ctx.remaining_accounts)?;
+        let config = &ctx.accounts.config;
+
+        let buyer = ctx.accounts.buyer.key();
+        let arbitrator = ctx.accounts.arbitrator.key();
+        let mint = ctx.accounts.mint.key();
+        let seller = intended_seller.unwrap_or_default();
+        ctx.accounts.counter.init_if_empty(buyer, ctx.bumps.counter);
+        ctx.accounts.stats.bump = ctx.bumps.stats;
+        ctx.accounts.buyer_index.init_if_empty(buyer, ctx.bumps.buyer_index);
+        ctx.accounts.arbitrator_index.init_if_empty(arbitrator, ctx.bumps.arbitrator_index);
+
+        for (entry, pair) in entries.into_iter().zip(ctx.remaining_accounts.chunks_exact(2)) {
+            let escrow_id = ctx.accounts.counter.next_id(ctx.accounts.config.max_open_escrows)?;
+            let id_bytes = escrow_id.to_le_bytes();
+            let (escrow_key, bump) = pda::escrow(&buyer, escrow_id);
+            let (vault_key, vault_bump) = pda::vault(&escrow_key);
+            let (escrow_info, vault_info) = (&pair[0], &pair[1]);
+            require_keys_eq!(escrow_info.key(), escrow_key, ClawscrowError::InvalidBatch);
+            require_keys_eq!(vault_info.key(), vault_key, ClawscrowError::InvalidBatch);
+
+            init_pda(
+                &ctx.accounts.payer,
+                escrow_info,
+                &ctx.accounts.system_program,
+                8 + Escrow::INIT_SPACE,
+                ctx.program_id,
+                &[b"escrow", buyer.as_ref(), &id_bytes, &[bump]],
+            )?;
+            init_pda(
+                &ctx.accounts.payer,
+                vault_info,
+                &ctx.accounts.system_program,
+                TokenAccount::LEN,
+                &token::ID,
+                &[b"vault", escrow_key.as_ref(), &[vault_bump]],
+            )?;
+            token::initialize_account3(CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::InitializeAccount3 {
+                    account: vault_info.clone(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    authority: escrow_info.clone(),
+                },
+            ))?;
+
+            let terms = terms::TermsV1 {
+                buyer,
+                seller,
+                arbitrator,
+                mint,
+                payment_amount: entry.payment_amount,
+                buyer_collateral: entry.buyer_collateral,
+                seller_collateral: entry.seller_collateral,
+                deadline_ts: entry.deadline_ts,
+                description_hash: terms::description_hash(&entry.description),
+                milestone_amounts: Vec::new(),
+                arbitration_sla: None,
+                platform: None,
+            };
+
+            // The account is freshly zeroed, so every field not set here starts out empty.
+            let mut escrow = Account::<Escrow>::try_from_unchecked(escrow_info)?;
+            let terms_hash = open_escrow(
+                &mut escrow,
+                config,
+                ctx.accounts.arbitrator_profile.as_ref(),
+                escrow_id,
+                terms,
+                entry.description,
+                ctx.accounts.mint.decimals,
+                REVIEW_PERIOD_SECS,
+                false,
+                now,
+            )?;
+            escrow.bump = bump;
+            escrow.vault_bump = vault_bump;
+            escrow.exit(ctx.program_id)?;
+
+            let index = &mut ctx.accounts.arbitrator_index;
+            require!(index.escrows.len() < MAX_INDEXED_ESCROWS, ClawscrowError::ArbitratorIndexFull);
+            index.escrows.push(escrow_key);
+            ctx.accounts.stats.record_created(entry.payment_amount);
+            ctx.accounts.buyer_index.push(escrow_key);
+
+            let total = entry.payment_amount.checked_add(entry.buyer_collateral)
+                .ok_or(ClawscrowError::Overflow)?;
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.buyer_token.to_account_info(),
+                        to: vault_info.clone(),
+                        authority: ctx.accounts.buyer.to_account_info(),
+                    },
+                ),
+                total,
+            )?;
+
+            let meta = event_meta(&escrow, buyer, ctx.remaining_accounts)?;
+            emit_cpi!(EscrowCreated {
+                escrow_id,
+                meta,
+                buyer,
+                mint,
+                payment_amount: entry.payment_amount,
+                buyer_collateral: entry.buyer_collateral,
+                seller_collateral: entry.seller_collateral,
+                decimals: ctx.accounts.mint.decimals,
+                terms_hash,
+                intended_seller,
+                contract: None,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Permissionless cleanup of listings that are no longer open offers.
+    pub fn prune_listing(ctx: Context<PruneListing>, escrow_id: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        require!(escrow.state != EscrowState::Created, ClawscrowError::InvalidState);
+
+        let escrow_key = ctx.accounts.escrow.key();
+        ctx.accounts.listing_index.escrows.retain(|key| *key != escrow_key);
+
+        Ok(())
+    }
+
+    pub fn renew_escrow(
+        ctx: Context<RenewEscrow>,
         payment_amount: u64,
-        buyer_collateral: u64,
-        seller_collateral: u64,
         deadline_ts: i64,
     ) -> Result<()> {
+        require!(!ctx.accounts.config.paused, ClawscrowError::ProtocolPaused);
+        require_credential(&ctx.accounts.config, ctx.accounts.buyer.key(), ctx.accounts.buyer_credential.as_ref())?;
+        let predecessor = &ctx.accounts.predecessor;
+        require!(predecessor.state == EscrowState::Approved, ClawscrowError::InvalidState);
+        require!(predecessor.successor_id.is_none(), ClawscrowError::AlreadyRenewed);
+        // An undrawn random arbitrator belongs to the predecessor alone.
+        require!(
+            predecessor.arbitrator != random_arbitration_address(&predecessor.key()),
+            ClawscrowError::RandomArbitratorUnavailable
+        );
         require!(payment_amount > 0, ClawscrowError::InvalidAmount);
-        require!(description.len() <= 500, ClawscrowError::DescriptionTooLong);
-        require!(deadline_ts > Clock::get()?.unix_timestamp, ClawscrowError::InvalidDeadline);
+        let now = unix_now(ctx.remaining_accounts)?;
+        require!(deadline_ts > now, ClawscrowError::InvalidDeadline);
+
+        let buyer_collateral = predecessor.buyer_collateral;
+        let seller_collateral = predecessor.seller_collateral;
+        // The bounds may have moved since the predecessor was created.
+        require_collateral_bounds(&ctx.accounts.config, buyer_collateral, seller_collateral)?;
+        if !predecessor.trusted_pair {
+            require_collateral_ratio(&ctx.accounts.config, payment_amount, buyer_collateral, seller_collateral)?;
+        }
+        require_vetted_arbitrator(&ctx.accounts.config, ctx.accounts.arbitrator_profile.as_ref(), payment_amount)?;
+        let predecessor_id = predecessor.escrow_id;
+        let predecessor_seller = predecessor.seller;
+        let terms_hash = terms::TermsV1 {
+            buyer: predecessor.buyer,
+            seller: predecessor.seller,
+            arbitrator: predecessor.arbitrator,
+            mint: predecessor.mint,
+            payment_amount,
+            buyer_collateral,
+            seller_collateral,
+            deadline_ts,
+            description_hash: terms::description_hash(&predecessor.description),
+            milestone_amounts: Vec::new(),
+            arbitration_sla: predecessor.arbitration_sla.clone(),
+            platform: predecessor.platform,
+        }.hash()?;
+
+        let new_escrow_id = ctx.accounts.counter.next_id(ctx.accounts.config.max_open_escrows)?;
 
         let escrow = &mut ctx.accounts.escrow;
-        escrow.escrow_id = escrow_id;
-        escrow.buyer = ctx.accounts.buyer.key();
-        escrow.seller = Pubkey::default();
-        escrow.arbitrator = ctx.accounts.arbitrator.key();
+        escrow.layout_version = ESCROW_LAYOUT_VERSION;
+        escrow.genesis_slot = Clock::get()?.slot;
+        escrow.holds_open_slot = true;
+        escrow.escrow_id = new_escrow_id;
+        escrow.buyer = predecessor.buyer;
+        escrow.original_buyer = predecessor.buyer;
+        escrow.seller = predecessor.seller;
+        escrow.arbitrator = predecessor.arbitrator;
+        escrow.backup_arbitrator = predecessor.backup_arbitrator;
+        escrow.completion_badge = predecessor.completion_badge;
+        escrow.compressed_receipts = predecessor.compressed_receipts;
+        escrow.deliverable = predecessor.deliverable.clone();
+        escrow.mint = predecessor.mint;
+        escrow.arbitration_sla = predecessor.arbitration_sla.clone();
+        escrow.heartbeat_policy = predecessor.heartbeat_policy.clone();
+        escrow.buyer_payout = predecessor.buyer_payout;
+        escrow.buyer_payout_token = escrow.buyer_payout_ata();
+        escrow.seller_payout_token = Pubkey::default();
+        escrow.panel = predecessor.panel.as_ref().map(|p| ArbitrationPanel::new(p.members));
+        escrow.vesting_secs = predecessor.vesting_secs;
+        escrow.review_period_secs = predecessor.review_period_secs;
+        escrow.contract = predecessor.contract.clone();
+        escrow.platform = predecessor.platform;
+        escrow.protocol_fee_bps = predecessor.protocol_fee_bps;
+        escrow.crank_tip_bps = predecessor.crank_tip_bps;
+        escrow.trusted_pair = predecessor.trusted_pair;
         escrow.payment_amount = payment_amount;
         escrow.buyer_collateral = buyer_collateral;
         escrow.seller_collateral = seller_collateral;
+        escrow.decimals = ctx.accounts.mint.decimals;
         escrow.deadline_ts = deadline_ts;
-        escrow.description = description;
+        escrow.description = predecessor.description.clone();
         escrow.state = EscrowState::Created;
-        escrow.delivery_hash = [0u8; 32];
-        escrow.created_at = Clock::get()?.unix_timestamp;
-        escrow.delivered_at = 0;
+        escrow.created_at = now;
+        escrow.predecessor_id = Some(predecessor_id);
+        escrow.terms_hash = terms_hash;
         escrow.bump = ctx.bumps.escrow;
         escrow.vault_bump = ctx.bumps.vault;
 
+        ctx.accounts.predecessor.successor_id = Some(new_escrow_id);
+
+        let arbitrator = ctx.accounts.predecessor.arbitrator;
+        let index = &mut ctx.accounts.arbitrator_index;
+        index.init_if_empty(arbitrator, ctx.bumps.arbitrator_index);
+        require!(index.escrows.len() < MAX_INDEXED_ESCROWS, ClawscrowError::ArbitratorIndexFull);
+        index.escrows.push(ctx.accounts.escrow.key());
+
+        let stats = &mut ctx.accounts.stats;
+        stats.bump = ctx.bumps.stats;
+        stats.record_created(payment_amount);
+
+        let buyer_index = &mut ctx.accounts.buyer_index;
+        buyer_index.init_if_empty(ctx.accounts.buyer.key(), ctx.bumps.buyer_index);
+        buyer_index.push(ctx.accounts.escrow.key());
+
         let total = payment_amount.checked_add(buyer_collateral)
             .ok_or(ClawscrowError::Overflow)?;
 
@@ -52,464 +740,10672 @@ pub mod clawscrow {
             total,
         )?;
 
-        emit!(EscrowCreated {
-            escrow_id,
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.buyer.key(), ctx.remaining_accounts)?;
+        emit_cpi!(EscrowCreated {
+            escrow_id: new_escrow_id,
+            meta,
             buyer: ctx.accounts.buyer.key(),
+            mint: ctx.accounts.mint.key(),
             payment_amount,
             buyer_collateral,
             seller_collateral,
+            decimals: ctx.accounts.mint.decimals,
+            terms_hash,
+            intended_seller: Some(predecessor_seller),
+            contract: ctx.accounts.predecessor.contract.clone(),
         });
+        emit!(EscrowRenewed { predecessor_id, successor_id: new_escrow_id, meta });
 
         Ok(())
     }
 
-    pub fn accept_escrow(ctx: Context<AcceptEscrow>, escrow_id: u64) -> Result<()> {
-        let escrow = &mut ctx.accounts.escrow;
-        require!(escrow.state == EscrowState::Created, ClawscrowError::InvalidState);
-        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
-        let collateral = escrow.seller_collateral;
-        let eid = escrow.escrow_id;
+    /// Seller publishes a fixed-price listing and locks their collateral
+    /// against it. The arbitrator co-signs, which stands in for
+    /// `accept_arbitration_role` on the escrow the offer becomes.
+    #[allow(clippy::too_many_arguments)]
+    pub fn post_offer(
+        ctx: Context<PostOffer>,
+        offer_id: u64,
+        description: String,
+        payment_amount: u64,
+        buyer_collateral: u64,
+        seller_collateral: u64,
+        delivery_secs: i64,
+        expires_at: i64,
+    ) -> Result<()> {
+        let config = &ctx.accounts.config;
+        require!(!config.paused, ClawscrowError::ProtocolPaused);
+        require_credential(config, ctx.accounts.seller.key(), ctx.accounts.seller_credential.as_ref())?;
+        let now = unix_now(ctx.remaining_accounts)?;
+        require!(payment_amount > 0, ClawscrowError::InvalidAmount);
+        text::validate_description(&description)?;
+        require!(delivery_secs > 0, ClawscrowError::InvalidDeadline);
+        require!(expires_at > now, ClawscrowError::InvalidDeadline);
+        require_collateral_bounds(config, buyer_collateral, seller_collateral)?;
+        require_collateral_ratio(config, payment_amount, buyer_collateral, seller_collateral)?;
 
-        escrow.seller = ctx.accounts.seller.key();
-        escrow.state = EscrowState::Accepted;
+        let offer = &mut ctx.accounts.offer;
+        offer.seller = ctx.accounts.seller.key();
+        offer.offer_id = offer_id;
+        offer.arbitrator = ctx.accounts.arbitrator.key();
+        offer.mint = ctx.accounts.mint.key();
+        offer.payment_amount = payment_amount;
+        offer.buyer_collateral = buyer_collateral;
+        offer.seller_collateral = seller_collateral;
+        offer.delivery_secs = delivery_secs;
+        offer.expires_at = expires_at;
+        offer.description = description;
+        offer.seller_payout_token = ctx.accounts.seller_token.key();
+        offer.bump = ctx.bumps.offer;
+        offer.vault_bump = ctx.bumps.offer_vault;
+
+        let profile = &mut ctx.accounts.profile;
+        profile.init_if_empty(ctx.accounts.arbitrator.key(), now, ctx.bumps.profile);
+        profile.roles_accepted = profile.roles_accepted.saturating_add(1);
 
         token::transfer(
             CpiContext::new(
                 ctx.accounts.token_program.to_account_info(),
                 Transfer {
                     from: ctx.accounts.seller_token.to_account_info(),
-                    to: ctx.accounts.vault.to_account_info(),
+                    to: ctx.accounts.offer_vault.to_account_info(),
                     authority: ctx.accounts.seller.to_account_info(),
                 },
             ),
-            collateral,
+            seller_collateral,
         )?;
 
-        emit!(EscrowAccepted { escrow_id: eid, seller: ctx.accounts.seller.key() });
+        emit!(OfferPosted {
+            seller: ctx.accounts.seller.key(),
+            offer_id,
+            mint: ctx.accounts.mint.key(),
+            payment_amount,
+            buyer_collateral,
+            seller_collateral,
+            delivery_secs,
+            expires_at,
+            timestamp: now,
+        });
 
         Ok(())
     }
 
-    pub fn deliver(ctx: Context<Deliver>, delivery_hash: [u8; 32]) -> Result<()> {
-        let escrow = &mut ctx.accounts.escrow;
-        require!(escrow.state == EscrowState::Accepted, ClawscrowError::InvalidState);
-        require!(ctx.accounts.seller.key() == escrow.seller, ClawscrowError::Unauthorized);
+    /// Buyer takes an offer: funds payment and collateral, and the escrow is
+    /// created already `Accepted`, with the seller's collateral moved over
+    /// from the offer. The offer is consumed.
+    pub fn accept_offer(ctx: Context<AcceptOffer>, offer_id: u64) -> Result<()> {
+        require!(!ctx.accounts.config.paused, ClawscrowError::ProtocolPaused);
+        require_credential(&ctx.accounts.config, ctx.accounts.buyer.key(), ctx.accounts.buyer_credential.as_ref())?;
+        let seller = ctx.accounts.seller.key();
+        let offer = &ctx.accounts.offer;
+        let now = unix_now(ctx.remaining_accounts)?;
+        require!(now <= offer.expires_at, ClawscrowError::AcceptanceExpired);
 
-        escrow.delivery_hash = delivery_hash;
-        escrow.state = EscrowState::Delivered;
-        escrow.delivered_at = Clock::get()?.unix_timestamp;
+        let deadline_ts = now.checked_add(offer.delivery_secs).ok_or(ClawscrowError::Overflow)?;
+        let terms_hash = terms::TermsV1 {
+            buyer: ctx.accounts.buyer.key(),
+            seller,
+            arbitrator: offer.arbitrator,
+            mint: offer.mint,
+            payment_amount: offer.payment_amount,
+            buyer_collateral: offer.buyer_collateral,
+            seller_collateral: offer.seller_collateral,
+            deadline_ts,
+            description_hash: terms::description_hash(&offer.description),
+            milestone_amounts: Vec::new(),
+            arbitration_sla: None,
+            platform: None,
+        }.hash()?;
 
-        emit!(WorkDelivered { escrow_id: escrow.escrow_id, delivery_hash });
+        let counter = &mut ctx.accounts.counter;
+        counter.init_if_empty(ctx.accounts.buyer.key(), ctx.bumps.counter);
+        let escrow_id = counter.next_id(ctx.accounts.config.max_open_escrows)?;
 
-        Ok(())
-    }
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.layout_version = ESCROW_LAYOUT_VERSION;
+        escrow.genesis_slot = Clock::get()?.slot;
+        escrow.holds_open_slot = true;
+        escrow.escrow_id = escrow_id;
+        escrow.buyer = ctx.accounts.buyer.key();
+        escrow.original_buyer = ctx.accounts.buyer.key();
+        escrow.seller = seller;
+        escrow.arbitrator = offer.arbitrator;
+        escrow.mint = offer.mint;
+        escrow.payment_amount = offer.payment_amount;
+        escrow.buyer_collateral = offer.buyer_collateral;
+        escrow.seller_collateral = offer.seller_collateral;
+        escrow.decimals = ctx.accounts.mint.decimals;
+        escrow.deadline_ts = deadline_ts;
+        escrow.description = offer.description.clone();
+        escrow.state = EscrowState::Accepted;
+        escrow.accepted_at = now;
+        escrow.created_at = now;
+        escrow.last_heartbeat_at = now;
+        escrow.protocol_fee_bps = ctx.accounts.config.protocol_fee_bps;
+        escrow.crank_tip_bps = ctx.accounts.config.crank_tip_bps;
+        escrow.terms_hash = terms_hash;
+        escrow.arbitrator_accepted = true;
+        escrow.review_period_secs = REVIEW_PERIOD_SECS;
+        escrow.buyer_payout_token = escrow.buyer_payout_ata();
+        escrow.seller_payout_token = escrow.seller_payout_ata();
+        escrow.bump = ctx.bumps.escrow;
+        escrow.vault_bump = ctx.bumps.vault;
 
-    pub fn approve(ctx: Context<Resolve>, escrow_id: u64) -> Result<()> {
-        let escrow = &ctx.accounts.escrow;
-        require!(escrow.state == EscrowState::Delivered, ClawscrowError::InvalidState);
-        require!(ctx.accounts.signer.key() == escrow.buyer, ClawscrowError::Unauthorized);
-        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        let (payment_amount, buyer_collateral, seller_collateral) =
+            (offer.payment_amount, offer.buyer_collateral, offer.seller_collateral);
+        let offer_bump = offer.bump;
 
-        let payment = escrow.payment_amount;
-        let seller_col = escrow.seller_collateral;
-        let buyer_col = escrow.buyer_collateral;
-        let bump = escrow.bump;
+        let index = &mut ctx.accounts.arbitrator_index;
+        index.init_if_empty(ctx.accounts.offer.arbitrator, ctx.bumps.arbitrator_index);
+        require!(index.escrows.len() < MAX_INDEXED_ESCROWS, ClawscrowError::ArbitratorIndexFull);
+        index.escrows.push(ctx.accounts.escrow.key());
 
-        let id_bytes = escrow_id.to_le_bytes();
-        let seeds: &[&[u8]] = &[b"escrow", id_bytes.as_ref(), &[bump]];
-        let signer_seeds = &[seeds];
+        let stats = &mut ctx.accounts.stats;
+        stats.bump = ctx.bumps.stats;
+        stats.record_created(payment_amount);
+
+        let buyer_index = &mut ctx.accounts.buyer_index;
+        buyer_index.init_if_empty(ctx.accounts.buyer.key(), ctx.bumps.buyer_index);
+        buyer_index.push(ctx.accounts.escrow.key());
 
-        let seller_total = payment.checked_add(seller_col).ok_or(ClawscrowError::Overflow)?;
+        let seller_index = &mut ctx.accounts.seller_index;
+        seller_index.init_if_empty(ctx.accounts.seller.key(), ctx.bumps.seller_index);
+        seller_index.push(ctx.accounts.escrow.key());
+
+        let total = payment_amount.checked_add(buyer_collateral)
+            .ok_or(ClawscrowError::Overflow)?;
 
         token::transfer(
-            CpiContext::new_with_signer(
+            CpiContext::new(
                 ctx.accounts.token_program.to_account_info(),
                 Transfer {
-                    from: ctx.accounts.vault.to_account_info(),
-                    to: ctx.accounts.seller_token.to_account_info(),
-                    authority: ctx.accounts.escrow.to_account_info(),
+                    from: ctx.accounts.buyer_token.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                    authority: ctx.accounts.buyer.to_account_info(),
                 },
-                signer_seeds,
             ),
-            seller_total,
+            total,
         )?;
 
+        let id_bytes = offer_id.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"offer", seller.as_ref(), id_bytes.as_ref(), &[offer_bump]];
+        let signer_seeds = &[seeds];
+
         token::transfer(
             CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
                 Transfer {
-                    from: ctx.accounts.vault.to_account_info(),
-                    to: ctx.accounts.buyer_token.to_account_info(),
-                    authority: ctx.accounts.escrow.to_account_info(),
+                    from: ctx.accounts.offer_vault.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                    authority: ctx.accounts.offer.to_account_info(),
                 },
                 signer_seeds,
             ),
-            buyer_col,
+            seller_collateral,
         )?;
 
-        let escrow = &mut ctx.accounts.escrow;
-        escrow.state = EscrowState::Approved;
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.offer_vault.to_account_info(),
+                destination: ctx.accounts.seller.to_account_info(),
+                authority: ctx.accounts.offer.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
 
-        emit!(EscrowApproved { escrow_id });
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.buyer.key(), ctx.remaining_accounts)?;
+        emit_cpi!(EscrowCreated {
+            escrow_id,
+            meta,
+            buyer: ctx.accounts.buyer.key(),
+            mint: ctx.accounts.mint.key(),
+            payment_amount,
+            buyer_collateral,
+            seller_collateral,
+            decimals: ctx.accounts.mint.decimals,
+            terms_hash,
+            intended_seller: Some(seller),
+            contract: None,
+        });
+        emit!(OfferAccepted { seller, offer_id, buyer: ctx.accounts.buyer.key(), escrow_id, meta });
+        emit_cpi!(EscrowAccepted { escrow_id, meta, seller });
 
         Ok(())
     }
 
-    pub fn raise_dispute(ctx: Context<DisputeCtx>) -> Result<()> {
-        let escrow = &mut ctx.accounts.escrow;
-        require!(escrow.state == EscrowState::Delivered, ClawscrowError::InvalidState);
-        require!(ctx.accounts.buyer.key() == escrow.buyer, ClawscrowError::Unauthorized);
+    /// Seller withdraws an untaken offer and gets their collateral back.
+    pub fn cancel_offer(ctx: Context<CancelOffer>, offer_id: u64) -> Result<()> {
+        let offer = &ctx.accounts.offer;
+        let amount = ctx.accounts.offer_vault.amount;
+        let seller = offer.seller;
+        let id_bytes = offer_id.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"offer", seller.as_ref(), id_bytes.as_ref(), &[offer.bump]];
+        let signer_seeds = &[seeds];
 
-        escrow.state = EscrowState::Disputed;
+        if amount > 0 {
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.offer_vault.to_account_info(),
+                        to: ctx.accounts.seller_token.to_account_info(),
+                        authority: ctx.accounts.offer.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                amount,
+            )?;
+        }
+
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.offer_vault.to_account_info(),
+                destination: ctx.accounts.seller.to_account_info(),
+                authority: ctx.accounts.offer.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
 
-        emit!(EscrowDisputed { escrow_id: escrow.escrow_id });
+        emit!(OfferCancelled { seller, offer_id, timestamp: unix_now(ctx.remaining_accounts)? });
 
         Ok(())
     }
 
-    pub fn arbitrate(ctx: Context<Arbitrate>, escrow_id: u64, ruling: Ruling) -> Result<()> {
-        let escrow = &ctx.accounts.escrow;
-        require!(escrow.state == EscrowState::Disputed, ClawscrowError::InvalidState);
-        require!(ctx.accounts.arbitrator.key() == escrow.arbitrator, ClawscrowError::Unauthorized);
-        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
-
-        let payment = escrow.payment_amount;
-        let buyer_col = escrow.buyer_collateral;
-        let seller_col = escrow.seller_collateral;
-        let bump = escrow.bump;
+    /// Buyer sets up a retainer: the same job with the same seller and
+    /// arbitrator every `period_secs`, starting at `first_period_at`. Each
+    /// period's escrow is spawned with `spawn_recurring_escrow`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_recurring_agreement(
+        ctx: Context<CreateRecurringAgreement>,
+        agreement_id: u64,
+        description: String,
+        payment_amount: u64,
+        buyer_collateral: u64,
+        seller_collateral: u64,
+        period_secs: i64,
+        first_period_at: i64,
+        max_periods: Option<u32>,
+    ) -> Result<()> {
+        let config = &ctx.accounts.config;
+        require!(!config.paused, ClawscrowError::ProtocolPaused);
+        require_credential(config, ctx.accounts.buyer.key(), ctx.accounts.buyer_credential.as_ref())?;
+        require_credential(config, ctx.accounts.seller.key(), ctx.accounts.seller_credential.as_ref())?;
+        require!(payment_amount > 0, ClawscrowError::InvalidAmount);
+        text::validate_description(&description)?;
+        require!(
+            period_secs > 0 && first_period_at > 0 && max_periods != Some(0),
+            ClawscrowError::InvalidRecurringAgreement
+        );
+        require_collateral_bounds(config, buyer_collateral, seller_collateral)?;
+        require_collateral_ratio(config, payment_amount, buyer_collateral, seller_collateral)?;
+        // Every period pays the same amount, so vetting the arbitrator once covers them all.
+        require_vetted_arbitrator(config, ctx.accounts.arbitrator_profile.as_ref(), payment_amount)?;
 
-        let id_bytes = escrow_id.to_le_bytes();
-        let seeds: &[&[u8]] = &[b"escrow", id_bytes.as_ref(), &[bump]];
-        let signer_seeds = &[seeds];
+        let agreement = &mut ctx.accounts.agreement;
+        agreement.buyer = ctx.accounts.buyer.key();
+        agreement.agreement_id = agreement_id;
+        agreement.seller = ctx.accounts.seller.key();
+        agreement.arbitrator = ctx.accounts.arbitrator.key();
+        agreement.mint = ctx.accounts.mint.key();
+        agreement.payment_amount = payment_amount;
+        agreement.buyer_collateral = buyer_collateral;
+        agreement.seller_collateral = seller_collateral;
+        agreement.period_secs = period_secs;
+        agreement.next_period_at = first_period_at;
+        agreement.periods_spawned = 0;
+        agreement.max_periods = max_periods;
+        agreement.description = description;
+        agreement.last_escrow_id = None;
+        agreement.bump = ctx.bumps.agreement;
 
-        let total_pool = payment
-            .checked_add(buyer_col).ok_or(ClawscrowError::Overflow)?
-            .checked_add(seller_col).ok_or(ClawscrowError::Overflow)?;
+        emit!(RecurringAgreementCreated {
+            agreement: ctx.accounts.agreement.key(),
+            buyer: ctx.accounts.buyer.key(),
+            seller: ctx.accounts.seller.key(),
+            mint: ctx.accounts.mint.key(),
+            payment_amount,
+            period_secs,
+            first_period_at,
+            max_periods,
+            timestamp: unix_now(ctx.remaining_accounts)?,
+        });
 
-        let arb_fee = buyer_col / 100;
-        let winner_amount = total_pool.checked_sub(arb_fee).ok_or(ClawscrowError::Overflow)?;
+        Ok(())
+    }
 
-        let winner_token = match ruling {
-            Ruling::BuyerWins => ctx.accounts.buyer_token.to_account_info(),
-            Ruling::SellerWins => ctx.accounts.seller_token.to_account_info(),
-        };
+    /// Buyer opens and funds the current period's escrow from a recurring
+    /// agreement. The work is due at the end of the period and the named
+    /// seller accepts it like any other escrow. Periods the buyer let pass
+    /// without spawning are skipped, not billed.
+    pub fn spawn_recurring_escrow(ctx: Context<SpawnRecurringEscrow>, agreement_id: u64) -> Result<()> {
+        require!(!ctx.accounts.config.paused, ClawscrowError::ProtocolPaused);
+        let agreement = &ctx.accounts.agreement;
+        require!(agreement.agreement_id == agreement_id, ClawscrowError::InvalidRecurringAgreement);
+        require!(
+            agreement.max_periods.is_none_or(|max| agreement.periods_spawned < max),
+            ClawscrowError::RecurringAgreementEnded
+        );
+        let now = unix_now(ctx.remaining_accounts)?;
+        require!(now >= agreement.next_period_at, ClawscrowError::PeriodNotStarted);
 
-        token::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.vault.to_account_info(),
-                    to: winner_token,
-                    authority: ctx.accounts.escrow.to_account_info(),
-                },
-                signer_seeds,
-            ),
-            winner_amount,
-        )?;
+        let elapsed_periods = (now - agreement.next_period_at) / agreement.period_secs;
+        let period_start = agreement.next_period_at + elapsed_periods * agreement.period_secs;
+        let deadline_ts = period_start.checked_add(agreement.period_secs).ok_or(ClawscrowError::Overflow)?;
+        let terms_hash = terms::TermsV1 {
+            buyer: agreement.buyer,
+            seller: agreement.seller,
+            arbitrator: agreement.arbitrator,
+            mint: agreement.mint,
+            payment_amount: agreement.payment_amount,
+            buyer_collateral: agreement.buyer_collateral,
+            seller_collateral: agreement.seller_collateral,
+            deadline_ts,
+            description_hash: terms::description_hash(&agreement.description),
+            milestone_amounts: Vec::new(),
+            arbitration_sla: None,
+            platform: None,
+        }.hash()?;
 
-        token::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.vault.to_account_info(),
-                    to: ctx.accounts.arbitrator_token.to_account_info(),
-                    authority: ctx.accounts.escrow.to_account_info(),
-                },
-                signer_seeds,
-            ),
-            arb_fee,
-        )?;
+        let counter = &mut ctx.accounts.counter;
+        counter.init_if_empty(ctx.accounts.buyer.key(), ctx.bumps.counter);
+        let escrow_id = counter.next_id(ctx.accounts.config.max_open_escrows)?;
 
         let escrow = &mut ctx.accounts.escrow;
-        escrow.state = match ruling {
-            Ruling::BuyerWins => EscrowState::ResolvedBuyer,
-            Ruling::SellerWins => EscrowState::ResolvedSeller,
-        };
-
-        emit!(DisputeResolved { escrow_id, ruling });
+        escrow.layout_version = ESCROW_LAYOUT_VERSION;
+        escrow.genesis_slot = Clock::get()?.slot;
+        escrow.holds_open_slot = true;
+        escrow.escrow_id = escrow_id;
+        escrow.buyer = agreement.buyer;
+        escrow.original_buyer = agreement.buyer;
+        escrow.seller = agreement.seller;
+        escrow.arbitrator = agreement.arbitrator;
+        escrow.mint = agreement.mint;
+        escrow.payment_amount = agreement.payment_amount;
+        escrow.buyer_collateral = agreement.buyer_collateral;
+        escrow.seller_collateral = agreement.seller_collateral;
+        escrow.decimals = ctx.accounts.mint.decimals;
+        escrow.deadline_ts = deadline_ts;
+        escrow.description = agreement.description.clone();
+        escrow.state = EscrowState::Created;
+        escrow.created_at = now;
+        escrow.protocol_fee_bps = ctx.accounts.config.protocol_fee_bps;
+        escrow.crank_tip_bps = ctx.accounts.config.crank_tip_bps;
+        escrow.terms_hash = terms_hash;
+        escrow.review_period_secs = REVIEW_PERIOD_SECS;
+        escrow.buyer_payout_token = escrow.buyer_payout_ata();
+        escrow.seller_payout_token = Pubkey::default();
+        escrow.bump = ctx.bumps.escrow;
+        escrow.vault_bump = ctx.bumps.vault;
 
-        Ok(())
-    }
+        let (payment_amount, buyer_collateral, seller_collateral) =
+            (agreement.payment_amount, agreement.buyer_collateral, agreement.seller_collateral);
+        let seller = agreement.seller;
 
-    pub fn auto_approve(ctx: Context<Resolve>, escrow_id: u64) -> Result<()> {
-        let escrow = &ctx.accounts.escrow;
-        require!(escrow.state == EscrowState::Delivered, ClawscrowError::InvalidState);
-        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        let agreement = &mut ctx.accounts.agreement;
+        agreement.periods_spawned += 1;
+        agreement.next_period_at = deadline_ts;
+        agreement.last_escrow_id = Some(escrow_id);
+        let period = agreement.periods_spawned;
 
-        let review_period: i64 = 3 * 24 * 60 * 60;
-        let now = Clock::get()?.unix_timestamp;
-        require!(now >= escrow.delivered_at + review_period, ClawscrowError::ReviewPeriodActive);
+        let index = &mut ctx.accounts.arbitrator_index;
+        index.init_if_empty(ctx.accounts.agreement.arbitrator, ctx.bumps.arbitrator_index);
+        require!(index.escrows.len() < MAX_INDEXED_ESCROWS, ClawscrowError::ArbitratorIndexFull);
+        index.escrows.push(ctx.accounts.escrow.key());
 
-        let payment = escrow.payment_amount;
-        let seller_col = escrow.seller_collateral;
-        let buyer_col = escrow.buyer_collateral;
-        let bump = escrow.bump;
+        let stats = &mut ctx.accounts.stats;
+        stats.bump = ctx.bumps.stats;
+        stats.record_created(payment_amount);
 
-        let id_bytes = escrow_id.to_le_bytes();
-        let seeds: &[&[u8]] = &[b"escrow", id_bytes.as_ref(), &[bump]];
-        let signer_seeds = &[seeds];
+        let buyer_index = &mut ctx.accounts.buyer_index;
+        buyer_index.init_if_empty(ctx.accounts.buyer.key(), ctx.bumps.buyer_index);
+        buyer_index.push(ctx.accounts.escrow.key());
 
-        let seller_total = payment.checked_add(seller_col).ok_or(ClawscrowError::Overflow)?;
+        let total = payment_amount.checked_add(buyer_collateral)
+            .ok_or(ClawscrowError::Overflow)?;
 
         token::transfer(
-            CpiContext::new_with_signer(
+            CpiContext::new(
                 ctx.accounts.token_program.to_account_info(),
                 Transfer {
-                    from: ctx.accounts.vault.to_account_info(),
-                    to: ctx.accounts.seller_token.to_account_info(),
-                    authority: ctx.accounts.escrow.to_account_info(),
+                    from: ctx.accounts.buyer_token.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                    authority: ctx.accounts.buyer.to_account_info(),
                 },
-                signer_seeds,
             ),
-            seller_total,
+            total,
         )?;
 
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.buyer.key(), ctx.remaining_accounts)?;
+        emit_cpi!(EscrowCreated {
+            escrow_id,
+            meta,
+            buyer: ctx.accounts.buyer.key(),
+            mint: ctx.accounts.mint.key(),
+            payment_amount,
+            buyer_collateral,
+            seller_collateral,
+            decimals: ctx.accounts.mint.decimals,
+            terms_hash,
+            intended_seller: Some(seller),
+            contract: None,
+        });
+        emit!(RecurringEscrowSpawned { agreement: ctx.accounts.agreement.key(), escrow_id, meta, period });
+
+        Ok(())
+    }
+
+    /// Buyer ends a recurring agreement. Escrows already spawned run their
+    /// course; no further periods can be spawned.
+    pub fn close_recurring_agreement(ctx: Context<CloseRecurringAgreement>, agreement_id: u64) -> Result<()> {
+        emit!(RecurringAgreementClosed {
+            agreement: ctx.accounts.agreement.key(),
+            agreement_id,
+            periods_spawned: ctx.accounts.agreement.periods_spawned,
+            timestamp: unix_now(ctx.remaining_accounts)?,
+        });
+
+        Ok(())
+    }
+
+    /// Pin the time seen by handlers that are passed the `TestClock` account.
+    /// Rejected unless built with `test-harness`.
+    pub fn set_test_clock(ctx: Context<SetTestClock>, unix_timestamp: i64) -> Result<()> {
+        require!(cfg!(feature = "test-harness"), ClawscrowError::HarnessDisabled);
+        let clock = &mut ctx.accounts.test_clock;
+        clock.unix_timestamp = unix_timestamp;
+        clock.bump = ctx.bumps.test_clock;
+        Ok(())
+    }
+
+    /// Set the ID the buyer's next escrow will get. Rejected unless built with `test-harness`.
+    pub fn seed_escrow_counter(ctx: Context<SeedEscrowCounter>, next_id: u64) -> Result<()> {
+        require!(cfg!(feature = "test-harness"), ClawscrowError::HarnessDisabled);
+        let counter = &mut ctx.accounts.counter;
+        counter.init_if_empty(ctx.accounts.buyer.key(), ctx.bumps.counter);
+        counter.count = next_id;
+        Ok(())
+    }
+
+    /// The named arbitrator opts in to an escrow. Until they do, no seller can
+    /// accept, so a dispute can never land on someone who never agreed to rule.
+    pub fn accept_arbitration_role(ctx: Context<AcceptArbitrationRole>, escrow_id: u64) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Created, ClawscrowError::InvalidState);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        require!(!escrow.arbitrator_accepted, ClawscrowError::InvalidState);
+        escrow.arbitrator_accepted = true;
+
+        let now = unix_now(ctx.remaining_accounts)?;
+        let profile = &mut ctx.accounts.profile;
+        profile.init_if_empty(ctx.accounts.arbitrator.key(), now, ctx.bumps.profile);
+        profile.roles_accepted = profile.roles_accepted.saturating_add(1);
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.arbitrator.key(), ctx.remaining_accounts)?;
+        emit!(ArbitrationRoleAccepted { escrow_id, meta, arbitrator: ctx.accounts.arbitrator.key() });
+
+        Ok(())
+    }
+
+    /// Stake `Config::arbitrator_stake_lamports` and join the registry that
+    /// random arbitrator draws pick from.
+    pub fn register_arbitrator(ctx: Context<RegisterArbitrator>) -> Result<()> {
+        let arbitrator = ctx.accounts.arbitrator.key();
+        let amount = ctx.accounts.config.arbitrator_stake_lamports;
+        if amount > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.arbitrator.to_account_info(),
+                        to: ctx.accounts.stake.to_account_info(),
+                    },
+                ),
+                amount,
+            )?;
+        }
+
+        let stake = &mut ctx.accounts.stake;
+        stake.arbitrator = arbitrator;
+        stake.amount = amount;
+        stake.bump = ctx.bumps.stake;
+
+        let registry = &mut ctx.accounts.registry;
+        registry.bump = ctx.bumps.registry;
+        require!(
+            registry.arbitrators.len() < MAX_REGISTERED_ARBITRATORS,
+            ClawscrowError::ArbitratorRegistryFull
+        );
+        registry.arbitrators.push(arbitrator);
+
+        emit!(ArbitratorRegistered { arbitrator, stake: amount });
+
+        Ok(())
+    }
+
+    /// Leave the registry and take the stake back. Draws already made keep
+    /// their arbitrator.
+    pub fn deregister_arbitrator(ctx: Context<DeregisterArbitrator>) -> Result<()> {
+        let arbitrator = ctx.accounts.arbitrator.key();
+        ctx.accounts.registry.arbitrators.retain(|key| *key != arbitrator);
+
+        emit!(ArbitratorDeregistered { arbitrator, stake: ctx.accounts.stake.amount });
+
+        Ok(())
+    }
+
+    /// Buyer hands the choice of arbitrator to a VRF draw from the staked
+    /// registry, made only once a dispute is raised. Until then the escrow's
+    /// arbitrator is its `RandomArbitration` PDA, which cannot sign, so
+    /// nobody can rule; the terms hash is recomputed to commit to that.
+    pub fn enable_random_arbitrator(ctx: Context<EnableRandomArbitrator>, escrow_id: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Created, ClawscrowError::InvalidState);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        require!(
+            escrow.panel.is_none() && escrow.backup_arbitrator.is_none(),
+            ClawscrowError::RandomArbitratorUnavailable
+        );
+
+        let draw = ctx.accounts.random_arbitration.key();
+        let terms_hash = terms::TermsV1 {
+            buyer: escrow.buyer,
+            seller: escrow.seller,
+            arbitrator: draw,
+            mint: escrow.mint,
+            payment_amount: escrow.payment_amount,
+            buyer_collateral: escrow.buyer_collateral,
+            seller_collateral: escrow.seller_collateral,
+            deadline_ts: escrow.deadline_ts,
+            description_hash: terms::description_hash(&escrow.description),
+            milestone_amounts: escrow.milestones.iter().map(|m| m.amount).collect(),
+            arbitration_sla: escrow.arbitration_sla.clone(),
+            platform: escrow.platform,
+        }.hash()?;
+
+        let escrow_key = ctx.accounts.escrow.key();
+        ctx.accounts.arbitrator_index.escrows.retain(|key| *key != escrow_key);
+        let draw_index = &mut ctx.accounts.draw_index;
+        draw_index.init_if_empty(draw, ctx.bumps.draw_index);
+        draw_index.escrows.push(escrow_key);
+
+        let random = &mut ctx.accounts.random_arbitration;
+        random.escrow = escrow_key;
+        random.status = RandomDrawStatus::Idle;
+        random.seed = draw_seed(&escrow_key);
+        random.bump = ctx.bumps.random_arbitration;
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.arbitrator = draw;
+        escrow.arbitrator_accepted = true;
+        escrow.terms_hash = terms_hash;
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.buyer.key(), ctx.remaining_accounts)?;
+        emit!(RandomArbitratorEnabled { escrow_id, meta, random_arbitration: draw, terms_hash });
+
+        Ok(())
+    }
+
+    /// A prospective seller proposes their own price and deadline for an open
+    /// escrow. Placing again replaces the bidder's previous bid.
+    pub fn place_bid(
+        ctx: Context<PlaceBid>,
+        escrow_id: u64,
+        payment_amount: u64,
+        deadline_ts: i64,
+    ) -> Result<()> {
+        require_credential(&ctx.accounts.config, ctx.accounts.bidder.key(), ctx.accounts.bidder_credential.as_ref())?;
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Created, ClawscrowError::InvalidState);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        let bidder = ctx.accounts.bidder.key();
+        require!(
+            escrow.seller == Pubkey::default() || escrow.seller == bidder,
+            ClawscrowError::Unauthorized
+        );
+        require!(escrow.milestones.is_empty(), ClawscrowError::InvalidBid);
+        require!(payment_amount > 0, ClawscrowError::InvalidAmount);
+        let now = unix_now(ctx.remaining_accounts)?;
+        require!(deadline_ts > now, ClawscrowError::InvalidDeadline);
+        if let Some(accept_by) = escrow.accept_by_ts {
+            require!(now <= accept_by, ClawscrowError::AcceptanceExpired);
+            require!(deadline_ts >= accept_by, ClawscrowError::InvalidDeadline);
+        }
+
+        let bid = &mut ctx.accounts.bid;
+        bid.escrow = ctx.accounts.escrow.key();
+        bid.bidder = bidder;
+        bid.payment_amount = payment_amount;
+        bid.deadline_ts = deadline_ts;
+        bid.placed_at = now;
+        bid.bump = ctx.bumps.bid;
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.bidder.key(), ctx.remaining_accounts)?;
+        emit!(BidPlaced { escrow_id, meta, bidder, payment_amount, deadline_ts });
+
+        Ok(())
+    }
+
+    /// Bidder withdraws a bid that was not selected.
+    pub fn withdraw_bid(ctx: Context<WithdrawBid>, escrow_id: u64) -> Result<()> {
+        emit!(BidWithdrawn {
+            escrow_id,
+            escrow: ctx.accounts.bid.escrow,
+            bidder: ctx.accounts.bidder.key(),
+            timestamp: unix_now(ctx.remaining_accounts)?,
+        });
+        Ok(())
+    }
+
+    /// Buyer picks a bid: the escrow takes the bid's price and deadline, the
+    /// vault is topped up or refunded to match, and only the bidder may accept.
+    pub fn select_bid(ctx: Context<SelectBid>, escrow_id: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Created, ClawscrowError::InvalidState);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        let now = unix_now(ctx.remaining_accounts)?;
+        let bid = &ctx.accounts.bid;
+        require!(bid.deadline_ts > now, ClawscrowError::InvalidDeadline);
+
+        let old_payment = escrow.payment_amount;
+        let (bidder, payment_amount, deadline_ts) = (bid.bidder, bid.payment_amount, bid.deadline_ts);
+        let terms_hash = terms::TermsV1 {
+            buyer: escrow.buyer,
+            seller: bidder,
+            arbitrator: escrow.arbitrator,
+            mint: escrow.mint,
+            payment_amount,
+            buyer_collateral: escrow.buyer_collateral,
+            seller_collateral: escrow.seller_collateral,
+            deadline_ts,
+            description_hash: terms::description_hash(&escrow.description),
+            milestone_amounts: Vec::new(),
+            arbitration_sla: escrow.arbitration_sla.clone(),
+            platform: escrow.platform,
+        }.hash()?;
+        let bump = escrow.bump;
+        let buyer_key = escrow.original_buyer;
+
+        if payment_amount > old_payment {
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.buyer_token.to_account_info(),
+                        to: ctx.accounts.vault.to_account_info(),
+                        authority: ctx.accounts.buyer.to_account_info(),
+                    },
+                ),
+                payment_amount - old_payment,
+            )?;
+        } else {
+            let refund = old_payment - payment_amount;
+            let id_bytes = escrow_id.to_le_bytes();
+            let seeds: &[&[u8]] = &[b"escrow", buyer_key.as_ref(), id_bytes.as_ref(), &[bump]];
+            let signer_seeds = &[seeds];
+
+            ensure_vault_covers(&ctx.accounts.vault, &[refund])?;
+
+            vault_transfer(
+                &ctx.accounts.token_program,
+                &ctx.accounts.vault,
+                ctx.accounts.buyer_payout_token.to_account_info(),
+                &ctx.accounts.escrow,
+                signer_seeds,
+                refund,
+            )?;
+        }
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.seller = bidder;
+        escrow.payment_amount = payment_amount;
+        escrow.deadline_ts = deadline_ts;
+        escrow.terms_hash = terms_hash;
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.buyer.key(), ctx.remaining_accounts)?;
+        emit!(BidSelected { escrow_id, meta, bidder, payment_amount, deadline_ts, terms_hash });
+
+        Ok(())
+    }
+
+    /// Move a `Draft` into `Created` by pulling whatever `deposit` has not
+    /// yet covered from the token account named at creation. The buyer can
+    /// sign as its owner; anyone else, typically the seller just before
+    /// `accept_escrow` in the same transaction, relies on the buyer having
+    /// delegated at least that much to the escrow PDA.
+    pub fn fund_escrow(ctx: Context<FundEscrow>, escrow_id: u64) -> Result<()> {
+        require!(!ctx.accounts.config.paused, ClawscrowError::ProtocolPaused);
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Draft, ClawscrowError::InvalidState);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        require!(!is_pooled(escrow), ClawscrowError::PooledEscrow);
+
+        let due = escrow.deposit_due().ok_or(ClawscrowError::Overflow)?;
+        let buyer_token = &ctx.accounts.buyer_token;
+        let owner_signed = ctx.accounts.signer.key() == buyer_token.owner;
+        if !owner_signed {
+            require!(
+                buyer_token.delegate == COption::Some(escrow.key()) && buyer_token.delegated_amount >= due,
+                ClawscrowError::FundsNotDelegated
+            );
+        }
+
+        let id_bytes = escrow_id.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"escrow", escrow.original_buyer.as_ref(), id_bytes.as_ref(), &[escrow.bump]];
+        let (authority, signer_seeds): (AccountInfo, &[&[&[u8]]]) = if owner_signed {
+            (ctx.accounts.signer.to_account_info(), &[])
+        } else {
+            (escrow.to_account_info(), &[seeds])
+        };
         token::transfer(
             CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
                 Transfer {
-                    from: ctx.accounts.vault.to_account_info(),
-                    to: ctx.accounts.buyer_token.to_account_info(),
-                    authority: ctx.accounts.escrow.to_account_info(),
+                    from: buyer_token.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                    authority,
                 },
                 signer_seeds,
             ),
-            buyer_col,
+            due,
         )?;
 
         let escrow = &mut ctx.accounts.escrow;
-        escrow.state = EscrowState::Approved;
+        escrow.credit_deposit(due).ok_or(ClawscrowError::Overflow)?;
+        let total = escrow.funded_amount;
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.signer.key(), ctx.remaining_accounts)?;
+        emit!(EscrowFunded { escrow_id, meta, amount: total });
+
+        Ok(())
+    }
+
+    /// Buyer pays part of a `Draft`'s payment and collateral into the vault.
+    /// Amounts beyond what is still due are capped; the deposit that completes
+    /// the total opens the escrow as `Created`, so acceptance waits for it.
+    pub fn deposit(ctx: Context<Deposit>, escrow_id: u64, amount: u64) -> Result<()> {
+        require!(!ctx.accounts.config.paused, ClawscrowError::ProtocolPaused);
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Draft, ClawscrowError::InvalidState);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        require!(!is_pooled(escrow), ClawscrowError::PooledEscrow);
+        let amount = amount.min(escrow.deposit_due().ok_or(ClawscrowError::Overflow)?);
+        require!(amount > 0, ClawscrowError::InvalidAmount);
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.buyer_token.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                    authority: ctx.accounts.buyer.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let escrow = &mut ctx.accounts.escrow;
+        let completed = escrow.credit_deposit(amount).ok_or(ClawscrowError::Overflow)?;
+        let funded = escrow.funded_amount;
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.buyer.key(), ctx.remaining_accounts)?;
+        emit!(DepositReceived { escrow_id, meta, amount, funded });
+        if completed {
+            emit!(EscrowFunded { escrow_id, meta, amount: funded });
+        }
+
+        Ok(())
+    }
+
+    /// Turn an empty `Draft` into a pooled escrow that any wallet can fund
+    /// with `contribute`. Buyer-side payouts and refunds then land in the
+    /// escrow PDA's own token account, and each contributor claims their
+    /// pro-rata share with `claim_contribution` once the escrow is settled.
+    /// Pooled escrows need `accept_by_ts`, so `expire_unaccepted` can always
+    /// hand contributions back if nobody takes the job.
+    pub fn open_pool(ctx: Context<OpenPool>, escrow_id: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Draft, ClawscrowError::InvalidState);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        require!(escrow.funded_amount == 0 && !is_pooled(escrow), ClawscrowError::InvalidState);
+        require!(escrow.accept_by_ts.is_some(), ClawscrowError::InvalidDeadline);
+
+        let (escrow_key, pool) = (escrow.key(), ctx.accounts.pool.key());
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.buyer_payout = Some(escrow_key);
+        escrow.buyer_payout_token = pool;
+        escrow.funding_source = None;
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.buyer.key(), ctx.remaining_accounts)?;
+        emit!(PoolOpened { escrow_id, meta, pool });
+
+        Ok(())
+    }
+
+    /// Fund part of a pooled `Draft`, recording the amount in the caller's
+    /// `Contribution`. Amounts beyond what is still due are capped.
+    pub fn contribute(ctx: Context<Contribute>, escrow_id: u64, amount: u64) -> Result<()> {
+        require!(!ctx.accounts.config.paused, ClawscrowError::ProtocolPaused);
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Draft, ClawscrowError::InvalidState);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        require!(is_pooled(escrow), ClawscrowError::InvalidState);
+        let amount = amount.min(escrow.deposit_due().ok_or(ClawscrowError::Overflow)?);
+        require!(amount > 0, ClawscrowError::InvalidAmount);
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.contributor_token.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                    authority: ctx.accounts.contributor.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let contributor = ctx.accounts.contributor.key();
+        let contribution = &mut ctx.accounts.contribution;
+        if contribution.contributor == Pubkey::default() {
+            contribution.escrow = ctx.accounts.escrow.key();
+            contribution.contributor = contributor;
+            contribution.bump = ctx.bumps.contribution;
+        }
+        contribution.amount = contribution.amount.checked_add(amount).ok_or(ClawscrowError::Overflow)?;
+
+        let escrow = &mut ctx.accounts.escrow;
+        let completed = escrow.credit_deposit(amount).ok_or(ClawscrowError::Overflow)?;
+        let funded = escrow.funded_amount;
+
+        let meta = event_meta(&ctx.accounts.escrow, contributor, ctx.remaining_accounts)?;
+        emit!(ContributionReceived { escrow_id, meta, amount, funded });
+        if completed {
+            emit!(EscrowFunded { escrow_id, meta, amount: funded });
+        }
+
+        Ok(())
+    }
+
+    /// Contributor takes their pro-rata share of whatever the buyer side of a
+    /// settled pooled escrow received, closing their `Contribution`.
+    pub fn claim_contribution(ctx: Context<ClaimContribution>, escrow_id: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state.is_terminal(), ClawscrowError::InvalidState);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+
+        let contributed = ctx.accounts.contribution.amount;
+        // `funded_amount` counts down as contributors claim, so the last
+        // claimant sweeps any rounding dust.
+        let share = math::pro_rata(ctx.accounts.pool.amount, contributed, escrow.funded_amount)
+            .ok_or(ClawscrowError::Overflow)?;
+
+        let id_bytes = escrow_id.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"escrow", escrow.original_buyer.as_ref(), id_bytes.as_ref(), &[escrow.bump]];
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.pool,
+            ctx.accounts.contributor_token.to_account_info(),
+            &ctx.accounts.escrow,
+            &[seeds],
+            share,
+        )?;
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.funded_amount -= contributed;
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.contributor.key(), ctx.remaining_accounts)?;
+        emit!(ContributionClaimed { escrow_id, meta, contributed, amount: share });
+
+        Ok(())
+    }
+
+    pub fn accept_escrow(
+        ctx: Context<AcceptEscrow>,
+        escrow_id: u64,
+        seller_payout: Option<Pubkey>,
+    ) -> Result<()> {
+        require!(!ctx.accounts.config.paused, ClawscrowError::ProtocolPaused);
+        require_credential(&ctx.accounts.config, ctx.accounts.seller.key(), ctx.accounts.seller_credential.as_ref())?;
+        let escrow = &mut ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Created, ClawscrowError::InvalidState);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        // Private escrows and renewals name their seller; only that key may accept.
+        if escrow.seller != Pubkey::default() {
+            require!(ctx.accounts.seller.key() == escrow.seller, ClawscrowError::Unauthorized);
+        }
+        if let Some(accept_by) = escrow.accept_by_ts {
+            require!(unix_now(ctx.remaining_accounts)? <= accept_by, ClawscrowError::AcceptanceExpired);
+        }
+        require!(escrow.arbitrator_accepted, ClawscrowError::ArbitratorNotAccepted);
+        let collateral = escrow.seller_collateral;
+        let eid = escrow.escrow_id;
+
+        escrow.seller = ctx.accounts.seller.key();
+        escrow.seller_payout = seller_payout;
+        escrow.seller_payout_token = escrow.seller_payout_ata();
+        escrow.state = EscrowState::Accepted;
+        escrow.accepted_at = unix_now(ctx.remaining_accounts)?;
+        escrow.last_heartbeat_at = escrow.accepted_at;
+
+        let seller_index = &mut ctx.accounts.seller_index;
+        seller_index.init_if_empty(ctx.accounts.seller.key(), ctx.bumps.seller_index);
+        seller_index.push(ctx.accounts.escrow.key());
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.seller_token.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                    authority: ctx.accounts.seller.to_account_info(),
+                },
+            ),
+            collateral,
+        )?;
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.seller.key(), ctx.remaining_accounts)?;
+        emit_cpi!(EscrowAccepted { escrow_id: eid, meta, seller: ctx.accounts.seller.key() });
+
+        Ok(())
+    }
+
+    /// Gasless `accept_escrow`: a relayer (often the buyer) submits the
+    /// seller's off-chain ed25519 signature over `terms::AcceptanceV1`, checked
+    /// through the ed25519 program instruction placed just before this one.
+    /// Seller collateral is pulled from `seller_token`, which must have
+    /// delegated at least that much to the escrow PDA.
+    pub fn accept_escrow_with_sig(
+        ctx: Context<AcceptEscrowWithSig>,
+        escrow_id: u64,
+        seller_payout: Option<Pubkey>,
+    ) -> Result<()> {
+        require!(!ctx.accounts.config.paused, ClawscrowError::ProtocolPaused);
+        require_credential(&ctx.accounts.config, ctx.accounts.seller.key(), ctx.accounts.seller_credential.as_ref())?;
+        let seller = ctx.accounts.seller.key();
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Created, ClawscrowError::InvalidState);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        if escrow.seller != Pubkey::default() {
+            require!(seller == escrow.seller, ClawscrowError::Unauthorized);
+        }
+        if let Some(accept_by) = escrow.accept_by_ts {
+            require!(unix_now(ctx.remaining_accounts)? <= accept_by, ClawscrowError::AcceptanceExpired);
+        }
+        require!(escrow.arbitrator_accepted, ClawscrowError::ArbitratorNotAccepted);
+
+        // The seller signs for the account their payouts will go to.
+        let seller_payout_token = get_associated_token_address(&seller_payout.unwrap_or(seller), &escrow.mint);
+
+        let acceptance = terms::AcceptanceV1 {
+            escrow: escrow.key(),
+            terms_hash: escrow.terms_hash,
+            seller_payout_token,
+            seller_payout,
+        };
+        sigverify::verify_previous_ed25519(&ctx.accounts.instructions, &seller, &acceptance.hash()?)?;
+
+        let collateral = escrow.seller_collateral;
+        if collateral > 0 {
+            let seller_token = &ctx.accounts.seller_token;
+            require!(
+                seller_token.delegate == COption::Some(escrow.key()) && seller_token.delegated_amount >= collateral,
+                ClawscrowError::CollateralNotDelegated
+            );
+            let id_bytes = escrow_id.to_le_bytes();
+            let seeds: &[&[u8]] = &[b"escrow", escrow.original_buyer.as_ref(), id_bytes.as_ref(), &[escrow.bump]];
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: seller_token.to_account_info(),
+                        to: ctx.accounts.vault.to_account_info(),
+                        authority: escrow.to_account_info(),
+                    },
+                    &[seeds],
+                ),
+                collateral,
+            )?;
+        }
+
+        let now = unix_now(ctx.remaining_accounts)?;
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.seller = seller;
+        escrow.seller_payout = seller_payout;
+        escrow.seller_payout_token = seller_payout_token;
+        escrow.state = EscrowState::Accepted;
+        escrow.accepted_at = now;
+        escrow.last_heartbeat_at = now;
+
+        let seller_index = &mut ctx.accounts.seller_index;
+        seller_index.init_if_empty(seller, ctx.bumps.seller_index);
+        seller_index.push(ctx.accounts.escrow.key());
+
+        let meta = event_meta(&ctx.accounts.escrow, seller, ctx.remaining_accounts)?;
+        emit_cpi!(EscrowAccepted { escrow_id, meta, seller });
+
+        Ok(())
+    }
+
+    pub fn cancel_escrow(ctx: Context<CancelEscrow>, escrow_id: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(
+            matches!(escrow.state, EscrowState::Created | EscrowState::Draft),
+            ClawscrowError::InvalidState
+        );
+        require!(ctx.accounts.buyer.key() == escrow.buyer, ClawscrowError::Unauthorized);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        require!(!is_pooled(escrow) || escrow.funded_amount == 0, ClawscrowError::ContributionsUnclaimed);
+
+        let refund = escrow.funded_deposit().ok_or(ClawscrowError::Overflow)?;
+        let bump = escrow.bump;
+        let buyer_key = escrow.original_buyer;
+
+        let id_bytes = escrow_id.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"escrow", buyer_key.as_ref(), id_bytes.as_ref(), &[bump]];
+        let signer_seeds = &[seeds];
+
+        ensure_vault_covers(&ctx.accounts.vault, &[refund])?;
+
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.buyer_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            refund,
+        )?;
+
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.vault.to_account_info(),
+                destination: ctx.accounts.buyer.to_account_info(),
+                authority: ctx.accounts.escrow.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+
+        // The escrow is closed below, so a second leg is refunded with it.
+        if escrow.second_leg.is_some() {
+            let (Some(second_vault), Some(buyer_second_token)) =
+                (&ctx.accounts.second_vault, &ctx.accounts.buyer_second_token)
+            else {
+                return err!(ClawscrowError::SecondLegAccountsMissing);
+            };
+            vault_transfer(
+                &ctx.accounts.token_program,
+                second_vault,
+                buyer_second_token.to_account_info(),
+                escrow,
+                signer_seeds,
+                second_vault.amount,
+            )?;
+            token::close_account(CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                CloseAccount {
+                    account: second_vault.to_account_info(),
+                    destination: ctx.accounts.buyer.to_account_info(),
+                    authority: escrow.to_account_info(),
+                },
+                signer_seeds,
+            ))?;
+        }
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.state = EscrowState::Cancelled;
+        release_open_slot(escrow, ctx.accounts.counter.as_mut())?;
+        let escrow_key = ctx.accounts.escrow.key();
+        ctx.accounts.arbitrator_index.escrows.retain(|key| *key != escrow_key);
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.buyer.key(), ctx.remaining_accounts)?;
+        emit_cpi!(EscrowCancelled { escrow_id, meta });
+
+        Ok(())
+    }
+
+    /// Permissionless refund of an offer nobody accepted in time.
+    pub fn expire_unaccepted(ctx: Context<ExpireUnaccepted>, escrow_id: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(
+            matches!(escrow.state, EscrowState::Created | EscrowState::Draft),
+            ClawscrowError::InvalidState
+        );
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        let accept_by = escrow.accept_by_ts.ok_or(ClawscrowError::InvalidDeadline)?;
+        require!(unix_now(ctx.remaining_accounts)? > accept_by, ClawscrowError::DeadlineNotReached);
+
+        let refund = escrow.funded_deposit().ok_or(ClawscrowError::Overflow)?;
+        let bump = escrow.bump;
+        let buyer_key = escrow.original_buyer;
+
+        let id_bytes = escrow_id.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"escrow", buyer_key.as_ref(), id_bytes.as_ref(), &[bump]];
+        let signer_seeds = &[seeds];
+
+        ensure_vault_covers(&ctx.accounts.vault, &[refund])?;
+
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.buyer_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            refund,
+        )?;
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.state = EscrowState::Cancelled;
+        release_open_slot(escrow, ctx.accounts.counter.as_mut())?;
+        let escrow_key = ctx.accounts.escrow.key();
+        ctx.accounts.arbitrator_index.escrows.retain(|key| *key != escrow_key);
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.signer.key(), ctx.remaining_accounts)?;
+        emit_cpi!(EscrowExpired { escrow_id, meta, refunded: refund });
+
+        Ok(())
+    }
+
+    /// Keeper-callable check of the vault against what the escrow's state says it
+    /// should hold. Any difference is recorded for the admin to resolve.
+    pub fn reconcile_vault(ctx: Context<ReconcileVault>, escrow_id: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+
+        let expected = escrow.expected_vault_balance().ok_or(ClawscrowError::Overflow)?;
+        let actual = ctx.accounts.vault.amount;
+        let discrepancy = (actual as i128 - expected as i128)
+            .clamp(i64::MIN as i128, i64::MAX as i128) as i64;
+
+        ctx.accounts.escrow.vault_discrepancy = discrepancy;
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.signer.key(), ctx.remaining_accounts)?;
+        emit!(VaultReconciled { escrow_id, meta, expected, actual, discrepancy });
+
+        Ok(())
+    }
+
+    /// Permissionless upgrade of an escrow written by an older program
+    /// version: rewrites it to the current layout and grows it to the current
+    /// size, with the payer covering the extra rent. Fields added since are
+    /// appended, and everything past the old layout is zeroed so they read as zero.
+    pub fn migrate_escrow(ctx: Context<MigrateEscrow>) -> Result<()> {
+        let info = ctx.accounts.escrow.to_account_info();
+        let from_version = {
+            let mut data = info.try_borrow_mut_data()?;
+            require!(
+                data.len() > layout::DESCRIPTION_OFFSET && data[..8] == Escrow::DISCRIMINATOR,
+                ClawscrowError::UnsupportedLayout
+            );
+            let version = data[layout::LAYOUT_VERSION_OFFSET];
+            require!(version != ESCROW_LAYOUT_VERSION, ClawscrowError::AlreadyMigrated);
+            // Once its state byte has moved, a v1 escrow is laid out like v2.
+            let source = match version {
+                1 => {
+                    layout::migrate_v1(&mut data)?;
+                    2
+                }
+                _ => version,
+            };
+            // Later layouts only appended fields, which must read as zero
+            // rather than whatever an earlier, longer write left behind.
+            layout::clear_tail(&mut data, source)?;
+            data[layout::LAYOUT_VERSION_OFFSET] = ESCROW_LAYOUT_VERSION;
+            version
+        };
+
+        grow_account(&ctx.accounts.payer, &info, &ctx.accounts.system_program, 8 + Escrow::INIT_SPACE)?;
+
+        // Fail the whole upgrade rather than leave an account that cannot be read.
+        let mut escrow = Escrow::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        // Every escrow from before v8 is still held by the buyer it was derived from.
+        if from_version < 8 {
+            escrow.original_buyer = escrow.buyer;
+            escrow.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+        }
+
+        emit!(EscrowMigrated {
+            escrow_id: escrow.escrow_id,
+            meta: EscrowEventMeta {
+                escrow: info.key(),
+                mint: escrow.mint,
+                actor: ctx.accounts.payer.key(),
+                timestamp: unix_now(ctx.remaining_accounts)?,
+            },
+            from_version,
+            to_version: ESCROW_LAYOUT_VERSION,
+        });
+
+        Ok(())
+    }
+
+    /// Permissionless upgrade of a buyer's counter from before
+    /// `open_escrows`, growing it with the payer covering the extra rent.
+    /// Escrows opened before the upgrade are not counted against the cap.
+    pub fn migrate_counter(ctx: Context<MigrateCounter>) -> Result<()> {
+        let info = ctx.accounts.counter.to_account_info();
+        let new_len = 8 + Counter::INIT_SPACE;
+        {
+            let data = info.try_borrow_data()?;
+            require!(data.len() >= 8 && data[..8] == Counter::DISCRIMINATOR, ClawscrowError::UnsupportedLayout);
+            require!(data.len() < new_len, ClawscrowError::AlreadyMigrated);
+        }
+        grow_account(&ctx.accounts.payer, &info, &ctx.accounts.system_program, new_len)?;
+        // Fail the whole upgrade rather than leave an account that cannot be read.
+        Counter::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+
+        Ok(())
+    }
+
+    /// Buyer opts into heartbeat monitoring before the offer is taken, so the
+    /// seller sees the policy when accepting.
+    pub fn set_heartbeat_policy(
+        ctx: Context<EscrowPartyAction>,
+        escrow_id: u64,
+        policy: Option<HeartbeatPolicy>,
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Created, ClawscrowError::InvalidState);
+        require!(ctx.accounts.signer.key() == escrow.buyer, ClawscrowError::Unauthorized);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        if let Some(p) = &policy {
+            require!(p.interval_secs > 0 && p.max_missed > 0, ClawscrowError::InvalidHeartbeatPolicy);
+        }
+
+        escrow.heartbeat_policy = policy.clone();
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.signer.key(), ctx.remaining_accounts)?;
+        emit!(HeartbeatPolicySet { escrow_id, meta, policy });
+
+        Ok(())
+    }
+
+    /// Seller registers how their payout is fanned out, e.g. across an
+    /// agency's members. Every release to the seller, whether an approval,
+    /// milestone, timesheet, partial approval, vesting claim or seller-wins
+    /// ruling, then pays everything above the seller's own collateral to these
+    /// wallets by weight. An empty list removes the split.
+    pub fn set_payout_split(
+        ctx: Context<SetPayoutSplit>,
+        escrow_id: u64,
+        recipients: Vec<SplitRecipient>,
+    ) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(
+            matches!(escrow.state, EscrowState::Accepted | EscrowState::Delivered),
+            ClawscrowError::InvalidState
+        );
+        require!(ctx.accounts.seller.key() == escrow.seller, ClawscrowError::Unauthorized);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        if !recipients.is_empty() {
+            require!(recipients.len() <= MAX_SPLIT_RECIPIENTS, ClawscrowError::InvalidPayoutSplit);
+            let weights: Vec<u16> = recipients.iter().map(|r| r.bps).collect();
+            require!(
+                weights.iter().all(|&bps| bps > 0) && math::weighted_shares(0, &weights).is_some(),
+                ClawscrowError::InvalidPayoutSplit
+            );
+        }
+
+        let split = &mut ctx.accounts.payout_split;
+        split.escrow = ctx.accounts.escrow.key();
+        split.recipients = recipients.clone();
+        split.bump = ctx.bumps.payout_split;
+        ctx.accounts.escrow.has_payout_split = !recipients.is_empty();
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.seller.key(), ctx.remaining_accounts)?;
+        emit!(PayoutSplitSet { escrow_id, meta, recipients });
+
+        Ok(())
+    }
+
+    /// Seller moves their payouts to another owner, e.g. a cold wallet, any
+    /// time before the escrow settles. Every seller-side payout from then on
+    /// goes to `seller_payout`'s (default: the seller's) associated token
+    /// account, exactly as when it was first chosen at acceptance.
+    pub fn set_seller_payout(
+        ctx: Context<SetSellerPayout>,
+        escrow_id: u64,
+        seller_payout: Option<Pubkey>,
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        require!(
+            !escrow.state.is_terminal() && !matches!(escrow.state, EscrowState::Created | EscrowState::Draft),
+            ClawscrowError::InvalidState
+        );
+        require!(ctx.accounts.seller.key() == escrow.seller, ClawscrowError::Unauthorized);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+
+        escrow.seller_payout = seller_payout;
+        escrow.seller_payout_token = escrow.seller_payout_ata();
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.seller.key(), ctx.remaining_accounts)?;
+        emit!(SellerPayoutSet {
+            escrow_id,
+            meta,
+            seller_payout,
+            seller_payout_token: ctx.accounts.escrow.seller_payout_token,
+        });
+
+        Ok(())
+    }
+
+    /// Either party insures the escrow before any dispute by paying the
+    /// configured premium, a share of the payment, into the mint's insurance
+    /// vault. Insured escrows may later draw on it through `claim_insurance`.
+    pub fn insure_escrow(ctx: Context<InsureEscrow>, escrow_id: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(
+            matches!(escrow.state, EscrowState::Created | EscrowState::Accepted | EscrowState::Delivered),
+            ClawscrowError::InvalidState
+        );
+        let payer = ctx.accounts.payer.key();
+        require!(payer == escrow.buyer || payer == escrow.seller, ClawscrowError::Unauthorized);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        require!(!escrow.insured, ClawscrowError::AlreadyInsured);
+        let premium_bps = ctx.accounts.config.insurance_premium_bps;
+        require!(premium_bps > 0, ClawscrowError::InsuranceDisabled);
+        let premium = math::bps_of(escrow.payment_amount, premium_bps).ok_or(ClawscrowError::Overflow)?;
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.payer_token.to_account_info(),
+                    to: ctx.accounts.insurance_vault.to_account_info(),
+                    authority: ctx.accounts.payer.to_account_info(),
+                },
+            ),
+            premium,
+        )?;
+
+        ctx.accounts.escrow.insured = true;
+
+        let meta = event_meta(&ctx.accounts.escrow, payer, ctx.remaining_accounts)?;
+        emit!(EscrowInsured { escrow_id, meta, premium });
+
+        Ok(())
+    }
+
+    /// Pays the winning side of a ruling on an insured escrow for losses the
+    /// counterparty's collateral did not cover. The admin co-signs and sets
+    /// the amount, capped at the payment; each escrow pays out once, to the
+    /// winner's payout account.
+    pub fn claim_insurance(ctx: Context<ClaimInsurance>, escrow_id: u64, amount: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        require!(escrow.insured && !escrow.insurance_claimed, ClawscrowError::NotInsured);
+        let (winner, payout_token) = match escrow.state {
+            EscrowState::ResolvedBuyer => (escrow.buyer, escrow.buyer_payout_ata()),
+            EscrowState::ResolvedSeller => (escrow.seller, escrow.seller_payout_ata()),
+            _ => return err!(ClawscrowError::InvalidState),
+        };
+        let claimant = ctx.accounts.claimant.key();
+        require!(claimant == winner, ClawscrowError::Unauthorized);
+        require!(ctx.accounts.claimant_token.key() == payout_token, ClawscrowError::PayoutAccountMismatch);
+        require!(amount > 0 && amount <= escrow.payment_amount, ClawscrowError::InvalidAmount);
+        require!(ctx.accounts.insurance_vault.amount >= amount, ClawscrowError::VaultImbalance);
+
+        let bump = ctx.accounts.config.bump;
+        let seeds: &[&[u8]] = &[b"config", &[bump]];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.insurance_vault.to_account_info(),
+                    to: ctx.accounts.claimant_token.to_account_info(),
+                    authority: ctx.accounts.config.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount,
+        )?;
+
+        ctx.accounts.escrow.insurance_claimed = true;
+
+        let meta = event_meta(&ctx.accounts.escrow, claimant, ctx.remaining_accounts)?;
+        emit!(InsuranceClaimed { escrow_id, meta, amount });
+
+        Ok(())
+    }
+
+    /// Mints a compressed NFT receipt to both parties of a resolved escrow that
+    /// opted in at creation, into the configured Bubblegum tree. Anyone may
+    /// crank it once; the payer covers the (tiny) cost.
+    pub fn mint_receipts(ctx: Context<MintReceipts>, escrow_id: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(
+            matches!(
+                escrow.state,
+                EscrowState::Approved
+                    | EscrowState::ResolvedBuyer
+                    | EscrowState::ResolvedSeller
+                    | EscrowState::ResolvedSplit
+            ),
+            ClawscrowError::InvalidState
+        );
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        require!(escrow.compressed_receipts, ClawscrowError::ReceiptsNotEnabled);
+        require!(!escrow.receipts_minted, ClawscrowError::ReceiptsAlreadyMinted);
+
+        let authority_seeds: &[&[u8]] = &[b"receipt_authority", &[ctx.bumps.receipt_authority]];
+        for owner in [&ctx.accounts.buyer, &ctx.accounts.seller] {
+            let owner = owner.to_account_info();
+            bubblegum::instructions::MintV1Cpi::new(
+                &ctx.accounts.bubblegum_program,
+                bubblegum::instructions::MintV1CpiAccounts {
+                    tree_config: &ctx.accounts.tree_config,
+                    leaf_owner: &owner,
+                    leaf_delegate: &owner,
+                    merkle_tree: &ctx.accounts.merkle_tree,
+                    payer: &ctx.accounts.payer.to_account_info(),
+                    tree_creator_or_delegate: &ctx.accounts.receipt_authority,
+                    log_wrapper: &ctx.accounts.log_wrapper,
+                    compression_program: &ctx.accounts.compression_program,
+                    system_program: &ctx.accounts.system_program.to_account_info(),
+                },
+                bubblegum::instructions::MintV1InstructionArgs {
+                    metadata: receipt_metadata(escrow_id),
+                },
+            )
+            .invoke_signed(&[authority_seeds])?;
+        }
+
+        ctx.accounts.escrow.receipts_minted = true;
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.payer.key(), ctx.remaining_accounts)?;
+        emit!(ReceiptsMinted { escrow_id, meta, merkle_tree: ctx.accounts.merkle_tree.key() });
+
+        Ok(())
+    }
+
+    /// Mints the seller a non-transferable Token-2022 badge for an approved
+    /// escrow that opted in at creation, alongside a `CompletionBadge` record
+    /// of the escrow, amount and completion time. Anyone may crank it; the
+    /// payer covers rent. The mint's authority is dropped, so supply stays 1.
+    pub fn mint_completion_badge(ctx: Context<MintCompletionBadge>, escrow_id: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Approved, ClawscrowError::InvalidState);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        require!(escrow.completion_badge, ClawscrowError::BadgeNotEnabled);
+
+        let escrow_key = escrow.key();
+        let mint_bump = ctx.bumps.badge_mint;
+        let mint_seeds: &[&[u8]] = &[b"badge_mint", escrow_key.as_ref(), &[mint_bump]];
+        let badge_bump = ctx.bumps.badge;
+        let badge_seeds: &[&[u8]] = &[b"badge", escrow_key.as_ref(), &[badge_bump]];
+        let token_program = ctx.accounts.token_program.to_account_info();
+        let mint_info = ctx.accounts.badge_mint.to_account_info();
+
+        let space = ExtensionType::try_calculate_account_len::<token_2022::spl_token_2022::state::Mint>(&[
+            ExtensionType::NonTransferable,
+        ])?;
+        system_program::create_account(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::CreateAccount {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: mint_info.clone(),
+                },
+                &[mint_seeds],
+            ),
+            Rent::get()?.minimum_balance(space),
+            space as u64,
+            &token_2022::ID,
+        )?;
+        token_2022_extensions::non_transferable_mint_initialize(CpiContext::new(
+            token_program.clone(),
+            token_2022_extensions::NonTransferableMintInitialize {
+                token_program_id: token_program.clone(),
+                mint: mint_info.clone(),
+            },
+        ))?;
+        token_2022::initialize_mint2(
+            CpiContext::new(token_program.clone(), token_2022::InitializeMint2 { mint: mint_info.clone() }),
+            0,
+            &ctx.accounts.badge.key(),
+            None,
+        )?;
+
+        associated_token::create(CpiContext::new(
+            ctx.accounts.associated_token_program.to_account_info(),
+            associated_token::Create {
+                payer: ctx.accounts.payer.to_account_info(),
+                associated_token: ctx.accounts.seller_badge_token.to_account_info(),
+                authority: ctx.accounts.seller.to_account_info(),
+                mint: mint_info.clone(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                token_program: token_program.clone(),
+            },
+        ))?;
+        token_2022::mint_to(
+            CpiContext::new_with_signer(
+                token_program.clone(),
+                token_2022::MintTo {
+                    mint: mint_info.clone(),
+                    to: ctx.accounts.seller_badge_token.to_account_info(),
+                    authority: ctx.accounts.badge.to_account_info(),
+                },
+                &[badge_seeds],
+            ),
+            1,
+        )?;
+        token_2022::set_authority(
+            CpiContext::new_with_signer(
+                token_program,
+                token_2022::SetAuthority {
+                    current_authority: ctx.accounts.badge.to_account_info(),
+                    account_or_mint: mint_info,
+                },
+                &[badge_seeds],
+            ),
+            AuthorityType::MintTokens,
+            None,
+        )?;
+
+        let escrow = &ctx.accounts.escrow;
+        let badge = &mut ctx.accounts.badge;
+        badge.escrow = escrow_key;
+        badge.escrow_id = escrow_id;
+        badge.seller = escrow.seller;
+        badge.mint = ctx.accounts.badge_mint.key();
+        badge.amount = escrow.payment_amount;
+        badge.completed_at = escrow.resolved_at;
+        badge.bump = badge_bump;
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.payer.key(), ctx.remaining_accounts)?;
+        emit!(CompletionBadgeMinted {
+            escrow_id,
+            meta,
+            seller: ctx.accounts.badge.seller,
+            badge_mint: ctx.accounts.badge.mint,
+        });
+
+        Ok(())
+    }
+
+    pub fn heartbeat(ctx: Context<EscrowPartyAction>, escrow_id: u64) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Accepted, ClawscrowError::InvalidState);
+        require!(ctx.accounts.signer.key() == escrow.seller, ClawscrowError::Unauthorized);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+
+        let now = unix_now(ctx.remaining_accounts)?;
+        escrow.last_heartbeat_at = now;
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.signer.key(), ctx.remaining_accounts)?;
+        emit!(HeartbeatRecorded { escrow_id, meta, at: now });
+
+        Ok(())
+    }
+
+    pub fn propose_cancel(ctx: Context<ProposeCancel>) -> Result<()> {
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.signer.key(), ctx.remaining_accounts)?;
+        let escrow = &mut ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Accepted, ClawscrowError::InvalidState);
+        let signer = ctx.accounts.signer.key();
+        require!(signer == escrow.buyer || signer == escrow.seller, ClawscrowError::Unauthorized);
+
+        escrow.cancel_proposed_by = Some(signer);
+
+        emit!(CancelProposed { escrow_id: escrow.escrow_id, meta, proposer: signer });
+
+        Ok(())
+    }
+
+    pub fn confirm_cancel(ctx: Context<ConfirmCancel>, escrow_id: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Accepted, ClawscrowError::InvalidState);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        let signer = ctx.accounts.signer.key();
+        require!(signer == escrow.buyer || signer == escrow.seller, ClawscrowError::Unauthorized);
+        let proposer = escrow.cancel_proposed_by.ok_or(ClawscrowError::NoCancelProposal)?;
+        require!(proposer != signer, ClawscrowError::Unauthorized);
+
+        let buyer_refund = escrow.remaining_payment()
+            .checked_add(escrow.buyer_collateral)
+            .ok_or(ClawscrowError::Overflow)?;
+        let seller_refund = escrow.seller_collateral;
+        let bump = escrow.bump;
+        let buyer_key = escrow.original_buyer;
+
+        let id_bytes = escrow_id.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"escrow", buyer_key.as_ref(), id_bytes.as_ref(), &[bump]];
+        let signer_seeds = &[seeds];
+
+        ensure_vault_covers(&ctx.accounts.vault, &[buyer_refund, seller_refund])?;
+
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.buyer_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            buyer_refund,
+        )?;
+
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.seller_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            seller_refund,
+        )?;
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.state = EscrowState::Cancelled;
+        release_open_slot(escrow, ctx.accounts.counter.as_mut())?;
+        escrow.cancel_proposed_by = None;
+        let escrow_key = ctx.accounts.escrow.key();
+        ctx.accounts.arbitrator_index.escrows.retain(|key| *key != escrow_key);
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.signer.key(), ctx.remaining_accounts)?;
+        emit_cpi!(EscrowCancelled { escrow_id, meta });
+
+        Ok(())
+    }
+
+    /// Buyer proposes a new price for work in progress. An increase is
+    /// deposited now so the seller's confirmation is all that is left.
+    pub fn propose_amendment(
+        ctx: Context<ProposeAmendment>,
+        escrow_id: u64,
+        new_payment_amount: u64,
+    ) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Accepted, ClawscrowError::InvalidState);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        require!(escrow.pending_amendment.is_none(), ClawscrowError::AmendmentPending);
+        require!(escrow.milestones.is_empty(), ClawscrowError::InvalidAmendment);
+        require!(
+            new_payment_amount > 0 && new_payment_amount != escrow.payment_amount,
+            ClawscrowError::InvalidAmendment
+        );
+
+        let deposit = new_payment_amount.saturating_sub(escrow.payment_amount);
+        if deposit > 0 {
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.buyer_token.to_account_info(),
+                        to: ctx.accounts.vault.to_account_info(),
+                        authority: ctx.accounts.buyer.to_account_info(),
+                    },
+                ),
+                deposit,
+            )?;
+        }
+
+        ctx.accounts.escrow.pending_amendment = Some(new_payment_amount);
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.buyer.key(), ctx.remaining_accounts)?;
+        emit!(AmendmentProposed { escrow_id, meta, new_payment_amount, deposit });
+
+        Ok(())
+    }
+
+    /// Seller agrees to the proposed price. A reduction is refunded to the buyer.
+    pub fn confirm_amendment(ctx: Context<SettleAmendment>, escrow_id: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Accepted, ClawscrowError::InvalidState);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        require!(ctx.accounts.signer.key() == escrow.seller, ClawscrowError::Unauthorized);
+        let new_payment_amount = escrow.pending_amendment.ok_or(ClawscrowError::NoAmendmentProposal)?;
+
+        let old_payment_amount = escrow.payment_amount;
+        let refund = old_payment_amount.saturating_sub(new_payment_amount);
+        let terms_hash = terms::TermsV1 {
+            buyer: escrow.buyer,
+            seller: escrow.seller,
+            arbitrator: escrow.arbitrator,
+            mint: escrow.mint,
+            payment_amount: new_payment_amount,
+            buyer_collateral: escrow.buyer_collateral,
+            seller_collateral: escrow.seller_collateral,
+            deadline_ts: escrow.deadline_ts,
+            description_hash: terms::description_hash(&escrow.description),
+            milestone_amounts: Vec::new(),
+            arbitration_sla: escrow.arbitration_sla.clone(),
+            platform: escrow.platform,
+        }.hash()?;
+        let bump = escrow.bump;
+        let buyer_key = escrow.original_buyer;
+
+        let id_bytes = escrow_id.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"escrow", buyer_key.as_ref(), id_bytes.as_ref(), &[bump]];
+        let signer_seeds = &[seeds];
+
+        ensure_vault_covers(&ctx.accounts.vault, &[refund])?;
+
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.buyer_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            refund,
+        )?;
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.payment_amount = new_payment_amount;
+        escrow.pending_amendment = None;
+        escrow.terms_hash = terms_hash;
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.signer.key(), ctx.remaining_accounts)?;
+        emit!(EscrowAmended { escrow_id, meta, old_payment_amount, new_payment_amount, terms_hash });
+
+        Ok(())
+    }
+
+    /// Buyer drops an unconfirmed amendment and takes back any deposit. Works
+    /// in any state so a deposit is never stranded by a later settlement.
+    pub fn withdraw_amendment(ctx: Context<SettleAmendment>, escrow_id: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        require!(ctx.accounts.signer.key() == escrow.buyer, ClawscrowError::Unauthorized);
+        require!(escrow.pending_amendment.is_some(), ClawscrowError::NoAmendmentProposal);
+
+        let refund = escrow.amendment_deposit();
+        let bump = escrow.bump;
+        let buyer_key = escrow.original_buyer;
+
+        let id_bytes = escrow_id.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"escrow", buyer_key.as_ref(), id_bytes.as_ref(), &[bump]];
+        let signer_seeds = &[seeds];
+
+        ensure_vault_covers(&ctx.accounts.vault, &[refund])?;
+
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.buyer_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            refund,
+        )?;
+
+        ctx.accounts.escrow.pending_amendment = None;
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.signer.key(), ctx.remaining_accounts)?;
+        emit!(AmendmentWithdrawn { escrow_id, meta, refund });
+
+        Ok(())
+    }
+
+    /// Buyer walks away once the seller has missed enough heartbeats; both
+    /// sides get their own funds back, as with a mutual cancel.
+    pub fn cancel_abandoned(ctx: Context<ConfirmCancel>, escrow_id: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Accepted, ClawscrowError::InvalidState);
+        require!(ctx.accounts.signer.key() == escrow.buyer, ClawscrowError::Unauthorized);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        let policy = escrow.heartbeat_policy.as_ref().ok_or(ClawscrowError::InvalidHeartbeatPolicy)?;
+        let now = unix_now(ctx.remaining_accounts)?;
+        require!(
+            now >= escrow.last_heartbeat_at.saturating_add(policy.grace_secs()),
+            ClawscrowError::HeartbeatCurrent
+        );
+
+        let buyer_refund = escrow.remaining_payment()
+            .checked_add(escrow.buyer_collateral)
+            .ok_or(ClawscrowError::Overflow)?;
+        let seller_refund = escrow.seller_collateral;
+        let bump = escrow.bump;
+        let buyer_key = escrow.original_buyer;
+
+        let id_bytes = escrow_id.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"escrow", buyer_key.as_ref(), id_bytes.as_ref(), &[bump]];
+        let signer_seeds = &[seeds];
+
+        ensure_vault_covers(&ctx.accounts.vault, &[buyer_refund, seller_refund])?;
+
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.buyer_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            buyer_refund,
+        )?;
+
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.seller_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            seller_refund,
+        )?;
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.state = EscrowState::Cancelled;
+        release_open_slot(escrow, ctx.accounts.counter.as_mut())?;
+        escrow.cancel_proposed_by = None;
+        let escrow_key = ctx.accounts.escrow.key();
+        ctx.accounts.arbitrator_index.escrows.retain(|key| *key != escrow_key);
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.signer.key(), ctx.remaining_accounts)?;
+        emit_cpi!(EscrowCancelled { escrow_id, meta });
+
+        Ok(())
+    }
+
+    /// Seller backs out before delivering. The buyer gets everything they put
+    /// in plus the configured share of the seller's collateral.
+    pub fn seller_withdraw(ctx: Context<SellerWithdraw>, escrow_id: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Accepted, ClawscrowError::InvalidState);
+        require!(ctx.accounts.seller.key() == escrow.seller, ClawscrowError::Unauthorized);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+
+        let (forfeited, seller_refund) =
+            math::split_bps(escrow.seller_collateral, ctx.accounts.config.seller_withdraw_forfeit_bps)
+                .ok_or(ClawscrowError::Overflow)?;
+        let buyer_refund = escrow.remaining_payment()
+            .checked_add(escrow.buyer_collateral)
+            .and_then(|total| total.checked_add(forfeited))
+            .ok_or(ClawscrowError::Overflow)?;
+        let bump = escrow.bump;
+        let buyer_key = escrow.original_buyer;
+
+        let id_bytes = escrow_id.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"escrow", buyer_key.as_ref(), id_bytes.as_ref(), &[bump]];
+        let signer_seeds = &[seeds];
+
+        ensure_vault_covers(&ctx.accounts.vault, &[buyer_refund, seller_refund])?;
+
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.buyer_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            buyer_refund,
+        )?;
+
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.seller_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            seller_refund,
+        )?;
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.state = EscrowState::Cancelled;
+        release_open_slot(escrow, ctx.accounts.counter.as_mut())?;
+        escrow.cancel_proposed_by = None;
+        let escrow_key = ctx.accounts.escrow.key();
+        ctx.accounts.arbitrator_index.escrows.retain(|key| *key != escrow_key);
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.seller.key(), ctx.remaining_accounts)?;
+        emit_cpi!(SellerWithdrew { escrow_id, meta, buyer_refund, seller_refund, forfeited });
+
+        Ok(())
+    }
+
+    pub fn claim_timeout(ctx: Context<ClaimTimeout>, escrow_id: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Accepted, ClawscrowError::InvalidState);
+        require!(ctx.accounts.buyer.key() == escrow.buyer, ClawscrowError::Unauthorized);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        require!(
+            unix_now(ctx.remaining_accounts)? > escrow.deadline_ts,
+            ClawscrowError::DeadlineNotReached
+        );
+
+        // Buyer is refunded and takes the seller's collateral as compensation.
+        let total = math::timeout_refund(
+            escrow.remaining_payment(),
+            escrow.buyer_collateral,
+            escrow.seller_collateral,
+        ).ok_or(ClawscrowError::Overflow)?;
+        let forfeited = escrow.seller_collateral;
+        let bump = escrow.bump;
+        let buyer_key = escrow.original_buyer;
+
+        let id_bytes = escrow_id.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"escrow", buyer_key.as_ref(), id_bytes.as_ref(), &[bump]];
+        let signer_seeds = &[seeds];
+
+        ensure_vault_covers(&ctx.accounts.vault, &[total])?;
+
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.buyer_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            total,
+        )?;
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.state = EscrowState::TimedOut;
+        release_open_slot(escrow, ctx.accounts.counter.as_mut())?;
+        escrow.resolved_at = unix_now(ctx.remaining_accounts)?;
+        let escrow_key = ctx.accounts.escrow.key();
+        ctx.accounts.arbitrator_index.escrows.retain(|key| *key != escrow_key);
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.buyer.key(), ctx.remaining_accounts)?;
+        emit_cpi!(EscrowTimedOut { escrow_id, meta, refunded: total, seller_collateral_forfeited: forfeited });
+
+        Ok(())
+    }
+
+    pub fn close_escrow(ctx: Context<CloseEscrow>, escrow_id: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state.is_terminal(), ClawscrowError::InvalidState);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        require!(!is_pooled(escrow) || escrow.funded_amount == 0, ClawscrowError::ContributionsUnclaimed);
+        require!(escrow.second_leg.as_ref().is_none_or(|leg| leg.settled), ClawscrowError::SecondLegUnsettled);
+        require!(escrow.early_bonus.is_none() || escrow.early_bonus_settled, ClawscrowError::EarlyBonusUnsettled);
+        require!(ctx.accounts.vault.amount == 0, ClawscrowError::VaultNotEmpty);
+        if escrow.deliverable.is_some() {
+            let asset_vault = ctx.accounts.asset_vault.as_ref().ok_or(ClawscrowError::DeliverableAccountsMissing)?;
+            let released = asset_vault.data_is_empty()
+                || TokenAccount::try_deserialize(&mut &asset_vault.try_borrow_data()?[..])?.amount == 0;
+            require!(released, ClawscrowError::DeliverableUnreleased);
+        }
+
+        let bump = escrow.bump;
+        let buyer_key = escrow.original_buyer;
+        let id_bytes = escrow_id.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"escrow", buyer_key.as_ref(), id_bytes.as_ref(), &[bump]];
+        let signer_seeds = &[seeds];
+
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.vault.to_account_info(),
+                destination: ctx.accounts.buyer.to_account_info(),
+                authority: ctx.accounts.escrow.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.signer.key(), ctx.remaining_accounts)?;
+        emit_cpi!(EscrowClosed { escrow_id, meta });
+
+        Ok(())
+    }
+
+    pub fn deliver_milestone(
+        ctx: Context<Deliver>,
+        index: u8,
+        delivery_hash: [u8; 32],
+    ) -> Result<()> {
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.seller.key(), ctx.remaining_accounts)?;
+        let escrow = &mut ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Accepted, ClawscrowError::InvalidState);
+        require!(
+            acts_for(ctx.accounts.seller.key(), escrow.seller, ctx.accounts.operator_key.as_ref()),
+            ClawscrowError::Unauthorized
+        );
+
+        let milestone = escrow.milestones
+            .get_mut(index as usize)
+            .ok_or(ClawscrowError::InvalidMilestone)?;
+        require!(milestone.status == MilestoneStatus::Pending, ClawscrowError::InvalidMilestone);
+
+        milestone.delivery_hash = delivery_hash;
+        milestone.status = MilestoneStatus::Delivered;
+
+        emit!(MilestoneDelivered { escrow_id: escrow.escrow_id, meta, index, delivery_hash });
+
+        Ok(())
+    }
+
+    pub fn approve_milestone<'info>(ctx: Context<'_, '_, '_, 'info, Resolve<'info>>, escrow_id: u64, index: u8) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Accepted, ClawscrowError::InvalidState);
+        require!(ctx.accounts.signer.key() == escrow.buyer, ClawscrowError::Unauthorized);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+
+        let milestone = escrow.milestones
+            .get(index as usize)
+            .ok_or(ClawscrowError::InvalidMilestone)?;
+        require!(milestone.status == MilestoneStatus::Delivered, ClawscrowError::InvalidMilestone);
+
+        let amount = milestone.amount;
+        let is_last = escrow.milestones
+            .iter()
+            .enumerate()
+            .all(|(i, m)| i == index as usize || m.status == MilestoneStatus::Approved);
+        // Collateral is only released with the final milestone.
+        let payout = math::milestone_payout(
+            amount,
+            is_last,
+            escrow.buyer_collateral,
+            escrow.seller_collateral,
+            escrow.protocol_fee_bps,
+        ).ok_or(ClawscrowError::Overflow)?;
+        let referral_bps = escrow.referral.as_ref().map_or(0, |r| r.fee_bps);
+        let (payout, referral_fee) = math::with_referral_fee(payout, amount, referral_bps)
+            .ok_or(ClawscrowError::Overflow)?;
+        let (seller_amount, buyer_amount, protocol_fee) = (payout.seller, payout.buyer, payout.protocol_fee);
+        let bump = escrow.bump;
+        let buyer_key = escrow.original_buyer;
+
+        let id_bytes = escrow_id.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"escrow", buyer_key.as_ref(), id_bytes.as_ref(), &[bump]];
+        let signer_seeds = &[seeds];
+
+        ensure_vault_covers(&ctx.accounts.vault, &[seller_amount, buyer_amount, protocol_fee, referral_fee])?;
+
+        let seller_col = if is_last { ctx.accounts.escrow.seller_collateral } else { 0 };
+        let split_paid = pay_payout_split(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            &ctx.accounts.escrow,
+            signer_seeds,
+            ctx.accounts.payout_split.as_deref(),
+            ctx.remaining_accounts,
+            seller_amount - seller_col,
+        )?;
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.seller_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            seller_amount - split_paid,
+        )?;
+
+        pay_referral_fee(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            &ctx.accounts.escrow,
+            signer_seeds,
+            ctx.accounts.referrer_token.as_ref(),
+            referral_fee,
+            ctx.accounts.signer.key(),
+            ctx.remaining_accounts,
+        )?;
+
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.buyer_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            buyer_amount,
+        )?;
+
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.treasury_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            protocol_fee,
+        )?;
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.milestones[index as usize].status = MilestoneStatus::Approved;
+        escrow.released_amount = escrow.released_amount
+            .checked_add(amount)
+            .ok_or(ClawscrowError::Overflow)?;
+        let (buyer, seller) = (escrow.buyer, escrow.seller);
+        let (volume, created_at) = (escrow.payment_amount, escrow.created_at);
+        if is_last {
+            escrow.state = EscrowState::Approved;
+            release_open_slot(escrow, ctx.accounts.counter.as_mut())?;
+            escrow.resolved_at = unix_now(ctx.remaining_accounts)?;
+            let escrow_key = ctx.accounts.escrow.key();
+            ctx.accounts.arbitrator_index.escrows.retain(|key| *key != escrow_key);
+        }
+
+        ctx.accounts.buyer_reputation.init_if_empty(buyer, ctx.bumps.buyer_reputation);
+        ctx.accounts.seller_reputation.init_if_empty(seller, ctx.bumps.seller_reputation);
+        if is_last {
+            let now = unix_now(ctx.remaining_accounts)?;
+            ctx.accounts.buyer_reputation.record_settlement(volume, created_at, now);
+            ctx.accounts.seller_reputation.record_settlement(volume, created_at, now);
+        }
+        ctx.accounts.stats.record_released(amount);
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.signer.key(), ctx.remaining_accounts)?;
+        emit!(MilestoneApproved { escrow_id, meta, index, amount });
+        if is_last {
+            emit_cpi!(EscrowApproved { escrow_id, meta, feedback_hash: None, satisfaction: None, protocol_fee, tip: 0 });
+        }
+
+        Ok(())
+    }
+
+    /// Buyer turns an escrow into a time-and-materials engagement before
+    /// acceptance: the payment becomes a budget cap, billed by the hour
+    /// through timesheets, and whatever is left is refunded at the end.
+    pub fn enable_hourly_billing(ctx: Context<TimesheetAction>, escrow_id: u64, hourly_rate: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Created, ClawscrowError::InvalidState);
+        require!(ctx.accounts.signer.key() == escrow.buyer, ClawscrowError::Unauthorized);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        require!(!is_pooled(escrow), ClawscrowError::PooledEscrow);
+        require!(
+            hourly_rate > 0
+                && escrow.hourly_rate.is_none()
+                && escrow.milestones.is_empty()
+                && escrow.vesting_secs.is_none()
+                && escrow.deliverable.is_none()
+                && escrow.usd_peg.is_none(),
+            ClawscrowError::InvalidHourlyBilling
+        );
+
+        ctx.accounts.escrow.hourly_rate = Some(hourly_rate);
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.signer.key(), ctx.remaining_accounts)?;
+        emit!(HourlyBillingEnabled { escrow_id, meta, hourly_rate, budget: ctx.accounts.escrow.payment_amount });
+
+        Ok(())
+    }
+
+    /// Seller bills hours worked at the escrow's hourly rate. `rate_hash`
+    /// commits to the off-chain timesheet. Replaces an entry the buyer has
+    /// not yet approved.
+    pub fn submit_timesheet(ctx: Context<TimesheetAction>, escrow_id: u64, hours: u32, rate_hash: [u8; 32]) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Accepted, ClawscrowError::InvalidState);
+        require!(ctx.accounts.signer.key() == escrow.seller, ClawscrowError::Unauthorized);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        let rate = escrow.hourly_rate.ok_or(ClawscrowError::InvalidHourlyBilling)?;
+        let amount = rate.checked_mul(hours as u64).ok_or(ClawscrowError::Overflow)?;
+        require!(amount > 0 && amount <= escrow.remaining_payment(), ClawscrowError::InvalidTimesheet);
+
+        let now = unix_now(ctx.remaining_accounts)?;
+        ctx.accounts.escrow.pending_timesheet = Some(Timesheet { hours, amount, rate_hash, submitted_at: now });
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.signer.key(), ctx.remaining_accounts)?;
+        emit!(TimesheetSubmitted { escrow_id, meta, hours, amount, rate_hash });
+
+        Ok(())
+    }
+
+    /// Buyer turns down the pending timesheet; the seller can submit a corrected one.
+    pub fn reject_timesheet(ctx: Context<TimesheetAction>, escrow_id: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Accepted, ClawscrowError::InvalidState);
+        require!(ctx.accounts.signer.key() == escrow.buyer, ClawscrowError::Unauthorized);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        let timesheet = escrow.pending_timesheet.clone().ok_or(ClawscrowError::NoPendingTimesheet)?;
+
+        ctx.accounts.escrow.pending_timesheet = None;
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.signer.key(), ctx.remaining_accounts)?;
+        emit!(TimesheetRejected { escrow_id, meta, hours: timesheet.hours, amount: timesheet.amount });
+
+        Ok(())
+    }
+
+    /// Buyer approves the pending timesheet, releasing its amount from the
+    /// budget to the seller. Collateral stays put until the engagement ends.
+    pub fn approve_timesheet<'info>(ctx: Context<'_, '_, '_, 'info, Resolve<'info>>, escrow_id: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Accepted, ClawscrowError::InvalidState);
+        require!(ctx.accounts.signer.key() == escrow.buyer, ClawscrowError::Unauthorized);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        let timesheet = escrow.pending_timesheet.clone().ok_or(ClawscrowError::NoPendingTimesheet)?;
+        let amount = timesheet.amount;
+        require!(amount <= escrow.remaining_payment(), ClawscrowError::InvalidTimesheet);
+
+        let payout = math::approval_payout(amount, 0, 0, escrow.protocol_fee_bps)
+            .ok_or(ClawscrowError::Overflow)?;
+        let referral_bps = escrow.referral.as_ref().map_or(0, |r| r.fee_bps);
+        let (payout, referral_fee) = math::with_referral_fee(payout, amount, referral_bps)
+            .ok_or(ClawscrowError::Overflow)?;
+        let bump = escrow.bump;
+        let buyer_key = escrow.original_buyer;
+
+        let id_bytes = escrow_id.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"escrow", buyer_key.as_ref(), id_bytes.as_ref(), &[bump]];
+        let signer_seeds = &[seeds];
+
+        ensure_vault_covers(&ctx.accounts.vault, &[payout.seller, payout.protocol_fee, referral_fee])?;
+
+        let split_paid = pay_payout_split(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            &ctx.accounts.escrow,
+            signer_seeds,
+            ctx.accounts.payout_split.as_deref(),
+            ctx.remaining_accounts,
+            payout.seller,
+        )?;
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.seller_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            payout.seller - split_paid,
+        )?;
+
+        pay_referral_fee(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            &ctx.accounts.escrow,
+            signer_seeds,
+            ctx.accounts.referrer_token.as_ref(),
+            referral_fee,
+            ctx.accounts.signer.key(),
+            ctx.remaining_accounts,
+        )?;
+
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.treasury_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            payout.protocol_fee,
+        )?;
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.pending_timesheet = None;
+        escrow.released_amount = escrow.released_amount
+            .checked_add(amount)
+            .ok_or(ClawscrowError::Overflow)?;
+        ctx.accounts.stats.record_released(amount);
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.signer.key(), ctx.remaining_accounts)?;
+        emit!(TimesheetApproved {
+            escrow_id,
+            meta,
+            hours: timesheet.hours,
+            amount,
+            protocol_fee: payout.protocol_fee,
+            remaining_budget: ctx.accounts.escrow.remaining_payment(),
+        });
+
+        Ok(())
+    }
+
+    /// Ends an hourly engagement: the unbilled budget and the buyer's
+    /// collateral go back to the buyer and the seller's collateral to the
+    /// seller. The seller can finish at any time, the buyer once the
+    /// deadline has passed; either way no timesheet may be pending.
+    pub fn finish_hourly_billing(ctx: Context<Resolve>, escrow_id: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Accepted, ClawscrowError::InvalidState);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        require!(escrow.hourly_rate.is_some(), ClawscrowError::InvalidHourlyBilling);
+        require!(escrow.pending_timesheet.is_none(), ClawscrowError::TimesheetPending);
+        let now = unix_now(ctx.remaining_accounts)?;
+        let signer = ctx.accounts.signer.key();
+        require!(
+            signer == escrow.seller || (signer == escrow.buyer && now > escrow.deadline_ts),
+            ClawscrowError::Unauthorized
+        );
+
+        let refunded = escrow.remaining_payment();
+        let buyer_amount = refunded.checked_add(escrow.buyer_collateral).ok_or(ClawscrowError::Overflow)?;
+        let seller_amount = escrow.seller_collateral;
+        let bump = escrow.bump;
+        let buyer_key = escrow.original_buyer;
+
+        let id_bytes = escrow_id.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"escrow", buyer_key.as_ref(), id_bytes.as_ref(), &[bump]];
+        let signer_seeds = &[seeds];
+
+        ensure_vault_covers(&ctx.accounts.vault, &[buyer_amount, seller_amount])?;
+
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.buyer_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            buyer_amount,
+        )?;
+
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.seller_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            seller_amount,
+        )?;
+
+        // What was billed is what the job cost, so settled escrows read the same as any other.
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.payment_amount = escrow.released_amount;
+        escrow.state = EscrowState::Approved;
+        release_open_slot(escrow, ctx.accounts.counter.as_mut())?;
+        escrow.resolved_at = now;
+        let (buyer, seller) = (escrow.buyer, escrow.seller);
+        let (billed, created_at) = (escrow.payment_amount, escrow.created_at);
+        let escrow_key = ctx.accounts.escrow.key();
+        ctx.accounts.arbitrator_index.escrows.retain(|key| *key != escrow_key);
+
+        ctx.accounts.buyer_reputation.init_if_empty(buyer, ctx.bumps.buyer_reputation);
+        ctx.accounts.buyer_reputation.record_settlement(billed, created_at, now);
+        ctx.accounts.seller_reputation.init_if_empty(seller, ctx.bumps.seller_reputation);
+        ctx.accounts.seller_reputation.record_settlement(billed, created_at, now);
+
+        let meta = event_meta(&ctx.accounts.escrow, signer, ctx.remaining_accounts)?;
+        emit!(HourlyBillingFinished { escrow_id, meta, billed, refunded });
+
+        Ok(())
+    }
+
+    /// `delivery_uri` tells the buyer and arbitrator where to fetch the work
+    /// that `delivery_hash` commits to.
+    pub fn deliver(
+        ctx: Context<Deliver>,
+        delivery_hash: [u8; 32],
+        delivery_uri: Option<String>,
+    ) -> Result<()> {
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.seller.key(), ctx.remaining_accounts)?;
+        let escrow = &mut ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Accepted, ClawscrowError::InvalidState);
+        require!(
+            acts_for(ctx.accounts.seller.key(), escrow.seller, ctx.accounts.operator_key.as_ref()),
+            ClawscrowError::Unauthorized
+        );
+        require!(escrow.deliverable.is_none(), ClawscrowError::AssetDeliveryRequired);
+        // Hourly work is paid by timesheet; a delivery would put the whole budget up for approval.
+        require!(escrow.hourly_rate.is_none(), ClawscrowError::InvalidHourlyBilling);
+        if let Some(uri) = &delivery_uri {
+            text::validate_uri(uri)?;
+        }
+
+        let now = unix_now(ctx.remaining_accounts)?;
+        escrow.delivery_hash = delivery_hash;
+        escrow.delivery_uri = delivery_uri.clone();
+        escrow.state = EscrowState::Delivered;
+        escrow.delivered_at = now;
+        escrow.record_delivery(delivery_hash, now);
+
+        emit_cpi!(WorkDelivered { escrow_id: escrow.escrow_id, meta, delivery_hash, delivery_uri });
+
+        Ok(())
+    }
+
+    /// `deliver` for escrows whose deliverable is an on-chain asset: the
+    /// seller deposits it into the escrow's asset vault in the same step, and
+    /// approval hands it to the buyer as the payment goes to the seller.
+    /// A revision leaves the asset in the vault, so redelivering afterwards
+    /// only tops it up to the agreed amount.
+    pub fn deliver_asset(
+        ctx: Context<DeliverAsset>,
+        escrow_id: u64,
+        delivery_hash: [u8; 32],
+        delivery_uri: Option<String>,
+    ) -> Result<()> {
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.seller.key(), ctx.remaining_accounts)?;
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Accepted, ClawscrowError::InvalidState);
+        require!(ctx.accounts.seller.key() == escrow.seller, ClawscrowError::Unauthorized);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        let deliverable = escrow.deliverable.clone().ok_or(ClawscrowError::InvalidDeliverable)?;
+        require_keys_eq!(ctx.accounts.asset_mint.key(), deliverable.mint, ClawscrowError::InvalidMint);
+        if let Some(uri) = &delivery_uri {
+            text::validate_uri(uri)?;
+        }
+
+        let shortfall = deliverable.amount.saturating_sub(ctx.accounts.asset_vault.amount);
+        if shortfall > 0 {
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.seller_asset_token.to_account_info(),
+                        to: ctx.accounts.asset_vault.to_account_info(),
+                        authority: ctx.accounts.seller.to_account_info(),
+                    },
+                ),
+                shortfall,
+            )?;
+        }
+
+        let now = unix_now(ctx.remaining_accounts)?;
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.delivery_hash = delivery_hash;
+        escrow.delivery_uri = delivery_uri.clone();
+        escrow.state = EscrowState::Delivered;
+        escrow.delivered_at = now;
+        escrow.record_delivery(delivery_hash, now);
+
+        emit_cpi!(WorkDelivered { escrow_id, meta, delivery_hash, delivery_uri });
+
+        Ok(())
+    }
+
+    /// Settles a deposited asset deliverable after a ruling or cancellation:
+    /// to the buyer when the seller was paid, otherwise back to the seller.
+    /// Approvals hand it over themselves. Permissionless.
+    pub fn release_deliverable(ctx: Context<ReleaseDeliverable>, escrow_id: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        let to_buyer = match escrow.state {
+            EscrowState::Approved | EscrowState::Vesting | EscrowState::ResolvedSeller => true,
+            EscrowState::ResolvedBuyer
+            | EscrowState::ResolvedSplit
+            | EscrowState::Cancelled
+            | EscrowState::TimedOut => false,
+            _ => return err!(ClawscrowError::InvalidState),
+        };
+        let owner = if to_buyer {
+            escrow.buyer_payout.unwrap_or(escrow.buyer)
+        } else {
+            escrow.seller_payout.unwrap_or(escrow.seller)
+        };
+        require_keys_eq!(ctx.accounts.recipient_token.owner, owner, ClawscrowError::PayoutAccountMismatch);
+        let bump = escrow.bump;
+        let buyer_key = escrow.original_buyer;
+
+        let id_bytes = escrow_id.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"escrow", buyer_key.as_ref(), id_bytes.as_ref(), &[bump]];
+        let signer_seeds = &[seeds];
+
+        release_asset(
+            &ctx.accounts.token_program,
+            &ctx.accounts.escrow,
+            Some(&ctx.accounts.asset_vault),
+            Some(&ctx.accounts.recipient_token),
+            ctx.accounts.seller_payout_owner.to_account_info(),
+            signer_seeds,
+        )?;
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.signer.key(), ctx.remaining_accounts)?;
+        emit!(DeliverableReleased { escrow_id, meta, recipient: owner });
+
+        Ok(())
+    }
+
+    /// Buyer adds a second payment leg in another mint, e.g. a project token
+    /// alongside USDC, before the escrow is accepted. It is held in its own
+    /// vault and divided by `settle_second_leg` once the escrow settles.
+    pub fn fund_second_leg(ctx: Context<FundSecondLeg>, escrow_id: u64, amount: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Created, ClawscrowError::InvalidState);
+        require!(ctx.accounts.buyer.key() == escrow.buyer, ClawscrowError::Unauthorized);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        require!(escrow.second_leg.is_none(), ClawscrowError::InvalidSecondLeg);
+        require!(!is_pooled(escrow), ClawscrowError::PooledEscrow);
+        // Clawbacks rewrite the vesting schedule, which the split below cannot see.
+        require!(escrow.vesting_secs.is_none(), ClawscrowError::InvalidSecondLeg);
+        let mint = ctx.accounts.second_mint.key();
+        require!(amount > 0 && mint != escrow.mint, ClawscrowError::InvalidSecondLeg);
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.buyer_second_token.to_account_info(),
+                    to: ctx.accounts.second_vault.to_account_info(),
+                    authority: ctx.accounts.buyer.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        ctx.accounts.escrow.second_leg = Some(PaymentLeg { mint, amount, settled: false });
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.buyer.key(), ctx.remaining_accounts)?;
+        emit!(SecondLegFunded { escrow_id, meta, second_mint: mint, amount });
+
+        Ok(())
+    }
+
+    /// Divides the second payment leg of a settled escrow in the same
+    /// proportion as its main payment went to the seller, refunds the rest to
+    /// the buyer and closes the second vault. Permissionless.
+    pub fn settle_second_leg(ctx: Context<SettleSecondLeg>, escrow_id: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state.is_terminal(), ClawscrowError::InvalidState);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        let leg = escrow.second_leg.clone().ok_or(ClawscrowError::InvalidSecondLeg)?;
+        require!(!leg.settled, ClawscrowError::InvalidSecondLeg);
+
+        let held = ctx.accounts.second_vault.amount;
+        let seller_paid = escrow.seller_payment_share().ok_or(ClawscrowError::InvalidState)?;
+        let seller_amount = math::pro_rata(held, seller_paid, escrow.payment_amount).ok_or(ClawscrowError::Overflow)?;
+        let buyer_amount = held - seller_amount;
+        let bump = escrow.bump;
+        let buyer_key = escrow.original_buyer;
+
+        let id_bytes = escrow_id.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"escrow", buyer_key.as_ref(), id_bytes.as_ref(), &[bump]];
+        let signer_seeds = &[seeds];
+
+        if seller_amount > 0 {
+            let seller_token = ctx.accounts.seller_second_token.as_ref().ok_or(ClawscrowError::SecondLegAccountsMissing)?;
+            vault_transfer(
+                &ctx.accounts.token_program,
+                &ctx.accounts.second_vault,
+                seller_token.to_account_info(),
+                &ctx.accounts.escrow,
+                signer_seeds,
+                seller_amount,
+            )?;
+        }
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.second_vault,
+            ctx.accounts.buyer_second_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            buyer_amount,
+        )?;
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.second_vault.to_account_info(),
+                destination: ctx.accounts.buyer.to_account_info(),
+                authority: ctx.accounts.escrow.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+
+        if let Some(leg) = ctx.accounts.escrow.second_leg.as_mut() {
+            leg.settled = true;
+        }
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.signer.key(), ctx.remaining_accounts)?;
+        emit!(SecondLegSettled { escrow_id, meta, buyer_amount, seller_amount });
+
+        Ok(())
+    }
+
+    /// Either party lends an accepted escrow's whole vault to the reserve
+    /// whitelisted for its mint. The vault is closed while the funds are out,
+    /// so nothing can pay out of it until `withdraw_yield` brings them back;
+    /// the caller takes its rent and pays for the position. The lending
+    /// program's `RefreshReserve` must come earlier in the same transaction.
+    pub fn enable_yield(ctx: Context<EnableYield>, escrow_id: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Accepted, ClawscrowError::InvalidState);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        let signer = ctx.accounts.signer.key();
+        require!(signer == escrow.buyer || signer == escrow.seller, ClawscrowError::Unauthorized);
+        let principal = ctx.accounts.vault.amount;
+        require!(principal > 0, ClawscrowError::InvalidAmount);
+
+        let target = ctx.accounts.lending_reserve.target.clone();
+        check_lending_accounts(
+            &target,
+            &ctx.accounts.lending_program,
+            &ctx.accounts.reserve,
+            &ctx.accounts.liquidity_supply,
+            &ctx.accounts.lending_market,
+            &ctx.accounts.market_authority,
+        )?;
+
+        let bump = escrow.bump;
+        let buyer_key = escrow.original_buyer;
+        let id_bytes = escrow_id.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"escrow", buyer_key.as_ref(), id_bytes.as_ref(), &[bump]];
+        let signer_seeds = &[seeds];
+
+        invoke_signed(
+            &lending::deposit_instruction(
+                &target,
+                ctx.accounts.vault.key(),
+                ctx.accounts.yield_collateral.key(),
+                escrow.key(),
+                principal,
+            ),
+            &[
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.yield_collateral.to_account_info(),
+                ctx.accounts.reserve.to_account_info(),
+                ctx.accounts.liquidity_supply.to_account_info(),
+                ctx.accounts.collateral_mint.to_account_info(),
+                ctx.accounts.lending_market.to_account_info(),
+                ctx.accounts.market_authority.to_account_info(),
+                ctx.accounts.escrow.to_account_info(),
+                ctx.accounts.clock.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+            ],
+            signer_seeds,
+        )?;
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.vault.to_account_info(),
+                destination: ctx.accounts.signer.to_account_info(),
+                authority: ctx.accounts.escrow.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+
+        let position = &mut ctx.accounts.yield_position;
+        position.escrow = ctx.accounts.escrow.key();
+        position.target = target;
+        position.principal = principal;
+        position.buyer_yield_bps = ctx.accounts.lending_reserve.buyer_yield_bps;
+        position.payer = signer;
+        position.bump = ctx.bumps.yield_position;
+
+        let meta = event_meta(&ctx.accounts.escrow, signer, ctx.remaining_accounts)?;
+        emit!(YieldEnabled {
+            escrow_id,
+            meta,
+            reserve: ctx.accounts.reserve.key(),
+            principal,
+            buyer_yield_bps: ctx.accounts.yield_position.buyer_yield_bps,
+        });
+
+        Ok(())
+    }
+
+    /// Bring a lent vault back: the collateral is redeemed into a recreated
+    /// vault and anything above the principal is split between buyer and
+    /// seller by the ratio fixed when it was lent. Permissionless, so a
+    /// settlement can put it first in its transaction; the caller pays the
+    /// vault's rent and the position's goes back to whoever opened it. The
+    /// lending program's `RefreshReserve` must come earlier in the same
+    /// transaction.
+    pub fn withdraw_yield(ctx: Context<WithdrawYield>, escrow_id: u64) -> Result<()> {
+        require!(ctx.accounts.escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        let position = &ctx.accounts.yield_position;
+        let target = position.target.clone();
+        check_lending_accounts(
+            &target,
+            &ctx.accounts.lending_program,
+            &ctx.accounts.reserve,
+            &ctx.accounts.liquidity_supply,
+            &ctx.accounts.lending_market,
+            &ctx.accounts.market_authority,
+        )?;
+
+        let escrow = &ctx.accounts.escrow;
+        let bump = escrow.bump;
+        let buyer_key = escrow.original_buyer;
+        let id_bytes = escrow_id.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"escrow", buyer_key.as_ref(), id_bytes.as_ref(), &[bump]];
+        let signer_seeds = &[seeds];
+
+        invoke_signed(
+            &lending::redeem_instruction(
+                &target,
+                ctx.accounts.yield_collateral.key(),
+                ctx.accounts.vault.key(),
+                escrow.key(),
+                ctx.accounts.yield_collateral.amount,
+            ),
+            &[
+                ctx.accounts.yield_collateral.to_account_info(),
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.reserve.to_account_info(),
+                ctx.accounts.collateral_mint.to_account_info(),
+                ctx.accounts.liquidity_supply.to_account_info(),
+                ctx.accounts.lending_market.to_account_info(),
+                ctx.accounts.market_authority.to_account_info(),
+                ctx.accounts.escrow.to_account_info(),
+                ctx.accounts.clock.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+            ],
+            signer_seeds,
+        )?;
+        ctx.accounts.vault.reload()?;
+        let redeemed = ctx.accounts.vault.amount;
+        // A loss stays in the vault; whitelisting is what guards against it.
+        let gain = redeemed.saturating_sub(position.principal);
+        let (buyer_gain, seller_gain) =
+            math::split_bps(gain, position.buyer_yield_bps).ok_or(ClawscrowError::InvalidConfig)?;
+
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.buyer_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            buyer_gain,
+        )?;
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.seller_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            seller_gain,
+        )?;
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.yield_collateral.to_account_info(),
+                destination: ctx.accounts.payer.to_account_info(),
+                authority: ctx.accounts.escrow.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.signer.key(), ctx.remaining_accounts)?;
+        emit!(YieldWithdrawn {
+            escrow_id,
+            meta,
+            principal: ctx.accounts.yield_position.principal,
+            redeemed,
+            buyer_gain,
+            seller_gain,
+        });
+
+        Ok(())
+    }
+
+    /// Pays the early-delivery bonus to the seller if they won the escrow and
+    /// first delivered by the bonus deadline, otherwise refunds it to the
+    /// buyer, and closes the bonus vault. Permissionless once settled.
+    pub fn settle_early_bonus(ctx: Context<SettleEarlyBonus>, escrow_id: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(
+            escrow.state.is_terminal() || escrow.state == EscrowState::Vesting,
+            ClawscrowError::InvalidState
+        );
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        let bonus = escrow.early_bonus.clone().ok_or(ClawscrowError::InvalidEarlyBonus)?;
+        require!(!escrow.early_bonus_settled, ClawscrowError::InvalidEarlyBonus);
+
+        let to_seller = escrow.earned_early_bonus(&bonus);
+        let recipient = if to_seller { escrow.seller_payout_ata() } else { escrow.buyer_payout_ata() };
+        require_keys_eq!(ctx.accounts.recipient_token.key(), recipient, ClawscrowError::PayoutAccountMismatch);
+        let amount = ctx.accounts.bonus_vault.amount;
+        let bump = escrow.bump;
+        let buyer_key = escrow.original_buyer;
+
+        let id_bytes = escrow_id.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"escrow", buyer_key.as_ref(), id_bytes.as_ref(), &[bump]];
+        let signer_seeds = &[seeds];
+
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.bonus_vault,
+            ctx.accounts.recipient_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            amount,
+        )?;
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.bonus_vault.to_account_info(),
+                destination: ctx.accounts.buyer.to_account_info(),
+                authority: ctx.accounts.escrow.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+
+        ctx.accounts.escrow.early_bonus_settled = true;
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.signer.key(), ctx.remaining_accounts)?;
+        emit!(EarlyBonusSettled { escrow_id, meta, to_seller, amount });
+
+        Ok(())
+    }
+
+    /// Fixes a wrapped SOL escrow's price in USD. The deposit becomes a cap:
+    /// the first payout prices the job in lamports at the Pyth SOL/USD rate
+    /// and refunds the surplus to the buyer. Before acceptance, so the seller
+    /// agrees to the peg.
+    pub fn set_usd_peg(ctx: Context<SetUsdPeg>, escrow_id: u64, usd_amount: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Created, ClawscrowError::InvalidState);
+        require!(ctx.accounts.buyer.key() == escrow.buyer, ClawscrowError::Unauthorized);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        require_keys_eq!(escrow.mint, token::spl_token::native_mint::ID, ClawscrowError::NotNativeMint);
+        require!(!is_pooled(escrow), ClawscrowError::PooledEscrow);
+        // Milestone amounts are fixed in lamports and cannot follow the price.
+        require!(
+            usd_amount > 0 && escrow.usd_peg.is_none() && escrow.milestones.is_empty(),
+            ClawscrowError::InvalidUsdPeg
+        );
+
+        ctx.accounts.escrow.usd_peg = Some(UsdPeg { usd_amount, priced: false });
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.buyer.key(), ctx.remaining_accounts)?;
+        emit!(UsdPegSet { escrow_id, meta, usd_amount, max_lamports: ctx.accounts.escrow.payment_amount });
+
+        Ok(())
+    }
+
+    pub fn acknowledge_delivery(ctx: Context<AcknowledgeDelivery>) -> Result<()> {
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.buyer.key(), ctx.remaining_accounts)?;
+        let escrow = &mut ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Delivered, ClawscrowError::InvalidState);
+        require!(ctx.accounts.buyer.key() == escrow.buyer, ClawscrowError::Unauthorized);
+        require!(escrow.acknowledged_at == 0, ClawscrowError::AlreadyAcknowledged);
+
+        let now = unix_now(ctx.remaining_accounts)?;
+        escrow.acknowledged_at = now;
+
+        emit!(DeliveryAcknowledged { escrow_id: escrow.escrow_id, meta, acknowledged_at: now });
+
+        Ok(())
+    }
+
+    /// Pay out whatever has vested since approval. Permissionless; funds only
+    /// ever go to the seller's payout account, or their payout split.
+    pub fn claim_vested<'info>(ctx: Context<'_, '_, '_, 'info, ClaimVested<'info>>, escrow_id: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Vesting, ClawscrowError::InvalidState);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        let schedule = escrow.vesting.clone().ok_or(ClawscrowError::InvalidState)?;
+        let duration = escrow.vesting_secs.ok_or(ClawscrowError::InvalidState)?;
+
+        let now = unix_now(ctx.remaining_accounts)?;
+        let vested = math::vested_amount(schedule.total, now.saturating_sub(schedule.start_ts), duration);
+        let amount = vested.saturating_sub(schedule.claimed);
+        let bump = escrow.bump;
+        let buyer_key = escrow.original_buyer;
+
+        let id_bytes = escrow_id.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"escrow", buyer_key.as_ref(), id_bytes.as_ref(), &[bump]];
+        let signer_seeds = &[seeds];
+
+        ensure_vault_covers(&ctx.accounts.vault, &[amount])?;
+
+        let split_paid = pay_payout_split(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            &ctx.accounts.escrow,
+            signer_seeds,
+            ctx.accounts.payout_split.as_ref(),
+            ctx.remaining_accounts,
+            amount,
+        )?;
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.seller_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            amount - split_paid,
+        )?;
+
+        let escrow = &mut ctx.accounts.escrow;
+        let claimed = schedule.claimed + amount;
+        escrow.vesting = Some(VestingSchedule { claimed, ..schedule });
+        if claimed == schedule.total {
+            escrow.state = EscrowState::Approved;
+            release_open_slot(escrow, ctx.accounts.counter.as_mut())?;
+        }
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.signer.key(), ctx.remaining_accounts)?;
+        emit!(VestedClaimed { escrow_id, meta, amount, claimed, total: schedule.total });
+
+        Ok(())
+    }
+
+    /// Buyer ends a vesting payout early: the seller keeps what has vested
+    /// and the unvested remainder returns to the buyer.
+    pub fn clawback_unvested<'info>(
+        ctx: Context<'_, '_, '_, 'info, ClawbackUnvested<'info>>,
+        escrow_id: u64,
+    ) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Vesting, ClawscrowError::InvalidState);
+        require!(ctx.accounts.buyer.key() == escrow.buyer, ClawscrowError::Unauthorized);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        let schedule = escrow.vesting.clone().ok_or(ClawscrowError::InvalidState)?;
+        let duration = escrow.vesting_secs.ok_or(ClawscrowError::InvalidState)?;
+
+        let now = unix_now(ctx.remaining_accounts)?;
+        let vested = math::vested_amount(schedule.total, now.saturating_sub(schedule.start_ts), duration);
+        let to_seller = vested.saturating_sub(schedule.claimed);
+        let to_buyer = schedule.total - vested;
+        let bump = escrow.bump;
+        let buyer_key = escrow.original_buyer;
+
+        let id_bytes = escrow_id.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"escrow", buyer_key.as_ref(), id_bytes.as_ref(), &[bump]];
+        let signer_seeds = &[seeds];
+
+        ensure_vault_covers(&ctx.accounts.vault, &[to_seller, to_buyer])?;
+
+        let split_paid = pay_payout_split(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            &ctx.accounts.escrow,
+            signer_seeds,
+            ctx.accounts.payout_split.as_ref(),
+            ctx.remaining_accounts,
+            to_seller,
+        )?;
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.seller_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            to_seller - split_paid,
+        )?;
+
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.buyer_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            to_buyer,
+        )?;
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.vesting = Some(VestingSchedule { claimed: vested, total: vested, ..schedule });
+        escrow.state = EscrowState::Approved;
+        release_open_slot(escrow, ctx.accounts.counter.as_mut())?;
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.buyer.key(), ctx.remaining_accounts)?;
+        emit!(VestingClawedBack { escrow_id, meta, to_seller, to_buyer });
+
+        Ok(())
+    }
+
+    /// Send a delivery back for rework instead of disputing it. The seller
+    /// gets a fresh deadline and redelivers through `deliver`.
+    pub fn request_revision(
+        ctx: Context<EscrowPartyAction>,
+        escrow_id: u64,
+        notes_hash: [u8; 32],
+    ) -> Result<()> {
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.signer.key(), ctx.remaining_accounts)?;
+        let escrow = &mut ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Delivered, ClawscrowError::InvalidState);
+        require!(ctx.accounts.signer.key() == escrow.buyer, ClawscrowError::Unauthorized);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        require!(escrow.revisions < MAX_REVISIONS, ClawscrowError::TooManyRevisions);
+
+        let now = unix_now(ctx.remaining_accounts)?;
+        escrow.revisions += 1;
+        escrow.deadline_ts = escrow.deadline_ts.max(now).saturating_add(REVISION_EXTENSION_SECS);
+        escrow.state = EscrowState::Accepted;
+        escrow.delivered_at = 0;
+        escrow.acknowledged_at = 0;
+        escrow.last_heartbeat_at = now;
+
+        emit!(RevisionRequested {
+            escrow_id,
+            meta,
+            revision: escrow.revisions,
+            notes_hash,
+            deadline_ts: escrow.deadline_ts,
+        });
+
+        Ok(())
+    }
+
+    pub fn approve<'info>(
+        mut ctx: Context<'_, '_, '_, 'info, Resolve<'info>>,
+        escrow_id: u64,
+        feedback_hash: Option<[u8; 32]>,
+        satisfaction: Option<u8>,
+    ) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Delivered, ClawscrowError::InvalidState);
+        require!(
+            acts_for(ctx.accounts.signer.key(), escrow.buyer, ctx.accounts.operator_key.as_ref()),
+            ClawscrowError::Unauthorized
+        );
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        if let Some(score) = satisfaction {
+            require!(
+                (1..=MAX_SATISFACTION_SCORE).contains(&score),
+                ClawscrowError::InvalidSatisfactionScore
+            );
+        }
+
+        let protocol_fee = settle_release(&mut ctx, escrow_id, feedback_hash, satisfaction)?;
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.signer.key(), ctx.remaining_accounts)?;
+        emit_cpi!(EscrowApproved { escrow_id, meta, feedback_hash, satisfaction, protocol_fee, tip: 0 });
+
+        Ok(())
+    }
+
+    /// `approve` plus a gratuity for the seller, paid from `tip_source` in
+    /// the same transaction. The tip is on top of the agreed terms, so no
+    /// protocol fee is taken from it.
+    pub fn approve_with_tip<'info>(
+        mut ctx: Context<'_, '_, '_, 'info, Resolve<'info>>,
+        escrow_id: u64,
+        feedback_hash: Option<[u8; 32]>,
+        satisfaction: Option<u8>,
+        tip: u64,
+    ) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Delivered, ClawscrowError::InvalidState);
+        // Tips come out of the buyer's own tokens, so operators cannot sign for them.
+        require!(ctx.accounts.signer.key() == escrow.buyer, ClawscrowError::Unauthorized);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        require!(tip > 0, ClawscrowError::InvalidAmount);
+        if let Some(score) = satisfaction {
+            require!(
+                (1..=MAX_SATISFACTION_SCORE).contains(&score),
+                ClawscrowError::InvalidSatisfactionScore
+            );
+        }
+        let tip_source = ctx.accounts.tip_source.as_ref().ok_or(ClawscrowError::TipSourceMissing)?;
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: tip_source.to_account_info(),
+                    to: ctx.accounts.seller_token.to_account_info(),
+                    authority: ctx.accounts.signer.to_account_info(),
+                },
+            ),
+            tip,
+        )?;
+
+        let protocol_fee = settle_release(&mut ctx, escrow_id, feedback_hash, satisfaction)?;
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.signer.key(), ctx.remaining_accounts)?;
+        emit_cpi!(EscrowApproved { escrow_id, meta, feedback_hash, satisfaction, protocol_fee, tip });
+
+        Ok(())
+    }
+
+    /// `approve` signed by the escrow's `buyer_authority` instead of the
+    /// buyer, e.g. a Squads vault that approves after a multisig vote.
+    pub fn approve_with_authority<'info>(
+        mut ctx: Context<'_, '_, '_, 'info, Resolve<'info>>,
+        escrow_id: u64,
+        feedback_hash: Option<[u8; 32]>,
+        satisfaction: Option<u8>,
+    ) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Delivered, ClawscrowError::InvalidState);
+        require!(
+            escrow.buyer_authority == Some(ctx.accounts.signer.key()),
+            ClawscrowError::Unauthorized
+        );
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        if let Some(score) = satisfaction {
+            require!(
+                (1..=MAX_SATISFACTION_SCORE).contains(&score),
+                ClawscrowError::InvalidSatisfactionScore
+            );
+        }
+
+        let protocol_fee = settle_release(&mut ctx, escrow_id, feedback_hash, satisfaction)?;
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.signer.key(), ctx.remaining_accounts)?;
+        emit_cpi!(EscrowApproved { escrow_id, meta, feedback_hash, satisfaction, protocol_fee, tip: 0 });
+
+        Ok(())
+    }
+
+    /// Register (or with `None`, revoke) a hot wallet that may deliver,
+    /// approve and raise disputes for the caller on any of their escrows.
+    /// Payouts still only go to the caller's bound accounts.
+    pub fn set_operator(ctx: Context<SetOperator>, operator: Option<Pubkey>) -> Result<()> {
+        let owner = ctx.accounts.owner.key();
+        let operator_key = &mut ctx.accounts.operator_key;
+        operator_key.owner = owner;
+        operator_key.operator = operator;
+        operator_key.bump = ctx.bumps.operator_key;
+
+        emit!(OperatorSet { owner, operator, timestamp: unix_now(ctx.remaining_accounts)? });
+
+        Ok(())
+    }
+
+    /// Buyer names a separate key, typically a multisig vault PDA, that may
+    /// approve through `approve_with_authority`. `None` clears it.
+    pub fn set_buyer_authority(
+        ctx: Context<EscrowPartyAction>,
+        escrow_id: u64,
+        authority: Option<Pubkey>,
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        require!(!escrow.state.is_terminal(), ClawscrowError::InvalidState);
+        require!(ctx.accounts.signer.key() == escrow.buyer, ClawscrowError::Unauthorized);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+
+        escrow.buyer_authority = authority;
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.signer.key(), ctx.remaining_accounts)?;
+        emit!(BuyerAuthoritySet { escrow_id, meta, authority });
+
+        Ok(())
+    }
+
+    /// Buyer hands their side of a funded escrow to `new_buyer`, who signs to
+    /// accept it. Refunds and the buyer's collateral now pay the new buyer's
+    /// associated token account; any payout override or buyer authority is
+    /// cleared with the old owner.
+    pub fn transfer_buyer_role(ctx: Context<TransferBuyerRole>, escrow_id: u64) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        require!(
+            !escrow.state.is_terminal() && escrow.state != EscrowState::Draft,
+            ClawscrowError::InvalidState
+        );
+        require!(ctx.accounts.buyer.key() == escrow.buyer, ClawscrowError::Unauthorized);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        require!(!is_pooled(escrow), ClawscrowError::PooledEscrow);
+        let new_buyer = ctx.accounts.new_buyer.key();
+        require!(
+            new_buyer != escrow.buyer && new_buyer != escrow.seller && new_buyer != escrow.arbitrator,
+            ClawscrowError::InvalidBuyerTransfer
+        );
+        require_credential(&ctx.accounts.config, new_buyer, ctx.accounts.new_buyer_credential.as_ref())?;
+
+        let previous_buyer = escrow.buyer;
+        escrow.buyer = new_buyer;
+        escrow.buyer_payout = None;
+        escrow.buyer_payout_token = escrow.buyer_payout_ata();
+        escrow.buyer_authority = None;
+
+        let meta = event_meta(&ctx.accounts.escrow, previous_buyer, ctx.remaining_accounts)?;
+        emit!(BuyerRoleTransferred { escrow_id, meta, previous_buyer, new_buyer });
+
+        Ok(())
+    }
+
+    /// Buyer pays the seller out of `Accepted` without waiting for a delivery,
+    /// e.g. when the work was handed over off-chain. Settles like `approve`.
+    pub fn release_early<'info>(
+        mut ctx: Context<'_, '_, '_, 'info, Resolve<'info>>,
+        escrow_id: u64,
+        feedback_hash: Option<[u8; 32]>,
+        satisfaction: Option<u8>,
+    ) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Accepted, ClawscrowError::InvalidState);
+        require!(ctx.accounts.signer.key() == escrow.buyer, ClawscrowError::Unauthorized);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        require!(escrow.deliverable.is_none(), ClawscrowError::AssetDeliveryRequired);
+        if let Some(score) = satisfaction {
+            require!(
+                (1..=MAX_SATISFACTION_SCORE).contains(&score),
+                ClawscrowError::InvalidSatisfactionScore
+            );
+        }
+
+        let protocol_fee = settle_release(&mut ctx, escrow_id, feedback_hash, satisfaction)?;
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.signer.key(), ctx.remaining_accounts)?;
+        emit_cpi!(EscrowReleasedEarly { escrow_id, meta, feedback_hash, satisfaction, protocol_fee });
+
+        Ok(())
+    }
+
+    /// Seller offers to hand back part of the payment for a delivery that
+    /// fell short, as an alternative to a dispute. Replaces any earlier
+    /// offer; zero withdraws it. A new delivery also clears it.
+    pub fn offer_discount(ctx: Context<OfferDiscount>, escrow_id: u64, refund_amount: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Delivered, ClawscrowError::InvalidState);
+        require!(ctx.accounts.seller.key() == escrow.seller, ClawscrowError::Unauthorized);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        // Refunds would have to be divided between contributors.
+        require!(!is_pooled(escrow), ClawscrowError::PooledEscrow);
+        require!(refund_amount < escrow.remaining_payment(), ClawscrowError::InvalidDiscount);
+
+        ctx.accounts.escrow.discount_offer = (refund_amount > 0).then_some(refund_amount);
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.seller.key(), ctx.remaining_accounts)?;
+        emit!(DiscountOffered { escrow_id, meta, refund_amount });
+
+        Ok(())
+    }
+
+    /// Buyer takes the seller's discount offer: the refund goes back to the
+    /// buyer and the rest settles like `approve`.
+    pub fn settle_with_discount<'info>(
+        mut ctx: Context<'_, '_, '_, 'info, Resolve<'info>>,
+        escrow_id: u64,
+        feedback_hash: Option<[u8; 32]>,
+        satisfaction: Option<u8>,
+    ) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Delivered, ClawscrowError::InvalidState);
+        require!(
+            acts_for(ctx.accounts.signer.key(), escrow.buyer, ctx.accounts.operator_key.as_ref()),
+            ClawscrowError::Unauthorized
+        );
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        if let Some(score) = satisfaction {
+            require!(
+                (1..=MAX_SATISFACTION_SCORE).contains(&score),
+                ClawscrowError::InvalidSatisfactionScore
+            );
+        }
+        let refund_amount = escrow.discount_offer.ok_or(ClawscrowError::NoDiscountOffer)?;
+        require!(refund_amount < escrow.remaining_payment(), ClawscrowError::InvalidDiscount);
+        let bump = escrow.bump;
+        let buyer_key = escrow.original_buyer;
+
+        let id_bytes = escrow_id.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"escrow", buyer_key.as_ref(), id_bytes.as_ref(), &[bump]];
+        let signer_seeds = &[seeds];
+
+        ensure_vault_covers(&ctx.accounts.vault, &[refund_amount])?;
+
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.buyer_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            refund_amount,
+        )?;
+        ctx.accounts.vault.reload()?;
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.payment_amount -= refund_amount;
+        escrow.discount_offer = None;
+
+        let protocol_fee = settle_release(&mut ctx, escrow_id, feedback_hash, satisfaction)?;
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.signer.key(), ctx.remaining_accounts)?;
+        emit_cpi!(EscrowSettledWithDiscount { escrow_id, meta, refund_amount, feedback_hash, satisfaction, protocol_fee });
+
+        Ok(())
+    }
+
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        dispute_window_secs: i64,
+        free_disputes_per_window: u16,
+        dispute_cooldown_secs: i64,
+    ) -> Result<()> {
+        require!(dispute_window_secs > 0, ClawscrowError::InvalidConfig);
+        require!(dispute_cooldown_secs >= 0, ClawscrowError::InvalidConfig);
+
+        let config = &mut ctx.accounts.config;
+        config.admin = ctx.accounts.admin.key();
+        config.dispute_window_secs = dispute_window_secs;
+        config.free_disputes_per_window = free_disputes_per_window;
+        config.dispute_cooldown_secs = dispute_cooldown_secs;
+        config.min_collateral = 0;
+        config.max_collateral = u64::MAX;
+        config.arbitration_timeout_secs = DEFAULT_ARBITRATION_TIMEOUT_SECS;
+        config.stalled_buyer_bps = 5_000;
+        config.min_review_period_secs = 60 * 60;
+        config.max_review_period_secs = 30 * 24 * 60 * 60;
+        config.crank_tip_bps = 10;
+        config.seller_withdraw_forfeit_bps = 2_500;
+        config.dispute_bond_bps = 100;
+        config.bump = ctx.bumps.config;
+        ctx.accounts.stats.bump = ctx.bumps.stats;
+
+        Ok(())
+    }
+
+    pub fn update_config(
+        ctx: Context<UpdateConfig>,
+        dispute_window_secs: i64,
+        free_disputes_per_window: u16,
+        dispute_cooldown_secs: i64,
+    ) -> Result<()> {
+        require!(dispute_window_secs > 0, ClawscrowError::InvalidConfig);
+        require!(dispute_cooldown_secs >= 0, ClawscrowError::InvalidConfig);
+
+        let config = &mut ctx.accounts.config;
+        config.dispute_window_secs = dispute_window_secs;
+        config.free_disputes_per_window = free_disputes_per_window;
+        config.dispute_cooldown_secs = dispute_cooldown_secs;
+
+        Ok(())
+    }
+
+    pub fn set_protocol_fee(
+        ctx: Context<UpdateConfig>,
+        protocol_fee_bps: u16,
+        treasury: Pubkey,
+    ) -> Result<()> {
+        require!(protocol_fee_bps <= MAX_PROTOCOL_FEE_BPS, ClawscrowError::FeeTooHigh);
+
+        let config = &mut ctx.accounts.config;
+        config.protocol_fee_bps = protocol_fee_bps;
+        config.treasury = treasury;
+
+        Ok(())
+    }
+
+    /// Bond a buyer posts with each dispute, as a share of the pool.
+    pub fn set_dispute_bond(ctx: Context<UpdateConfig>, dispute_bond_bps: u16) -> Result<()> {
+        require!(dispute_bond_bps as u64 <= BPS_DENOMINATOR, ClawscrowError::InvalidConfig);
+        ctx.accounts.config.dispute_bond_bps = dispute_bond_bps;
+
+        Ok(())
+    }
+
+    /// Share of seller collateral handed to the buyer when a seller withdraws.
+    pub fn set_seller_withdraw_forfeit(ctx: Context<UpdateConfig>, forfeit_bps: u16) -> Result<()> {
+        require!(forfeit_bps as u64 <= BPS_DENOMINATOR, ClawscrowError::InvalidConfig);
+        ctx.accounts.config.seller_withdraw_forfeit_bps = forfeit_bps;
+
+        Ok(())
+    }
+
+    /// Bubblegum tree that `mint_receipts` appends to. The tree's creator must
+    /// delegate it to the `["receipt_authority"]` PDA first.
+    pub fn set_receipt_tree(ctx: Context<UpdateConfig>, merkle_tree: Pubkey) -> Result<()> {
+        ctx.accounts.config.receipt_tree = merkle_tree;
+
+        Ok(())
+    }
+
+    /// Turns compliance mode on with a credential mint, or off with the
+    /// default pubkey. Escrows already open are unaffected.
+    pub fn set_credential_mint(ctx: Context<UpdateConfig>, credential_mint: Pubkey) -> Result<()> {
+        ctx.accounts.config.credential_mint = credential_mint;
+
+        emit!(CredentialMintChanged {
+            credential_mint,
+            admin: ctx.accounts.admin.key(),
+            timestamp: unix_now(ctx.remaining_accounts)?,
+        });
+
+        Ok(())
+    }
+
+    /// Risk limits while the protocol is young; zero lifts either one. They
+    /// apply to escrows created afterwards.
+    pub fn set_risk_limits(ctx: Context<UpdateConfig>, max_open_escrows: u32, max_unvetted_payment: u64) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.max_open_escrows = max_open_escrows;
+        config.max_unvetted_payment = max_unvetted_payment;
+
+        emit!(RiskLimitsChanged {
+            max_open_escrows,
+            max_unvetted_payment,
+            admin: ctx.accounts.admin.key(),
+            timestamp: unix_now(ctx.remaining_accounts)?,
+        });
+
+        Ok(())
+    }
+
+    /// Premium an escrow pays into the insurance vault to opt in. Zero turns
+    /// insurance off for new policies; existing ones can still be claimed.
+    pub fn set_insurance_premium(ctx: Context<UpdateConfig>, premium_bps: u16) -> Result<()> {
+        require!(premium_bps <= MAX_INSURANCE_PREMIUM_BPS, ClawscrowError::FeeTooHigh);
+        ctx.accounts.config.insurance_premium_bps = premium_bps;
+
+        Ok(())
+    }
+
+    /// Keeper tip for `auto_approve`; applies to escrows created afterwards.
+    pub fn set_crank_tip(ctx: Context<UpdateConfig>, crank_tip_bps: u16) -> Result<()> {
+        require!(crank_tip_bps <= MAX_CRANK_TIP_BPS, ClawscrowError::FeeTooHigh);
+        ctx.accounts.config.crank_tip_bps = crank_tip_bps;
+
+        Ok(())
+    }
+
+    /// Incident switch: halts new escrows and acceptances. Settlements and
+    /// refunds keep working so funds are never trapped.
+    pub fn set_paused(ctx: Context<UpdateConfig>, paused: bool) -> Result<()> {
+        ctx.accounts.config.paused = paused;
+
+        emit!(PauseStateChanged {
+            paused,
+            admin: ctx.accounts.admin.key(),
+            timestamp: unix_now(ctx.remaining_accounts)?,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_collateral_bounds(
+        ctx: Context<UpdateConfig>,
+        min_collateral: u64,
+        max_collateral: u64,
+    ) -> Result<()> {
+        require!(min_collateral <= max_collateral, ClawscrowError::InvalidConfig);
+
+        let config = &mut ctx.accounts.config;
+        config.min_collateral = min_collateral;
+        config.max_collateral = max_collateral;
+
+        Ok(())
+    }
+
+    /// Minimum buyer and seller collateral as a share of the payment, so a
+    /// dispute always has enough at stake to pay the arbitrator.
+    pub fn set_collateral_ratios(
+        ctx: Context<UpdateConfig>,
+        min_buyer_collateral_bps: u16,
+        min_seller_collateral_bps: u16,
+    ) -> Result<()> {
+        require!(
+            min_buyer_collateral_bps as u64 <= BPS_DENOMINATOR && min_seller_collateral_bps as u64 <= BPS_DENOMINATOR,
+            ClawscrowError::InvalidConfig
+        );
+
+        let config = &mut ctx.accounts.config;
+        config.min_buyer_collateral_bps = min_buyer_collateral_bps;
+        config.min_seller_collateral_bps = min_seller_collateral_bps;
+
+        Ok(())
+    }
+
+    /// Range escrows may pick their buyer review window from.
+    pub fn set_review_period_bounds(
+        ctx: Context<UpdateConfig>,
+        min_review_period_secs: i64,
+        max_review_period_secs: i64,
+    ) -> Result<()> {
+        require!(min_review_period_secs > 0, ClawscrowError::InvalidConfig);
+        require!(min_review_period_secs <= max_review_period_secs, ClawscrowError::InvalidConfig);
+
+        let config = &mut ctx.accounts.config;
+        config.min_review_period_secs = min_review_period_secs;
+        config.max_review_period_secs = max_review_period_secs;
+
+        Ok(())
+    }
+
+    pub fn set_appeal_params(
+        ctx: Context<UpdateConfig>,
+        ruling_challenge_secs: i64,
+        appeal_bond_bps: u16,
+    ) -> Result<()> {
+        require!(ruling_challenge_secs >= 0, ClawscrowError::InvalidConfig);
+        require!(appeal_bond_bps as u64 <= BPS_DENOMINATOR, ClawscrowError::InvalidConfig);
+
+        let config = &mut ctx.accounts.config;
+        config.ruling_challenge_secs = ruling_challenge_secs;
+        config.appeal_bond_bps = appeal_bond_bps;
+
+        Ok(())
+    }
+
+    /// How long arbitrators get to rule, and how a stalled dispute is split.
+    pub fn set_arbitration_timeout(
+        ctx: Context<UpdateConfig>,
+        arbitration_timeout_secs: i64,
+        stalled_buyer_bps: u16,
+    ) -> Result<()> {
+        require!(arbitration_timeout_secs > 0, ClawscrowError::InvalidConfig);
+        require!(stalled_buyer_bps as u64 <= BPS_DENOMINATOR, ClawscrowError::InvalidConfig);
+
+        let config = &mut ctx.accounts.config;
+        config.arbitration_timeout_secs = arbitration_timeout_secs;
+        config.stalled_buyer_bps = stalled_buyer_bps;
+
+        Ok(())
+    }
+
+    /// Lamports an arbitrator locks to join the random-draw registry. Only
+    /// later registrations are affected.
+    pub fn set_arbitrator_stake(ctx: Context<UpdateConfig>, arbitrator_stake_lamports: u64) -> Result<()> {
+        ctx.accounts.config.arbitrator_stake_lamports = arbitrator_stake_lamports;
+
+        Ok(())
+    }
+
+    /// The SPL Governance realm disputes can be escalated to, and the pool
+    /// size from which they may be; `None` turns escalation off. Disputes
+    /// already escalated are unaffected.
+    pub fn set_dao_escalation(ctx: Context<UpdateConfig>, dao_escalation: Option<DaoEscalation>) -> Result<()> {
+        ctx.accounts.config.dao_escalation = dao_escalation;
+
+        Ok(())
+    }
+
+    pub fn set_fee_override(
+        ctx: Context<SetFeeOverride>,
+        platform: Pubkey,
+        fee_bps: u16,
+    ) -> Result<()> {
+        require!(fee_bps <= MAX_PROTOCOL_FEE_BPS, ClawscrowError::FeeTooHigh);
+
+        let fee_override = &mut ctx.accounts.fee_override;
+        fee_override.platform = platform;
+        fee_override.fee_bps = fee_bps;
+        fee_override.bump = ctx.bumps.fee_override;
+
+        emit!(FeeOverrideSet {
+            platform,
+            fee_bps,
+            admin: ctx.accounts.admin.key(),
+            timestamp: unix_now(ctx.remaining_accounts)?,
+        });
+
+        Ok(())
+    }
+
+    /// Whitelist the lending reserve `enable_yield` uses for `mint`, and the
+    /// buyer's share of the yield it earns. Escrows already lent keep the
+    /// reserve and ratio they were lent with.
+    pub fn set_lending_reserve(
+        ctx: Context<SetLendingReserve>,
+        target: LendingTarget,
+        buyer_yield_bps: u16,
+    ) -> Result<()> {
+        require!(buyer_yield_bps as u64 <= BPS_DENOMINATOR, ClawscrowError::InvalidConfig);
+
+        let lending_reserve = &mut ctx.accounts.lending_reserve;
+        lending_reserve.mint = ctx.accounts.mint.key();
+        lending_reserve.target = target;
+        lending_reserve.buyer_yield_bps = buyer_yield_bps;
+        lending_reserve.bump = ctx.bumps.lending_reserve;
+
+        emit!(LendingReserveSet {
+            mint: ctx.accounts.mint.key(),
+            reserve: ctx.accounts.lending_reserve.target.reserve,
+            buyer_yield_bps,
+            admin: ctx.accounts.admin.key(),
+            timestamp: unix_now(ctx.remaining_accounts)?,
+        });
+
+        Ok(())
+    }
+
+    pub fn remove_lending_reserve(ctx: Context<RemoveLendingReserve>) -> Result<()> {
+        emit!(LendingReserveRemoved {
+            mint: ctx.accounts.lending_reserve.mint,
+            admin: ctx.accounts.admin.key(),
+            timestamp: unix_now(ctx.remaining_accounts)?,
+        });
+
+        Ok(())
+    }
+
+    pub fn remove_fee_override(ctx: Context<RemoveFeeOverride>) -> Result<()> {
+        emit!(FeeOverrideRemoved {
+            platform: ctx.accounts.fee_override.platform,
+            admin: ctx.accounts.admin.key(),
+            timestamp: unix_now(ctx.remaining_accounts)?,
+        });
+
+        Ok(())
+    }
+
+    /// The buyer posts a dispute bond, sized from the pool, that follows the ruling.
+    pub fn raise_dispute(ctx: Context<DisputeCtx>) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(
+            acts_for(ctx.accounts.buyer.key(), escrow.buyer, ctx.accounts.operator_key.as_ref()),
+            ClawscrowError::Unauthorized
+        );
+        let now = unix_now(ctx.remaining_accounts)?;
+        // Work that never arrives can be disputed once the deadline has passed.
+        let undelivered = match escrow.state {
+            EscrowState::Delivered => false,
+            EscrowState::Accepted => {
+                require!(now > escrow.deadline_ts, ClawscrowError::DeadlineNotReached);
+                true
+            }
+            _ => return err!(ClawscrowError::InvalidState),
+        };
+
+        let bond = escrow.dispute_bond_for(ctx.accounts.config.dispute_bond_bps)
+            .ok_or(ClawscrowError::Overflow)?;
+        if bond > 0 {
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.buyer_token.to_account_info(),
+                        to: ctx.accounts.vault.to_account_info(),
+                        authority: ctx.accounts.buyer.to_account_info(),
+                    },
+                ),
+                bond,
+            )?;
+        }
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.buyer.key(), ctx.remaining_accounts)?;
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.dispute_bond = bond;
+        let config = &ctx.accounts.config;
+        let reputation = &mut ctx.accounts.reputation;
+        reputation.init_if_empty(escrow.buyer, ctx.bumps.reputation);
+        reputation.record_dispute(config, now)?;
+        ctx.accounts.stats.record_dispute();
+
+        let queue = &mut ctx.accounts.case_queue;
+        queue.init_if_empty(escrow.arbitrator, ctx.bumps.case_queue);
+        queue.enqueue(escrow.key())?;
+
+        escrow.state = EscrowState::Disputed;
+        escrow.disputed_at = now;
+        escrow.arbitration_deadline = now.saturating_add(config.arbitration_timeout_secs);
+
+        emit_cpi!(EscrowDisputed { escrow_id: escrow.escrow_id, meta, undelivered, bond });
+
+        Ok(())
+    }
+
+    /// Accept part of a delivery: `amount` of the payment and the matching
+    /// share of seller collateral are released now, and only the remainder
+    /// goes to dispute.
+    pub fn approve_partial<'info>(ctx: Context<'_, '_, '_, 'info, ApprovePartial<'info>>, escrow_id: u64, amount: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Delivered, ClawscrowError::InvalidState);
+        require!(ctx.accounts.buyer.key() == escrow.buyer, ClawscrowError::Unauthorized);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        let payment = escrow.remaining_payment();
+        // Approving everything is just `approve`; approving nothing is just `raise_dispute`.
+        require!(amount > 0 && amount < payment, ClawscrowError::InvalidAmount);
+
+        let (payout, collateral) = math::partial_approval(amount, payment, escrow.seller_collateral, escrow.protocol_fee_bps)
+            .ok_or(ClawscrowError::Overflow)?;
+        let referral_bps = escrow.referral.as_ref().map_or(0, |r| r.fee_bps);
+        let (payout, referral_fee) = math::with_referral_fee(payout, amount, referral_bps)
+            .ok_or(ClawscrowError::Overflow)?;
+        let bump = escrow.bump;
+        let buyer_key = escrow.original_buyer;
+
+        let id_bytes = escrow_id.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"escrow", buyer_key.as_ref(), id_bytes.as_ref(), &[bump]];
+        let signer_seeds = &[seeds];
+
+        ensure_vault_covers(&ctx.accounts.vault, &[payout.seller, payout.protocol_fee, referral_fee])?;
+
+        let split_paid = pay_payout_split(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            &ctx.accounts.escrow,
+            signer_seeds,
+            ctx.accounts.payout_split.as_ref(),
+            ctx.remaining_accounts,
+            payout.seller - collateral,
+        )?;
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.seller_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            payout.seller - split_paid,
+        )?;
+
+        pay_referral_fee(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            &ctx.accounts.escrow,
+            signer_seeds,
+            ctx.accounts.referrer_token.as_ref(),
+            referral_fee,
+            ctx.accounts.buyer.key(),
+            ctx.remaining_accounts,
+        )?;
+
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.treasury_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            payout.protocol_fee,
+        )?;
+
+        let now = unix_now(ctx.remaining_accounts)?;
+        let config = &ctx.accounts.config;
+        let reputation = &mut ctx.accounts.reputation;
+        reputation.init_if_empty(escrow.buyer, ctx.bumps.reputation);
+        reputation.record_dispute(config, now)?;
+        ctx.accounts.stats.record_released(amount);
+        ctx.accounts.stats.record_dispute();
+
+        let escrow = &mut ctx.accounts.escrow;
+        let queue = &mut ctx.accounts.case_queue;
+        queue.init_if_empty(escrow.arbitrator, ctx.bumps.case_queue);
+        queue.enqueue(escrow.key())?;
+
+        escrow.released_amount = escrow.released_amount
+            .checked_add(amount)
+            .ok_or(ClawscrowError::Overflow)?;
+        escrow.seller_collateral -= collateral;
+        escrow.state = EscrowState::Disputed;
+        escrow.disputed_at = now;
+        escrow.arbitration_deadline = now.saturating_add(config.arbitration_timeout_secs);
+
+        // The bond is sized from what is still in dispute.
+        let bond = escrow.dispute_bond_for(config.dispute_bond_bps).ok_or(ClawscrowError::Overflow)?;
+        escrow.dispute_bond = bond;
+        if bond > 0 {
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.buyer_token.to_account_info(),
+                        to: ctx.accounts.vault.to_account_info(),
+                        authority: ctx.accounts.buyer.to_account_info(),
+                    },
+                ),
+                bond,
+            )?;
+        }
+        let escrow = &ctx.accounts.escrow;
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.buyer.key(), ctx.remaining_accounts)?;
+        emit!(PartiallyApproved {
+            escrow_id,
+            meta,
+            amount,
+            seller_amount: payout.seller,
+            protocol_fee: payout.protocol_fee,
+            disputed_payment: escrow.remaining_payment(),
+        });
+        emit_cpi!(EscrowDisputed { escrow_id, meta, undelivered: false, bond });
+
+        Ok(())
+    }
+
+    /// Once the arbitration deadline passes, the escrow's backup arbitrator
+    /// (if any) may rule in the primary's place and earns the fee instead.
+    pub fn arbitrate(ctx: Context<Arbitrate>, escrow_id: u64, ruling: Ruling) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Disputed, ClawscrowError::InvalidState);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        require!(escrow.panel.is_none(), ClawscrowError::PanelRulingRequired);
+        require!(ruling.is_valid(), ClawscrowError::InvalidRuling);
+
+        let now = unix_now(ctx.remaining_accounts)?;
+        let signer = ctx.accounts.arbitrator.key();
+        let by_backup = signer != escrow.arbitrator;
+        if by_backup {
+            require!(escrow.backup_arbitrator == Some(signer), ClawscrowError::Unauthorized);
+            require!(now > escrow.arbitration_deadline, ClawscrowError::ArbitrationDeadlineNotReached);
+        }
+        let arb_fee = escrow.arbitrator_fee_at(now);
+        let executable_at = now.saturating_add(ctx.accounts.config.ruling_challenge_secs);
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.pending_ruling = Some(ruling.clone());
+        escrow.ruled_at = now;
+        escrow.arbitrator_fee = arb_fee;
+        escrow.ruled_by_backup = by_backup;
+        escrow.state = EscrowState::RulingPending;
+
+        let escrow_key = ctx.accounts.escrow.key();
+        ctx.accounts.case_queue.escrows.retain(|key| *key != escrow_key);
+
+        let meta = event_meta(&ctx.accounts.escrow, signer, ctx.remaining_accounts)?;
+        emit!(RulingRecorded { escrow_id, meta, ruling, executable_at });
+
+        Ok(())
+    }
+
+    /// A panelist's vote. The first ruling to reach a majority becomes the
+    /// pending ruling, exactly as if a sole arbitrator had made it.
+    pub fn cast_ruling_vote(ctx: Context<CastRulingVote>, escrow_id: u64, ruling: Ruling) -> Result<()> {
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.panelist.key(), ctx.remaining_accounts)?;
+        let escrow = &mut ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Disputed, ClawscrowError::InvalidState);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        require!(ruling.is_valid(), ClawscrowError::InvalidRuling);
+        let panelist = ctx.accounts.panelist.key();
+        let now = unix_now(ctx.remaining_accounts)?;
+        let arb_fee = escrow.arbitrator_fee_at(now);
+
+        let panel = escrow.panel.as_mut().ok_or(ClawscrowError::InvalidPanel)?;
+        let seat = panel.seat_of(&panelist).ok_or(ClawscrowError::Unauthorized)?;
+        require!(panel.votes[seat].is_none(), ClawscrowError::AlreadyVoted);
+        panel.votes[seat] = Some(ruling.clone());
+        let majority = panel.majority();
+
+        emit!(RulingVoteCast { escrow_id, meta, panelist, ruling });
+
+        if let Some(ruling) = majority {
+            escrow.pending_ruling = Some(ruling.clone());
+            escrow.ruled_at = now;
+            escrow.arbitrator_fee = arb_fee;
+            escrow.state = EscrowState::RulingPending;
+
+            let escrow_key = ctx.accounts.escrow.key();
+            ctx.accounts.case_queue.escrows.retain(|key| *key != escrow_key);
+
+            let executable_at = now.saturating_add(ctx.accounts.config.ruling_challenge_secs);
+            emit!(RulingRecorded { escrow_id, meta, ruling, executable_at });
+        }
+
+        Ok(())
+    }
+
+    /// Once a random-arbitrator escrow is disputed, anyone can request the
+    /// VRF draw, paying ORAO's fee. The candidates are fixed here: every
+    /// registered arbitrator other than the buyer and seller.
+    pub fn request_random_arbitrator(ctx: Context<RequestRandomArbitrator>, escrow_id: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Disputed, ClawscrowError::InvalidState);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        require!(
+            ctx.accounts.random_arbitration.status == RandomDrawStatus::Idle,
+            ClawscrowError::InvalidState
+        );
+        let candidates: Vec<Pubkey> = ctx.accounts.registry.arbitrators
+            .iter()
+            .filter(|key| **key != escrow.buyer && **key != escrow.seller)
+            .copied()
+            .collect();
+        require!(!candidates.is_empty(), ClawscrowError::NoEligibleArbitrators);
+
+        let seed = ctx.accounts.random_arbitration.seed;
+        require_keys_eq!(
+            ctx.accounts.randomness.key(),
+            vrf::randomness_address(&seed),
+            ClawscrowError::InvalidRandomness
+        );
+        invoke(
+            &vrf::request_instruction(ctx.accounts.payer.key(), ctx.accounts.treasury.key(), seed),
+            &[
+                ctx.accounts.payer.to_account_info(),
+                ctx.accounts.network_state.to_account_info(),
+                ctx.accounts.treasury.to_account_info(),
+                ctx.accounts.randomness.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        let random = &mut ctx.accounts.random_arbitration;
+        random.status = RandomDrawStatus::Pending;
+        random.candidates = candidates;
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.payer.key(), ctx.remaining_accounts)?;
+        emit!(RandomArbitratorRequested {
+            escrow_id,
+            meta,
+            randomness: ctx.accounts.randomness.key(),
+            candidates: ctx.accounts.random_arbitration.candidates.len() as u8,
+        });
+
+        Ok(())
+    }
+
+    /// Apply a fulfilled draw: the candidate the randomness picks becomes the
+    /// arbitrator, the case moves to their queue, and they get a full
+    /// arbitration timeout from now. If ORAO never answers, the dispute
+    /// falls through to `resolve_stalled_dispute` like any other.
+    pub fn fulfill_random_arbitrator(ctx: Context<FulfillRandomArbitrator>, escrow_id: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Disputed, ClawscrowError::InvalidState);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        let random = &ctx.accounts.random_arbitration;
+        require!(random.status == RandomDrawStatus::Pending, ClawscrowError::InvalidState);
+
+        let randomness = vrf::read_randomness(&ctx.accounts.randomness, &random.seed)?;
+        let drawn = random.candidates[vrf::draw_index(&randomness, random.candidates.len())];
+        require_keys_eq!(ctx.accounts.arbitrator.key(), drawn, ClawscrowError::NotDrawnArbitrator);
+
+        let escrow_key = ctx.accounts.escrow.key();
+        let queue = &mut ctx.accounts.case_queue;
+        queue.init_if_empty(drawn, ctx.bumps.case_queue);
+        queue.enqueue(escrow_key)?;
+        let index = &mut ctx.accounts.arbitrator_index;
+        index.init_if_empty(drawn, ctx.bumps.arbitrator_index);
+        require!(index.escrows.len() < MAX_INDEXED_ESCROWS, ClawscrowError::ArbitratorIndexFull);
+        index.escrows.push(escrow_key);
+
+        ctx.accounts.random_arbitration.status = RandomDrawStatus::Drawn;
+
+        let now = unix_now(ctx.remaining_accounts)?;
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.arbitrator = drawn;
+        escrow.arbitration_deadline = now.saturating_add(ctx.accounts.config.arbitration_timeout_secs);
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.payer.key(), ctx.remaining_accounts)?;
+        emit!(RandomArbitratorDrawn { escrow_id, meta, arbitrator: drawn });
+
+        Ok(())
+    }
+
+    pub fn submit_appeal(
+        ctx: Context<SubmitAppeal>,
+        escrow_id: u64,
+        evidence_hash: [u8; 32],
+    ) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::RulingPending, ClawscrowError::InvalidState);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        require!(escrow.appeal.is_none(), ClawscrowError::AppealAlreadySubmitted);
+        let appellant = ctx.accounts.appellant.key();
+        require!(appellant == escrow.buyer || appellant == escrow.seller, ClawscrowError::Unauthorized);
+
+        let config = &ctx.accounts.config;
+        let now = unix_now(ctx.remaining_accounts)?;
+        require!(
+            now < escrow.ruled_at.saturating_add(config.ruling_challenge_secs),
+            ClawscrowError::ChallengeWindowClosed
+        );
+
+        let pool = math::total_pool(
+            escrow.remaining_payment(),
+            escrow.buyer_collateral,
+            escrow.seller_collateral,
+        ).ok_or(ClawscrowError::Overflow)?;
+        let bond = math::appeal_bond(pool, config.appeal_bond_bps).ok_or(ClawscrowError::Overflow)?;
+
+        if bond > 0 {
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.appellant_token.to_account_info(),
+                        to: ctx.accounts.vault.to_account_info(),
+                        authority: ctx.accounts.appellant.to_account_info(),
+                    },
+                ),
+                bond,
+            )?;
+        }
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.appeal = Some(Appeal {
+            appellant,
+            evidence_hash,
+            bond,
+            status: AppealStatus::Submitted,
+        });
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.appellant.key(), ctx.remaining_accounts)?;
+        emit!(AppealSubmitted { escrow_id, meta, appellant, evidence_hash, bond });
+
+        Ok(())
+    }
+
+    pub fn freeze_ruling(ctx: Context<AdminEscrowAction>, escrow_id: u64) -> Result<()> {
+        let config = &ctx.accounts.config;
+        let escrow = &mut ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::RulingPending, ClawscrowError::InvalidState);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        let now = unix_now(ctx.remaining_accounts)?;
+        require!(
+            now < escrow.ruled_at.saturating_add(config.ruling_challenge_secs),
+            ClawscrowError::ChallengeWindowClosed
+        );
+
+        let appeal = escrow.appeal.as_mut().ok_or(ClawscrowError::NoAppeal)?;
+        require!(appeal.status == AppealStatus::Submitted, ClawscrowError::NoAppeal);
+        appeal.status = AppealStatus::Frozen;
+        escrow.state = EscrowState::RulingFrozen;
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.admin.key(), ctx.remaining_accounts)?;
+        emit!(RulingFrozen { escrow_id, meta });
+
+        Ok(())
+    }
+
+    pub fn resolve_appeal(
+        ctx: Context<AdminEscrowAction>,
+        escrow_id: u64,
+        final_ruling: Ruling,
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::RulingFrozen, ClawscrowError::InvalidState);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        require!(final_ruling.is_valid(), ClawscrowError::InvalidRuling);
+
+        // An appeal is upheld when the admin overturns the arbitrator's ruling.
+        let upheld = escrow.pending_ruling.as_ref() != Some(&final_ruling);
+        let appeal = escrow.appeal.as_mut().ok_or(ClawscrowError::NoAppeal)?;
+        appeal.status = if upheld { AppealStatus::Upheld } else { AppealStatus::Rejected };
+        escrow.pending_ruling = Some(final_ruling.clone());
+        escrow.state = EscrowState::RulingPending;
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.admin.key(), ctx.remaining_accounts)?;
+        emit!(AppealResolved { escrow_id, meta, ruling: final_ruling, upheld });
+
+        Ok(())
+    }
+
+    /// Panel escrows split the arbitrator fee among everyone who voted; each
+    /// voter's token account must be passed in the remaining accounts.
+    pub fn execute_ruling<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExecuteRuling<'info>>,
+        escrow_id: u64,
+    ) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        // An escalated dispute only carries a ruling inside
+        // `finalize_governance_ruling`, which has checked the vote.
+        let by_dao = escrow.state == EscrowState::Escalated;
+        require!(escrow.state == EscrowState::RulingPending || by_dao, ClawscrowError::InvalidState);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+
+        // DAO votes and resolved appeals are final; otherwise wait out the
+        // challenge window.
+        let appeal_resolved = matches!(
+            escrow.appeal.as_ref().map(|a| &a.status),
+            Some(AppealStatus::Upheld) | Some(AppealStatus::Rejected)
+        );
+        if !appeal_resolved && !by_dao {
+            let now = unix_now(ctx.remaining_accounts)?;
+            require!(
+                now >= escrow.ruled_at.saturating_add(ctx.accounts.config.ruling_challenge_secs),
+                ClawscrowError::ChallengeWindowOpen
+            );
+        }
+
+        reprice_usd_peg(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.buyer_token.to_account_info(),
+            &mut ctx.accounts.escrow,
+            ctx.accounts.price_update.as_ref(),
+            ctx.accounts.signer.key(),
+            ctx.remaining_accounts,
+        )?;
+        ctx.accounts.vault.reload()?;
+        let escrow = &ctx.accounts.escrow;
+        let ruling = escrow.pending_ruling.clone().ok_or(ClawscrowError::InvalidState)?;
+        let payment = escrow.remaining_payment();
+        let buyer_col = escrow.buyer_collateral;
+        let seller_col = escrow.seller_collateral;
+        let arb_fee = escrow.arbitrator_fee;
+        let bump = escrow.bump;
+        let buyer_key = escrow.original_buyer;
+
+        let id_bytes = escrow_id.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"escrow", buyer_key.as_ref(), id_bytes.as_ref(), &[bump]];
+        let signer_seeds = &[seeds];
+
+        // The arbitrator fee is charged to the losing side's collateral first.
+        let buyer_bps = match ruling {
+            Ruling::BuyerWins => math::BPS_DENOMINATOR as u16,
+            Ruling::SellerWins => 0,
+            Ruling::Split { buyer_bps } => buyer_bps,
+        };
+        let split = math::ruling_split(payment, buyer_col, seller_col, arb_fee, escrow.protocol_fee_bps, buyer_bps)
+            .ok_or(ClawscrowError::InvalidRuling)?;
+        let protocol_fee = split.protocol_fee;
+        let (buyer_amount, seller_amount) = (split.buyer, split.seller);
+
+        let bond = escrow.appeal.as_ref().map_or(0, |a| a.bond);
+        // The dispute bond follows the ruling, like the pool.
+        let (buyer_bond, seller_bond) = match ruling {
+            Ruling::BuyerWins => (escrow.dispute_bond, 0),
+            Ruling::SellerWins => (0, escrow.dispute_bond),
+            Ruling::Split { buyer_bps } => {
+                math::split_bps(escrow.dispute_bond, buyer_bps).ok_or(ClawscrowError::InvalidRuling)?
+            }
+        };
+        let buyer_amount = buyer_amount.checked_add(buyer_bond).ok_or(ClawscrowError::Overflow)?;
+        let seller_amount = seller_amount.checked_add(seller_bond).ok_or(ClawscrowError::Overflow)?;
+        // Whatever the ruling leaves the seller settles that much of the sale the
+        // referrer brought in, like an approval.
+        let referral_fee = match &escrow.referral {
+            Some(referral) => math::ruling_referral_fee(payment, buyer_bps, referral.fee_bps)
+                .ok_or(ClawscrowError::Overflow)?,
+            None => 0,
+        };
+        let seller_amount = seller_amount.checked_sub(referral_fee).ok_or(ClawscrowError::Overflow)?;
+        ensure_vault_covers(
+            &ctx.accounts.vault,
+            &[buyer_amount, seller_amount, arb_fee, protocol_fee, bond, referral_fee],
+        )?;
+
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.buyer_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            buyer_amount,
+        )?;
+
+        // A seller-wins ruling honours the seller's payout split like an approval.
+        let split_paid = match ruling {
+            Ruling::SellerWins => pay_payout_split(
+                &ctx.accounts.token_program,
+                &ctx.accounts.vault,
+                &ctx.accounts.escrow,
+                signer_seeds,
+                ctx.accounts.payout_split.as_deref(),
+                ctx.remaining_accounts,
+                seller_amount.saturating_sub(seller_col),
+            )?,
+            _ => 0,
+        };
+        let seller_amount = seller_amount - split_paid;
+
+        match &ctx.accounts.unwrap_token {
+            Some(unwrap_token) => unwrap_native_payout(
+                &ctx.accounts.token_program,
+                &ctx.accounts.vault,
+                unwrap_token,
+                &ctx.accounts.escrow,
+                signer_seeds,
+                ctx.accounts.seller_payout_owner.to_account_info(),
+                ctx.accounts.signer.to_account_info(),
+                seller_amount,
+            )?,
+            None => vault_transfer(
+                &ctx.accounts.token_program,
+                &ctx.accounts.vault,
+                ctx.accounts.seller_token.to_account_info(),
+                &ctx.accounts.escrow,
+                signer_seeds,
+                seller_amount,
+            )?,
+        }
+
+        if ruling == Ruling::SellerWins {
+            pay_referral_fee(
+                &ctx.accounts.token_program,
+                &ctx.accounts.vault,
+                &ctx.accounts.escrow,
+                signer_seeds,
+                ctx.accounts.referrer_token.as_ref(),
+                referral_fee,
+                ctx.accounts.signer.key(),
+                ctx.remaining_accounts,
+            )?;
+        }
+
+        match &escrow.panel {
+            Some(panel) => {
+                let voters = panel.voters();
+                let shares = math::equal_shares(arb_fee, voters.len()).ok_or(ClawscrowError::InvalidPanel)?;
+                for (voter, share) in voters.iter().zip(shares) {
+                    let to = panel_token_account(ctx.remaining_accounts, voter, &escrow.mint)?;
+                    vault_transfer(
+                        &ctx.accounts.token_program,
+                        &ctx.accounts.vault,
+                        to,
+                        &ctx.accounts.escrow,
+                        signer_seeds,
+                        share,
+                    )?;
+                }
+            }
+            None => {
+                vault_transfer(
+                    &ctx.accounts.token_program,
+                    &ctx.accounts.vault,
+                    ctx.accounts.arbitrator_token.to_account_info(),
+                    &ctx.accounts.escrow,
+                    signer_seeds,
+                    arb_fee,
+                )?;
+            }
+        }
+
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.treasury_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            protocol_fee,
+        )?;
+
+        // Appeal bonds are refunded unless the admin rejected the appeal.
+        if let Some(appeal) = &escrow.appeal {
+            let bond_to = if appeal.status == AppealStatus::Rejected {
+                ctx.accounts.treasury_token.to_account_info()
+            } else if appeal.appellant == escrow.buyer {
+                ctx.accounts.buyer_token.to_account_info()
+            } else {
+                ctx.accounts.seller_token.to_account_info()
+            };
+            vault_transfer(
+                &ctx.accounts.token_program,
+                &ctx.accounts.vault,
+                bond_to,
+                &ctx.accounts.escrow,
+                signer_seeds,
+                appeal.bond,
+            )?;
+        }
+
+        let now = unix_now(ctx.remaining_accounts)?;
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.state = match ruling {
+            Ruling::BuyerWins => EscrowState::ResolvedBuyer,
+            Ruling::SellerWins => EscrowState::ResolvedSeller,
+            Ruling::Split { .. } => EscrowState::ResolvedSplit,
+        };
+        release_open_slot(escrow, ctx.accounts.counter.as_mut())?;
+        escrow.resolved_at = now;
+        let (buyer, seller) = (escrow.buyer, escrow.seller);
+        let (volume, created_at) = (escrow.payment_amount, escrow.created_at);
+
+        let buyer_rep = &mut ctx.accounts.buyer_reputation;
+        buyer_rep.init_if_empty(buyer, ctx.bumps.buyer_reputation);
+        buyer_rep.record_settlement(volume, created_at, now);
+        if ruling == Ruling::SellerWins {
+            buyer_rep.disputes_lost = buyer_rep.disputes_lost.saturating_add(1);
+        }
+
+        let seller_rep = &mut ctx.accounts.seller_reputation;
+        seller_rep.init_if_empty(seller, ctx.bumps.seller_reputation);
+        seller_rep.record_settlement(volume, created_at, now);
+        if ruling == Ruling::BuyerWins {
+            seller_rep.disputes_lost = seller_rep.disputes_lost.saturating_add(1);
+        }
+
+        let escrow_key = ctx.accounts.escrow.key();
+        ctx.accounts.arbitrator_index.escrows.retain(|key| *key != escrow_key);
+        ctx.accounts.stats.record_ruling(&ruling);
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.signer.key(), ctx.remaining_accounts)?;
+        emit_cpi!(DisputeResolved { escrow_id, meta, ruling, buyer_amount, seller_amount, protocol_fee });
+
+        Ok(())
+    }
+
+    /// Either party may take a dispute whose pool reaches the configured
+    /// threshold away from its arbitrator and put it to a DAO vote: a
+    /// "Buyer"/"Seller" proposal is created in the configured realm and
+    /// signed off, so voting opens at once. The caller pays the proposal's
+    /// rent and the realm's proposal deposit.
+    pub fn escalate_to_dao(ctx: Context<EscalateToDao>, escrow_id: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Disputed, ClawscrowError::InvalidState);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        let signer = ctx.accounts.signer.key();
+        require!(signer == escrow.buyer || signer == escrow.seller, ClawscrowError::Unauthorized);
+
+        let dao = ctx.accounts.config.dao_escalation.clone().ok_or(ClawscrowError::DaoEscalationDisabled)?;
+        let pool = math::total_pool(escrow.remaining_payment(), escrow.buyer_collateral, escrow.seller_collateral)
+            .ok_or(ClawscrowError::Overflow)?;
+        require!(pool >= dao.min_pool, ClawscrowError::BelowEscalationThreshold);
+
+        let escrow_key = escrow.key();
+        let proposal = governance::proposal_address(&dao, &escrow_key);
+        for (passed, expected) in [
+            (ctx.accounts.governance_program.key(), dao.program),
+            (ctx.accounts.realm.key(), dao.realm),
+            (ctx.accounts.governance.key(), dao.governance),
+            (ctx.accounts.governing_token_mint.key(), dao.governing_token_mint),
+            (ctx.accounts.proposal_owner_record.key(), dao.proposal_owner_record),
+            (ctx.accounts.proposal.key(), proposal),
+        ] {
+            require_keys_eq!(passed, expected, ClawscrowError::InvalidGovernanceAccount);
+        }
+
+        let authority = ctx.accounts.governance_authority.key();
+        let authority_seeds: &[&[u8]] = &[b"governance_authority", &[ctx.bumps.governance_authority]];
+        invoke_signed(
+            &governance::create_proposal_instruction(
+                &dao,
+                escrow_key,
+                authority,
+                signer,
+                &format!("Clawscrow dispute #{escrow_id}"),
+            ),
+            &[
+                ctx.accounts.realm.to_account_info(),
+                ctx.accounts.proposal.to_account_info(),
+                ctx.accounts.governance.to_account_info(),
+                ctx.accounts.proposal_owner_record.to_account_info(),
+                ctx.accounts.governing_token_mint.to_account_info(),
+                ctx.accounts.governance_authority.to_account_info(),
+                ctx.accounts.signer.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+                ctx.accounts.realm_config.to_account_info(),
+                ctx.accounts.proposal_deposit.to_account_info(),
+            ],
+            &[authority_seeds],
+        )?;
+        invoke_signed(
+            &governance::sign_off_instruction(&dao, proposal, authority),
+            &[
+                ctx.accounts.realm.to_account_info(),
+                ctx.accounts.governance.to_account_info(),
+                ctx.accounts.proposal.to_account_info(),
+                ctx.accounts.governance_authority.to_account_info(),
+                ctx.accounts.proposal_owner_record.to_account_info(),
+            ],
+            &[authority_seeds],
+        )?;
+
+        let escalation = &mut ctx.accounts.escalation;
+        escalation.escrow = escrow_key;
+        escalation.program = dao.program;
+        escalation.proposal = proposal;
+        escalation.bump = ctx.bumps.escalation;
+
+        ctx.accounts.escrow.state = EscrowState::Escalated;
+        ctx.accounts.case_queue.escrows.retain(|key| *key != escrow_key);
+
+        let meta = event_meta(&ctx.accounts.escrow, signer, ctx.remaining_accounts)?;
+        emit!(DisputeEscalated { escrow_id, meta, proposal, pool });
+
+        Ok(())
+    }
+
+    /// Settle an escalated dispute by its finished vote: the winning option
+    /// rules fully for that side, and a vote that ends without exactly one
+    /// winner (or is vetoed) splits the pool like a stalled dispute. There
+    /// is no arbitrator fee and no challenge window. Permissionless, with
+    /// the same accounts as `execute_ruling` plus the escalation record and
+    /// the proposal.
+    pub fn finalize_governance_ruling<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExecuteRuling<'info>>,
+        escrow_id: u64,
+    ) -> Result<()> {
+        require!(ctx.accounts.escrow.state == EscrowState::Escalated, ClawscrowError::InvalidState);
+        let escalation = ctx.accounts.escalation.as_ref().ok_or(ClawscrowError::InvalidGovernanceAccount)?;
+        let proposal = ctx.accounts.governance_proposal.as_ref().ok_or(ClawscrowError::InvalidGovernanceAccount)?;
+        require_keys_eq!(proposal.key(), escalation.proposal, ClawscrowError::InvalidGovernanceAccount);
+
+        let ruling = match governance::read_outcome(proposal, &escalation.program)? {
+            governance::ProposalOutcome::Winner(0) => Ruling::BuyerWins,
+            governance::ProposalOutcome::Winner(_) => Ruling::SellerWins,
+            _ => Ruling::Split { buyer_bps: ctx.accounts.config.stalled_buyer_bps },
+        };
+        let now = unix_now(ctx.remaining_accounts)?;
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.pending_ruling = Some(ruling);
+        escrow.ruled_at = now;
+        escrow.arbitrator_fee = 0;
+        escrow.ruled_by_backup = false;
+
+        execute_ruling(ctx, escrow_id)
+    }
+
+    /// Permissionless fallback once the arbitrator misses the deadline: the
+    /// pool is split by the configured default and the arbitrator fee is forfeited.
+    pub fn resolve_stalled_dispute<'info>(
+        ctx: Context<'_, '_, '_, 'info, ResolveStalled<'info>>,
+        escrow_id: u64,
+    ) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Disputed, ClawscrowError::InvalidState);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        let now = unix_now(ctx.remaining_accounts)?;
+        require!(now > escrow.arbitration_deadline, ClawscrowError::ArbitrationDeadlineNotReached);
+
+        reprice_usd_peg(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.buyer_token.to_account_info(),
+            &mut ctx.accounts.escrow,
+            ctx.accounts.price_update.as_ref(),
+            ctx.accounts.signer.key(),
+            ctx.remaining_accounts,
+        )?;
+        ctx.accounts.vault.reload()?;
+        let escrow = &ctx.accounts.escrow;
+        let forfeited_fee = escrow.arbitrator_fee_at(escrow.arbitration_deadline);
+        let bump = escrow.bump;
+        let buyer_key = escrow.original_buyer;
+
+        let id_bytes = escrow_id.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"escrow", buyer_key.as_ref(), id_bytes.as_ref(), &[bump]];
+        let signer_seeds = &[seeds];
+
+        let payout = math::ruling_payout(
+            escrow.remaining_payment(),
+            escrow.buyer_collateral,
+            escrow.seller_collateral,
+            0,
+            escrow.protocol_fee_bps,
+        ).ok_or(ClawscrowError::Overflow)?;
+        let protocol_fee = payout.protocol_fee;
+        let buyer_bps = ctx.accounts.config.stalled_buyer_bps;
+        let (buyer_amount, seller_amount) = math::split_bps(payout.winner, buyer_bps)
+            .ok_or(ClawscrowError::InvalidConfig)?;
+        // The arbitrator, not the buyer, stalled the case, so the dispute bond is refunded.
+        let buyer_amount = buyer_amount.checked_add(escrow.dispute_bond).ok_or(ClawscrowError::Overflow)?;
+        let referral_fee = match &escrow.referral {
+            Some(referral) => math::ruling_referral_fee(escrow.remaining_payment(), buyer_bps, referral.fee_bps)
+                .ok_or(ClawscrowError::Overflow)?,
+            None => 0,
+        };
+        let seller_amount = seller_amount.checked_sub(referral_fee).ok_or(ClawscrowError::Overflow)?;
+
+        ensure_vault_covers(&ctx.accounts.vault, &[buyer_amount, seller_amount, protocol_fee, referral_fee])?;
+
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.buyer_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            buyer_amount,
+        )?;
+
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.seller_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            seller_amount,
+        )?;
+
+        pay_referral_fee(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            &ctx.accounts.escrow,
+            signer_seeds,
+            ctx.accounts.referrer_token.as_ref(),
+            referral_fee,
+            ctx.accounts.signer.key(),
+            ctx.remaining_accounts,
+        )?;
+
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.treasury_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            protocol_fee,
+        )?;
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.state = EscrowState::ResolvedSplit;
+        release_open_slot(escrow, ctx.accounts.counter.as_mut())?;
+        // Recorded like an arbitrator's split so the outcome can be read back, e.g. by `settle_second_leg`.
+        escrow.pending_ruling = Some(Ruling::Split { buyer_bps });
+        escrow.resolved_at = now;
+        let escrow_key = ctx.accounts.escrow.key();
+        ctx.accounts.case_queue.escrows.retain(|key| *key != escrow_key);
+        ctx.accounts.arbitrator_index.escrows.retain(|key| *key != escrow_key);
+        let stats = &mut ctx.accounts.stats;
+        stats.disputes_stalled = stats.disputes_stalled.saturating_add(1);
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.signer.key(), ctx.remaining_accounts)?;
+        emit!(StalledDisputeResolved { escrow_id, meta, buyer_amount, seller_amount, protocol_fee, forfeited_fee });
+
+        Ok(())
+    }
+
+    /// Permissionless once the review window has passed. A keeper that passes
+    /// `crank_token` earns the escrow's crank tip, taken from the seller's payment.
+    pub fn auto_approve<'info>(ctx: Context<'_, '_, '_, 'info, Resolve<'info>>, escrow_id: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Delivered, ClawscrowError::InvalidState);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+
+        let now = unix_now(ctx.remaining_accounts)?;
+        require!(
+            now >= escrow.review_started_at().saturating_add(escrow.review_period_secs),
+            ClawscrowError::ReviewPeriodActive
+        );
+
+        reprice_usd_peg(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.buyer_token.to_account_info(),
+            &mut ctx.accounts.escrow,
+            ctx.accounts.price_update.as_ref(),
+            ctx.accounts.signer.key(),
+            ctx.remaining_accounts,
+        )?;
+        ctx.accounts.vault.reload()?;
+        let escrow = &ctx.accounts.escrow;
+        let payment = escrow.remaining_payment();
+        let seller_col = escrow.seller_collateral;
+        let buyer_col = escrow.buyer_collateral;
+        let bump = escrow.bump;
+        let buyer_key = escrow.original_buyer;
+
+        let id_bytes = escrow_id.to_le_bytes();
+        let seeds: &[&[u8]] = &[b"escrow", buyer_key.as_ref(), id_bytes.as_ref(), &[bump]];
+        let signer_seeds = &[seeds];
+
+        let payout = math::approval_payout(payment, buyer_col, seller_col, escrow.protocol_fee_bps)
+            .ok_or(ClawscrowError::Overflow)?;
+        let tip_bps = if ctx.accounts.crank_token.is_some() { escrow.crank_tip_bps } else { 0 };
+        let (payout, crank_tip) = math::with_crank_tip(payout, payment, tip_bps)
+            .ok_or(ClawscrowError::Overflow)?;
+        let referral_bps = escrow.referral.as_ref().map_or(0, |r| r.fee_bps);
+        let (payout, referral_fee) = math::with_referral_fee(payout, payment, referral_bps)
+            .ok_or(ClawscrowError::Overflow)?;
+        let (seller_total, protocol_fee) = (payout.seller, payout.protocol_fee);
+        // Vesting escrows return the seller's collateral now and stream the payment via `claim_vested`.
+        let net_payment = seller_total - seller_col;
+        let seller_now = if escrow.vesting_secs.is_some() { seller_col } else { seller_total };
+
+        ensure_vault_covers(&ctx.accounts.vault, &[seller_total, payout.buyer, protocol_fee, crank_tip, referral_fee])?;
+
+        release_asset(
+            &ctx.accounts.token_program,
+            &ctx.accounts.escrow,
+            ctx.accounts.asset_vault.as_ref(),
+            ctx.accounts.buyer_asset_token.as_ref(),
+            ctx.accounts.seller_payout_owner.to_account_info(),
+            signer_seeds,
+        )?;
+
+        let split_paid = pay_payout_split(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            &ctx.accounts.escrow,
+            signer_seeds,
+            ctx.accounts.payout_split.as_deref(),
+            ctx.remaining_accounts,
+            seller_now - seller_col,
+        )?;
+        let seller_now = seller_now - split_paid;
+
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.seller_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            seller_now,
+        )?;
+
+        if let Some(crank_token) = &ctx.accounts.crank_token {
+            vault_transfer(
+                &ctx.accounts.token_program,
+                &ctx.accounts.vault,
+                crank_token.to_account_info(),
+                &ctx.accounts.escrow,
+                signer_seeds,
+                crank_tip,
+            )?;
+        }
+
+        pay_referral_fee(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            &ctx.accounts.escrow,
+            signer_seeds,
+            ctx.accounts.referrer_token.as_ref(),
+            referral_fee,
+            ctx.accounts.signer.key(),
+            ctx.remaining_accounts,
+        )?;
+
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.buyer_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            payout.buyer,
+        )?;
+
+        vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.treasury_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            protocol_fee,
+        )?;
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.settle_approval(now, net_payment);
+        release_open_slot(escrow, ctx.accounts.counter.as_mut())?;
+        let (buyer, seller) = (escrow.buyer, escrow.seller);
+        let (volume, created_at) = (escrow.payment_amount, escrow.created_at);
+
+        let buyer_rep = &mut ctx.accounts.buyer_reputation;
+        buyer_rep.init_if_empty(buyer, ctx.bumps.buyer_reputation);
+        buyer_rep.record_settlement(volume, created_at, now);
+
+        let seller_rep = &mut ctx.accounts.seller_reputation;
+        seller_rep.init_if_empty(seller, ctx.bumps.seller_reputation);
+        seller_rep.record_settlement(volume, created_at, now);
+        ctx.accounts.stats.record_released(payment);
+
+        let escrow_key = ctx.accounts.escrow.key();
+        ctx.accounts.arbitrator_index.escrows.retain(|key| *key != escrow_key);
+
+        let meta = event_meta(&ctx.accounts.escrow, ctx.accounts.signer.key(), ctx.remaining_accounts)?;
+        if crank_tip > 0 {
+            emit!(CrankTipPaid { escrow_id, meta, cranker: ctx.accounts.signer.key(), amount: crank_tip });
+        }
+        emit_cpi!(EscrowAutoApproved { escrow_id, meta, protocol_fee, crank_tip });
+
+        Ok(())
+    }
+
+    /// Register a Clockwork thread that runs `auto_approve` on a delivered
+    /// escrow as soon as its review period lapses, so approval does not
+    /// depend on a keeper. The seller (or their operator) pays the thread's
+    /// rent plus `amount` lamports for the worker's fees and any accounts
+    /// `auto_approve` creates; `cancel_auto_approve` returns what is left.
+    ///
+    /// The thread's accounts are fixed here: the payout split, the buyer's
+    /// asset token account, the referrer's associated token account and,
+    /// for a USD-pegged escrow, a sponsored Pyth feed must already exist. A later acknowledgement does not move the
+    /// trigger forward, but a keeper can still approve earlier.
+    pub fn schedule_auto_approve(ctx: Context<ScheduleAutoApprove>, escrow_id: u64, amount: u64) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.state == EscrowState::Delivered, ClawscrowError::InvalidState);
+        require!(escrow.escrow_id == escrow_id, ClawscrowError::InvalidState);
+        let payer = ctx.accounts.seller.key();
+        require!(acts_for(payer, escrow.seller, ctx.accounts.operator_key.as_ref()), ClawscrowError::Unauthorized);
+
+        let escrow_key = escrow.key();
+        let pda = |seeds: &[&[u8]]| Pubkey::find_program_address(seeds, &crate::ID).0;
+        let buyer_payout_owner = escrow.buyer_payout.unwrap_or(escrow.buyer);
+        let seller_payout_owner = escrow.seller_payout.unwrap_or(escrow.seller);
+        let mut resolve = crate::accounts::Resolve {
+            signer: automation::PAYER_PUBKEY,
+            escrow: escrow_key,
+            vault: pda(&[b"vault", escrow_key.as_ref()]),
+            counter: escrow.holds_open_slot.then(|| pda(&[b"counter", escrow.original_buyer.as_ref()])),
+            buyer_payout_owner,
+            seller_payout_owner,
+            mint: escrow.mint,
+            buyer_token: get_associated_token_address(&buyer_payout_owner, &escrow.mint),
+            seller_token: get_associated_token_address(&seller_payout_owner, &escrow.mint),
+            unwrap_token: None,
+            payout_split: None,
+            operator_key: None,
+            asset_vault: None,
+            buyer_asset_token: None,
+            price_update: None,
+            tip_source: None,
+            referrer_token: escrow.referral.as_ref().map(|r| get_associated_token_address(&r.referrer, &escrow.mint)),
+            config: ctx.accounts.config.key(),
+            stats: pda(&[b"stats"]),
+            treasury_token: get_associated_token_address(&ctx.accounts.config.treasury, &escrow.mint),
+            buyer_reputation: pda(&[b"reputation", escrow.buyer.as_ref()]),
+            seller_reputation: pda(&[b"reputation", escrow.seller.as_ref()]),
+            arbitrator_index: pda(&[b"arbitrator_index", escrow.arbitrator.as_ref()]),
+            crank_token: None,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            event_authority: pda(&[b"__event_authority"]),
+            program: crate::ID,
+        };
+        let mut recipients = Vec::new();
+        if escrow.has_payout_split {
+            let split = ctx.accounts.payout_split.as_ref().ok_or(ClawscrowError::PayoutSplitMissing)?;
+            resolve.payout_split = Some(split.key());
+            recipients.extend(split.recipients.iter().map(|r| {
+                AccountMeta::new(get_associated_token_address(&r.wallet, &escrow.mint), false)
+            }));
+        }
+        if let Some(deliverable) = &escrow.deliverable {
+            resolve.asset_vault = Some(pda(&[b"asset_vault", escrow_key.as_ref()]));
+            resolve.buyer_asset_token = Some(get_associated_token_address(&buyer_payout_owner, &deliverable.mint));
+        }
+        if escrow.usd_peg.as_ref().is_some_and(|peg| !peg.priced) {
+            let feed = ctx.accounts.price_update.as_ref().ok_or(ClawscrowError::PriceUpdateMissing)?;
+            resolve.price_update = Some(feed.key());
+        }
+        let mut metas = resolve.to_account_metas(None);
+        metas.extend(recipients);
+        let approve = Instruction {
+            program_id: crate::ID,
+            accounts: metas,
+            data: crate::instruction::AutoApprove { escrow_id }.data(),
+        };
+
+        let fire_at = escrow.review_started_at().saturating_add(escrow.review_period_secs);
+        let authority_seeds: &[&[u8]] = &[automation::THREAD_AUTHORITY_SEED, &[ctx.bumps.thread_authority]];
+        invoke_signed(
+            &automation::thread_create_instruction(escrow_key, payer, amount, &approve, fire_at),
+            &[
+                ctx.accounts.thread_authority.to_account_info(),
+                ctx.accounts.seller.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+                ctx.accounts.thread.to_account_info(),
+            ],
+            &[authority_seeds],
+        )?;
+
+        let record = &mut ctx.accounts.auto_approve_thread;
+        record.escrow = escrow_key;
+        record.payer = payer;
+        record.fire_at = fire_at;
+        record.bump = ctx.bumps.auto_approve_thread;
+
+        let meta = event_meta(&ctx.accounts.escrow, payer, ctx.remaining_accounts)?;
+        emit!(AutoApproveScheduled { escrow_id, meta, thread: ctx.accounts.thread.key(), fire_at, amount });
+
+        Ok(())
+    }
+
+    /// Delete an escrow's auto-approve thread, returning its remaining
+    /// lamports and the record's rent to whoever scheduled it. Works at any
+    /// time, including after the thread has fired or the escrow has closed.
+    pub fn cancel_auto_approve(ctx: Context<CancelAutoApprove>) -> Result<()> {
+        let escrow = ctx.accounts.auto_approve_thread.escrow;
+        let authority_seeds: &[&[u8]] = &[automation::THREAD_AUTHORITY_SEED, &[ctx.bumps.thread_authority]];
+        invoke_signed(
+            &automation::thread_delete_instruction(escrow, ctx.accounts.payer.key()),
+            &[
+                ctx.accounts.thread_authority.to_account_info(),
+                ctx.accounts.payer.to_account_info(),
+                ctx.accounts.thread.to_account_info(),
+            ],
+            &[authority_seeds],
+        )?;
+
+        emit!(AutoApproveCancelled {
+            escrow,
+            thread: ctx.accounts.thread.key(),
+            payer: ctx.accounts.payer.key(),
+            timestamp: unix_now(ctx.remaining_accounts)?,
+        });
+
+        Ok(())
+    }
+}
+
+// === HELPERS ===
+
+/// Checks the terms every new escrow shares and writes them into `escrow` as
+/// a funded, unaccepted escrow owed to the buyer's own token account.
+/// Callers set their extras and bumps on top. Returns the terms hash.
+#[allow(clippy::too_many_arguments)]
+fn open_escrow(
+    escrow: &mut Escrow,
+    config: &Config,
+    arbitrator_profile: Option<&Account<ArbitratorProfile>>,
+    escrow_id: u64,
+    terms: terms::TermsV1,
+    description: String,
+    decimals: u8,
+    review_period_secs: i64,
+    trusted_pair: bool,
+    now: i64,
+) -> Result<[u8; 32]> {
+    require!(terms.payment_amount > 0, ClawscrowError::InvalidAmount);
+    text::validate_description(&description)?;
+    require!(terms.deadline_ts > now, ClawscrowError::InvalidDeadline);
+    require_collateral_bounds(config, terms.buyer_collateral, terms.seller_collateral)?;
+    // A trusted pair names its seller, so the waiver can't be picked up by a stranger.
+    require!(!trusted_pair || terms.seller != Pubkey::default(), ClawscrowError::InvalidTrustedPair);
+    if !trusted_pair {
+        require_collateral_ratio(config, terms.payment_amount, terms.buyer_collateral, terms.seller_collateral)?;
+    }
+    require_vetted_arbitrator(config, arbitrator_profile, terms.payment_amount)?;
+    require!(
+        review_period_secs >= config.min_review_period_secs
+            && review_period_secs <= config.max_review_period_secs,
+        ClawscrowError::ReviewPeriodOutOfBounds
+    );
+    let terms_hash = terms.hash()?;
+
+    escrow.layout_version = ESCROW_LAYOUT_VERSION;
+    escrow.genesis_slot = Clock::get()?.slot;
+    escrow.holds_open_slot = true;
+    escrow.escrow_id = escrow_id;
+    escrow.buyer = terms.buyer;
+    escrow.original_buyer = terms.buyer;
+    escrow.seller = terms.seller;
+    escrow.arbitrator = terms.arbitrator;
+    escrow.mint = terms.mint;
+    escrow.payment_amount = terms.payment_amount;
+    escrow.buyer_collateral = terms.buyer_collateral;
+    escrow.seller_collateral = terms.seller_collateral;
+    escrow.decimals = decimals;
+    escrow.deadline_ts = terms.deadline_ts;
+    escrow.description = description;
+    escrow.state = EscrowState::Created;
+    escrow.created_at = now;
+    escrow.milestones = terms.milestone_amounts
+        .into_iter()
+        .map(|amount| Milestone {
+            amount,
+            status: MilestoneStatus::Pending,
+            delivery_hash: [0u8; 32],
+        })
+        .collect();
+    escrow.arbitration_sla = terms.arbitration_sla;
+    escrow.platform = terms.platform;
+    escrow.protocol_fee_bps = config.protocol_fee_bps;
+    escrow.crank_tip_bps = config.crank_tip_bps;
+    escrow.terms_hash = terms_hash;
+    escrow.trusted_pair = trusted_pair;
+    escrow.buyer_payout = None;
+    escrow.buyer_payout_token = escrow.buyer_payout_ata();
+    escrow.review_period_secs = review_period_secs;
+    Ok(terms_hash)
+}
+
+/// In compliance mode, `holder` must pass a token account of theirs holding
+/// the configured credential.
+fn require_credential(config: &Config, holder: Pubkey, credential: Option<&Account<TokenAccount>>) -> Result<()> {
+    if config.credential_mint == Pubkey::default() {
+        return Ok(());
+    }
+    let credential = credential.ok_or(ClawscrowError::CredentialRequired)?;
+    require!(
+        credential.mint == config.credential_mint && credential.owner == holder && credential.amount > 0,
+        ClawscrowError::CredentialRequired
+    );
+    Ok(())
+}
+
+/// Each side's collateral must lie within `Config::min_collateral` and `max_collateral`.
+fn require_collateral_bounds(config: &Config, buyer_collateral: u64, seller_collateral: u64) -> Result<()> {
+    for collateral in [buyer_collateral, seller_collateral] {
+        require!(
+            collateral >= config.min_collateral && collateral <= config.max_collateral,
+            ClawscrowError::CollateralOutOfBounds
+        );
+    }
+    Ok(())
+}
+
+/// Each side's collateral must be at least its `Config` share of the payment.
+fn require_collateral_ratio(config: &Config, payment_amount: u64, buyer_collateral: u64, seller_collateral: u64) -> Result<()> {
+    for (collateral, min_bps) in [
+        (buyer_collateral, config.min_buyer_collateral_bps),
+        (seller_collateral, config.min_seller_collateral_bps),
+    ] {
+        let min = math::bps_of(payment_amount, min_bps).ok_or(ClawscrowError::Overflow)?;
+        require!(collateral >= min, ClawscrowError::CollateralBelowRatio);
+    }
+    Ok(())
+}
+
+/// Payments above `Config::max_unvetted_payment` need an arbitrator with an
+/// `ArbitratorProfile`, passed as `profile`; its seeds tie it to the escrow's arbitrator.
+fn require_vetted_arbitrator(config: &Config, profile: Option<&Account<ArbitratorProfile>>, payment_amount: u64) -> Result<()> {
+    if config.max_unvetted_payment == 0 || payment_amount <= config.max_unvetted_payment {
+        return Ok(());
+    }
+    require!(profile.is_some_and(|p| p.roles_accepted > 0), ClawscrowError::ArbitratorNotVetted);
+    Ok(())
+}
+
+/// Grow `info` to `new_len`, with `payer` covering any extra rent. New bytes read as zero.
+fn grow_account<'info>(
+    payer: &Signer<'info>,
+    info: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    new_len: usize,
+) -> Result<()> {
+    if info.data_len() >= new_len {
+        return Ok(());
+    }
+    let shortfall = Rent::get()?.minimum_balance(new_len).saturating_sub(info.lamports());
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer { from: payer.to_account_info(), to: info.clone() },
+            ),
+            shortfall,
+        )?;
+    }
+    info.realloc(new_len, true)?;
+    Ok(())
+}
+
+/// Current unix time for handlers. `test-harness` builds let a `TestClock`
+/// passed in the remaining accounts stand in for the sysvar.
+fn unix_now(remaining_accounts: &[AccountInfo]) -> Result<i64> {
+    #[cfg(feature = "test-harness")]
+    if let Some(pinned) = harness::clock_override(remaining_accounts)? {
+        return Ok(pinned);
+    }
+    #[cfg(not(feature = "test-harness"))]
+    let _ = remaining_accounts;
+    Ok(Clock::get()?.unix_timestamp)
+}
+
+/// The `RandomArbitration` PDA of `escrow`, which stands in as its
+/// arbitrator until a draw is made.
+fn random_arbitration_address(escrow: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"random_arbitration", escrow.as_ref()], &crate::ID).0
+}
+
+/// ORAO request seed for an escrow's draw. Each escrow draws at most once,
+/// so a seed derived from its address is unique.
+fn draw_seed(escrow: &Pubkey) -> [u8; 32] {
+    hashv(&[b"arbitrator_draw", escrow.as_ref()]).to_bytes()
+}
+
+/// Shared event fields for `escrow`, attributed to `actor` at the current time.
+fn event_meta(escrow: &Account<Escrow>, actor: Pubkey, remaining_accounts: &[AccountInfo]) -> Result<EscrowEventMeta> {
+    Ok(EscrowEventMeta {
+        escrow: escrow.key(),
+        mint: escrow.mint,
+        actor,
+        timestamp: unix_now(remaining_accounts)?,
+    })
+}
+
+/// Buyer-approved release: payment (less protocol fee) and seller collateral
+/// to the seller, buyer collateral back, reputation updated. Callers check
+/// state and signer. Returns the protocol fee.
+fn settle_release<'info>(
+    ctx: &mut Context<'_, '_, '_, 'info, Resolve<'info>>,
+    escrow_id: u64,
+    feedback_hash: Option<[u8; 32]>,
+    satisfaction: Option<u8>,
+) -> Result<u64> {
+    reprice_usd_peg(
+        &ctx.accounts.token_program,
+        &ctx.accounts.vault,
+        ctx.accounts.buyer_token.to_account_info(),
+        &mut ctx.accounts.escrow,
+        ctx.accounts.price_update.as_ref(),
+        ctx.accounts.signer.key(),
+        ctx.remaining_accounts,
+    )?;
+    ctx.accounts.vault.reload()?;
+    let escrow = &ctx.accounts.escrow;
+    let payment = escrow.remaining_payment();
+    let seller_col = escrow.seller_collateral;
+    let buyer_col = escrow.buyer_collateral;
+    let bump = escrow.bump;
+    let buyer_key = escrow.original_buyer;
+
+    let id_bytes = escrow_id.to_le_bytes();
+    let seeds: &[&[u8]] = &[b"escrow", buyer_key.as_ref(), id_bytes.as_ref(), &[bump]];
+    let signer_seeds = &[seeds];
+
+    let payout = math::approval_payout(payment, buyer_col, seller_col, escrow.protocol_fee_bps)
+        .ok_or(ClawscrowError::Overflow)?;
+    let referral_bps = escrow.referral.as_ref().map_or(0, |r| r.fee_bps);
+    let (payout, referral_fee) = math::with_referral_fee(payout, payment, referral_bps)
+        .ok_or(ClawscrowError::Overflow)?;
+    let (seller_total, protocol_fee) = (payout.seller, payout.protocol_fee);
+    // Vesting escrows return the seller's collateral now and stream the payment via `claim_vested`.
+    let net_payment = seller_total - seller_col;
+    let seller_now = if escrow.vesting_secs.is_some() { seller_col } else { seller_total };
+
+    ensure_vault_covers(&ctx.accounts.vault, &[seller_total, payout.buyer, protocol_fee, referral_fee])?;
+
+    release_asset(
+        &ctx.accounts.token_program,
+        &ctx.accounts.escrow,
+        ctx.accounts.asset_vault.as_ref(),
+        ctx.accounts.buyer_asset_token.as_ref(),
+        ctx.accounts.seller_payout_owner.to_account_info(),
+        signer_seeds,
+    )?;
+
+    let split_paid = pay_payout_split(
+        &ctx.accounts.token_program,
+        &ctx.accounts.vault,
+        &ctx.accounts.escrow,
+        signer_seeds,
+        ctx.accounts.payout_split.as_deref(),
+        ctx.remaining_accounts,
+        seller_now - seller_col,
+    )?;
+    let seller_now = seller_now - split_paid;
+
+    match &ctx.accounts.unwrap_token {
+        Some(unwrap_token) => unwrap_native_payout(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            unwrap_token,
+            &ctx.accounts.escrow,
+            signer_seeds,
+            ctx.accounts.seller_payout_owner.to_account_info(),
+            ctx.accounts.signer.to_account_info(),
+            seller_now,
+        )?,
+        None => vault_transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            ctx.accounts.seller_token.to_account_info(),
+            &ctx.accounts.escrow,
+            signer_seeds,
+            seller_now,
+        )?,
+    }
+
+    pay_referral_fee(
+        &ctx.accounts.token_program,
+        &ctx.accounts.vault,
+        &ctx.accounts.escrow,
+        signer_seeds,
+        ctx.accounts.referrer_token.as_ref(),
+        referral_fee,
+        ctx.accounts.signer.key(),
+        ctx.remaining_accounts,
+    )?;
+
+    vault_transfer(
+        &ctx.accounts.token_program,
+        &ctx.accounts.vault,
+        ctx.accounts.buyer_token.to_account_info(),
+        &ctx.accounts.escrow,
+        signer_seeds,
+        payout.buyer,
+    )?;
+
+    vault_transfer(
+        &ctx.accounts.token_program,
+        &ctx.accounts.vault,
+        ctx.accounts.treasury_token.to_account_info(),
+        &ctx.accounts.escrow,
+        signer_seeds,
+        protocol_fee,
+    )?;
+
+    let now = unix_now(ctx.remaining_accounts)?;
+    let escrow = &mut ctx.accounts.escrow;
+    escrow.settle_approval(now, net_payment);
+    release_open_slot(escrow, ctx.accounts.counter.as_mut())?;
+    escrow.feedback_hash = feedback_hash;
+    escrow.satisfaction = satisfaction;
+    let (buyer, seller) = (escrow.buyer, escrow.seller);
+    let (volume, created_at) = (escrow.payment_amount, escrow.created_at);
+    let escrow_key = ctx.accounts.escrow.key();
+    ctx.accounts.arbitrator_index.escrows.retain(|key| *key != escrow_key);
+
+    let buyer_rep = &mut ctx.accounts.buyer_reputation;
+    buyer_rep.init_if_empty(buyer, ctx.bumps.buyer_reputation);
+    buyer_rep.record_settlement(volume, created_at, now);
+
+    let seller_rep = &mut ctx.accounts.seller_reputation;
+    seller_rep.init_if_empty(seller, ctx.bumps.seller_reputation);
+    seller_rep.record_settlement(volume, created_at, now);
+    if let Some(score) = satisfaction {
+        seller_rep.ratings_count = seller_rep.ratings_count.saturating_add(1);
+        seller_rep.ratings_total = seller_rep.ratings_total.saturating_add(score as u64);
+    }
+    ctx.accounts.stats.record_released(payment);
+
+    Ok(protocol_fee)
+}
+
+/// A panel voter's token account for `mint`, found among the remaining accounts.
+fn panel_token_account<'info>(
+    remaining_accounts: &[AccountInfo<'info>],
+    voter: &Pubkey,
+    mint: &Pubkey,
+) -> Result<AccountInfo<'info>> {
+    find_token_account(remaining_accounts, voter, mint)?.ok_or(error!(ClawscrowError::PanelTokenAccountMissing))
+}
+
+/// A writable token account of `owner` for `mint` among the remaining accounts.
+fn find_token_account<'info>(
+    remaining_accounts: &[AccountInfo<'info>],
+    owner: &Pubkey,
+    mint: &Pubkey,
+) -> Result<Option<AccountInfo<'info>>> {
+    for info in remaining_accounts {
+        if info.owner != &token::ID || !info.is_writable {
+            continue;
+        }
+        let data = info.try_borrow_data()?;
+        let Ok(account) = TokenAccount::try_deserialize(&mut &data[..]) else {
+            continue;
+        };
+        if account.owner == *owner && account.mint == *mint {
+            return Ok(Some(info.clone()));
+        }
+    }
+    Ok(None)
+}
+
+/// Fan `amount` out to the seller's payout split, if they registered one,
+/// using each recipient's token account from the remaining accounts. Returns
+/// what was paid, so the caller sends the seller only the rest.
+fn pay_payout_split<'info>(
+    token_program: &Program<'info, Token>,
+    vault: &Account<'info, TokenAccount>,
+    escrow: &Account<'info, Escrow>,
+    signer_seeds: &[&[&[u8]]],
+    split: Option<&Account<'info, PayoutSplit>>,
+    remaining_accounts: &[AccountInfo<'info>],
+    amount: u64,
+) -> Result<u64> {
+    if !escrow.has_payout_split {
+        return Ok(0);
+    }
+    let split = split.ok_or(ClawscrowError::PayoutSplitMissing)?;
+    let weights: Vec<u16> = split.recipients.iter().map(|r| r.bps).collect();
+    let shares = math::weighted_shares(amount, &weights).ok_or(ClawscrowError::InvalidPayoutSplit)?;
+    for (recipient, share) in split.recipients.iter().zip(shares) {
+        let to = find_token_account(remaining_accounts, &recipient.wallet, &escrow.mint)?
+            .ok_or(ClawscrowError::PayoutSplitMissing)?;
+        vault_transfer(token_program, vault, to, escrow, signer_seeds, share)?;
+    }
+    Ok(amount)
+}
+
+/// Route the referral fee of any settlement that pays the seller (approvals,
+/// milestones, timesheets, partial approvals, and rulings or stalled-dispute
+/// splits, pro rata to the seller's share) to the referrer named at creation.
+/// `referrer_token` is required whenever the escrow has a referral, so
+/// neither party can withhold the fee by leaving it out.
+#[allow(clippy::too_many_arguments)]
+fn pay_referral_fee<'info>(
+    token_program: &Program<'info, Token>,
+    vault: &Account<'info, TokenAccount>,
+    escrow: &Account<'info, Escrow>,
+    signer_seeds: &[&[&[u8]]],
+    referrer_token: Option<&Account<'info, TokenAccount>>,
+    amount: u64,
+    actor: Pubkey,
+    remaining_accounts: &[AccountInfo<'info>],
+) -> Result<()> {
+    let Some(referral) = &escrow.referral else {
+        return Ok(());
+    };
+    let referrer_token = referrer_token.ok_or(ClawscrowError::ReferrerAccountMismatch)?;
+    require_keys_eq!(referrer_token.owner, referral.referrer, ClawscrowError::ReferrerAccountMismatch);
+    vault_transfer(token_program, vault, referrer_token.to_account_info(), escrow, signer_seeds, amount)?;
+
+    emit!(ReferralFeePaid {
+        escrow_id: escrow.escrow_id,
+        meta: event_meta(escrow, actor, remaining_accounts)?,
+        referrer: referral.referrer,
+        amount,
+    });
+    Ok(())
+}
+
+/// Pay `amount` of a wrapped SOL escrow to `recipient` as native SOL: move it
+/// into the temporary `unwrap_token`, close that into the escrow account and
+/// forward the lamports, refunding the temporary account's rent to `payer`.
+#[allow(clippy::too_many_arguments)]
+fn unwrap_native_payout<'info>(
+    token_program: &Program<'info, Token>,
+    vault: &Account<'info, TokenAccount>,
+    unwrap_token: &Account<'info, TokenAccount>,
+    escrow: &Account<'info, Escrow>,
+    signer_seeds: &[&[&[u8]]],
+    recipient: AccountInfo<'info>,
+    payer: AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    require_keys_eq!(vault.mint, token::spl_token::native_mint::ID, ClawscrowError::NotNativeMint);
+    vault_transfer(token_program, vault, unwrap_token.to_account_info(), escrow, signer_seeds, amount)?;
+
+    let escrow_info = escrow.to_account_info();
+    let before = escrow_info.lamports();
+    token::close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        CloseAccount {
+            account: unwrap_token.to_account_info(),
+            destination: escrow_info.clone(),
+            authority: escrow_info.clone(),
+        },
+        signer_seeds,
+    ))?;
+    let reclaimed = escrow_info.lamports().checked_sub(before).ok_or(ClawscrowError::Overflow)?;
+    let rent = reclaimed.checked_sub(amount).ok_or(ClawscrowError::Overflow)?;
+    escrow_info.sub_lamports(reclaimed)?;
+    recipient.add_lamports(amount)?;
+    payer.add_lamports(rent)?;
+    Ok(())
+}
+
+/// Whether `signer` is `party` itself or the operator `party` registered.
+fn acts_for(signer: Pubkey, party: Pubkey, operator_key: Option<&Account<OperatorKey>>) -> bool {
+    signer == party || operator_key.is_some_and(|k| k.owner == party && k.operator == Some(signer))
+}
+
+/// Pooled escrows pay their buyer side to the escrow PDA itself; see `open_pool`.
+fn is_pooled(escrow: &Account<Escrow>) -> bool {
+    escrow.buyer_payout == Some(escrow.key())
+}
+
+/// Create a PDA the way Anchor's `init` does, topping up and assigning it
+/// instead of failing if someone has already sent it lamports.
+fn init_pda<'info>(
+    payer: &Signer<'info>,
+    account: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    space: usize,
+    owner: &Pubkey,
+    seeds: &[&[u8]],
+) -> Result<()> {
+    let required = Rent::get()?.minimum_balance(space);
+    let current = account.lamports();
+    let program = system_program.to_account_info();
+    if current == 0 {
+        return system_program::create_account(
+            CpiContext::new_with_signer(
+                program,
+                system_program::CreateAccount { from: payer.to_account_info(), to: account.clone() },
+                &[seeds],
+            ),
+            required,
+            space as u64,
+            owner,
+        );
+    }
+    if required > current {
+        system_program::transfer(
+            CpiContext::new(
+                program.clone(),
+                system_program::Transfer { from: payer.to_account_info(), to: account.clone() },
+            ),
+            required - current,
+        )?;
+    }
+    system_program::allocate(
+        CpiContext::new_with_signer(
+            program.clone(),
+            system_program::Allocate { account_to_allocate: account.clone() },
+            &[seeds],
+        ),
+        space as u64,
+    )?;
+    system_program::assign(
+        CpiContext::new_with_signer(program, system_program::Assign { account_to_assign: account.clone() }, &[seeds]),
+        owner,
+    )
+}
+
+/// Move `amount` out of an escrow's vault, signed by the escrow PDA. Zero amounts are skipped.
+fn vault_transfer<'info>(
+    token_program: &Program<'info, Token>,
+    vault: &Account<'info, TokenAccount>,
+    to: AccountInfo<'info>,
+    escrow: &Account<'info, Escrow>,
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    token::transfer(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            Transfer {
+                from: vault.to_account_info(),
+                to,
+                authority: escrow.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+    )
+}
+
+/// Prices a USD-pegged escrow in lamports the first time it pays out, capped
+/// at the deposit and floored at what was already released, and refunds the
+/// surplus to `buyer_token`. A no-op for other escrows. The refund leaves
+/// `vault` stale, so callers reload it before checking the balance.
+fn reprice_usd_peg<'info>(
+    token_program: &Program<'info, Token>,
+    vault: &Account<'info, TokenAccount>,
+    buyer_token: AccountInfo<'info>,
+    escrow: &mut Account<'info, Escrow>,
+    price_update: Option<&UncheckedAccount<'info>>,
+    actor: Pubkey,
+    remaining_accounts: &[AccountInfo<'info>],
+) -> Result<()> {
+    let usd_amount = match &escrow.usd_peg {
+        Some(peg) if !peg.priced => peg.usd_amount,
+        _ => return Ok(()),
+    };
+    let price_update = price_update.ok_or(ClawscrowError::PriceUpdateMissing)?;
+    let now = unix_now(remaining_accounts)?;
+    let price = oracle::read_sol_usd(price_update, now, MAX_PRICE_AGE_SECS, MAX_PRICE_CONF_BPS)?;
+    let owed = math::usd_to_lamports(usd_amount, price.price, price.exponent).ok_or(ClawscrowError::Overflow)?;
+    let payment = owed.clamp(escrow.released_amount, escrow.payment_amount);
+    let refunded = escrow.payment_amount - payment;
+
+    escrow.payment_amount = payment;
+    if let Some(peg) = escrow.usd_peg.as_mut() {
+        peg.priced = true;
+    }
+
+    let id_bytes = escrow.escrow_id.to_le_bytes();
+    let seeds: &[&[u8]] = &[b"escrow", escrow.original_buyer.as_ref(), id_bytes.as_ref(), &[escrow.bump]];
+    vault_transfer(token_program, vault, buyer_token, escrow, &[seeds], refunded)?;
+
+    let meta = event_meta(escrow, actor, remaining_accounts)?;
+    emit!(UsdPaymentPriced {
+        escrow_id: escrow.escrow_id,
+        meta,
+        usd_amount,
+        price: price.price,
+        exponent: price.exponent,
+        payment_amount: payment,
+        refunded,
+    });
+    Ok(())
+}
+
+/// Compressed NFT metadata for an escrow's receipt. Receipts are proof of a
+/// past engagement, not collectibles, so they are immutable and royalty-free.
+/// Bubblegum caps names at 32 bytes, which fits any `u64` ID here.
+fn receipt_metadata(escrow_id: u64) -> bubblegum::types::MetadataArgs {
+    bubblegum::types::MetadataArgs {
+        name: format!("Clawscrow #{escrow_id}"),
+        symbol: "CLAW".to_string(),
+        uri: String::new(),
+        seller_fee_basis_points: 0,
+        primary_sale_happened: true,
+        is_mutable: false,
+        edition_nonce: None,
+        token_standard: Some(bubblegum::types::TokenStandard::NonFungible),
+        collection: None,
+        uses: None,
+        token_program_version: bubblegum::types::TokenProgramVersion::Original,
+        creators: Vec::new(),
+    }
+}
+
+/// Hand a deposited asset deliverable to `to` and close its vault, returning
+/// the rent to the seller's payout owner. A no-op for escrows without one.
+fn release_asset<'info>(
+    token_program: &Program<'info, Token>,
+    escrow: &Account<'info, Escrow>,
+    asset_vault: Option<&Account<'info, TokenAccount>>,
+    to: Option<&Account<'info, TokenAccount>>,
+    rent_to: AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let Some(deliverable) = &escrow.deliverable else {
+        return Ok(());
+    };
+    let (Some(asset_vault), Some(to)) = (asset_vault, to) else {
+        return err!(ClawscrowError::DeliverableAccountsMissing);
+    };
+    require_keys_eq!(to.mint, deliverable.mint, ClawscrowError::InvalidMint);
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            Transfer {
+                from: asset_vault.to_account_info(),
+                to: to.to_account_info(),
+                authority: escrow.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        asset_vault.amount,
+    )?;
+    token::close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        CloseAccount {
+            account: asset_vault.to_account_info(),
+            destination: rent_to,
+            authority: escrow.to_account_info(),
+        },
+        signer_seeds,
+    ))
+}
+
+/// Refuse to settle unless the vault can cover every planned outflow, so a
+/// short vault never pays out partially.
+/// The lending accounts passed alongside a yield position must be the
+/// reserve it was whitelisted with.
+fn check_lending_accounts(
+    target: &LendingTarget,
+    program: &AccountInfo,
+    reserve: &AccountInfo,
+    liquidity_supply: &AccountInfo,
+    lending_market: &AccountInfo,
+    market_authority: &AccountInfo,
+) -> Result<()> {
+    for (passed, expected) in [
+        (program.key(), target.program),
+        (reserve.key(), target.reserve),
+        (liquidity_supply.key(), target.liquidity_supply),
+        (lending_market.key(), target.lending_market),
+        (market_authority.key(), lending::market_authority(target)),
+    ] {
+        require_keys_eq!(passed, expected, ClawscrowError::InvalidLendingAccount);
+    }
+    Ok(())
+}
+
+fn ensure_vault_covers(vault: &Account<TokenAccount>, outflows: &[u64]) -> Result<()> {
+    let mut total: u64 = 0;
+    for amount in outflows {
+        total = total.checked_add(*amount).ok_or(ClawscrowError::Overflow)?;
+    }
+    require!(vault.amount >= total, ClawscrowError::VaultImbalance);
+    Ok(())
+}
+
+// === ACCOUNTS ===
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CreateEscrow<'info> {
+    /// Authorizes the buyer's token transfers. Rent comes from `payer`.
+    pub buyer: Signer<'info>,
+
+    /// Covers rent for new accounts; a marketplace can sponsor its users here.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + Counter::INIT_SPACE,
+        seeds = [b"counter", buyer.key().as_ref()],
+        bump,
+    )]
+    pub counter: Account<'info, Counter>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Escrow::INIT_SPACE,
+        seeds = [b"escrow", buyer.key().as_ref(), counter.count.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        init,
+        payer = payer,
+        token::mint = mint,
+        token::authority = escrow,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    /// Required with `early_bonus`: holds the bonus until `settle_early_bonus`.
+    #[account(
+        init,
+        payer = payer,
+        token::mint = mint,
+        token::authority = escrow,
+        seeds = [b"bonus_vault", escrow.key().as_ref()],
+        bump,
+    )]
+    pub bonus_vault: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = buyer_token.mint == mint.key() @ ClawscrowError::InvalidMint,
+        constraint = buyer_token.mint == escrow.mint @ ClawscrowError::InvalidMint,
+    )]
+    pub buyer_token: Account<'info, TokenAccount>,
+
+    pub mint: Account<'info, Mint>,
+
+    /// CHECK: Arbitrator pubkey stored in escrow
+    pub arbitrator: UncheckedAccount<'info>,
+
+    /// The arbitrator's profile; required above `Config::max_unvetted_payment`.
+    #[account(seeds = [b"arbitrator_profile", arbitrator.key().as_ref()], bump = arbitrator_profile.bump)]
+    pub arbitrator_profile: Option<Account<'info, ArbitratorProfile>>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// Buyer's credential token account; required in compliance mode.
+    pub buyer_credential: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + Stats::INIT_SPACE,
+        seeds = [b"stats"],
+        bump,
+    )]
+    pub stats: Account<'info, Stats>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + UserIndex::INIT_SPACE,
+        seeds = [b"user_index", buyer.key().as_ref()],
+        bump,
+    )]
+    pub buyer_index: Account<'info, UserIndex>,
+
+    /// Negotiated fee for the escrow's platform, if one is registered.
+    #[account(seeds = [b"fee_override", fee_override.platform.as_ref()], bump = fee_override.bump)]
+    pub fee_override: Option<Account<'info, FeeOverride>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ArbitratorIndex::INIT_SPACE,
+        seeds = [b"arbitrator_index", arbitrator.key().as_ref()],
+        bump,
+    )]
+    pub arbitrator_index: Account<'info, ArbitratorIndex>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(params: CreateEscrowParams)]
+pub struct CreateAndList<'info> {
+    pub create: CreateEscrow<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = create.buyer,
+        space = 8 + ListingIndex::INIT_SPACE,
+        seeds = [b"listing_index", params.platform.unwrap_or_default().as_ref()],
+        bump,
+    )]
+    pub listing_index: Account<'info, ListingIndex>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(template_id: u64)]
+pub struct CreateTemplate<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// CHECK: Arbitrator named on escrows created from the template.
+    pub arbitrator: UncheckedAccount<'info>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Template::INIT_SPACE,
+        seeds = [b"template", owner.key().as_ref(), template_id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub template: Account<'info, Template>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(template_id: u64)]
+pub struct CloseTemplate<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"template", owner.key().as_ref(), template_id.to_le_bytes().as_ref()],
+        bump = template.bump,
+        has_one = owner @ ClawscrowError::Unauthorized,
+        close = owner,
+    )]
+    pub template: Account<'info, Template>,
+}
+
+/// Any template can be used by anyone; it only saves passing the terms.
+#[derive(Accounts)]
+pub struct CreateFromTemplate<'info> {
+    pub create: CreateEscrow<'info>,
+
+    #[account(
+        seeds = [b"template", template.owner.as_ref(), template.template_id.to_le_bytes().as_ref()],
+        bump = template.bump,
+        constraint = template.arbitrator == create.arbitrator.key() @ ClawscrowError::InvalidTemplate,
+        constraint = template.mint == create.mint.key() @ ClawscrowError::InvalidMint,
+    )]
+    pub template: Account<'info, Template>,
+}
+
+/// Escrows and vaults are created by the handler from remaining accounts,
+/// so only the accounts they share are listed here.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CreateEscrowsBatch<'info> {
+    pub buyer: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + Counter::INIT_SPACE,
+        seeds = [b"counter", buyer.key().as_ref()],
+        bump,
+    )]
+    pub counter: Account<'info, Counter>,
+
+    #[account(mut, constraint = buyer_token.mint == mint.key() @ ClawscrowError::InvalidMint)]
+    pub buyer_token: Account<'info, TokenAccount>,
+
+    pub mint: Account<'info, Mint>,
+
+    /// CHECK: Arbitrator pubkey stored in each escrow
+    pub arbitrator: UncheckedAccount<'info>,
+
+    /// The arbitrator's profile; required above `Config::max_unvetted_payment`.
+    #[account(seeds = [b"arbitrator_profile", arbitrator.key().as_ref()], bump = arbitrator_profile.bump)]
+    pub arbitrator_profile: Option<Account<'info, ArbitratorProfile>>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// Buyer's credential token account; required in compliance mode.
+    pub buyer_credential: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + Stats::INIT_SPACE,
+        seeds = [b"stats"],
+        bump,
+    )]
+    pub stats: Account<'info, Stats>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + UserIndex::INIT_SPACE,
+        seeds = [b"user_index", buyer.key().as_ref()],
+        bump,
+    )]
+    pub buyer_index: Account<'info, UserIndex>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ArbitratorIndex::INIT_SPACE,
+        seeds = [b"arbitrator_index", arbitrator.key().as_ref()],
+        bump,
+    )]
+    pub arbitrator_index: Account<'info, ArbitratorIndex>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct PruneListing<'info> {
+    pub signer: Signer<'info>,
+
+    #[account(
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+        constraint = escrow.platform == Some(listing_index.platform) @ ClawscrowError::InvalidState,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        mut,
+        seeds = [b"listing_index", listing_index.platform.as_ref()],
+        bump = listing_index.bump,
+    )]
+    pub listing_index: Account<'info, ListingIndex>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RenewEscrow<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"counter", buyer.key().as_ref()],
+        bump = counter.bump,
+    )]
+    pub counter: Account<'info, Counter>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", predecessor.original_buyer.as_ref(), predecessor.escrow_id.to_le_bytes().as_ref()],
+        bump = predecessor.bump,
+        constraint = predecessor.buyer == buyer.key() @ ClawscrowError::Unauthorized,
+    )]
+    pub predecessor: Account<'info, Escrow>,
+
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + Escrow::INIT_SPACE,
+        seeds = [b"escrow", buyer.key().as_ref(), counter.count.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        init,
+        payer = buyer,
+        token::mint = mint,
+        token::authority = escrow,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = buyer_token.mint == mint.key() @ ClawscrowError::InvalidMint,
+        constraint = buyer_token.mint == escrow.mint @ ClawscrowError::InvalidMint,
+    )]
+    pub buyer_token: Account<'info, TokenAccount>,
+
+    #[account(address = predecessor.mint @ ClawscrowError::InvalidMint)]
+    pub mint: Account<'info, Mint>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// Buyer's credential token account; required in compliance mode.
+    pub buyer_credential: Option<Account<'info, TokenAccount>>,
+
+    /// The arbitrator's profile; required above `Config::max_unvetted_payment`.
+    #[account(seeds = [b"arbitrator_profile", predecessor.arbitrator.as_ref()], bump = arbitrator_profile.bump)]
+    pub arbitrator_profile: Option<Account<'info, ArbitratorProfile>>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + Stats::INIT_SPACE,
+        seeds = [b"stats"],
+        bump,
+    )]
+    pub stats: Account<'info, Stats>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + UserIndex::INIT_SPACE,
+        seeds = [b"user_index", buyer.key().as_ref()],
+        bump,
+    )]
+    pub buyer_index: Account<'info, UserIndex>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + ArbitratorIndex::INIT_SPACE,
+        seeds = [b"arbitrator_index", predecessor.arbitrator.as_ref()],
+        bump,
+    )]
+    pub arbitrator_index: Account<'info, ArbitratorIndex>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(offer_id: u64)]
+pub struct PostOffer<'info> {
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    pub arbitrator: Signer<'info>,
+
+    #[account(
+        init,
+        payer = seller,
+        space = 8 + Offer::INIT_SPACE,
+        seeds = [b"offer", seller.key().as_ref(), offer_id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub offer: Account<'info, Offer>,
+
+    #[account(
+        init,
+        payer = seller,
+        token::mint = mint,
+        token::authority = offer,
+        seeds = [b"offer_vault", offer.key().as_ref()],
+        bump,
+    )]
+    pub offer_vault: Account<'info, TokenAccount>,
+
+    /// Funds the seller's collateral and receives the seller's payouts.
+    #[account(
+        mut,
+        constraint = seller_token.owner == seller.key() @ ClawscrowError::Unauthorized,
+        constraint = seller_token.mint == mint.key() @ ClawscrowError::InvalidMint,
+    )]
+    pub seller_token: Account<'info, TokenAccount>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = seller,
+        space = 8 + ArbitratorProfile::INIT_SPACE,
+        seeds = [b"arbitrator_profile", arbitrator.key().as_ref()],
+        bump,
+    )]
+    pub profile: Account<'info, ArbitratorProfile>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// Seller's credential token account; required in compliance mode.
+    pub seller_credential: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(offer_id: u64)]
+pub struct AcceptOffer<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    /// CHECK: The offer's seller, checked by `has_one`; receives the offer's rent.
+    #[account(mut)]
+    pub seller: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"offer", seller.key().as_ref(), offer_id.to_le_bytes().as_ref()],
+        bump = offer.bump,
+        has_one = seller,
+        has_one = mint @ ClawscrowError::InvalidMint,
+        close = seller,
+    )]
+    pub offer: Account<'info, Offer>,
+
+    #[account(
+        mut,
+        seeds = [b"offer_vault", offer.key().as_ref()],
+        bump = offer.vault_bump,
+    )]
+    pub offer_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + Counter::INIT_SPACE,
+        seeds = [b"counter", buyer.key().as_ref()],
+        bump,
+    )]
+    pub counter: Account<'info, Counter>,
+
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + Escrow::INIT_SPACE,
+        seeds = [b"escrow", buyer.key().as_ref(), counter.count.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        init,
+        payer = buyer,
+        token::mint = mint,
+        token::authority = escrow,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    /// Funds payment and collateral and receives the buyer's payouts.
+    #[account(
+        mut,
+        constraint = buyer_token.owner == buyer.key() @ ClawscrowError::Unauthorized,
+        constraint = buyer_token.mint == mint.key() @ ClawscrowError::InvalidMint,
+    )]
+    pub buyer_token: Account<'info, TokenAccount>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// Buyer's credential token account; required in compliance mode.
+    pub buyer_credential: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + Stats::INIT_SPACE,
+        seeds = [b"stats"],
+        bump,
+    )]
+    pub stats: Account<'info, Stats>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + UserIndex::INIT_SPACE,
+        seeds = [b"user_index", buyer.key().as_ref()],
+        bump,
+    )]
+    pub buyer_index: Account<'info, UserIndex>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + UserIndex::INIT_SPACE,
+        seeds = [b"user_index", seller.key().as_ref()],
+        bump,
+    )]
+    pub seller_index: Account<'info, UserIndex>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + ArbitratorIndex::INIT_SPACE,
+        seeds = [b"arbitrator_index", offer.arbitrator.as_ref()],
+        bump,
+    )]
+    pub arbitrator_index: Account<'info, ArbitratorIndex>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(offer_id: u64)]
+pub struct CancelOffer<'info> {
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"offer", seller.key().as_ref(), offer_id.to_le_bytes().as_ref()],
+        bump = offer.bump,
+        has_one = seller @ ClawscrowError::Unauthorized,
+        close = seller,
+    )]
+    pub offer: Account<'info, Offer>,
+
+    #[account(
+        mut,
+        seeds = [b"offer_vault", offer.key().as_ref()],
+        bump = offer.vault_bump,
+    )]
+    pub offer_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = seller_token.key() == offer.seller_payout_token @ ClawscrowError::PayoutAccountMismatch,
+    )]
+    pub seller_token: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(agreement_id: u64)]
+pub struct CreateRecurringAgreement<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    /// CHECK: Seller named on every spawned escrow.
+    pub seller: UncheckedAccount<'info>,
+
+    /// CHECK: Arbitrator named on every spawned escrow.
+    pub arbitrator: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + RecurringAgreement::INIT_SPACE,
+        seeds = [b"recurring", buyer.key().as_ref(), agreement_id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub agreement: Account<'info, RecurringAgreement>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// Buyer's and seller's credential token accounts; required in compliance mode.
+    pub buyer_credential: Option<Account<'info, TokenAccount>>,
+    pub seller_credential: Option<Account<'info, TokenAccount>>,
+
+    /// The arbitrator's profile; required above `Config::max_unvetted_payment`.
+    #[account(seeds = [b"arbitrator_profile", arbitrator.key().as_ref()], bump = arbitrator_profile.bump)]
+    pub arbitrator_profile: Option<Account<'info, ArbitratorProfile>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(agreement_id: u64)]
+pub struct SpawnRecurringEscrow<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"recurring", buyer.key().as_ref(), agreement_id.to_le_bytes().as_ref()],
+        bump = agreement.bump,
+        has_one = buyer @ ClawscrowError::Unauthorized,
+        has_one = mint @ ClawscrowError::InvalidMint,
+    )]
+    pub agreement: Account<'info, RecurringAgreement>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + Counter::INIT_SPACE,
+        seeds = [b"counter", buyer.key().as_ref()],
+        bump,
+    )]
+    pub counter: Account<'info, Counter>,
+
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + Escrow::INIT_SPACE,
+        seeds = [b"escrow", buyer.key().as_ref(), counter.count.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        init,
+        payer = buyer,
+        token::mint = mint,
+        token::authority = escrow,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    /// Funds payment and collateral and receives the buyer's payouts.
+    #[account(
+        mut,
+        constraint = buyer_token.owner == buyer.key() @ ClawscrowError::Unauthorized,
+        constraint = buyer_token.mint == mint.key() @ ClawscrowError::InvalidMint,
+    )]
+    pub buyer_token: Account<'info, TokenAccount>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + Stats::INIT_SPACE,
+        seeds = [b"stats"],
+        bump,
+    )]
+    pub stats: Account<'info, Stats>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + UserIndex::INIT_SPACE,
+        seeds = [b"user_index", buyer.key().as_ref()],
+        bump,
+    )]
+    pub buyer_index: Account<'info, UserIndex>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + ArbitratorIndex::INIT_SPACE,
+        seeds = [b"arbitrator_index", agreement.arbitrator.as_ref()],
+        bump,
+    )]
+    pub arbitrator_index: Account<'info, ArbitratorIndex>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(agreement_id: u64)]
+pub struct CloseRecurringAgreement<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"recurring", buyer.key().as_ref(), agreement_id.to_le_bytes().as_ref()],
+        bump = agreement.bump,
+        has_one = buyer @ ClawscrowError::Unauthorized,
+        close = buyer,
+    )]
+    pub agreement: Account<'info, RecurringAgreement>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct AcceptArbitrationRole<'info> {
+    #[account(mut)]
+    pub arbitrator: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+        has_one = arbitrator,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        init_if_needed,
+        payer = arbitrator,
+        space = 8 + ArbitratorProfile::INIT_SPACE,
+        seeds = [b"arbitrator_profile", arbitrator.key().as_ref()],
+        bump,
+    )]
+    pub profile: Account<'info, ArbitratorProfile>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterArbitrator<'info> {
+    #[account(mut)]
+    pub arbitrator: Signer<'info>,
+
+    #[account(
+        init,
+        payer = arbitrator,
+        space = 8 + ArbitratorStake::INIT_SPACE,
+        seeds = [b"arbitrator_stake", arbitrator.key().as_ref()],
+        bump,
+    )]
+    pub stake: Account<'info, ArbitratorStake>,
+
+    #[account(
+        init_if_needed,
+        payer = arbitrator,
+        space = 8 + ArbitratorRegistry::INIT_SPACE,
+        seeds = [b"arbitrator_registry"],
+        bump,
+    )]
+    pub registry: Account<'info, ArbitratorRegistry>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DeregisterArbitrator<'info> {
+    #[account(mut)]
+    pub arbitrator: Signer<'info>,
+
+    /// Closing returns the stake along with the rent.
+    #[account(
+        mut,
+        seeds = [b"arbitrator_stake", arbitrator.key().as_ref()],
+        bump = stake.bump,
+        has_one = arbitrator @ ClawscrowError::Unauthorized,
+        close = arbitrator,
+    )]
+    pub stake: Account<'info, ArbitratorStake>,
+
+    #[account(mut, seeds = [b"arbitrator_registry"], bump = registry.bump)]
+    pub registry: Account<'info, ArbitratorRegistry>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct EnableRandomArbitrator<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+        has_one = buyer @ ClawscrowError::Unauthorized,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + RandomArbitration::INIT_SPACE,
+        seeds = [b"random_arbitration", escrow.key().as_ref()],
+        bump,
+    )]
+    pub random_arbitration: Account<'info, RandomArbitration>,
+
+    /// Index of the arbitrator named at creation, who is replaced.
+    #[account(
+        mut,
+        seeds = [b"arbitrator_index", escrow.arbitrator.as_ref()],
+        bump = arbitrator_index.bump,
+    )]
+    pub arbitrator_index: Account<'info, ArbitratorIndex>,
+
+    /// Index under the `RandomArbitration` PDA until the draw.
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + ArbitratorIndex::INIT_SPACE,
+        seeds = [b"arbitrator_index", random_arbitration.key().as_ref()],
+        bump,
+    )]
+    pub draw_index: Account<'info, ArbitratorIndex>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct PlaceBid<'info> {
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+
+    #[account(
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        init_if_needed,
+        payer = bidder,
+        space = 8 + Bid::INIT_SPACE,
+        seeds = [b"bid", escrow.key().as_ref(), bidder.key().as_ref()],
+        bump,
+    )]
+    pub bid: Account<'info, Bid>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// Bidder's credential token account; required in compliance mode.
+    pub bidder_credential: Option<Account<'info, TokenAccount>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct WithdrawBid<'info> {
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"bid", bid.escrow.as_ref(), bidder.key().as_ref()],
+        bump = bid.bump,
+        has_one = bidder @ ClawscrowError::Unauthorized,
+        close = bidder,
+    )]
+    pub bid: Account<'info, Bid>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct SelectBid<'info> {
+    pub buyer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+        has_one = buyer @ ClawscrowError::Unauthorized,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump = escrow.vault_bump,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"bid", escrow.key().as_ref(), bidder.key().as_ref()],
+        bump = bid.bump,
+        has_one = bidder,
+        close = bidder,
+    )]
+    pub bid: Account<'info, Bid>,
+
+    /// CHECK: The selected bidder, checked by `has_one`; receives the bid's rent.
+    #[account(mut)]
+    pub bidder: UncheckedAccount<'info>,
+
+    /// Funds a top-up when the bid is above the current price.
+    #[account(
+        mut,
+        constraint = buyer_token.owner == buyer.key() @ ClawscrowError::Unauthorized,
+        constraint = buyer_token.mint == escrow.mint @ ClawscrowError::InvalidMint,
+    )]
+    pub buyer_token: Account<'info, TokenAccount>,
+
+    /// Receives the refund when the bid is below the current price.
+    #[account(
+        mut,
+        constraint = buyer_payout_token.key() == escrow.buyer_payout_ata() @ ClawscrowError::PayoutAccountMismatch,
+    )]
+    pub buyer_payout_token: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct Deposit<'info> {
+    pub buyer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", buyer.key().as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump = escrow.vault_bump,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(mut, constraint = buyer_token.mint == escrow.mint @ ClawscrowError::InvalidMint)]
+    pub buyer_token: Account<'info, TokenAccount>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct OpenPool<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", buyer.key().as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(address = escrow.mint @ ClawscrowError::InvalidMint)]
+    pub mint: Account<'info, Mint>,
+
+    /// Collects buyer-side payouts for contributors to claim.
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = mint,
+        associated_token::authority = escrow,
+    )]
+    pub pool: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct Contribute<'info> {
+    #[account(mut)]
+    pub contributor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump = escrow.vault_bump,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(mut, constraint = contributor_token.mint == escrow.mint @ ClawscrowError::InvalidMint)]
+    pub contributor_token: Account<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = contributor,
+        space = 8 + Contribution::INIT_SPACE,
+        seeds = [b"contribution", escrow.key().as_ref(), contributor.key().as_ref()],
+        bump,
+    )]
+    pub contribution: Account<'info, Contribution>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct ClaimContribution<'info> {
+    #[account(mut)]
+    pub contributor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(mut, address = escrow.buyer_payout_ata() @ ClawscrowError::PayoutAccountMismatch)]
+    pub pool: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = contributor_token.owner == contributor.key() @ ClawscrowError::Unauthorized,
+        constraint = contributor_token.mint == escrow.mint @ ClawscrowError::InvalidMint,
+    )]
+    pub contributor_token: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"contribution", escrow.key().as_ref(), contributor.key().as_ref()],
+        bump = contribution.bump,
+        close = contributor,
+    )]
+    pub contribution: Account<'info, Contribution>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct FundEscrow<'info> {
+    pub signer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump = escrow.vault_bump,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    /// The account named at creation, delegated to the escrow PDA.
+    #[account(
+        mut,
+        constraint = Some(buyer_token.key()) == escrow.funding_source @ ClawscrowError::PayoutAccountMismatch,
+    )]
+    pub buyer_token: Account<'info, TokenAccount>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct AcceptEscrow<'info> {
+    /// Authorizes the seller's collateral transfer. Rent comes from `payer`.
+    pub seller: Signer<'info>,
+
+    /// Covers rent for new accounts; a marketplace can sponsor its users here.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump = escrow.vault_bump,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(mut, constraint = seller_token.mint == escrow.mint @ ClawscrowError::InvalidMint)]
+    pub seller_token: Account<'info, TokenAccount>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// Seller's credential token account; required in compliance mode.
+    pub seller_credential: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + UserIndex::INIT_SPACE,
+        seeds = [b"user_index", seller.key().as_ref()],
+        bump,
+    )]
+    pub seller_index: Account<'info, UserIndex>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct AcceptEscrowWithSig<'info> {
+    /// Relayer submitting the seller's signature; pays rent and fees.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: authenticated by the ed25519 signature over `terms::AcceptanceV1`.
+    pub seller: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump = escrow.vault_bump,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    /// Source of the seller's collateral, delegated to the escrow PDA.
+    #[account(
+        mut,
+        constraint = seller_token.owner == seller.key() @ ClawscrowError::Unauthorized,
+        constraint = seller_token.mint == escrow.mint @ ClawscrowError::InvalidMint,
+    )]
+    pub seller_token: Account<'info, TokenAccount>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// Seller's credential token account; required in compliance mode.
+    pub seller_credential: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + UserIndex::INIT_SPACE,
+        seeds = [b"user_index", seller.key().as_ref()],
+        bump,
+    )]
+    pub seller_index: Account<'info, UserIndex>,
+
+    /// CHECK: the instructions sysvar, read for the ed25519 check.
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct CancelEscrow<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+        has_one = buyer @ ClawscrowError::Unauthorized,
+        close = buyer,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump = escrow.vault_bump,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    /// The original buyer's `Counter`; required while the escrow holds one
+    /// of its open-escrow slots, which settling frees.
+    #[account(mut, seeds = [b"counter", escrow.original_buyer.as_ref()], bump = counter.bump)]
+    pub counter: Option<Account<'info, Counter>>,
+
+    #[account(
+        mut,
+        constraint = buyer_token.key() == escrow.buyer_payout_ata() @ ClawscrowError::PayoutAccountMismatch,
+        constraint = buyer_token.mint == escrow.mint @ ClawscrowError::InvalidMint,
+    )]
+    pub buyer_token: Account<'info, TokenAccount>,
+
+    /// Required, with `buyer_second_token`, when the buyer funded a second
+    /// payment leg; it is refunded and closed along with the escrow.
+    #[account(mut, seeds = [b"second_vault", escrow.key().as_ref()], bump)]
+    pub second_vault: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = escrow.second_leg.as_ref().map(|leg| get_associated_token_address(
+            &escrow.buyer_payout.unwrap_or(escrow.buyer),
+            &leg.mint,
+        )) == Some(buyer_second_token.key()) @ ClawscrowError::PayoutAccountMismatch,
+    )]
+    pub buyer_second_token: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"arbitrator_index", escrow.arbitrator.as_ref()],
+        bump = arbitrator_index.bump,
+    )]
+    pub arbitrator_index: Account<'info, ArbitratorIndex>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeCancel<'info> {
+    pub signer: Signer<'info>,
+
+    #[account(mut)]
+    pub escrow: Account<'info, Escrow>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct TimesheetAction<'info> {
+    pub signer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct OfferDiscount<'info> {
+    pub seller: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct SetPayoutSplit<'info> {
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        init_if_needed,
+        payer = seller,
+        space = 8 + PayoutSplit::INIT_SPACE,
+        seeds = [b"payout_split", escrow.key().as_ref()],
+        bump,
+    )]
+    pub payout_split: Account<'info, PayoutSplit>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct SetSellerPayout<'info> {
+    pub seller: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct InsureEscrow<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(address = escrow.mint @ ClawscrowError::InvalidMint)]
+    pub mint: Account<'info, Mint>,
+
+    #[account(mut, constraint = payer_token.mint == escrow.mint @ ClawscrowError::InvalidMint)]
+    pub payer_token: Account<'info, TokenAccount>,
+
+    /// Shared by every escrow in this mint and owned by the config PDA.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        seeds = [b"insurance_vault", mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = config,
+    )]
+    pub insurance_vault: Account<'info, TokenAccount>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct MintReceipts<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: Receipt owner only; pinned to the escrow's buyer.
+    #[account(address = escrow.buyer @ ClawscrowError::Unauthorized)]
+    pub buyer: UncheckedAccount<'info>,
+
+    /// CHECK: Receipt owner only; pinned to the escrow's seller.
+    #[account(address = escrow.seller @ ClawscrowError::Unauthorized)]
+    pub seller: UncheckedAccount<'info>,
+
+    /// CHECK: PDA the receipt tree is delegated to; signs the mints.
+    #[account(seeds = [b"receipt_authority"], bump)]
+    pub receipt_authority: UncheckedAccount<'info>,
+
+    /// CHECK: The tree's Bubblegum config; Bubblegum checks its derivation.
+    #[account(mut)]
+    pub tree_config: UncheckedAccount<'info>,
+
+    /// CHECK: Must be the configured receipt tree; owned by account compression.
+    #[account(mut, address = config.receipt_tree @ ClawscrowError::InvalidReceiptTree)]
+    pub merkle_tree: UncheckedAccount<'info>,
+
+    /// CHECK: SPL noop program.
+    #[account(address = bubblegum::programs::SPL_NOOP_ID)]
+    pub log_wrapper: UncheckedAccount<'info>,
+
+    /// CHECK: SPL account compression program.
+    #[account(address = bubblegum::programs::SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+
+    /// CHECK: Bubblegum program.
+    #[account(address = bubblegum::ID)]
+    pub bubblegum_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct MintCompletionBadge<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    /// CHECK: Only receives the badge; pinned to the escrow's seller.
+    #[account(address = escrow.seller @ ClawscrowError::Unauthorized)]
+    pub seller: UncheckedAccount<'info>,
+
+    /// Also the badge mint's authority until it is revoked at the end of minting.
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + CompletionBadge::INIT_SPACE,
+        seeds = [b"badge", escrow.key().as_ref()],
+        bump,
+    )]
+    pub badge: Account<'info, CompletionBadge>,
+
+    /// CHECK: Created and initialised as a non-transferable Token-2022 mint by
+    /// the handler, which needs the extension set before `InitializeMint2`.
+    #[account(mut, seeds = [b"badge_mint", escrow.key().as_ref()], bump)]
+    pub badge_mint: UncheckedAccount<'info>,
+
+    /// CHECK: The seller's associated token account for `badge_mint`; the
+    /// associated token program verifies the address when creating it.
+    #[account(mut)]
+    pub seller_badge_token: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct ClaimInsurance<'info> {
+    pub claimant: Signer<'info>,
+
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ClawscrowError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"insurance_vault", escrow.mint.as_ref()],
+        bump,
+    )]
+    pub insurance_vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub claimant_token: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetOperator<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + OperatorKey::INIT_SPACE,
+        seeds = [b"operator", owner.key().as_ref()],
+        bump,
+    )]
+    pub operator_key: Account<'info, OperatorKey>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct EscrowPartyAction<'info> {
+    pub signer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct TransferBuyerRole<'info> {
+    pub buyer: Signer<'info>,
+
+    pub new_buyer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// New buyer's credential token account; required in compliance mode.
+    pub new_buyer_credential: Option<Account<'info, TokenAccount>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct ConfirmCancel<'info> {
+    pub signer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump = escrow.vault_bump,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    /// The original buyer's `Counter`; required while the escrow holds one
+    /// of its open-escrow slots, which settling frees.
+    #[account(mut, seeds = [b"counter", escrow.original_buyer.as_ref()], bump = counter.bump)]
+    pub counter: Option<Account<'info, Counter>>,
+
+    #[account(
+        mut,
+        constraint = buyer_token.key() == escrow.buyer_payout_ata() @ ClawscrowError::PayoutAccountMismatch,
+        constraint = buyer_token.mint == escrow.mint @ ClawscrowError::InvalidMint,
+    )]
+    pub buyer_token: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = seller_token.key() == escrow.seller_payout_ata() @ ClawscrowError::PayoutAccountMismatch,
+        constraint = seller_token.mint == escrow.mint @ ClawscrowError::InvalidMint,
+    )]
+    pub seller_token: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"arbitrator_index", escrow.arbitrator.as_ref()],
+        bump = arbitrator_index.bump,
+    )]
+    pub arbitrator_index: Account<'info, ArbitratorIndex>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct ProposeAmendment<'info> {
+    pub buyer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+        has_one = buyer @ ClawscrowError::Unauthorized,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump = escrow.vault_bump,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = buyer_token.owner == buyer.key() @ ClawscrowError::Unauthorized,
+        constraint = buyer_token.mint == escrow.mint @ ClawscrowError::InvalidMint,
+    )]
+    pub buyer_token: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct SettleAmendment<'info> {
+    pub signer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump = escrow.vault_bump,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = buyer_token.key() == escrow.buyer_payout_ata() @ ClawscrowError::PayoutAccountMismatch,
+        constraint = buyer_token.mint == escrow.mint @ ClawscrowError::InvalidMint,
+    )]
+    pub buyer_token: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct SellerWithdraw<'info> {
+    pub seller: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump = escrow.vault_bump,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    /// The original buyer's `Counter`; required while the escrow holds one
+    /// of its open-escrow slots, which settling frees.
+    #[account(mut, seeds = [b"counter", escrow.original_buyer.as_ref()], bump = counter.bump)]
+    pub counter: Option<Account<'info, Counter>>,
+
+    #[account(
+        mut,
+        constraint = buyer_token.key() == escrow.buyer_payout_ata() @ ClawscrowError::PayoutAccountMismatch,
+        constraint = buyer_token.mint == escrow.mint @ ClawscrowError::InvalidMint,
+    )]
+    pub buyer_token: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = seller_token.key() == escrow.seller_payout_ata() @ ClawscrowError::PayoutAccountMismatch,
+        constraint = seller_token.mint == escrow.mint @ ClawscrowError::InvalidMint,
+    )]
+    pub seller_token: Account<'info, TokenAccount>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"arbitrator_index", escrow.arbitrator.as_ref()],
+        bump = arbitrator_index.bump,
+    )]
+    pub arbitrator_index: Account<'info, ArbitratorIndex>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct ReconcileVault<'info> {
+    pub signer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump = escrow.vault_bump,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateCounter<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Only used to derive the counter address
+    pub buyer: UncheckedAccount<'info>,
+
+    /// CHECK: Old counters do not deserialize as `Counter`; the handler checks
+    /// the discriminator and length.
+    #[account(
+        mut,
+        seeds = [b"counter", buyer.key().as_ref()],
+        bump,
+        owner = crate::ID @ ClawscrowError::UnsupportedLayout,
+    )]
+    pub counter: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateEscrow<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Old layouts do not deserialize as `Escrow`; the handler checks
+    /// the owner (via `owner`), the discriminator and the layout version.
+    #[account(mut, owner = crate::ID @ ClawscrowError::UnsupportedLayout)]
+    pub escrow: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct ExpireUnaccepted<'info> {
+    pub signer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump = escrow.vault_bump,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    /// The original buyer's `Counter`; required while the escrow holds one
+    /// of its open-escrow slots, which settling frees.
+    #[account(mut, seeds = [b"counter", escrow.original_buyer.as_ref()], bump = counter.bump)]
+    pub counter: Option<Account<'info, Counter>>,
+
+    #[account(
+        mut,
+        constraint = buyer_token.key() == escrow.buyer_payout_ata() @ ClawscrowError::PayoutAccountMismatch,
+        constraint = buyer_token.mint == escrow.mint @ ClawscrowError::InvalidMint,
+    )]
+    pub buyer_token: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"arbitrator_index", escrow.arbitrator.as_ref()],
+        bump = arbitrator_index.bump,
+    )]
+    pub arbitrator_index: Account<'info, ArbitratorIndex>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct ClaimTimeout<'info> {
+    pub buyer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump = escrow.vault_bump,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    /// The original buyer's `Counter`; required while the escrow holds one
+    /// of its open-escrow slots, which settling frees.
+    #[account(mut, seeds = [b"counter", escrow.original_buyer.as_ref()], bump = counter.bump)]
+    pub counter: Option<Account<'info, Counter>>,
+
+    #[account(
+        mut,
+        constraint = buyer_token.key() == escrow.buyer_payout_ata() @ ClawscrowError::PayoutAccountMismatch,
+        constraint = buyer_token.mint == escrow.mint @ ClawscrowError::InvalidMint,
+    )]
+    pub buyer_token: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"arbitrator_index", escrow.arbitrator.as_ref()],
+        bump = arbitrator_index.bump,
+    )]
+    pub arbitrator_index: Account<'info, ArbitratorIndex>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct CloseEscrow<'info> {
+    pub signer: Signer<'info>,
+
+    /// CHECK: Rent destination, must be the escrow's buyer
+    #[account(mut, address = escrow.buyer @ ClawscrowError::Unauthorized)]
+    pub buyer: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+        close = buyer,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump = escrow.vault_bump,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    /// CHECK: required for asset-deliverable escrows, to show the asset was
+    /// settled: closed, or a token account left empty.
+    #[account(seeds = [b"asset_vault", escrow.key().as_ref()], bump)]
+    pub asset_vault: Option<UncheckedAccount<'info>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct Deliver<'info> {
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    #[account(mut)]
+    pub escrow: Account<'info, Escrow>,
+
+    /// The party's `OperatorKey`, when an operator signs on their behalf.
+    #[account(seeds = [b"operator", operator_key.owner.as_ref()], bump = operator_key.bump)]
+    pub operator_key: Option<Account<'info, OperatorKey>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct DeliverAsset<'info> {
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    pub asset_mint: Account<'info, Mint>,
+
+    #[account(mut, constraint = seller_asset_token.mint == asset_mint.key() @ ClawscrowError::InvalidMint)]
+    pub seller_asset_token: Account<'info, TokenAccount>,
+
+    /// Already holds the asset when redelivering after a revision.
+    #[account(
+        init_if_needed,
+        payer = seller,
+        seeds = [b"asset_vault", escrow.key().as_ref()],
+        bump,
+        token::mint = asset_mint,
+        token::authority = escrow,
+    )]
+    pub asset_vault: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct FundSecondLeg<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    pub second_mint: Account<'info, Mint>,
+
+    #[account(mut, constraint = buyer_second_token.mint == second_mint.key() @ ClawscrowError::InvalidMint)]
+    pub buyer_second_token: Account<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = buyer,
+        seeds = [b"second_vault", escrow.key().as_ref()],
+        bump,
+        token::mint = second_mint,
+        token::authority = escrow,
+    )]
+    pub second_vault: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct SettleEarlyBonus<'info> {
+    pub signer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(mut, seeds = [b"bonus_vault", escrow.key().as_ref()], bump)]
+    pub bonus_vault: Account<'info, TokenAccount>,
+
+    /// The seller's or the buyer's payout account, whichever is owed the bonus.
+    #[account(mut)]
+    pub recipient_token: Account<'info, TokenAccount>,
+
+    /// CHECK: receives the bonus vault's rent.
+    #[account(mut, address = escrow.buyer @ ClawscrowError::Unauthorized)]
+    pub buyer: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct SetUsdPeg<'info> {
+    pub buyer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct SettleSecondLeg<'info> {
+    pub signer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(mut, seeds = [b"second_vault", escrow.key().as_ref()], bump)]
+    pub second_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = buyer_second_token.key() == get_associated_token_address(
+            &escrow.buyer_payout.unwrap_or(escrow.buyer),
+            &second_vault.mint,
+        ) @ ClawscrowError::PayoutAccountMismatch,
+    )]
+    pub buyer_second_token: Account<'info, TokenAccount>,
+
+    /// Required when the seller is owed part of the leg.
+    #[account(
+        mut,
+        constraint = seller_second_token.key() == get_associated_token_address(
+            &escrow.seller_payout.unwrap_or(escrow.seller),
+            &second_vault.mint,
+        ) @ ClawscrowError::PayoutAccountMismatch,
+    )]
+    pub seller_second_token: Option<Account<'info, TokenAccount>>,
+
+    /// CHECK: receives the second vault's rent.
+    #[account(mut, address = escrow.buyer @ ClawscrowError::Unauthorized)]
+    pub buyer: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct EnableYield<'info> {
+    /// The buyer or the seller; pays for the position and takes the vault's rent.
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account(
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump = escrow.vault_bump,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(seeds = [b"lending_reserve", escrow.mint.as_ref()], bump = lending_reserve.bump)]
+    pub lending_reserve: Account<'info, LendingReserve>,
+
+    #[account(
+        init,
+        payer = signer,
+        space = 8 + YieldPosition::INIT_SPACE,
+        seeds = [b"yield_position", escrow.key().as_ref()],
+        bump,
+    )]
+    pub yield_position: Account<'info, YieldPosition>,
+
+    #[account(
+        mut,
+        address = lending_reserve.target.collateral_mint @ ClawscrowError::InvalidLendingAccount,
+    )]
+    pub collateral_mint: Account<'info, Mint>,
+
+    /// Receives the reserve collateral tokens.
+    #[account(
+        init,
+        payer = signer,
+        seeds = [b"yield_collateral", escrow.key().as_ref()],
+        bump,
+        token::mint = collateral_mint,
+        token::authority = escrow,
+    )]
+    pub yield_collateral: Account<'info, TokenAccount>,
+
+    /// CHECK: this and the lending accounts below are checked against
+    /// `lending_reserve` by `check_lending_accounts`.
+    pub lending_program: UncheckedAccount<'info>,
+
+    /// CHECK: see `lending_program`.
+    #[account(mut)]
+    pub reserve: UncheckedAccount<'info>,
+
+    /// CHECK: see `lending_program`.
+    #[account(mut)]
+    pub liquidity_supply: UncheckedAccount<'info>,
+
+    /// CHECK: see `lending_program`.
+    pub lending_market: UncheckedAccount<'info>,
+
+    /// CHECK: see `lending_program`.
+    pub market_authority: UncheckedAccount<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct WithdrawYield<'info> {
+    /// Anyone; pays the recreated vault's rent.
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account(
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(address = escrow.mint @ ClawscrowError::InvalidMint)]
+    pub mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = signer,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = escrow,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"yield_position", escrow.key().as_ref()],
+        bump = yield_position.bump,
+        close = payer,
+    )]
+    pub yield_position: Account<'info, YieldPosition>,
+
+    #[account(mut, seeds = [b"yield_collateral", escrow.key().as_ref()], bump)]
+    pub yield_collateral: Account<'info, TokenAccount>,
+
+    /// CHECK: whoever opened the position, refunded its rent.
+    #[account(mut, address = yield_position.payer @ ClawscrowError::Unauthorized)]
+    pub payer: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        constraint = buyer_token.key() == escrow.buyer_payout_ata() @ ClawscrowError::PayoutAccountMismatch,
+        constraint = buyer_token.mint == escrow.mint @ ClawscrowError::InvalidMint,
+    )]
+    pub buyer_token: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = seller_token.key() == escrow.seller_payout_ata() @ ClawscrowError::PayoutAccountMismatch,
+        constraint = seller_token.mint == escrow.mint @ ClawscrowError::InvalidMint,
+    )]
+    pub seller_token: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        address = yield_position.target.collateral_mint @ ClawscrowError::InvalidLendingAccount,
+    )]
+    pub collateral_mint: Account<'info, Mint>,
+
+    /// CHECK: this and the lending accounts below are checked against
+    /// `yield_position` by `check_lending_accounts`.
+    pub lending_program: UncheckedAccount<'info>,
+
+    /// CHECK: see `lending_program`.
+    #[account(mut)]
+    pub reserve: UncheckedAccount<'info>,
+
+    /// CHECK: see `lending_program`.
+    #[account(mut)]
+    pub liquidity_supply: UncheckedAccount<'info>,
+
+    /// CHECK: see `lending_program`.
+    pub lending_market: UncheckedAccount<'info>,
+
+    /// CHECK: see `lending_program`.
+    pub market_authority: UncheckedAccount<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct ReleaseDeliverable<'info> {
+    pub signer: Signer<'info>,
+
+    #[account(
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(mut, seeds = [b"asset_vault", escrow.key().as_ref()], bump)]
+    pub asset_vault: Account<'info, TokenAccount>,
+
+    /// Buyer's or seller's token account for the asset, depending on the outcome.
+    #[account(mut)]
+    pub recipient_token: Account<'info, TokenAccount>,
+
+    /// CHECK: receives the asset vault's rent.
+    #[account(mut, address = escrow.seller_payout.unwrap_or(escrow.seller) @ ClawscrowError::PayoutAccountMismatch)]
+    pub seller_payout_owner: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct AcknowledgeDelivery<'info> {
+    pub buyer: Signer<'info>,
+
+    #[account(mut)]
+    pub escrow: Account<'info, Escrow>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Config::INIT_SPACE,
+        seeds = [b"config"],
+        bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    /// The program's upgrade state. Only its upgrade authority may become the
+    /// first admin, so nobody can claim the config between deploy and setup.
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(admin.key()) @ ClawscrowError::Unauthorized,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + Stats::INIT_SPACE,
+        seeds = [b"stats"],
+        bump,
+    )]
+    pub stats: Account<'info, Stats>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ClawscrowError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+#[instruction(platform: Pubkey)]
+pub struct SetFeeOverride<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ClawscrowError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + FeeOverride::INIT_SPACE,
+        seeds = [b"fee_override", platform.as_ref()],
+        bump,
+    )]
+    pub fee_override: Account<'info, FeeOverride>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetLendingReserve<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ClawscrowError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + LendingReserve::INIT_SPACE,
+        seeds = [b"lending_reserve", mint.key().as_ref()],
+        bump,
+    )]
+    pub lending_reserve: Account<'info, LendingReserve>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveLendingReserve<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ClawscrowError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"lending_reserve", lending_reserve.mint.as_ref()],
+        bump = lending_reserve.bump,
+        close = admin,
+    )]
+    pub lending_reserve: Account<'info, LendingReserve>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveFeeOverride<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ClawscrowError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"fee_override", fee_override.platform.as_ref()],
+        bump = fee_override.bump,
+        close = admin,
+    )]
+    pub fee_override: Account<'info, FeeOverride>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct DisputeCtx<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    #[account(mut)]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump = escrow.vault_bump,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    /// Funds the dispute bond.
+    #[account(
+        mut,
+        constraint = buyer_token.owner == buyer.key() @ ClawscrowError::Unauthorized,
+        constraint = buyer_token.mint == escrow.mint @ ClawscrowError::InvalidMint,
+    )]
+    pub buyer_token: Account<'info, TokenAccount>,
+
+    /// The party's `OperatorKey`, when an operator signs on their behalf.
+    #[account(seeds = [b"operator", operator_key.owner.as_ref()], bump = operator_key.bump)]
+    pub operator_key: Option<Account<'info, OperatorKey>>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(mut, seeds = [b"stats"], bump = stats.bump)]
+    pub stats: Account<'info, Stats>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + Reputation::INIT_SPACE,
+        seeds = [b"reputation", escrow.buyer.as_ref()],
+        bump,
+    )]
+    pub reputation: Account<'info, Reputation>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + CaseQueue::INIT_SPACE,
+        seeds = [b"case_queue", escrow.arbitrator.as_ref()],
+        bump,
+    )]
+    pub case_queue: Account<'info, CaseQueue>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct Resolve<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Box<Account<'info, Escrow>>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump = escrow.vault_bump,
+    )]
+    pub vault: Box<Account<'info, TokenAccount>>,
+
+    /// The original buyer's `Counter`; required while the escrow holds one
+    /// of its open-escrow slots, which settling frees.
+    #[account(mut, seeds = [b"counter", escrow.original_buyer.as_ref()], bump = counter.bump)]
+    pub counter: Option<Account<'info, Counter>>,
+
+    /// CHECK: only used as the authority of `buyer_token`.
+    #[account(address = escrow.buyer_payout.unwrap_or(escrow.buyer) @ ClawscrowError::PayoutAccountMismatch)]
+    pub buyer_payout_owner: UncheckedAccount<'info>,
+
+    /// CHECK: authority of `seller_token`; receives native SOL when unwrapping.
+    #[account(mut, address = escrow.seller_payout.unwrap_or(escrow.seller) @ ClawscrowError::PayoutAccountMismatch)]
+    pub seller_payout_owner: UncheckedAccount<'info>,
+
+    #[account(address = escrow.mint @ ClawscrowError::InvalidMint)]
+    pub mint: Account<'info, Mint>,
+
+    /// Created at the caller's expense if the payout owner closed it.
+    #[account(
+        init_if_needed,
+        payer = signer,
+        associated_token::mint = mint,
+        associated_token::authority = buyer_payout_owner,
+    )]
+    pub buyer_token: Box<Account<'info, TokenAccount>>,
+
+    /// Created at the caller's expense if the payout owner closed it.
+    #[account(
+        init_if_needed,
+        payer = signer,
+        associated_token::mint = mint,
+        associated_token::authority = seller_payout_owner,
+    )]
+    pub seller_token: Box<Account<'info, TokenAccount>>,
+
+    /// Pass on a wrapped SOL escrow to pay the seller native SOL instead of
+    /// wSOL; see `unwrap_native_payout`. Only `approve` and
+    /// `release_early` use it.
+    #[account(
+        init,
+        payer = signer,
+        seeds = [b"unwrap", escrow.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = escrow,
+    )]
+    pub unwrap_token: Option<Account<'info, TokenAccount>>,
+
+    /// Required when the seller registered a payout split; the recipients'
+    /// token accounts go in the remaining accounts.
+    #[account(seeds = [b"payout_split", escrow.key().as_ref()], bump = payout_split.bump)]
+    pub payout_split: Option<Box<Account<'info, PayoutSplit>>>,
+
+    /// The party's `OperatorKey`, when an operator signs on their behalf.
+    #[account(seeds = [b"operator", operator_key.owner.as_ref()], bump = operator_key.bump)]
+    pub operator_key: Option<Account<'info, OperatorKey>>,
+
+    /// Required for asset-deliverable escrows: the deposited asset, handed to
+    /// `buyer_asset_token` on approval.
+    #[account(mut, seeds = [b"asset_vault", escrow.key().as_ref()], bump)]
+    pub asset_vault: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = buyer_asset_token.owner == escrow.buyer_payout.unwrap_or(escrow.buyer) @ ClawscrowError::PayoutAccountMismatch,
+    )]
+    pub buyer_asset_token: Option<Account<'info, TokenAccount>>,
+
+    /// CHECK: Pyth SOL/USD `PriceUpdateV2`, verified by `oracle::read_sol_usd`.
+    /// Required the first time a USD-pegged escrow pays out.
+    pub price_update: Option<UncheckedAccount<'info>>,
+
+    /// The buyer's token account a tip is paid from; only `approve_with_tip` uses it.
+    #[account(mut, constraint = tip_source.mint == escrow.mint @ ClawscrowError::InvalidMint)]
+    pub tip_source: Option<Account<'info, TokenAccount>>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(mut, seeds = [b"stats"], bump = stats.bump)]
+    pub stats: Box<Account<'info, Stats>>,
+
+    #[account(
+        mut,
+        constraint = treasury_token.owner == config.treasury @ ClawscrowError::Unauthorized,
+        constraint = treasury_token.mint == escrow.mint @ ClawscrowError::InvalidMint,
+    )]
+    pub treasury_token: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + Reputation::INIT_SPACE,
+        seeds = [b"reputation", escrow.buyer.as_ref()],
+        bump,
+    )]
+    pub buyer_reputation: Box<Account<'info, Reputation>>,
+
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + Reputation::INIT_SPACE,
+        seeds = [b"reputation", escrow.seller.as_ref()],
+        bump,
+    )]
+    pub seller_reputation: Box<Account<'info, Reputation>>,
+
+    #[account(
+        mut,
+        seeds = [b"arbitrator_index", escrow.arbitrator.as_ref()],
+        bump = arbitrator_index.bump,
+    )]
+    pub arbitrator_index: Box<Account<'info, ArbitratorIndex>>,
+
+    /// Keeper's account for the `auto_approve` crank tip; ignored by other instructions.
+    #[account(
+        mut,
+        constraint = crank_token.owner == signer.key() @ ClawscrowError::Unauthorized,
+        constraint = crank_token.mint == escrow.mint @ ClawscrowError::InvalidMint,
+    )]
+    pub crank_token: Option<Account<'info, TokenAccount>>,
+
+    /// Referrer's account for the referral fee; required whenever an escrow
+    /// with a referral pays the seller.
+    #[account(mut, constraint = referrer_token.mint == escrow.mint @ ClawscrowError::InvalidMint)]
+    pub referrer_token: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct ScheduleAutoApprove<'info> {
+    /// The seller or their operator; pays for the thread and funds it.
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    #[account(
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    /// The party's `OperatorKey`, when an operator signs on their behalf.
+    #[account(seeds = [b"operator", operator_key.owner.as_ref()], bump = operator_key.bump)]
+    pub operator_key: Option<Account<'info, OperatorKey>>,
+
+    /// Required when the seller registered a payout split.
+    #[account(seeds = [b"payout_split", escrow.key().as_ref()], bump = payout_split.bump)]
+    pub payout_split: Option<Account<'info, PayoutSplit>>,
+
+    /// CHECK: a Pyth feed account the thread reads at payout; required for
+    /// an unpriced USD-pegged escrow and verified by `auto_approve`.
+    pub price_update: Option<UncheckedAccount<'info>>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = seller,
+        space = 8 + AutoApproveThread::INIT_SPACE,
+        seeds = [b"auto_approve_thread", escrow.key().as_ref()],
+        bump,
+    )]
+    pub auto_approve_thread: Account<'info, AutoApproveThread>,
+
+    /// CHECK: signs as the thread's authority.
+    #[account(seeds = [automation::THREAD_AUTHORITY_SEED], bump)]
+    pub thread_authority: UncheckedAccount<'info>,
+
+    /// CHECK: the thread Clockwork creates, checked by address.
+    #[account(mut, address = automation::thread_address(&escrow.key()))]
+    pub thread: UncheckedAccount<'info>,
+
+    /// CHECK: the Clockwork thread program, checked by address.
+    #[account(address = automation::CLOCKWORK_THREAD_ID)]
+    pub clockwork_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelAutoApprove<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        close = payer,
+        has_one = payer @ ClawscrowError::Unauthorized,
+        seeds = [b"auto_approve_thread", auto_approve_thread.escrow.as_ref()],
+        bump = auto_approve_thread.bump,
+    )]
+    pub auto_approve_thread: Account<'info, AutoApproveThread>,
+
+    /// CHECK: signs as the thread's authority.
+    #[account(seeds = [automation::THREAD_AUTHORITY_SEED], bump)]
+    pub thread_authority: UncheckedAccount<'info>,
+
+    /// CHECK: the escrow's thread, checked by address.
+    #[account(mut, address = automation::thread_address(&auto_approve_thread.escrow))]
+    pub thread: UncheckedAccount<'info>,
+
+    /// CHECK: the Clockwork thread program, checked by address.
+    #[account(address = automation::CLOCKWORK_THREAD_ID)]
+    pub clockwork_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct Arbitrate<'info> {
+    /// The escrow's arbitrator, or its backup once the arbitration deadline passes.
+    #[account(mut)]
+    pub arbitrator: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// The primary arbitrator's queue, even when the backup rules.
+    #[account(
+        mut,
+        seeds = [b"case_queue", escrow.arbitrator.as_ref()],
+        bump = case_queue.bump,
+    )]
+    pub case_queue: Account<'info, CaseQueue>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct ClaimVested<'info> {
+    pub signer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump = escrow.vault_bump,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    /// The original buyer's `Counter`; required while the escrow holds one
+    /// of its open-escrow slots, which settling frees.
+    #[account(mut, seeds = [b"counter", escrow.original_buyer.as_ref()], bump = counter.bump)]
+    pub counter: Option<Account<'info, Counter>>,
+
+    #[account(
+        mut,
+        constraint = seller_token.key() == escrow.seller_payout_ata() @ ClawscrowError::PayoutAccountMismatch,
+        constraint = seller_token.mint == escrow.mint @ ClawscrowError::InvalidMint,
+    )]
+    pub seller_token: Account<'info, TokenAccount>,
+
+    /// Required when the seller registered a payout split; the recipients'
+    /// token accounts go in the remaining accounts.
+    #[account(seeds = [b"payout_split", escrow.key().as_ref()], bump = payout_split.bump)]
+    pub payout_split: Option<Account<'info, PayoutSplit>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct ClawbackUnvested<'info> {
+    pub buyer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump = escrow.vault_bump,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    /// The original buyer's `Counter`; required while the escrow holds one
+    /// of its open-escrow slots, which settling frees.
+    #[account(mut, seeds = [b"counter", escrow.original_buyer.as_ref()], bump = counter.bump)]
+    pub counter: Option<Account<'info, Counter>>,
+
+    #[account(
+        mut,
+        constraint = buyer_token.key() == escrow.buyer_payout_ata() @ ClawscrowError::PayoutAccountMismatch,
+        constraint = buyer_token.mint == escrow.mint @ ClawscrowError::InvalidMint,
+    )]
+    pub buyer_token: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = seller_token.key() == escrow.seller_payout_ata() @ ClawscrowError::PayoutAccountMismatch,
+        constraint = seller_token.mint == escrow.mint @ ClawscrowError::InvalidMint,
+    )]
+    pub seller_token: Account<'info, TokenAccount>,
+
+    /// Required when the seller registered a payout split; the recipients'
+    /// token accounts go in the remaining accounts.
+    #[account(seeds = [b"payout_split", escrow.key().as_ref()], bump = payout_split.bump)]
+    pub payout_split: Option<Account<'info, PayoutSplit>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct ApprovePartial<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump = escrow.vault_bump,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    /// Funds the dispute bond.
+    #[account(
+        mut,
+        constraint = buyer_token.owner == buyer.key() @ ClawscrowError::Unauthorized,
+        constraint = buyer_token.mint == escrow.mint @ ClawscrowError::InvalidMint,
+    )]
+    pub buyer_token: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = seller_token.key() == escrow.seller_payout_ata() @ ClawscrowError::PayoutAccountMismatch,
+        constraint = seller_token.mint == escrow.mint @ ClawscrowError::InvalidMint,
+    )]
+    pub seller_token: Account<'info, TokenAccount>,
+
+    /// Required when the seller registered a payout split; the recipients'
+    /// token accounts go in the remaining accounts.
+    #[account(seeds = [b"payout_split", escrow.key().as_ref()], bump = payout_split.bump)]
+    pub payout_split: Option<Account<'info, PayoutSplit>>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(mut, seeds = [b"stats"], bump = stats.bump)]
+    pub stats: Account<'info, Stats>,
+
+    #[account(
+        mut,
+        constraint = treasury_token.owner == config.treasury @ ClawscrowError::Unauthorized,
+        constraint = treasury_token.mint == escrow.mint @ ClawscrowError::InvalidMint,
+    )]
+    pub treasury_token: Account<'info, TokenAccount>,
+
+    /// Referrer's account for the referral fee; required when the escrow has one.
+    #[account(mut, constraint = referrer_token.mint == escrow.mint @ ClawscrowError::InvalidMint)]
+    pub referrer_token: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + Reputation::INIT_SPACE,
+        seeds = [b"reputation", buyer.key().as_ref()],
+        bump,
+    )]
+    pub reputation: Account<'info, Reputation>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + CaseQueue::INIT_SPACE,
+        seeds = [b"case_queue", escrow.arbitrator.as_ref()],
+        bump,
+    )]
+    pub case_queue: Account<'info, CaseQueue>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct EscalateToDao<'info> {
+    /// The buyer or the seller; pays for the proposal and its deposit.
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"case_queue", escrow.arbitrator.as_ref()],
+        bump = case_queue.bump,
+    )]
+    pub case_queue: Account<'info, CaseQueue>,
+
+    #[account(
+        init,
+        payer = signer,
+        space = 8 + GovernanceEscalation::INIT_SPACE,
+        seeds = [b"governance_escalation", escrow.key().as_ref()],
+        bump,
+    )]
+    pub escalation: Account<'info, GovernanceEscalation>,
+
+    /// CHECK: signs for the proposal owner record, whose governance
+    /// delegate the realm must have set to this PDA.
+    #[account(seeds = [b"governance_authority"], bump)]
+    pub governance_authority: UncheckedAccount<'info>,
+
+    /// CHECK: the governance program and accounts below are checked against
+    /// `config.dao_escalation`; the rest are checked by the program itself.
+    pub governance_program: UncheckedAccount<'info>,
+
+    /// CHECK: see `governance_program`.
+    pub realm: UncheckedAccount<'info>,
+
+    /// CHECK: see `governance_program`.
+    #[account(mut)]
+    pub governance: UncheckedAccount<'info>,
+
+    /// CHECK: see `governance_program`.
+    #[account(mut)]
+    pub proposal_owner_record: UncheckedAccount<'info>,
+
+    /// CHECK: see `governance_program`.
+    pub governing_token_mint: UncheckedAccount<'info>,
+
+    /// CHECK: created by the governance program at `governance::proposal_address`.
+    #[account(mut)]
+    pub proposal: UncheckedAccount<'info>,
+
+    /// CHECK: see `governance_program`.
+    pub realm_config: UncheckedAccount<'info>,
+
+    /// CHECK: see `governance_program`.
+    #[account(mut)]
+    pub proposal_deposit: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct ResolveStalled<'info> {
+    pub signer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump = escrow.vault_bump,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    /// The original buyer's `Counter`; required while the escrow holds one
+    /// of its open-escrow slots, which settling frees.
+    #[account(mut, seeds = [b"counter", escrow.original_buyer.as_ref()], bump = counter.bump)]
+    pub counter: Option<Account<'info, Counter>>,
+
+    #[account(
+        mut,
+        constraint = buyer_token.key() == escrow.buyer_payout_ata() @ ClawscrowError::PayoutAccountMismatch,
+        constraint = buyer_token.mint == escrow.mint @ ClawscrowError::InvalidMint,
+    )]
+    pub buyer_token: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = seller_token.key() == escrow.seller_payout_ata() @ ClawscrowError::PayoutAccountMismatch,
+        constraint = seller_token.mint == escrow.mint @ ClawscrowError::InvalidMint,
+    )]
+    pub seller_token: Account<'info, TokenAccount>,
+
+    /// CHECK: Pyth SOL/USD `PriceUpdateV2`, verified by `oracle::read_sol_usd`.
+    /// Required the first time a USD-pegged escrow pays out.
+    pub price_update: Option<UncheckedAccount<'info>>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(mut, seeds = [b"stats"], bump = stats.bump)]
+    pub stats: Account<'info, Stats>,
+
+    #[account(
+        mut,
+        constraint = treasury_token.owner == config.treasury @ ClawscrowError::Unauthorized,
+        constraint = treasury_token.mint == escrow.mint @ ClawscrowError::InvalidMint,
+    )]
+    pub treasury_token: Account<'info, TokenAccount>,
+
+    /// Referrer's account for the referral fee; required when the escrow has one.
+    #[account(mut, constraint = referrer_token.mint == escrow.mint @ ClawscrowError::InvalidMint)]
+    pub referrer_token: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"case_queue", escrow.arbitrator.as_ref()],
+        bump = case_queue.bump,
+    )]
+    pub case_queue: Account<'info, CaseQueue>,
+
+    #[account(
+        mut,
+        seeds = [b"arbitrator_index", escrow.arbitrator.as_ref()],
+        bump = arbitrator_index.bump,
+    )]
+    pub arbitrator_index: Account<'info, ArbitratorIndex>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct CastRulingVote<'info> {
+    pub panelist: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"case_queue", escrow.arbitrator.as_ref()],
+        bump = case_queue.bump,
+    )]
+    pub case_queue: Account<'info, CaseQueue>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct RequestRandomArbitrator<'info> {
+    /// Pays ORAO's fee and the rent of its `Randomness` account.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        mut,
+        seeds = [b"random_arbitration", escrow.key().as_ref()],
+        bump = random_arbitration.bump,
+    )]
+    pub random_arbitration: Account<'info, RandomArbitration>,
+
+    #[account(seeds = [b"arbitrator_registry"], bump = registry.bump)]
+    pub registry: Account<'info, ArbitratorRegistry>,
+
+    /// CHECK: ORAO's network configuration, checked by address.
+    #[account(mut, address = vrf::network_state_address())]
+    pub network_state: UncheckedAccount<'info>,
+
+    /// CHECK: ORAO's fee treasury; ORAO checks it against `network_state`.
+    #[account(mut)]
+    pub treasury: UncheckedAccount<'info>,
+
+    /// CHECK: the `Randomness` account ORAO creates, checked against the seed.
+    #[account(mut)]
+    pub randomness: UncheckedAccount<'info>,
+
+    /// CHECK: the ORAO VRF program, checked by address.
+    #[account(address = vrf::ORAO_VRF_ID)]
+    pub vrf_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct FulfillRandomArbitrator<'info> {
+    /// Anyone; pays rent if the drawn arbitrator has no queue or index yet.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        mut,
+        seeds = [b"random_arbitration", escrow.key().as_ref()],
+        bump = random_arbitration.bump,
+    )]
+    pub random_arbitration: Account<'info, RandomArbitration>,
+
+    /// CHECK: ORAO's `Randomness` account, decoded and checked in `vrf::read_randomness`.
+    pub randomness: UncheckedAccount<'info>,
+
+    /// CHECK: must be the candidate the randomness picks.
+    pub arbitrator: UncheckedAccount<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// Queue and index under the `RandomArbitration` PDA, no longer needed.
+    #[account(
+        mut,
+        seeds = [b"case_queue", escrow.arbitrator.as_ref()],
+        bump = draw_queue.bump,
+        close = buyer,
+    )]
+    pub draw_queue: Account<'info, CaseQueue>,
+
+    #[account(
+        mut,
+        seeds = [b"arbitrator_index", escrow.arbitrator.as_ref()],
+        bump = draw_index.bump,
+        close = buyer,
+    )]
+    pub draw_index: Account<'info, ArbitratorIndex>,
+
+    /// CHECK: the buyer, who paid for the queue and index being closed.
+    #[account(mut, address = escrow.buyer @ ClawscrowError::Unauthorized)]
+    pub buyer: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + CaseQueue::INIT_SPACE,
+        seeds = [b"case_queue", arbitrator.key().as_ref()],
+        bump,
+    )]
+    pub case_queue: Account<'info, CaseQueue>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ArbitratorIndex::INIT_SPACE,
+        seeds = [b"arbitrator_index", arbitrator.key().as_ref()],
+        bump,
+    )]
+    pub arbitrator_index: Account<'info, ArbitratorIndex>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct SubmitAppeal<'info> {
+    pub appellant: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump = escrow.vault_bump,
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(mut, constraint = appellant_token.mint == escrow.mint @ ClawscrowError::InvalidMint)]
+    pub appellant_token: Account<'info, TokenAccount>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct AdminEscrowAction<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ClawscrowError::Unauthorized,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(escrow_id: u64)]
+pub struct ExecuteRuling<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.original_buyer.as_ref(), escrow_id.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Box<Account<'info, Escrow>>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump = escrow.vault_bump,
+    )]
+    pub vault: Box<Account<'info, TokenAccount>>,
+
+    /// The original buyer's `Counter`; required while the escrow holds one
+    /// of its open-escrow slots, which settling frees.
+    #[account(mut, seeds = [b"counter", escrow.original_buyer.as_ref()], bump = counter.bump)]
+    pub counter: Option<Account<'info, Counter>>,
+
+    /// CHECK: only used as the authority of `buyer_token`.
+    #[account(address = escrow.buyer_payout.unwrap_or(escrow.buyer) @ ClawscrowError::PayoutAccountMismatch)]
+    pub buyer_payout_owner: UncheckedAccount<'info>,
+
+    /// CHECK: authority of `seller_token`; receives native SOL when unwrapping.
+    #[account(mut, address = escrow.seller_payout.unwrap_or(escrow.seller) @ ClawscrowError::PayoutAccountMismatch)]
+    pub seller_payout_owner: UncheckedAccount<'info>,
+
+    #[account(address = escrow.mint @ ClawscrowError::InvalidMint)]
+    pub mint: Account<'info, Mint>,
+
+    /// Created at the caller's expense if the payout owner closed it.
+    #[account(
+        init_if_needed,
+        payer = signer,
+        associated_token::mint = mint,
+        associated_token::authority = buyer_payout_owner,
+    )]
+    pub buyer_token: Box<Account<'info, TokenAccount>>,
+
+    /// Created at the caller's expense if the payout owner closed it.
+    #[account(
+        init_if_needed,
+        payer = signer,
+        associated_token::mint = mint,
+        associated_token::authority = seller_payout_owner,
+    )]
+    pub seller_token: Box<Account<'info, TokenAccount>>,
+
+    /// Pass on a wrapped SOL escrow to pay the seller native SOL instead of
+    /// wSOL; see `unwrap_native_payout`.
+    #[account(
+        init,
+        payer = signer,
+        seeds = [b"unwrap", escrow.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = escrow,
+    )]
+    pub unwrap_token: Option<Account<'info, TokenAccount>>,
+
+    /// Required when the seller registered a payout split; the recipients'
+    /// token accounts go in the remaining accounts.
+    #[account(seeds = [b"payout_split", escrow.key().as_ref()], bump = payout_split.bump)]
+    pub payout_split: Option<Box<Account<'info, PayoutSplit>>>,
+
+    /// Required by `finalize_governance_ruling`.
+    #[account(seeds = [b"governance_escalation", escrow.key().as_ref()], bump = escalation.bump)]
+    pub escalation: Option<Box<Account<'info, GovernanceEscalation>>>,
+
+    /// CHECK: required by `finalize_governance_ruling`; checked against
+    /// `escalation.proposal` and decoded by `governance::read_outcome`.
+    pub governance_proposal: Option<UncheckedAccount<'info>>,
+
+    /// Referrer's account for the referral fee; required for seller-wins rulings when the escrow has one.
+    #[account(mut, constraint = referrer_token.mint == escrow.mint @ ClawscrowError::InvalidMint)]
+    pub referrer_token: Option<Account<'info, TokenAccount>>,
+
+    /// CHECK: Pyth SOL/USD `PriceUpdateV2`, verified by `oracle::read_sol_usd`.
+    /// Required the first time a USD-pegged escrow pays out.
+    pub price_update: Option<UncheckedAccount<'info>>,
+
+    #[account(
+        mut,
+        constraint = arbitrator_token.owner == escrow.ruling_arbitrator() @ ClawscrowError::Unauthorized,
+        constraint = arbitrator_token.mint == escrow.mint @ ClawscrowError::InvalidMint,
+    )]
+    pub arbitrator_token: Account<'info, TokenAccount>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, Config>>,
+
+    #[account(mut, seeds = [b"stats"], bump = stats.bump)]
+    pub stats: Box<Account<'info, Stats>>,
+
+    #[account(
+        mut,
+        constraint = treasury_token.owner == config.treasury @ ClawscrowError::Unauthorized,
+        constraint = treasury_token.mint == escrow.mint @ ClawscrowError::InvalidMint,
+    )]
+    pub treasury_token: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"arbitrator_index", escrow.arbitrator.as_ref()],
+        bump = arbitrator_index.bump,
+    )]
+    pub arbitrator_index: Box<Account<'info, ArbitratorIndex>>,
+
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + Reputation::INIT_SPACE,
+        seeds = [b"reputation", escrow.buyer.as_ref()],
+        bump,
+    )]
+    pub buyer_reputation: Box<Account<'info, Reputation>>,
+
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + Reputation::INIT_SPACE,
+        seeds = [b"reputation", escrow.seller.as_ref()],
+        bump,
+    )]
+    pub seller_reputation: Box<Account<'info, Reputation>>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+// === STATE ===
+
+#[account]
+#[derive(InitSpace)]
+pub struct Escrow {
+    /// Kept first so it sits at byte 8, right after the discriminator.
+    pub layout_version: u8,
+    /// Slot the escrow was created in.
+    pub genesis_slot: u64,
+    pub escrow_id: u64,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub arbitrator: Pubkey,
+    /// SPL mint the escrow is denominated in; every token account must match it.
+    pub mint: Pubkey,
+    /// Ahead of the description so it has a fixed offset; see [`layout`].
+    pub state: EscrowState,
+    pub payment_amount: u64,
+    pub buyer_collateral: u64,
+    pub seller_collateral: u64,
+    /// Decimals of the payment mint, for rendering amounts off-chain.
+    pub decimals: u8,
+    pub deadline_ts: i64,
+    #[max_len(MAX_DESCRIPTION_BYTES)]
+    pub description: String,
+    pub delivery_hash: [u8; 32],
+    pub created_at: i64,
+    pub delivered_at: i64,
+    pub acknowledged_at: i64,
+    pub feedback_hash: Option<[u8; 32]>,
+    pub satisfaction: Option<u8>,
+    pub cancel_proposed_by: Option<Pubkey>,
+    pub predecessor_id: Option<u64>,
+    pub successor_id: Option<u64>,
+    #[max_len(MAX_MILESTONES)]
+    pub milestones: Vec<Milestone>,
+    /// Payment already paid out through approved milestones.
+    pub released_amount: u64,
+    pub arbitration_sla: Option<ArbitrationSla>,
+    pub disputed_at: i64,
+    /// Integrator that originated the escrow, used for fee overrides.
+    pub platform: Option<Pubkey>,
+    /// Protocol fee snapshotted at creation, skimmed from the payment at settlement.
+    pub protocol_fee_bps: u16,
+    /// Ruling awaiting execution once the challenge window passes.
+    pub pending_ruling: Option<Ruling>,
+    pub ruled_at: i64,
+    /// Arbitrator fee fixed at ruling time.
+    pub arbitrator_fee: u64,
+    pub appeal: Option<Appeal>,
+    /// Hash of the `TermsV1` the escrow was created under.
+    pub terms_hash: [u8; 32],
+    pub heartbeat_policy: Option<HeartbeatPolicy>,
+    /// Last seller heartbeat, seeded with the acceptance time.
+    pub last_heartbeat_at: i64,
+    /// Offer lapses if no seller accepts by this time.
+    pub accept_by_ts: Option<i64>,
+    /// Vault balance minus expected balance at the last reconciliation.
+    pub vault_discrepancy: i64,
+    /// Owners of the token accounts that receive settlements, when they differ
+    /// from the signing wallets. Fixed once set so a hot key cannot redirect funds.
+    pub buyer_payout: Option<Pubkey>,
+    pub seller_payout: Option<Pubkey>,
+    /// Set once the named arbitrator has opted in; sellers cannot accept before then.
+    pub arbitrator_accepted: bool,
+    /// Three-arbitrator panel for high-value escrows; `arbitrator` chairs it.
+    pub panel: Option<ArbitrationPanel>,
+    /// After this, an unruled dispute can be force-resolved by anyone.
+    pub arbitration_deadline: i64,
+    /// Revisions requested so far, capped at `MAX_REVISIONS`.
+    pub revisions: u8,
+    /// When set, approval streams the net payment to the seller over this many seconds.
+    pub vesting_secs: Option<i64>,
+    /// Progress of the payment stream once a vesting escrow is approved.
+    pub vesting: Option<VestingSchedule>,
+    /// Buyer review window for this escrow, fixed at creation.
+    pub review_period_secs: i64,
+    /// Last recorded `buyer_payout_ata()`, kept for indexers; payouts derive
+    /// the account afresh rather than reading this.
+    pub buyer_payout_token: Pubkey,
+    /// Last recorded `seller_payout_ata()`, default until acceptance.
+    pub seller_payout_token: Pubkey,
+    /// Keeper tip for `auto_approve`, snapshotted from config at creation.
+    pub crank_tip_bps: u16,
+    /// Payment amount the buyer has proposed and the seller has yet to confirm.
+    pub pending_amendment: Option<u64>,
+    /// Full statement of work kept off-chain, committed to by hash.
+    pub contract: Option<OffchainContract>,
+    /// Where the latest delivery can be fetched, if the seller published it.
+    #[max_len(MAX_URI_BYTES)]
+    pub delivery_uri: Option<String>,
+    /// Every delivery so far, oldest first, so arbitrators can see what was
+    /// sent before each revision request.
+    #[max_len(MAX_DELIVERIES)]
+    pub deliveries: Vec<DeliveryRecord>,
+    /// Bond the buyer posted when disputing; paid out by the ruling.
+    pub dispute_bond: u64,
+    pub bump: u8,
+    pub vault_bump: u8,
+    // Fields added since layout v2 go below, so older escrows are a prefix of
+    // this layout; `migrate_escrow` clears past that prefix and grows the account.
+    /// When a seller accepted, or the offer was taken; zero before that.
+    pub accepted_at: i64,
+    /// When the escrow settled by approval, ruling or timeout; zero until then.
+    pub resolved_at: i64,
+    /// Token account `fund_escrow` pulls from while the escrow is a `Draft`.
+    pub funding_source: Option<Pubkey>,
+    /// Payment and buyer collateral deposited so far while a `Draft`.
+    pub funded_amount: u64,
+    /// Set while the seller has a `PayoutSplit` that settlements must honour.
+    pub has_payout_split: bool,
+    /// Extra approval signer for the buyer side, such as a multisig vault.
+    pub buyer_authority: Option<Pubkey>,
+    /// Buyer the escrow PDA was derived from. `buyer` moves on
+    /// `transfer_buyer_role`; this never does, so it stays in the seeds.
+    pub original_buyer: Pubkey,
+    /// May rule in the arbitrator's place once the arbitration deadline passes.
+    pub backup_arbitrator: Option<Pubkey>,
+    /// Set when the pending ruling came from `backup_arbitrator`, who is then paid the fee.
+    pub ruled_by_backup: bool,
+    /// A premium was paid into the insurance vault for this escrow.
+    pub insured: bool,
+    /// The winner has drawn on the insurance vault for this escrow.
+    pub insurance_claimed: bool,
+    /// Seller may receive a `CompletionBadge` once the escrow is approved.
+    pub completion_badge: bool,
+    /// Both parties may receive a compressed NFT receipt once it resolves.
+    pub compressed_receipts: bool,
+    pub receipts_minted: bool,
+    /// Asset the seller deposits with `deliver_asset` and approval swaps to the buyer.
+    pub deliverable: Option<AssetDeliverable>,
+    /// Payment in a second mint, held in `["second_vault", escrow]`.
+    pub second_leg: Option<PaymentLeg>,
+    /// Set on wrapped SOL escrows whose price is fixed in USD.
+    pub usd_peg: Option<UsdPeg>,
+    /// Extra payment for delivering early, held in `["bonus_vault", escrow]`.
+    pub early_bonus: Option<EarlyBonus>,
+    pub early_bonus_settled: bool,
+    /// Refund the seller has offered in place of a dispute; see `offer_discount`.
+    pub discount_offer: Option<u64>,
+    /// Set on hourly escrows, whose payment is a budget billed by timesheet.
+    pub hourly_rate: Option<u64>,
+    /// The seller's latest timesheet, awaiting the buyer.
+    pub pending_timesheet: Option<Timesheet>,
+    /// Marketplace that brought the escrow in, paid a share of the payment on approval.
+    pub referral: Option<Referral>,
+    /// Created for a named seller with the config's collateral ratios waived.
+    pub trusted_pair: bool,
+    /// Counted in the buyer's `Counter::open_escrows` until it settles.
+    /// Migrated escrows predate the count and never hold a slot.
+    pub holds_open_slot: bool,
+}
+
+impl Escrow {
+    /// Where every buyer-side payout and refund goes: the associated token
+    /// account of `buyer_payout` (default: the buyer) for the escrow's mint.
+    pub fn buyer_payout_ata(&self) -> Pubkey {
+        get_associated_token_address(&self.buyer_payout.unwrap_or(self.buyer), &self.mint)
+    }
+
+    /// Where every seller-side payout goes, as `buyer_payout_ata` for the seller.
+    pub fn seller_payout_ata(&self) -> Pubkey {
+        get_associated_token_address(&self.seller_payout.unwrap_or(self.seller), &self.mint)
+    }
+
+    /// Payment and buyer collateral an unaccepted escrow holds: only what has
+    /// been deposited so far while it is still a `Draft`.
+    pub fn funded_deposit(&self) -> Option<u64> {
+        if self.state == EscrowState::Draft {
+            return Some(self.funded_amount);
+        }
+        self.remaining_payment().checked_add(self.buyer_collateral)
+    }
+
+    /// What a `Draft` still needs before it can open as `Created`.
+    pub fn deposit_due(&self) -> Option<u64> {
+        self.payment_amount
+            .checked_add(self.buyer_collateral)?
+            .checked_sub(self.funded_amount)
+    }
+
+    /// Credit a deposit to a `Draft`, opening it as `Created` once the full
+    /// payment and buyer collateral are in. Returns whether it opened.
+    pub fn credit_deposit(&mut self, amount: u64) -> Option<bool> {
+        self.funded_amount = self.funded_amount.checked_add(amount)?;
+        if self.deposit_due()? > 0 {
+            return Some(false);
+        }
+        self.state = EscrowState::Created;
+        self.funding_source = None;
+        Some(true)
+    }
+
+    /// Payment still held in the vault after any milestone releases.
+    pub fn remaining_payment(&self) -> u64 {
+        self.payment_amount.saturating_sub(self.released_amount)
+    }
+
+    /// Tokens the vault should hold given the escrow's state.
+    pub fn expected_vault_balance(&self) -> Option<u64> {
+        let deposit = self.amendment_deposit();
+        if self.state.is_terminal() {
+            return Some(deposit);
+        }
+        if self.state == EscrowState::Vesting {
+            return self.vesting.as_ref().and_then(|v| (v.total - v.claimed).checked_add(deposit));
+        }
+        if self.state == EscrowState::Draft {
+            return self.funded_amount.checked_add(deposit);
+        }
+        let seller_collateral = if self.state == EscrowState::Created { 0 } else { self.seller_collateral };
+        let bond = self.appeal.as_ref().map_or(0, |a| a.bond);
+        math::total_pool(self.remaining_payment(), self.buyer_collateral, seller_collateral)?
+            .checked_add(bond)?
+            .checked_add(self.dispute_bond)?
+            .checked_add(deposit)
+    }
+
+    /// Whether the seller earned `bonus`: the escrow settled in their favour
+    /// and the work was first delivered by the bonus deadline.
+    pub fn earned_early_bonus(&self, bonus: &EarlyBonus) -> bool {
+        let seller_won = matches!(
+            self.state,
+            EscrowState::Approved | EscrowState::Vesting | EscrowState::ResolvedSeller
+        );
+        seller_won && self.deliveries.first().is_some_and(|d| d.delivered_at <= bonus.bonus_deadline_ts)
+    }
+
+    /// Append to the delivery history, dropping the oldest entry if full.
+    pub fn record_delivery(&mut self, delivery_hash: [u8; 32], delivered_at: i64) {
+        if self.deliveries.len() >= MAX_DELIVERIES {
+            self.deliveries.remove(0);
+        }
+        self.deliveries.push(DeliveryRecord { delivery_hash, delivered_at });
+        self.discount_offer = None;
+    }
+
+    /// Dispute bond for the pool still held: `bond_bps` of payment plus both collaterals.
+    pub fn dispute_bond_for(&self, bond_bps: u16) -> Option<u64> {
+        let pool = math::total_pool(self.remaining_payment(), self.buyer_collateral, self.seller_collateral)?;
+        math::bps_of(pool, bond_bps)
+    }
+
+    /// Tokens the buyer has deposited for an unconfirmed price increase.
+    pub fn amendment_deposit(&self) -> u64 {
+        self.pending_amendment.map_or(0, |amount| amount.saturating_sub(self.payment_amount))
+    }
+
+    /// Finish an approval. Vesting escrows keep `net_payment` in the vault and
+    /// release it through `claim_vested`.
+    pub fn settle_approval(&mut self, now: i64, net_payment: u64) {
+        if self.vesting_secs.is_some() {
+            self.vesting = Some(VestingSchedule { start_ts: now, total: net_payment, claimed: 0 });
+            self.state = EscrowState::Vesting;
+        } else {
+            self.state = EscrowState::Approved;
+        }
+        self.resolved_at = now;
+    }
+
+    /// Payment the seller ended up with, before fees, once the escrow has
+    /// settled; `None` while it is still open.
+    pub fn seller_payment_share(&self) -> Option<u64> {
+        match self.state {
+            EscrowState::Approved | EscrowState::ResolvedSeller => Some(self.payment_amount),
+            EscrowState::ResolvedBuyer | EscrowState::Cancelled | EscrowState::TimedOut => Some(self.released_amount),
+            EscrowState::ResolvedSplit => {
+                let Some(Ruling::Split { buyer_bps }) = &self.pending_ruling else {
+                    return None;
+                };
+                let (_, seller) = math::split_bps(self.remaining_payment(), *buyer_bps)?;
+                self.released_amount.checked_add(seller)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whoever made the pending ruling, and so is owed the arbitrator fee.
+    pub fn ruling_arbitrator(&self) -> Pubkey {
+        match self.backup_arbitrator {
+            Some(backup) if self.ruled_by_backup => backup,
+            _ => self.arbitrator,
+        }
+    }
+
+    /// Arbitrator fee for a ruling made at `now`. The fee is fixed when the
+    /// ruling is made, so SLA decay stops there.
+    pub fn arbitrator_fee_at(&self, now: i64) -> u64 {
+        let base_fee = math::arbitrator_base_fee(self.buyer_collateral);
+        match &self.arbitration_sla {
+            Some(sla) => sla.decayed_fee(base_fee, now.saturating_sub(self.disputed_at)),
+            None => base_fee,
+        }
+    }
+
+    /// Start of the buyer's review window: the acknowledgment, or the fallback
+    /// point after delivery, whichever comes first.
+    pub fn review_started_at(&self) -> i64 {
+        let fallback = self.delivered_at.saturating_add(ACK_FALLBACK_SECS);
+        if self.acknowledged_at > 0 {
+            self.acknowledged_at.min(fallback)
+        } else {
+            fallback
+        }
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    pub admin: Pubkey,
+    pub dispute_window_secs: i64,
+    pub free_disputes_per_window: u16,
+    pub dispute_cooldown_secs: i64,
+    /// Owner of the token accounts that receive protocol fees.
+    pub treasury: Pubkey,
+    /// Default protocol fee for escrows without a platform override.
+    pub protocol_fee_bps: u16,
+    /// Delay between a ruling and its execution, during which fraud can be flagged.
+    pub ruling_challenge_secs: i64,
+    /// Appeal bond as a share of the escrow pool.
+    pub appeal_bond_bps: u16,
+    /// Bounds applied to both buyer and seller collateral at creation.
+    pub min_collateral: u64,
+    pub max_collateral: u64,
+    pub paused: bool,
+    /// Time from a dispute to the point anyone may force the fallback split.
+    pub arbitration_timeout_secs: i64,
+    /// Buyer's share of the pool when a stalled dispute is force-resolved.
+    pub stalled_buyer_bps: u16,
+    /// Bounds on the per-escrow review window chosen at creation.
+    pub min_review_period_secs: i64,
+    pub max_review_period_secs: i64,
+    /// Keeper tip for `auto_approve`, as a share of the payment.
+    pub crank_tip_bps: u16,
+    /// Share of seller collateral forfeited to the buyer by `seller_withdraw`.
+    pub seller_withdraw_forfeit_bps: u16,
+    /// Dispute bond as a share of the escrow pool.
+    pub dispute_bond_bps: u16,
+    /// Lamports locked by `register_arbitrator`.
+    pub arbitrator_stake_lamports: u64,
+    pub dao_escalation: Option<DaoEscalation>,
+    /// Premium `insure_escrow` charges, as a share of the payment; zero disables it.
+    pub insurance_premium_bps: u16,
+    /// Bubblegum tree for compressed receipts; unset until the admin configures one.
+    pub receipt_tree: Pubkey,
+    /// Compliance mode: when set, buyers and sellers must hold this token
+    /// (e.g. a KYC pass) to create, accept, offer or bid.
+    pub credential_mint: Pubkey,
+    /// Cap on a buyer's open escrows; zero means no cap.
+    pub max_open_escrows: u32,
+    /// Largest payment allowed with an arbitrator who has never accepted a
+    /// role (no `ArbitratorProfile`); zero means no cap.
+    pub max_unvetted_payment: u64,
+    /// Minimum collateral as a share of the payment, waived for trusted pairs.
+    pub min_buyer_collateral_bps: u16,
+    pub min_seller_collateral_bps: u16,
+    pub bump: u8,
+}
+
+/// Where `escalate_to_dao` files its proposals.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct DaoEscalation {
+    /// SPL Governance program the realm lives under.
+    pub program: Pubkey,
+    pub realm: Pubkey,
+    pub governance: Pubkey,
+    /// Mint whose holders vote on the proposals.
+    pub governing_token_mint: Pubkey,
+    /// Token owner record that owns the proposals; its governance delegate
+    /// must be the `governance_authority` PDA.
+    pub proposal_owner_record: Pubkey,
+    /// Smallest pool (payment plus both collaterals, in base units) that
+    /// may be escalated.
+    pub min_pool: u64,
+}
+
+/// Protocol-wide counters for dashboards, so they can read one account
+/// instead of scanning every escrow. Volumes are summed in base units across
+/// mints.
+#[account]
+#[derive(InitSpace)]
+pub struct Stats {
+    pub escrows_created: u64,
+    /// Payment amounts of escrows at creation.
+    pub volume_escrowed: u64,
+    /// Payment released to sellers by approval, before fees.
+    pub volume_released: u64,
+    pub disputes_raised: u64,
+    pub rulings_buyer: u64,
+    pub rulings_seller: u64,
+    pub rulings_split: u64,
+    /// Disputes force-resolved after the arbitrator missed the deadline.
+    pub disputes_stalled: u64,
+    pub bump: u8,
+}
+
+impl Stats {
+    pub fn record_created(&mut self, payment: u64) {
+        self.escrows_created = self.escrows_created.saturating_add(1);
+        self.volume_escrowed = self.volume_escrowed.saturating_add(payment);
+    }
+
+    pub fn record_released(&mut self, payment: u64) {
+        self.volume_released = self.volume_released.saturating_add(payment);
+    }
+
+    pub fn record_dispute(&mut self) {
+        self.disputes_raised = self.disputes_raised.saturating_add(1);
+    }
+
+    pub fn record_ruling(&mut self, ruling: &Ruling) {
+        let counter = match ruling {
+            Ruling::BuyerWins => &mut self.rulings_buyer,
+            Ruling::SellerWins => &mut self.rulings_seller,
+            Ruling::Split { .. } => &mut self.rulings_split,
+        };
+        *counter = counter.saturating_add(1);
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct SplitRecipient {
+    pub wallet: Pubkey,
+    pub bps: u16,
+}
+
+/// A hot wallet allowed to run an owner's escrow workflow steps.
+#[account]
+#[derive(InitSpace)]
+pub struct OperatorKey {
+    pub owner: Pubkey,
+    pub operator: Option<Pubkey>,
+    pub bump: u8,
+}
+
+/// Portfolio credential for a completed escrow; `mint` is the seller's
+/// non-transferable badge token.
+#[account]
+#[derive(InitSpace)]
+pub struct CompletionBadge {
+    pub escrow: Pubkey,
+    pub escrow_id: u64,
+    pub seller: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub completed_at: i64,
+    pub bump: u8,
+}
+
+/// One wallet's share of a pooled escrow's buyer-side deposit.
+#[account]
+#[derive(InitSpace)]
+pub struct Contribution {
+    pub escrow: Pubkey,
+    pub contributor: Pubkey,
+    pub amount: u64,
+    pub bump: u8,
+}
+
+/// How a seller's payout on one escrow is shared out; weights sum to 100%.
+#[account]
+#[derive(InitSpace)]
+pub struct PayoutSplit {
+    pub escrow: Pubkey,
+    #[max_len(MAX_SPLIT_RECIPIENTS)]
+    pub recipients: Vec<SplitRecipient>,
+    pub bump: u8,
+}
+
+/// Negotiated protocol fee for one integrator platform.
+#[account]
+#[derive(InitSpace)]
+pub struct FeeOverride {
+    pub platform: Pubkey,
+    pub fee_bps: u16,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Reputation {
+    pub user: Pubkey,
+    pub disputes_raised: u32,
+    pub window_start: i64,
+    pub window_disputes: u16,
+    pub last_dispute_at: i64,
+    pub ratings_count: u32,
+    pub ratings_total: u64,
+    /// Escrows this wallet was a party to that settled by approval or ruling.
+    pub escrows_completed: u32,
+    /// Sum of payment amounts across completed escrows, in base units of each escrow's mint.
+    pub total_volume: u64,
+    /// Rulings that went fully against this wallet.
+    pub disputes_lost: u32,
+    /// Sum of creation-to-settlement times across completed escrows.
+    pub resolution_secs_total: u64,
+    pub bump: u8,
+}
+
+impl Reputation {
+    /// Stamp owner and bump on a freshly `init_if_needed`-created account.
+    pub fn init_if_empty(&mut self, user: Pubkey, bump: u8) {
+        if self.user == Pubkey::default() {
+            self.user = user;
+            self.bump = bump;
+        }
+    }
+
+    /// Apply the rolling-window dispute limits and count a new dispute.
+    pub fn record_dispute(&mut self, config: &Config, now: i64) -> Result<()> {
+        // Rolling window: reset the recent-dispute counter once the window lapses.
+        if now.saturating_sub(self.window_start) >= config.dispute_window_secs {
+            self.window_start = now;
+            self.window_disputes = 0;
+        }
+
+        // Past the free quota, each further dispute in the window waits one more cooldown step.
+        if self.window_disputes >= config.free_disputes_per_window {
+            let excess = (self.window_disputes - config.free_disputes_per_window) as i64 + 1;
+            let cooldown = config.dispute_cooldown_secs.saturating_mul(excess);
+            require!(
+                now >= self.last_dispute_at.saturating_add(cooldown),
+                ClawscrowError::DisputeCooldownActive
+            );
+        }
+
+        self.window_disputes = self.window_disputes.saturating_add(1);
+        self.disputes_raised = self.disputes_raised.saturating_add(1);
+        self.last_dispute_at = now;
+        Ok(())
+    }
+
+    /// Count a settled escrow toward this wallet's track record.
+    pub fn record_settlement(&mut self, volume: u64, created_at: i64, now: i64) {
+        self.escrows_completed = self.escrows_completed.saturating_add(1);
+        self.total_volume = self.total_volume.saturating_add(volume);
+        let elapsed = now.saturating_sub(created_at).max(0) as u64;
+        self.resolution_secs_total = self.resolution_secs_total.saturating_add(elapsed);
+    }
+
+    pub fn average_resolution_secs(&self) -> Option<u64> {
+        self.resolution_secs_total.checked_div(self.escrows_completed as u64)
+    }
+}
+
+/// Per-buyer escrow ID allocator. Escrow PDAs are seeded by
+/// `[b"escrow", buyer, id]`, so IDs only need to be unique per buyer.
+#[account]
+#[derive(InitSpace)]
+pub struct Counter {
+    pub buyer: Pubkey,
+    /// Next ID to assign.
+    pub count: u64,
+    pub bump: u8,
+    /// Escrows created and not yet closed or cancelled. Appended after
+    /// `bump`, so `migrate_counter` can grow older counters in place.
+    pub open_escrows: u32,
+}
+
+impl Counter {
+    pub fn init_if_empty(&mut self, buyer: Pubkey, bump: u8) {
+        if self.buyer == Pubkey::default() {
+            self.buyer = buyer;
+            self.bump = bump;
+        }
+    }
+
+    /// Hand out the current ID and advance, counting the new escrow as open.
+    /// `max_open` is `Config::max_open_escrows`; zero means no cap.
+    pub fn next_id(&mut self, max_open: u32) -> Result<u64> {
+        require!(max_open == 0 || self.open_escrows < max_open, ClawscrowError::OpenEscrowLimit);
+        let id = self.count;
+        self.count = id.checked_add(1).ok_or(ClawscrowError::Overflow)?;
+        self.open_escrows = self.open_escrows.saturating_add(1);
+        Ok(id)
+    }
+
+    pub fn record_closed(&mut self) {
+        self.open_escrows = self.open_escrows.saturating_sub(1);
+    }
+}
+
+/// Free the buyer's open-escrow slot the first time `escrow` reaches a
+/// terminal state; a no-op for open escrows and those holding no slot.
+fn release_open_slot(escrow: &mut Escrow, counter: Option<&mut Account<Counter>>) -> Result<()> {
+    if !escrow.holds_open_slot || !escrow.state.is_terminal() {
+        return Ok(());
+    }
+    counter.ok_or(ClawscrowError::CounterMissing)?.record_closed();
+    escrow.holds_open_slot = false;
+    Ok(())
+}
+
+/// A seller's fixed-price listing at `[b"offer", seller, offer_id]`. The
+/// seller's collateral waits in `[b"offer_vault", offer]` until a buyer takes it.
+#[account]
+#[derive(InitSpace)]
+pub struct Offer {
+    pub seller: Pubkey,
+    pub offer_id: u64,
+    pub arbitrator: Pubkey,
+    pub mint: Pubkey,
+    pub payment_amount: u64,
+    pub buyer_collateral: u64,
+    pub seller_collateral: u64,
+    /// Time the seller has to deliver, counted from when a buyer accepts.
+    pub delivery_secs: i64,
+    pub expires_at: i64,
+    #[max_len(MAX_DESCRIPTION_BYTES)]
+    pub description: String,
+    /// Where `cancel_offer` returns the seller's collateral.
+    pub seller_payout_token: Pubkey,
+    pub bump: u8,
+    pub vault_bump: u8,
+}
+
+/// Reusable escrow terms at `[b"template", owner, template_id]`.
+#[account]
+#[derive(InitSpace)]
+pub struct Template {
+    pub owner: Pubkey,
+    pub template_id: u64,
+    pub arbitrator: Pubkey,
+    pub mint: Pubkey,
+    /// Collateral as a share of each escrow's payment.
+    pub buyer_collateral_bps: u16,
+    pub seller_collateral_bps: u16,
+    pub review_period_secs: i64,
+    /// Escrows are refused if the protocol fee they would snapshot is higher.
+    pub max_protocol_fee_bps: u16,
+    #[max_len(MAX_DESCRIPTION_BYTES)]
+    pub description: String,
+    pub contract: Option<OffchainContract>,
+    pub bump: u8,
+}
+
+/// A buyer's retainer with one seller at `[b"recurring", buyer, agreement_id]`:
+/// the terms each period's escrow is spawned with.
+#[account]
+#[derive(InitSpace)]
+pub struct RecurringAgreement {
+    pub buyer: Pubkey,
+    pub agreement_id: u64,
+    pub seller: Pubkey,
+    pub arbitrator: Pubkey,
+    pub mint: Pubkey,
+    pub payment_amount: u64,
+    pub buyer_collateral: u64,
+    pub seller_collateral: u64,
+    pub period_secs: i64,
+    /// Earliest start of the next period to spawn.
+    pub next_period_at: i64,
+    pub periods_spawned: u32,
+    /// Unlimited when `None`.
+    pub max_periods: Option<u32>,
+    pub last_escrow_id: Option<u64>,
+    #[max_len(MAX_DESCRIPTION_BYTES)]
+    pub description: String,
+    pub bump: u8,
+}
+
+/// A seller's proposed price and deadline for an open escrow, at
+/// `[b"bid", escrow, bidder]`.
+#[account]
+#[derive(InitSpace)]
+pub struct Bid {
+    pub escrow: Pubkey,
+    pub bidder: Pubkey,
+    pub payment_amount: u64,
+    pub deadline_ts: i64,
+    pub placed_at: i64,
+    pub bump: u8,
+}
+
+/// Escrows currently awaiting a ruling from one arbitrator.
+#[account]
+#[derive(InitSpace)]
+pub struct CaseQueue {
+    pub arbitrator: Pubkey,
+    #[max_len(MAX_QUEUED_CASES)]
+    pub escrows: Vec<Pubkey>,
+    pub bump: u8,
+}
+
+impl CaseQueue {
+    pub fn init_if_empty(&mut self, arbitrator: Pubkey, bump: u8) {
+        if self.arbitrator == Pubkey::default() {
+            self.arbitrator = arbitrator;
+            self.bump = bump;
+        }
+    }
+
+    pub fn enqueue(&mut self, escrow: Pubkey) -> Result<()> {
+        require!(self.escrows.len() < MAX_QUEUED_CASES, ClawscrowError::CaseQueueFull);
+        self.escrows.push(escrow);
+        Ok(())
+    }
+}
+
+/// Open escrows naming one arbitrator, so they can enumerate their exposure.
+#[account]
+#[derive(InitSpace)]
+pub struct ArbitratorIndex {
+    pub arbitrator: Pubkey,
+    #[max_len(MAX_INDEXED_ESCROWS)]
+    pub escrows: Vec<Pubkey>,
+    pub bump: u8,
+}
+
+impl ArbitratorIndex {
+    pub fn init_if_empty(&mut self, arbitrator: Pubkey, bump: u8) {
+        if self.arbitrator == Pubkey::default() {
+            self.arbitrator = arbitrator;
+            self.bump = bump;
+        }
+    }
+}
+
+/// Escrows a wallet has created or accepted, newest last, so wallets can
+/// find their escrows without a `getProgramAccounts` scan. Once full, the
+/// oldest entry is dropped; closed escrows are not pruned.
+#[account]
+#[derive(InitSpace)]
+pub struct UserIndex {
+    pub user: Pubkey,
+    #[max_len(MAX_INDEXED_ESCROWS)]
+    pub escrows: Vec<Pubkey>,
+    pub bump: u8,
+}
+
+impl UserIndex {
+    pub fn init_if_empty(&mut self, user: Pubkey, bump: u8) {
+        if self.user == Pubkey::default() {
+            self.user = user;
+            self.bump = bump;
+        }
+    }
+
+    pub fn push(&mut self, escrow: Pubkey) {
+        if self.escrows.len() >= MAX_INDEXED_ESCROWS {
+            self.escrows.remove(0);
+        }
+        self.escrows.push(escrow);
+    }
+}
+
+/// Open offers listed through one marketplace platform.
+#[account]
+#[derive(InitSpace)]
+pub struct ListingIndex {
+    pub platform: Pubkey,
+    #[max_len(MAX_LISTINGS)]
+    pub escrows: Vec<Pubkey>,
+    pub bump: u8,
+}
+
+impl ListingIndex {
+    pub fn init_if_empty(&mut self, platform: Pubkey, bump: u8) {
+        if self.platform == Pubkey::default() {
+            self.platform = platform;
+            self.bump = bump;
+        }
+    }
+}
+
+/// Created the first time a key agrees to arbitrate an escrow.
+#[account]
+#[derive(InitSpace)]
+pub struct ArbitratorProfile {
+    pub arbitrator: Pubkey,
+    pub roles_accepted: u64,
+    pub first_accepted_at: i64,
+    pub bump: u8,
+}
+
+impl ArbitratorProfile {
+    pub fn init_if_empty(&mut self, arbitrator: Pubkey, now: i64, bump: u8) {
+        if self.arbitrator == Pubkey::default() {
+            self.arbitrator = arbitrator;
+            self.first_accepted_at = now;
+            self.bump = bump;
+        }
+    }
+}
+
+/// Arbitrators who have staked to be drawn for random-arbitrator escrows,
+/// at `[b"arbitrator_registry"]`.
+#[account]
+#[derive(InitSpace)]
+pub struct ArbitratorRegistry {
+    #[max_len(MAX_REGISTERED_ARBITRATORS)]
+    pub arbitrators: Vec<Pubkey>,
+    pub bump: u8,
+}
+
+/// Lamports an arbitrator locked to join the registry, held in this account
+/// at `[b"arbitrator_stake", arbitrator]` until they deregister.
+#[account]
+#[derive(InitSpace)]
+pub struct ArbitratorStake {
+    pub arbitrator: Pubkey,
+    pub amount: u64,
+    pub bump: u8,
+}
+
+/// Random arbitrator draw for one escrow, at `[b"random_arbitration",
+/// escrow]`. Its address is the escrow's arbitrator until the draw.
+#[account]
+#[derive(InitSpace)]
+pub struct RandomArbitration {
+    pub escrow: Pubkey,
+    pub status: RandomDrawStatus,
+    /// ORAO request seed; the randomness lands at `vrf::randomness_address(seed)`.
+    pub seed: [u8; 32],
+    /// Registry snapshot taken at request time, in registry order.
+    #[max_len(MAX_REGISTERED_ARBITRATORS)]
+    pub candidates: Vec<Pubkey>,
+    pub bump: u8,
+}
+
+/// The proposal an escalated dispute is decided by, at
+/// `[b"governance_escalation", escrow]`. Kept after the ruling as the record
+/// of which vote decided the escrow.
+#[account]
+#[derive(InitSpace)]
+pub struct GovernanceEscalation {
+    pub escrow: Pubkey,
+    /// Governance program at escalation time, in case the config moves on.
+    pub program: Pubkey,
+    pub proposal: Pubkey,
+    pub bump: u8,
+}
+
+/// `[b"auto_approve_thread", escrow]`: who funded the escrow's Clockwork
+/// thread, so `cancel_auto_approve` can refund them even after it closes.
+#[account]
+#[derive(InitSpace)]
+pub struct AutoApproveThread {
+    pub escrow: Pubkey,
+    pub payer: Pubkey,
+    pub fire_at: i64,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum RandomDrawStatus {
+    /// Waiting for a dispute.
+    Idle,
+    /// Randomness requested from ORAO, not yet applied.
+    Pending,
+    Drawn,
+}
+
+/// Lending reserve whitelisted for one mint, at `[b"lending_reserve", mint]`.
+#[account]
+#[derive(InitSpace)]
+pub struct LendingReserve {
+    pub mint: Pubkey,
+    pub target: LendingTarget,
+    /// Buyer's share of the yield; the seller takes the rest.
+    pub buyer_yield_bps: u16,
+    pub bump: u8,
+}
+
+/// An SPL token-lending reserve and the accounts its instructions need.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub struct LendingTarget {
+    pub program: Pubkey,
+    pub reserve: Pubkey,
+    pub lending_market: Pubkey,
+    /// The reserve's liquidity supply token account.
+    pub liquidity_supply: Pubkey,
+    pub collateral_mint: Pubkey,
+}
+
+/// An escrow vault out on loan, at `[b"yield_position", escrow]`; its
+/// collateral tokens sit in `[b"yield_collateral", escrow]`.
+#[account]
+#[derive(InitSpace)]
+pub struct YieldPosition {
+    pub escrow: Pubkey,
+    /// Reserve the vault was lent to, fixed in case the whitelist changes.
+    pub target: LendingTarget,
+    /// Vault balance lent out; anything redeemed above it is yield.
+    pub principal: u64,
+    pub buyer_yield_bps: u16,
+    /// Paid the position's rent, and gets it back on withdrawal.
+    pub payer: Pubkey,
+    pub bump: u8,
+}
+
+/// Additional payment in a second mint, divided like the main payment.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub struct PaymentLeg {
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub settled: bool,
+}
+
+/// Hours billed on an hourly escrow.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub struct Timesheet {
+    pub hours: u32,
+    /// `hours` at the escrow's hourly rate.
+    pub amount: u64,
+    /// Commitment to the off-chain timesheet and rate card.
+    pub rate_hash: [u8; 32],
+    pub submitted_at: i64,
+}
+
+/// Terms for `create_escrow` and `create_and_list`. Every option left at its
+/// default gives a plain escrow: no milestones, platform or extras.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct CreateEscrowParams {
+    pub description: String,
+    pub payment_amount: u64,
+    pub buyer_collateral: u64,
+    pub seller_collateral: u64,
+    pub deadline_ts: i64,
+    /// Empty for a single delivery; otherwise must sum to the payment.
+    pub milestone_amounts: Vec<u64>,
+    pub arbitration_sla: Option<ArbitrationSla>,
+    pub platform: Option<Pubkey>,
+    /// Rejects the escrow unless it matches the `TermsV1` hash of these terms.
+    pub terms_hash: Option<[u8; 32]>,
+    pub accept_by_ts: Option<i64>,
+    /// Only this seller may accept; `None` lets anyone.
+    pub intended_seller: Option<Pubkey>,
+    /// Wallet whose associated token account receives the buyer's payouts.
+    pub buyer_payout: Option<Pubkey>,
+    pub panel: Option<[Pubkey; PANEL_SIZE]>,
+    pub vesting_secs: Option<i64>,
+    /// Defaults to `REVIEW_PERIOD_SECS`.
+    pub review_period_secs: Option<i64>,
+    pub contract: Option<OffchainContract>,
+    /// Opens a `Draft` that `fund_escrow` pays for later.
+    pub deferred_funding: bool,
+    pub backup_arbitrator: Option<Pubkey>,
+    pub completion_badge: bool,
+    pub compressed_receipts: bool,
+    pub deliverable: Option<AssetDeliverable>,
+    pub early_bonus: Option<EarlyBonus>,
+    pub referral: Option<Referral>,
+    /// Waives the collateral ratio for a named seller the buyer trusts.
+    pub trusted_pair: bool,
+}
+
+/// Per-escrow terms for `create_escrows_batch`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct BatchEscrow {
+    pub description: String,
+    pub payment_amount: u64,
+    pub buyer_collateral: u64,
+    pub seller_collateral: u64,
+    pub deadline_ts: i64,
+}
+
+/// Bonus the seller earns on top of the payment by delivering early.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub struct EarlyBonus {
+    pub amount: u64,
+    /// The first delivery must land at or before this time.
+    pub bonus_deadline_ts: i64,
+}
+
+/// Referral fee owed to the marketplace that brought an escrow in.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub struct Referral {
+    pub referrer: Pubkey,
+    /// Share of the payment, taken from the seller's side at approval.
+    pub fee_bps: u16,
+}
+
+/// USD price of a wrapped SOL escrow, converted to lamports at payout.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub struct UsdPeg {
+    /// Millionths of a dollar, like USDC base units.
+    pub usd_amount: u64,
+    /// Whether `payment_amount` has been repriced from `usd_amount` yet.
+    pub priced: bool,
+}
+
+/// On-chain asset the seller hands over as the work itself, e.g. an NFT.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub struct AssetDeliverable {
+    pub mint: Pubkey,
+    /// Base units of `mint`; 1 for an NFT.
+    pub amount: u64,
+}
+
+/// Commitment to a contract stored off-chain (IPFS, Arweave) when the
+/// description is too short for the real statement of work.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub struct OffchainContract {
+    /// SHA-256 of the document the URI points to.
+    pub hash: [u8; 32],
+    #[max_len(MAX_URI_BYTES)]
+    pub uri: String,
+}
+
+/// Arbitrator fee decays linearly to zero over `decay_secs` once `sla_secs`
+/// have elapsed since the dispute was raised; the forgone fee stays with the winner.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub struct ArbitrationSla {
+    pub sla_secs: i64,
+    pub decay_secs: i64,
+}
+
+impl ArbitrationSla {
+    pub fn decayed_fee(&self, fee: u64, elapsed: i64) -> u64 {
+        math::decayed_fee(fee, elapsed, self.sla_secs, self.decay_secs)
+    }
+}
+
+/// Seller liveness requirement: missing `max_missed` consecutive heartbeats
+/// of `interval_secs` lets the buyer cancel before the deadline.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub struct HeartbeatPolicy {
+    pub interval_secs: i64,
+    pub max_missed: u8,
+}
+
+impl HeartbeatPolicy {
+    pub fn grace_secs(&self) -> i64 {
+        self.interval_secs.saturating_mul(self.max_missed as i64)
+    }
+}
+
+/// Fraud claim against a pending ruling, backed by a bond held in the vault.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub struct Appeal {
+    pub appellant: Pubkey,
+    pub evidence_hash: [u8; 32],
+    pub bond: u64,
+    pub status: AppealStatus,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub enum AppealStatus {
+    Submitted,
+    Frozen,
+    Upheld,
+    Rejected,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub struct DeliveryRecord {
+    pub delivery_hash: [u8; 32],
+    pub delivered_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub struct Milestone {
+    pub amount: u64,
+    pub status: MilestoneStatus,
+    pub delivery_hash: [u8; 32],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub enum MilestoneStatus {
+    Pending,
+    Delivered,
+    Approved,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub enum EscrowState {
+    Created,
+    Accepted,
+    Delivered,
+    Approved,
+    Disputed,
+    ResolvedBuyer,
+    ResolvedSeller,
+    Cancelled,
+    TimedOut,
+    RulingPending,
+    RulingFrozen,
+    ResolvedSplit,
+    /// Approved, with the payment still streaming to the seller.
+    Vesting,
+    /// Created without a deposit; `fund_escrow` moves it to `Created`.
+    Draft,
+    /// Disputed and put to a DAO vote; see `finalize_governance_ruling`.
+    Escalated,
+}
+
+impl EscrowState {
+    /// States from which no further funds movement is possible.
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            EscrowState::Approved
+                | EscrowState::ResolvedBuyer
+                | EscrowState::ResolvedSeller
+                | EscrowState::ResolvedSplit
+                | EscrowState::Cancelled
+                | EscrowState::TimedOut
+        )
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub enum Ruling {
+    BuyerWins,
+    SellerWins,
+    /// Share of the winnings to the buyer; the seller takes the rest, including rounding dust.
+    Split { buyer_bps: u16 },
+}
+
+impl Ruling {
+    pub fn is_valid(&self) -> bool {
+        match self {
+            Ruling::Split { buyer_bps } => *buyer_bps as u64 <= BPS_DENOMINATOR,
+            _ => true,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub struct VestingSchedule {
+    pub start_ts: i64,
+    /// Payment net of protocol fee, streamed linearly to the seller.
+    pub total: u64,
+    pub claimed: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub struct ArbitrationPanel {
+    pub members: [Pubkey; PANEL_SIZE],
+    /// Each member's vote, by seat.
+    pub votes: [Option<Ruling>; PANEL_SIZE],
+}
+
+impl ArbitrationPanel {
+    pub fn new(members: [Pubkey; PANEL_SIZE]) -> Self {
+        Self { members, votes: [None, None, None] }
+    }
+
+    pub fn seat_of(&self, member: &Pubkey) -> Option<usize> {
+        self.members.iter().position(|m| m == member)
+    }
+
+    /// The ruling backed by a strict majority of seats, if any.
+    pub fn majority(&self) -> Option<Ruling> {
+        self.votes.iter().flatten().find(|candidate| {
+            self.votes.iter().flatten().filter(|v| v == candidate).count() * 2 > PANEL_SIZE
+        }).cloned()
+    }
+
+    /// Members who have voted, in seat order.
+    pub fn voters(&self) -> Vec<Pubkey> {
+        self.members
+            .iter()
+            .zip(self.votes.iter())
+            .filter(|(_, vote)| vote.is_some())
+            .map(|(member, _)| *member)
+            .collect()
+    }
+}
+
+// === EVENTS ===
+
+/// Fields shared by every escrow event, so indexers can attribute and order
+/// events without fetching the escrow account.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct EscrowEventMeta {
+    /// Address of the escrow PDA.
+    pub escrow: Pubkey,
+    pub mint: Pubkey,
+    /// Signer of the instruction that emitted the event.
+    pub actor: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EscrowCreated {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub buyer: Pubkey,
+    pub mint: Pubkey,
+    pub payment_amount: u64,
+    pub buyer_collateral: u64,
+    pub seller_collateral: u64,
+    pub decimals: u8,
+    pub terms_hash: [u8; 32],
+    /// Set for private escrows that only one seller may accept.
+    pub intended_seller: Option<Pubkey>,
+    pub contract: Option<OffchainContract>,
+}
+
+#[event]
+pub struct EscrowListed {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub platform: Pubkey,
+    pub buyer: Pubkey,
+    pub mint: Pubkey,
+    pub payment_amount: u64,
+    pub buyer_collateral: u64,
+    pub seller_collateral: u64,
+    pub deadline_ts: i64,
+    pub accept_by_ts: Option<i64>,
+    pub protocol_fee_bps: u16,
+    pub terms_hash: [u8; 32],
+}
+
+#[event]
+pub struct EscrowRenewed {
+    pub predecessor_id: u64,
+    pub successor_id: u64,
+    /// For the successor escrow.
+    pub meta: EscrowEventMeta,
+}
+
+#[event]
+pub struct EscrowAccepted {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub seller: Pubkey,
+}
+
+#[event]
+pub struct HeartbeatPolicySet {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub policy: Option<HeartbeatPolicy>,
+}
+
+#[event]
+pub struct HeartbeatRecorded {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub at: i64,
+}
+
+#[event]
+pub struct CancelProposed {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub proposer: Pubkey,
+}
+
+#[event]
+pub struct EscrowCancelled {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+}
+
+#[event]
+pub struct VaultReconciled {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub expected: u64,
+    pub actual: u64,
+    pub discrepancy: i64,
+}
+
+#[event]
+pub struct EscrowExpired {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub refunded: u64,
+}
+
+#[event]
+pub struct EscrowTimedOut {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub refunded: u64,
+    pub seller_collateral_forfeited: u64,
+}
+
+#[event]
+pub struct EscrowClosed {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+}
+
+#[event]
+pub struct WorkDelivered {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub delivery_hash: [u8; 32],
+    pub delivery_uri: Option<String>,
+}
+
+#[event]
+pub struct MilestoneDelivered {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub index: u8,
+    pub delivery_hash: [u8; 32],
+}
+
+#[event]
+pub struct MilestoneApproved {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub index: u8,
+    pub amount: u64,
+}
+
+#[event]
+pub struct CrankTipPaid {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub cranker: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct AutoApproveScheduled {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub thread: Pubkey,
+    pub fire_at: i64,
+    pub amount: u64,
+}
+
+#[event]
+pub struct AutoApproveCancelled {
+    pub escrow: Pubkey,
+    pub thread: Pubkey,
+    pub payer: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VestedClaimed {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub amount: u64,
+    pub claimed: u64,
+    pub total: u64,
+}
+
+#[event]
+pub struct VestingClawedBack {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub to_seller: u64,
+    pub to_buyer: u64,
+}
+
+#[event]
+pub struct PartiallyApproved {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub amount: u64,
+    /// Released amount plus the matching share of seller collateral, net of fee.
+    pub seller_amount: u64,
+    pub protocol_fee: u64,
+    /// Payment left in the vault for the arbitrator to rule on.
+    pub disputed_payment: u64,
+}
+
+#[event]
+pub struct RevisionRequested {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub revision: u8,
+    /// Hash of the buyer's off-chain revision notes.
+    pub notes_hash: [u8; 32],
+    pub deadline_ts: i64,
+}
+
+#[event]
+pub struct DeliveryAcknowledged {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub acknowledged_at: i64,
+}
+
+#[event]
+pub struct EscrowApproved {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub feedback_hash: Option<[u8; 32]>,
+    pub satisfaction: Option<u8>,
+    pub protocol_fee: u64,
+    /// Gratuity paid on top by `approve_with_tip`.
+    pub tip: u64,
+}
+
+#[event]
+pub struct EscrowDisputed {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    /// Raised against an accepted escrow whose deadline passed without delivery.
+    pub undelivered: bool,
+    pub bond: u64,
+}
+
+#[event]
+pub struct RulingRecorded {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub ruling: Ruling,
+    pub executable_at: i64,
+}
+
+#[event]
+pub struct AppealSubmitted {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub appellant: Pubkey,
+    pub evidence_hash: [u8; 32],
+    pub bond: u64,
+}
+
+#[event]
+pub struct RulingFrozen {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+}
+
+#[event]
+pub struct AppealResolved {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub ruling: Ruling,
+    pub upheld: bool,
+}
+
+#[event]
+pub struct DisputeResolved {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub ruling: Ruling,
+    pub buyer_amount: u64,
+    pub seller_amount: u64,
+    pub protocol_fee: u64,
+}
+
+#[event]
+pub struct DisputeEscalated {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub proposal: Pubkey,
+    pub pool: u64,
+}
+
+#[event]
+pub struct StalledDisputeResolved {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub buyer_amount: u64,
+    pub seller_amount: u64,
+    pub protocol_fee: u64,
+    /// Arbitrator fee that would have been paid had they ruled on time.
+    pub forfeited_fee: u64,
+}
+
+#[event]
+pub struct RulingVoteCast {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub panelist: Pubkey,
+    pub ruling: Ruling,
+}
+
+#[event]
+pub struct ArbitrationRoleAccepted {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub arbitrator: Pubkey,
+}
+
+#[event]
+pub struct ArbitratorRegistered {
+    pub arbitrator: Pubkey,
+    pub stake: u64,
+}
+
+#[event]
+pub struct ArbitratorDeregistered {
+    pub arbitrator: Pubkey,
+    pub stake: u64,
+}
+
+#[event]
+pub struct RandomArbitratorEnabled {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub random_arbitration: Pubkey,
+    pub terms_hash: [u8; 32],
+}
+
+#[event]
+pub struct RandomArbitratorRequested {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub randomness: Pubkey,
+    pub candidates: u8,
+}
+
+#[event]
+pub struct RandomArbitratorDrawn {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub arbitrator: Pubkey,
+}
+
+#[event]
+pub struct PauseStateChanged {
+    pub paused: bool,
+    pub admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeeOverrideSet {
+    pub platform: Pubkey,
+    pub fee_bps: u16,
+    pub admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeeOverrideRemoved {
+    pub platform: Pubkey,
+    pub admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OfferPosted {
+    pub seller: Pubkey,
+    pub offer_id: u64,
+    pub mint: Pubkey,
+    pub payment_amount: u64,
+    pub buyer_collateral: u64,
+    pub seller_collateral: u64,
+    pub delivery_secs: i64,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OfferAccepted {
+    pub seller: Pubkey,
+    pub offer_id: u64,
+    pub buyer: Pubkey,
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+}
+
+#[event]
+pub struct OfferCancelled {
+    pub seller: Pubkey,
+    pub offer_id: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TemplateCreated {
+    pub template: Pubkey,
+    pub owner: Pubkey,
+    pub template_id: u64,
+    pub arbitrator: Pubkey,
+    pub mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TemplateClosed {
+    pub template: Pubkey,
+    pub owner: Pubkey,
+    pub template_id: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EscrowCreatedFromTemplate {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub template: Pubkey,
+}
+
+#[event]
+pub struct RecurringAgreementCreated {
+    pub agreement: Pubkey,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub mint: Pubkey,
+    pub payment_amount: u64,
+    pub period_secs: i64,
+    pub first_period_at: i64,
+    pub max_periods: Option<u32>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RecurringEscrowSpawned {
+    pub agreement: Pubkey,
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    /// 1 for the first period.
+    pub period: u32,
+}
 
-        emit!(EscrowApproved { escrow_id });
+#[event]
+pub struct RecurringAgreementClosed {
+    pub agreement: Pubkey,
+    pub agreement_id: u64,
+    pub periods_spawned: u32,
+    pub timestamp: i64,
+}
 
-        Ok(())
-    }
+#[event]
+pub struct EscrowReleasedEarly {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub feedback_hash: Option<[u8; 32]>,
+    pub satisfaction: Option<u8>,
+    pub protocol_fee: u64,
 }
 
-// === ACCOUNTS ===
+#[event]
+pub struct SellerWithdrew {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub buyer_refund: u64,
+    pub seller_refund: u64,
+    pub forfeited: u64,
+}
 
-#[derive(Accounts)]
-#[instruction(escrow_id: u64)]
-pub struct CreateEscrow<'info> {
-    #[account(mut)]
-    pub buyer: Signer<'info>,
+#[event]
+pub struct AmendmentProposed {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub new_payment_amount: u64,
+    pub deposit: u64,
+}
 
-    #[account(
-        init,
-        payer = buyer,
-        space = 8 + Escrow::INIT_SPACE,
-        seeds = [b"escrow", escrow_id.to_le_bytes().as_ref()],
-        bump,
-    )]
-    pub escrow: Account<'info, Escrow>,
+#[event]
+pub struct EscrowAmended {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub old_payment_amount: u64,
+    pub new_payment_amount: u64,
+    pub terms_hash: [u8; 32],
+}
 
-    #[account(
-        init,
-        payer = buyer,
-        token::mint = usdc_mint,
-        token::authority = escrow,
-        seeds = [b"vault", escrow_id.to_le_bytes().as_ref()],
-        bump,
-    )]
-    pub vault: Account<'info, TokenAccount>,
+#[event]
+pub struct AmendmentWithdrawn {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub refund: u64,
+}
 
-    #[account(mut)]
-    pub buyer_token: Account<'info, TokenAccount>,
+#[event]
+pub struct BidPlaced {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub bidder: Pubkey,
+    pub payment_amount: u64,
+    pub deadline_ts: i64,
+}
 
-    pub usdc_mint: Account<'info, Mint>,
+#[event]
+pub struct BidWithdrawn {
+    pub escrow_id: u64,
+    pub escrow: Pubkey,
+    pub bidder: Pubkey,
+    pub timestamp: i64,
+}
 
-    /// CHECK: Arbitrator pubkey stored in escrow
-    pub arbitrator: UncheckedAccount<'info>,
+#[event]
+pub struct BidSelected {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub bidder: Pubkey,
+    pub payment_amount: u64,
+    pub deadline_ts: i64,
+    pub terms_hash: [u8; 32],
+}
 
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
+#[event]
+pub struct EscrowMigrated {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub from_version: u8,
+    pub to_version: u8,
 }
 
-#[derive(Accounts)]
-#[instruction(escrow_id: u64)]
-pub struct AcceptEscrow<'info> {
-    #[account(mut)]
-    pub seller: Signer<'info>,
+/// Emitted instead of `EscrowApproved` when a keeper releases an escrow
+/// after the review window.
+#[event]
+pub struct EscrowAutoApproved {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub protocol_fee: u64,
+    pub crank_tip: u64,
+}
 
-    #[account(
-        mut,
-        seeds = [b"escrow", escrow_id.to_le_bytes().as_ref()],
-        bump = escrow.bump,
-    )]
-    pub escrow: Account<'info, Escrow>,
+#[event]
+pub struct EscrowFunded {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub amount: u64,
+}
 
-    #[account(
-        mut,
-        seeds = [b"vault", escrow_id.to_le_bytes().as_ref()],
-        bump = escrow.vault_bump,
-    )]
-    pub vault: Account<'info, TokenAccount>,
+#[event]
+pub struct DepositReceived {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub amount: u64,
+    /// Total deposited so far, including `amount`.
+    pub funded: u64,
+}
 
-    #[account(mut)]
-    pub seller_token: Account<'info, TokenAccount>,
+#[event]
+pub struct PayoutSplitSet {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub recipients: Vec<SplitRecipient>,
+}
 
-    pub token_program: Program<'info, Token>,
+#[event]
+pub struct SellerPayoutSet {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub seller_payout: Option<Pubkey>,
+    pub seller_payout_token: Pubkey,
 }
 
-#[derive(Accounts)]
-pub struct Deliver<'info> {
-    #[account(mut)]
-    pub seller: Signer<'info>,
+#[event]
+pub struct PoolOpened {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub pool: Pubkey,
+}
 
-    #[account(mut)]
-    pub escrow: Account<'info, Escrow>,
+#[event]
+pub struct ContributionReceived {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub amount: u64,
+    /// Total contributed so far, including `amount`.
+    pub funded: u64,
 }
 
-#[derive(Accounts)]
-pub struct DisputeCtx<'info> {
-    #[account(mut)]
-    pub buyer: Signer<'info>,
+#[event]
+pub struct ContributionClaimed {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub contributed: u64,
+    pub amount: u64,
+}
 
-    #[account(mut)]
-    pub escrow: Account<'info, Escrow>,
+#[event]
+pub struct BuyerAuthoritySet {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub authority: Option<Pubkey>,
 }
 
-#[derive(Accounts)]
-#[instruction(escrow_id: u64)]
-pub struct Resolve<'info> {
-    #[account(mut)]
-    pub signer: Signer<'info>,
+#[event]
+pub struct OperatorSet {
+    pub owner: Pubkey,
+    pub operator: Option<Pubkey>,
+    pub timestamp: i64,
+}
 
-    #[account(
-        mut,
-        seeds = [b"escrow", escrow_id.to_le_bytes().as_ref()],
-        bump = escrow.bump,
-    )]
-    pub escrow: Account<'info, Escrow>,
+#[event]
+pub struct BuyerRoleTransferred {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub previous_buyer: Pubkey,
+    pub new_buyer: Pubkey,
+}
 
-    #[account(
-        mut,
-        seeds = [b"vault", escrow_id.to_le_bytes().as_ref()],
-        bump = escrow.vault_bump,
-    )]
-    pub vault: Account<'info, TokenAccount>,
+#[event]
+pub struct EscrowInsured {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub premium: u64,
+}
 
-    #[account(mut)]
-    pub buyer_token: Account<'info, TokenAccount>,
+#[event]
+pub struct InsuranceClaimed {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub amount: u64,
+}
 
-    #[account(mut)]
-    pub seller_token: Account<'info, TokenAccount>,
+#[event]
+pub struct CompletionBadgeMinted {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub seller: Pubkey,
+    pub badge_mint: Pubkey,
+}
 
-    pub token_program: Program<'info, Token>,
+#[event]
+pub struct ReceiptsMinted {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub merkle_tree: Pubkey,
 }
 
-#[derive(Accounts)]
-#[instruction(escrow_id: u64)]
-pub struct Arbitrate<'info> {
-    #[account(mut)]
-    pub arbitrator: Signer<'info>,
+#[event]
+pub struct DeliverableReleased {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub recipient: Pubkey,
+}
 
-    #[account(
-        mut,
-        seeds = [b"escrow", escrow_id.to_le_bytes().as_ref()],
-        bump = escrow.bump,
-        has_one = arbitrator,
-    )]
-    pub escrow: Account<'info, Escrow>,
+#[event]
+pub struct SecondLegFunded {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub second_mint: Pubkey,
+    pub amount: u64,
+}
 
-    #[account(
-        mut,
-        seeds = [b"vault", escrow_id.to_le_bytes().as_ref()],
-        bump = escrow.vault_bump,
-    )]
-    pub vault: Account<'info, TokenAccount>,
+#[event]
+pub struct SecondLegSettled {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub buyer_amount: u64,
+    pub seller_amount: u64,
+}
 
-    #[account(mut)]
-    pub buyer_token: Account<'info, TokenAccount>,
+#[event]
+pub struct YieldEnabled {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub reserve: Pubkey,
+    pub principal: u64,
+    pub buyer_yield_bps: u16,
+}
 
-    #[account(mut)]
-    pub seller_token: Account<'info, TokenAccount>,
+#[event]
+pub struct YieldWithdrawn {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub principal: u64,
+    pub redeemed: u64,
+    pub buyer_gain: u64,
+    pub seller_gain: u64,
+}
 
-    #[account(mut)]
-    pub arbitrator_token: Account<'info, TokenAccount>,
+#[event]
+pub struct LendingReserveSet {
+    pub mint: Pubkey,
+    pub reserve: Pubkey,
+    pub buyer_yield_bps: u16,
+    pub admin: Pubkey,
+    pub timestamp: i64,
+}
 
-    pub token_program: Program<'info, Token>,
+#[event]
+pub struct LendingReserveRemoved {
+    pub mint: Pubkey,
+    pub admin: Pubkey,
+    pub timestamp: i64,
 }
 
-// === STATE ===
+#[event]
+pub struct UsdPegSet {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub usd_amount: u64,
+    pub max_lamports: u64,
+}
 
-#[account]
-#[derive(InitSpace)]
-pub struct Escrow {
+#[event]
+pub struct UsdPaymentPriced {
     pub escrow_id: u64,
-    pub buyer: Pubkey,
-    pub seller: Pubkey,
-    pub arbitrator: Pubkey,
+    pub meta: EscrowEventMeta,
+    pub usd_amount: u64,
+    /// Pyth SOL/USD price, `price * 10^exponent`.
+    pub price: i64,
+    pub exponent: i32,
     pub payment_amount: u64,
-    pub buyer_collateral: u64,
-    pub seller_collateral: u64,
-    pub deadline_ts: i64,
-    #[max_len(500)]
-    pub description: String,
-    pub state: EscrowState,
-    pub delivery_hash: [u8; 32],
-    pub created_at: i64,
-    pub delivered_at: i64,
-    pub bump: u8,
-    pub vault_bump: u8,
+    pub refunded: u64,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
-pub enum EscrowState {
-    Created,
-    Accepted,
-    Delivered,
-    Approved,
-    Disputed,
-    ResolvedBuyer,
-    ResolvedSeller,
-    Cancelled,
+#[event]
+pub struct EarlyBonusSettled {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub to_seller: bool,
+    pub amount: u64,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
-pub enum Ruling {
-    BuyerWins,
-    SellerWins,
+#[event]
+pub struct DiscountOffered {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    /// Zero when the offer was withdrawn.
+    pub refund_amount: u64,
 }
 
-// === EVENTS ===
+#[event]
+pub struct EscrowSettledWithDiscount {
+    pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub refund_amount: u64,
+    pub feedback_hash: Option<[u8; 32]>,
+    pub satisfaction: Option<u8>,
+    pub protocol_fee: u64,
+}
 
 #[event]
-pub struct EscrowCreated {
+pub struct HourlyBillingEnabled {
     pub escrow_id: u64,
-    pub buyer: Pubkey,
-    pub payment_amount: u64,
-    pub buyer_collateral: u64,
-    pub seller_collateral: u64,
+    pub meta: EscrowEventMeta,
+    pub hourly_rate: u64,
+    pub budget: u64,
 }
 
 #[event]
-pub struct EscrowAccepted {
+pub struct TimesheetSubmitted {
     pub escrow_id: u64,
-    pub seller: Pubkey,
+    pub meta: EscrowEventMeta,
+    pub hours: u32,
+    pub amount: u64,
+    pub rate_hash: [u8; 32],
 }
 
 #[event]
-pub struct WorkDelivered {
+pub struct TimesheetRejected {
     pub escrow_id: u64,
-    pub delivery_hash: [u8; 32],
+    pub meta: EscrowEventMeta,
+    pub hours: u32,
+    pub amount: u64,
 }
 
 #[event]
-pub struct EscrowApproved {
+pub struct TimesheetApproved {
     pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub hours: u32,
+    pub amount: u64,
+    pub protocol_fee: u64,
+    pub remaining_budget: u64,
 }
 
 #[event]
-pub struct EscrowDisputed {
+pub struct RiskLimitsChanged {
+    pub max_open_escrows: u32,
+    pub max_unvetted_payment: u64,
+    pub admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CredentialMintChanged {
+    pub credential_mint: Pubkey,
+    pub admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReferralFeePaid {
     pub escrow_id: u64,
+    pub meta: EscrowEventMeta,
+    pub referrer: Pubkey,
+    pub amount: u64,
 }
 
 #[event]
-pub struct DisputeResolved {
+pub struct HourlyBillingFinished {
     pub escrow_id: u64,
-    pub ruling: Ruling,
+    pub meta: EscrowEventMeta,
+    pub billed: u64,
+    pub refunded: u64,
 }
 
 // === ERRORS ===
@@ -530,4 +11426,230 @@ pub enum ClawscrowError {
     Overflow,
     #[msg("Review period still active")]
     ReviewPeriodActive,
+    #[msg("Invalid config parameters")]
+    InvalidConfig,
+    #[msg("Dispute cooldown still active")]
+    DisputeCooldownActive,
+    #[msg("Delivery already acknowledged")]
+    AlreadyAcknowledged,
+    #[msg("Satisfaction score must be between 1 and 5")]
+    InvalidSatisfactionScore,
+    #[msg("No cancellation has been proposed")]
+    NoCancelProposal,
+    #[msg("Deadline has not passed yet")]
+    DeadlineNotReached,
+    #[msg("Escrow has already been renewed")]
+    AlreadyRenewed,
+    #[msg("Vault still holds funds")]
+    VaultNotEmpty,
+    #[msg("Arbitrator case queue is full")]
+    CaseQueueFull,
+    #[msg("Too many milestones")]
+    TooManyMilestones,
+    #[msg("Milestone amounts must sum to the payment amount")]
+    MilestoneSumMismatch,
+    #[msg("Invalid milestone index or status")]
+    InvalidMilestone,
+    #[msg("Token account mint does not match the escrow mint")]
+    InvalidMint,
+    #[msg("Invalid arbitration SLA")]
+    InvalidArbitrationSla,
+    #[msg("Fee exceeds the protocol maximum")]
+    FeeTooHigh,
+    #[msg("Fee override does not match the escrow platform")]
+    InvalidFeeOverride,
+    #[msg("Ruling challenge window has closed")]
+    ChallengeWindowClosed,
+    #[msg("Ruling challenge window is still open")]
+    ChallengeWindowOpen,
+    #[msg("An appeal has already been submitted")]
+    AppealAlreadySubmitted,
+    #[msg("No open appeal for this ruling")]
+    NoAppeal,
+    #[msg("Escrow arguments do not match the agreed terms hash")]
+    TermsMismatch,
+    #[msg("Split ruling must allocate at most 10000 bps to the buyer")]
+    InvalidRuling,
+    #[msg("Arbitrator is named on too many open escrows")]
+    ArbitratorIndexFull,
+    #[msg("Heartbeat policy is missing or invalid")]
+    InvalidHeartbeatPolicy,
+    #[msg("Seller has not missed enough heartbeats")]
+    HeartbeatCurrent,
+    #[msg("Acceptance deadline has passed")]
+    AcceptanceExpired,
+    #[msg("Vault balance does not cover the planned payouts")]
+    VaultImbalance,
+    #[msg("Text must be NFC-normalized UTF-8")]
+    TextNotNormalized,
+    #[msg("Collateral is outside the protocol's allowed range")]
+    CollateralOutOfBounds,
+    #[msg("Protocol is paused")]
+    ProtocolPaused,
+    #[msg("Platform listing index is full")]
+    ListingIndexFull,
+    #[msg("Arbitrator has not accepted the role for this escrow")]
+    ArbitratorNotAccepted,
+    #[msg("Test harness instructions are not available in this build")]
+    HarnessDisabled,
+    #[msg("Panel must have distinct members chaired by the named arbitrator")]
+    InvalidPanel,
+    #[msg("Panel escrows are ruled by majority vote")]
+    PanelRulingRequired,
+    #[msg("Panelist has already voted")]
+    AlreadyVoted,
+    #[msg("Token account for a panel voter is missing")]
+    PanelTokenAccountMissing,
+    #[msg("Arbitrator still has time to rule")]
+    ArbitrationDeadlineNotReached,
+    #[msg("Revision limit reached; approve or dispute instead")]
+    TooManyRevisions,
+    #[msg("Vesting period must be positive and cannot be combined with milestones")]
+    InvalidVesting,
+    #[msg("Review period is outside the protocol's allowed range")]
+    ReviewPeriodOutOfBounds,
+    #[msg("Token account is not the payout account for this escrow")]
+    PayoutAccountMismatch,
+    #[msg("Escrows split into milestones cannot take bids")]
+    InvalidBid,
+    #[msg("An amendment is already awaiting confirmation")]
+    AmendmentPending,
+    #[msg("No amendment has been proposed")]
+    NoAmendmentProposal,
+    #[msg("Amendment must change the price of a single-payment escrow")]
+    InvalidAmendment,
+    #[msg("URI must be 1-200 bytes of printable ASCII")]
+    InvalidUri,
+    #[msg("Account is not an escrow in a layout this program can migrate")]
+    UnsupportedLayout,
+    #[msg("Escrow already uses the current layout")]
+    AlreadyMigrated,
+    #[msg("Only wrapped SOL escrows can pay out native SOL")]
+    NotNativeMint,
+    #[msg("Missing or mismatched ed25519 signature")]
+    InvalidSignature,
+    #[msg("Seller token account has not delegated the collateral to the escrow")]
+    CollateralNotDelegated,
+    #[msg("Buyer token account has not delegated the deposit to the escrow")]
+    FundsNotDelegated,
+    #[msg("Payout split needs 1-5 recipients with non-zero weights summing to 100%, and no vesting")]
+    InvalidPayoutSplit,
+    #[msg("Payout split or a recipient's token account was not passed")]
+    PayoutSplitMissing,
+    #[msg("Pooled escrows are funded with contribute")]
+    PooledEscrow,
+    #[msg("Contributors have not claimed their shares yet")]
+    ContributionsUnclaimed,
+    #[msg("New buyer must differ from the current buyer, seller and arbitrator")]
+    InvalidBuyerTransfer,
+    #[msg("Backup arbitrator must differ from the arbitrator and cannot back a panel")]
+    InvalidBackupArbitrator,
+    #[msg("Arbitrator registry is full")]
+    ArbitratorRegistryFull,
+    #[msg("Random arbitrators cannot be combined with a panel or backup arbitrator")]
+    RandomArbitratorUnavailable,
+    #[msg("No registered arbitrator is eligible for this escrow")]
+    NoEligibleArbitrators,
+    #[msg("Randomness account does not belong to this draw")]
+    InvalidRandomness,
+    #[msg("VRF randomness has not been fulfilled yet")]
+    RandomnessNotReady,
+    #[msg("Arbitrator is not the one the randomness drew")]
+    NotDrawnArbitrator,
+    #[msg("DAO escalation is not configured")]
+    DaoEscalationDisabled,
+    #[msg("Escrow pool is below the DAO escalation threshold")]
+    BelowEscalationThreshold,
+    #[msg("Governance account does not match the configured realm or proposal")]
+    InvalidGovernanceAccount,
+    #[msg("Governance proposal has not finished voting")]
+    ProposalNotFinal,
+    #[msg("Insurance is not enabled")]
+    InsuranceDisabled,
+    #[msg("Escrow is already insured")]
+    AlreadyInsured,
+    #[msg("Escrow is not insured or its insurance was already claimed")]
+    NotInsured,
+    #[msg("Escrow did not opt into a completion badge")]
+    BadgeNotEnabled,
+    #[msg("Escrow did not opt into compressed receipts")]
+    ReceiptsNotEnabled,
+    #[msg("Receipts were already minted for this escrow")]
+    ReceiptsAlreadyMinted,
+    #[msg("Merkle tree is not the configured receipt tree")]
+    InvalidReceiptTree,
+    #[msg("Asset deliverable needs a non-zero amount and no milestones")]
+    InvalidDeliverable,
+    #[msg("Escrows with an asset deliverable are delivered with deliver_asset")]
+    AssetDeliveryRequired,
+    #[msg("Asset vault or the recipient's asset token account was not passed")]
+    DeliverableAccountsMissing,
+    #[msg("Second payment leg needs a non-zero amount in another mint, at most once and not with vesting")]
+    InvalidSecondLeg,
+    #[msg("Second vault or a party's token account for the second payment leg was not passed")]
+    SecondLegAccountsMissing,
+    #[msg("Second payment leg has not been settled yet")]
+    SecondLegUnsettled,
+    #[msg("USD peg needs a non-zero amount, at most once and not with milestones")]
+    InvalidUsdPeg,
+    #[msg("USD-pegged escrow needs a Pyth price update to pay out")]
+    PriceUpdateMissing,
+    #[msg("Account is not a verified Pyth SOL/USD price update")]
+    InvalidPriceUpdate,
+    #[msg("Pyth price is too old")]
+    StalePrice,
+    #[msg("Pyth confidence interval is too wide")]
+    PriceTooUncertain,
+    #[msg("Lending account does not match the whitelisted reserve")]
+    InvalidLendingAccount,
+    #[msg("Early bonus needs a non-zero amount, a bonus vault and a deadline between now and the delivery deadline, and no milestones")]
+    InvalidEarlyBonus,
+    #[msg("Early-delivery bonus has not been settled yet")]
+    EarlyBonusUnsettled,
+    #[msg("Token account to pay the tip from was not passed")]
+    TipSourceMissing,
+    #[msg("Discount must be less than the payment still held")]
+    InvalidDiscount,
+    #[msg("Seller has not offered a discount")]
+    NoDiscountOffer,
+    #[msg("Recurring agreement needs a positive period, a start time and a non-zero period limit")]
+    InvalidRecurringAgreement,
+    #[msg("Recurring agreement has spawned all its periods")]
+    RecurringAgreementEnded,
+    #[msg("Next period of the recurring agreement has not started")]
+    PeriodNotStarted,
+    #[msg("Hourly billing needs a non-zero rate on a plain escrow, and replaces deliveries")]
+    InvalidHourlyBilling,
+    #[msg("Timesheet must bill a non-zero amount within the remaining budget")]
+    InvalidTimesheet,
+    #[msg("No timesheet is awaiting approval")]
+    NoPendingTimesheet,
+    #[msg("Approve or reject the pending timesheet first")]
+    TimesheetPending,
+    #[msg("Template collateral ratios or fee cap are out of range, or it names another arbitrator")]
+    InvalidTemplate,
+    #[msg("Protocol fee is above the template's cap")]
+    TemplateFeeExceeded,
+    #[msg("Batch needs 1 to MAX_BATCH_ESCROWS entries, each with its escrow and vault in order")]
+    InvalidBatch,
+    #[msg("Referral fee must be between 1 and MAX_REFERRAL_FEE_BPS, with someone other than the buyer as referrer")]
+    InvalidReferral,
+    #[msg("Escrow has a referral; pass the referrer's token account")]
+    ReferrerAccountMismatch,
+    #[msg("Compliance mode is on; pass a token account of yours holding the credential")]
+    CredentialRequired,
+    #[msg("Buyer has reached the configured cap on open escrows")]
+    OpenEscrowLimit,
+    #[msg("Payment is above the cap for arbitrators without a profile; pass the arbitrator's profile")]
+    ArbitratorNotVetted,
+    #[msg("Collateral is below the configured share of the payment")]
+    CollateralBelowRatio,
+    #[msg("Trusted-pair escrows must name their seller")]
+    InvalidTrustedPair,
+    #[msg("Asset deliverable is still in its vault; settle it with release_deliverable first")]
+    DeliverableUnreleased,
+    #[msg("Buyer's counter is required to free this escrow's open-escrow slot")]
+    CounterMissing,
+    #[msg("Listings must name their platform and be funded up front")]
+    InvalidListing,
 }
@@ -0,0 +1,624 @@
+//! Payout math shared by the program and off-chain clients.
+//!
+//! Everything here is pure and checked: a `None` means the inputs would
+//! overflow, never that tokens were silently lost. Rounding always favours
+//! the vault's counterparties over fees, and every split hands its dust to
+//! the second recipient so outputs sum exactly to inputs.
+
+/// Basis-point denominator for fee math.
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// Arbitrators earn 1% of the buyer's collateral per ruling.
+pub const ARBITRATOR_FEE_DIVISOR: u64 = 100;
+
+/// `amount * bps / 10_000`, rounded down.
+pub fn bps_of(amount: u64, bps: u16) -> Option<u64> {
+    let value = (amount as u128) * (bps as u128) / (BPS_DENOMINATOR as u128);
+    u64::try_from(value).ok()
+}
+
+/// Split `amount` so the first share is `bps` of it; rounding dust goes to the second share.
+pub fn split_bps(amount: u64, bps: u16) -> Option<(u64, u64)> {
+    if bps as u64 > BPS_DENOMINATOR {
+        return None;
+    }
+    let first = bps_of(amount, bps)?;
+    Some((first, amount - first))
+}
+
+/// Everything held in the vault for a fully funded escrow.
+pub fn total_pool(payment: u64, buyer_collateral: u64, seller_collateral: u64) -> Option<u64> {
+    payment.checked_add(buyer_collateral)?.checked_add(seller_collateral)
+}
+
+/// Undecayed arbitrator fee for a dispute.
+pub fn arbitrator_base_fee(buyer_collateral: u64) -> u64 {
+    buyer_collateral / ARBITRATOR_FEE_DIVISOR
+}
+
+/// Linearly decay `fee` to zero over `decay_secs` once `elapsed` exceeds `sla_secs`.
+pub fn decayed_fee(fee: u64, elapsed: i64, sla_secs: i64, decay_secs: i64) -> u64 {
+    let late = elapsed.saturating_sub(sla_secs);
+    if late <= 0 {
+        return fee;
+    }
+    if decay_secs <= 0 || late >= decay_secs {
+        return 0;
+    }
+    let remaining = (decay_secs - late) as u128;
+    ((fee as u128) * remaining / (decay_secs as u128)) as u64
+}
+
+/// Outflows when the buyer's side of an escrow is released to the seller.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ApprovalPayout {
+    pub seller: u64,
+    pub buyer: u64,
+    pub protocol_fee: u64,
+}
+
+impl ApprovalPayout {
+    pub fn total(&self) -> Option<u64> {
+        self.seller.checked_add(self.buyer)?.checked_add(self.protocol_fee)
+    }
+}
+
+/// Seller receives payment minus protocol fee plus their collateral; buyer gets collateral back.
+pub fn approval_payout(
+    payment: u64,
+    buyer_collateral: u64,
+    seller_collateral: u64,
+    protocol_fee_bps: u16,
+) -> Option<ApprovalPayout> {
+    let protocol_fee = bps_of(payment, protocol_fee_bps)?;
+    let seller = payment.checked_sub(protocol_fee)?.checked_add(seller_collateral)?;
+    Some(ApprovalPayout { seller, buyer: buyer_collateral, protocol_fee })
+}
+
+/// Release of one milestone; collateral only moves with the final milestone.
+pub fn milestone_payout(
+    amount: u64,
+    is_last: bool,
+    buyer_collateral: u64,
+    seller_collateral: u64,
+    protocol_fee_bps: u16,
+) -> Option<ApprovalPayout> {
+    if is_last {
+        approval_payout(amount, buyer_collateral, seller_collateral, protocol_fee_bps)
+    } else {
+        approval_payout(amount, 0, 0, protocol_fee_bps)
+    }
+}
+
+/// Early release of `amount` out of `payment`, with the same share of the
+/// seller's collateral. Returns the payout and the collateral released; the
+/// collateral share rounds down so the disputed remainder keeps the dust.
+pub fn partial_approval(
+    amount: u64,
+    payment: u64,
+    seller_collateral: u64,
+    protocol_fee_bps: u16,
+) -> Option<(ApprovalPayout, u64)> {
+    if amount > payment || payment == 0 {
+        return None;
+    }
+    let collateral = (seller_collateral as u128) * (amount as u128) / (payment as u128);
+    let collateral = u64::try_from(collateral).ok()?;
+    let payout = approval_payout(amount, 0, collateral, protocol_fee_bps)?;
+    Some((payout, collateral))
+}
+
+/// Carve a keeper's tip out of the seller's share of an approval. Returns the
+/// reduced payout and the tip.
+pub fn with_crank_tip(payout: ApprovalPayout, payment: u64, tip_bps: u16) -> Option<(ApprovalPayout, u64)> {
+    let tip = bps_of(payment, tip_bps)?;
+    let seller = payout.seller.checked_sub(tip)?;
+    Some((ApprovalPayout { seller, ..payout }, tip))
+}
+
+/// Carve a marketplace's referral fee out of the seller's share of an
+/// approval, the same way as a crank tip.
+pub fn with_referral_fee(payout: ApprovalPayout, payment: u64, fee_bps: u16) -> Option<(ApprovalPayout, u64)> {
+    with_crank_tip(payout, payment, fee_bps)
+}
+
+/// Referral fee on a ruling that leaves `buyer_bps` of the payment to the
+/// buyer: the referrer earns on the seller's share only.
+pub fn ruling_referral_fee(payment: u64, buyer_bps: u16, fee_bps: u16) -> Option<u64> {
+    let (_, seller_share) = split_bps(payment, buyer_bps)?;
+    bps_of(seller_share, fee_bps)
+}
+
+/// Outflows when an arbitrator's ruling is executed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RulingPayout {
+    pub winner: u64,
+    pub arbitrator: u64,
+    pub protocol_fee: u64,
+}
+
+impl RulingPayout {
+    pub fn total(&self) -> Option<u64> {
+        self.winner.checked_add(self.arbitrator)?.checked_add(self.protocol_fee)
+    }
+}
+
+/// Winner takes the whole pool less the arbitrator fee and the protocol fee on the payment.
+pub fn ruling_payout(
+    payment: u64,
+    buyer_collateral: u64,
+    seller_collateral: u64,
+    arbitrator_fee: u64,
+    protocol_fee_bps: u16,
+) -> Option<RulingPayout> {
+    let pool = total_pool(payment, buyer_collateral, seller_collateral)?;
+    let protocol_fee = bps_of(payment, protocol_fee_bps)?;
+    let winner = pool.checked_sub(arbitrator_fee)?.checked_sub(protocol_fee)?;
+    Some(RulingPayout { winner, arbitrator: arbitrator_fee, protocol_fee })
+}
+
+/// Per-party outflows of an executed ruling.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RulingSplit {
+    pub buyer: u64,
+    pub seller: u64,
+    pub arbitrator: u64,
+    pub protocol_fee: u64,
+}
+
+impl RulingSplit {
+    pub fn total(&self) -> Option<u64> {
+        self.buyer.checked_add(self.seller)?.checked_add(self.arbitrator)?.checked_add(self.protocol_fee)
+    }
+}
+
+/// Split the pool by `buyer_bps` and charge the arbitrator fee to the losing
+/// side: it comes out of the loser's share, up to their collateral, and any
+/// shortfall comes out of the winner's share. An even split has no loser, so
+/// the fee comes out of the pool before splitting.
+pub fn ruling_split(
+    payment: u64,
+    buyer_collateral: u64,
+    seller_collateral: u64,
+    arbitrator_fee: u64,
+    protocol_fee_bps: u16,
+    buyer_bps: u16,
+) -> Option<RulingSplit> {
+    let pool = total_pool(payment, buyer_collateral, seller_collateral)?;
+    let protocol_fee = bps_of(payment, protocol_fee_bps)?;
+    let distributable = pool.checked_sub(protocol_fee)?;
+    let half = (BPS_DENOMINATOR / 2) as u16;
+
+    if buyer_bps == half {
+        let (buyer, seller) = split_bps(distributable.checked_sub(arbitrator_fee)?, buyer_bps)?;
+        return Some(RulingSplit { buyer, seller, arbitrator: arbitrator_fee, protocol_fee });
+    }
+
+    let (buyer_share, seller_share) = split_bps(distributable, buyer_bps)?;
+    let buyer_lost = buyer_bps < half;
+    let (loser_share, loser_collateral, winner_share) = if buyer_lost {
+        (buyer_share, buyer_collateral, seller_share)
+    } else {
+        (seller_share, seller_collateral, buyer_share)
+    };
+    // With a full ruling the loser's share is zero: their collateral already
+    // went to the winner, so the winner's share is where it is paid from.
+    let from_loser = arbitrator_fee.min(loser_collateral).min(loser_share);
+    let loser_share = loser_share - from_loser;
+    let winner_share = winner_share.checked_sub(arbitrator_fee - from_loser)?;
+    let (buyer, seller) = if buyer_lost { (loser_share, winner_share) } else { (winner_share, loser_share) };
+    Some(RulingSplit { buyer, seller, arbitrator: arbitrator_fee, protocol_fee })
+}
+
+/// Buyer refund on a missed deadline: payment, their collateral, and the seller's forfeited collateral.
+pub fn timeout_refund(payment: u64, buyer_collateral: u64, seller_collateral: u64) -> Option<u64> {
+    total_pool(payment, buyer_collateral, seller_collateral)
+}
+
+/// Bond required to appeal a ruling, as a share of the escrow pool.
+pub fn appeal_bond(pool: u64, bond_bps: u16) -> Option<u64> {
+    bps_of(pool, bond_bps)
+}
+
+/// Portion of `total` vested `elapsed` seconds into a linear `duration`.
+pub fn vested_amount(total: u64, elapsed: i64, duration: i64) -> u64 {
+    if elapsed <= 0 {
+        return 0;
+    }
+    if duration <= 0 || elapsed >= duration {
+        return total;
+    }
+    ((total as u128) * (elapsed as u128) / (duration as u128)) as u64
+}
+
+/// Split `amount` into `n` equal shares; rounding dust goes to the last share.
+pub fn equal_shares(amount: u64, n: usize) -> Option<Vec<u64>> {
+    if n == 0 {
+        return None;
+    }
+    let share = amount / n as u64;
+    let mut shares = vec![share; n];
+    shares[n - 1] = amount - share * (n as u64 - 1);
+    Some(shares)
+}
+
+/// Split `amount` by basis-point weights that must sum to 100%; rounding
+/// dust goes to the last share.
+pub fn weighted_shares(amount: u64, weights_bps: &[u16]) -> Option<Vec<u64>> {
+    let total: u64 = weights_bps.iter().map(|&bps| bps as u64).sum();
+    if weights_bps.is_empty() || total != BPS_DENOMINATOR {
+        return None;
+    }
+    let mut shares = weights_bps.iter().map(|&bps| bps_of(amount, bps)).collect::<Option<Vec<_>>>()?;
+    let paid: u64 = shares.iter().sum();
+    *shares.last_mut()? += amount - paid;
+    Some(shares)
+}
+
+/// `part / whole` of `amount`, rounded down.
+pub fn pro_rata(amount: u64, part: u64, whole: u64) -> Option<u64> {
+    if whole == 0 || part > whole {
+        return None;
+    }
+    Some(((amount as u128) * (part as u128) / (whole as u128)) as u64)
+}
+
+/// Lamports worth `usd_micros` millionths of a dollar when one SOL costs
+/// `price * 10^exponent` USD, rounded up so the seller is never short.
+pub fn usd_to_lamports(usd_micros: u64, price: i64, exponent: i32) -> Option<u64> {
+    if price <= 0 {
+        return None;
+    }
+    // lamports = usd_micros * 10^(9 - 6) / (price * 10^exponent)
+    let mut numerator = (usd_micros as u128).checked_mul(1_000)?;
+    let mut denominator = price as u128;
+    if exponent < 0 {
+        numerator = numerator.checked_mul(10u128.checked_pow(exponent.unsigned_abs())?)?;
+    } else {
+        denominator = denominator.checked_mul(10u128.checked_pow(exponent as u32)?)?;
+    }
+    u64::try_from(numerator.div_ceil(denominator)).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn bps_of_rounds_down() {
+        assert_eq!(bps_of(1_000_000, 100), Some(10_000));
+        assert_eq!(bps_of(99, 100), Some(0));
+        assert_eq!(bps_of(0, 10_000), Some(0));
+        assert_eq!(bps_of(u64::MAX, 10_000), Some(u64::MAX));
+        assert_eq!(bps_of(u64::MAX, u16::MAX), None);
+    }
+
+    #[test]
+    fn split_bps_gives_dust_to_second_share() {
+        assert_eq!(split_bps(101, 5_000), Some((50, 51)));
+        assert_eq!(split_bps(100, 0), Some((0, 100)));
+        assert_eq!(split_bps(100, 10_000), Some((100, 0)));
+        assert_eq!(split_bps(100, 10_001), None);
+    }
+
+    #[test]
+    fn total_pool_detects_overflow() {
+        assert_eq!(total_pool(1, 2, 3), Some(6));
+        assert_eq!(total_pool(u64::MAX, 1, 0), None);
+        assert_eq!(total_pool(u64::MAX - 1, 0, 1), Some(u64::MAX));
+    }
+
+    #[test]
+    fn arbitrator_base_fee_is_one_percent_of_buyer_collateral() {
+        assert_eq!(arbitrator_base_fee(100_000), 1_000);
+        assert_eq!(arbitrator_base_fee(99), 0);
+        assert_eq!(arbitrator_base_fee(0), 0);
+    }
+
+    #[test]
+    fn decayed_fee_is_linear_after_sla() {
+        assert_eq!(decayed_fee(1_000, 0, 100, 100), 1_000);
+        assert_eq!(decayed_fee(1_000, 100, 100, 100), 1_000);
+        assert_eq!(decayed_fee(1_000, 150, 100, 100), 500);
+        assert_eq!(decayed_fee(1_000, 200, 100, 100), 0);
+        assert_eq!(decayed_fee(1_000, i64::MAX, 100, 100), 0);
+        assert_eq!(decayed_fee(1_000, -5, 100, 100), 1_000);
+        assert_eq!(decayed_fee(1_000, 101, 100, 0), 0);
+    }
+
+    #[test]
+    fn approval_payout_matches_original_split() {
+        // 1 USDC payment, 0.1 buyer collateral, 0.05 seller collateral, no fee.
+        let payout = approval_payout(1_000_000, 100_000, 50_000, 0).unwrap();
+        assert_eq!(payout, ApprovalPayout { seller: 1_050_000, buyer: 100_000, protocol_fee: 0 });
+
+        let payout = approval_payout(1_000_000, 100_000, 50_000, 250).unwrap();
+        assert_eq!(payout.protocol_fee, 25_000);
+        assert_eq!(payout.seller, 1_025_000);
+    }
+
+    #[test]
+    fn approval_payout_overflow_is_none() {
+        assert_eq!(approval_payout(u64::MAX, 0, 1, 0), None);
+    }
+
+    #[test]
+    fn milestone_payout_holds_collateral_until_last() {
+        let mid = milestone_payout(400, false, 100, 50, 0).unwrap();
+        assert_eq!(mid, ApprovalPayout { seller: 400, buyer: 0, protocol_fee: 0 });
+
+        let last = milestone_payout(600, true, 100, 50, 0).unwrap();
+        assert_eq!(last, ApprovalPayout { seller: 650, buyer: 100, protocol_fee: 0 });
+    }
+
+    #[test]
+    fn partial_approval_releases_proportional_collateral() {
+        let (payout, collateral) = partial_approval(400_000, 1_000_000, 50_000, 0).unwrap();
+        assert_eq!(collateral, 20_000);
+        assert_eq!(payout, ApprovalPayout { seller: 420_000, buyer: 0, protocol_fee: 0 });
+
+        let (_, collateral) = partial_approval(1, 3, 2, 0).unwrap();
+        assert_eq!(collateral, 0);
+        assert_eq!(partial_approval(2, 1, 0, 0), None);
+        assert_eq!(partial_approval(0, 0, 0, 0), None);
+    }
+
+    #[test]
+    fn crank_tip_comes_out_of_seller_share() {
+        let payout = approval_payout(1_000_000, 100_000, 50_000, 250).unwrap();
+        let (tipped, tip) = with_crank_tip(payout, 1_000_000, 10).unwrap();
+        assert_eq!(tip, 1_000);
+        assert_eq!(tipped, ApprovalPayout { seller: 1_024_000, ..payout });
+    }
+
+    #[test]
+    fn referral_fee_stacks_with_crank_tip() {
+        let payout = approval_payout(1_000_000, 100_000, 50_000, 250).unwrap();
+        let (tipped, _) = with_crank_tip(payout, 1_000_000, 10).unwrap();
+        let (referred, fee) = with_referral_fee(tipped, 1_000_000, 200).unwrap();
+        assert_eq!(fee, 20_000);
+        assert_eq!(referred, ApprovalPayout { seller: 1_004_000, ..payout });
+        assert_eq!(with_referral_fee(payout, 1_000_000, 0).unwrap(), (payout, 0));
+    }
+
+    #[test]
+    fn ruling_referral_fee_follows_the_seller_share() {
+        assert_eq!(ruling_referral_fee(1_000_000, 0, 200), Some(20_000));
+        assert_eq!(ruling_referral_fee(1_000_000, 7_500, 200), Some(5_000));
+        assert_eq!(ruling_referral_fee(1_000_000, 10_000, 200), Some(0));
+        assert_eq!(ruling_referral_fee(1_000_000, 10_001, 200), None);
+    }
+
+    #[test]
+    fn ruling_payout_matches_original_split() {
+        let payout = ruling_payout(1_000_000, 100_000, 50_000, 1_000, 0).unwrap();
+        assert_eq!(payout.winner, 1_149_000);
+        assert_eq!(payout.arbitrator, 1_000);
+    }
+
+    #[test]
+    fn ruling_payout_rejects_fee_above_pool() {
+        assert_eq!(ruling_payout(10, 0, 0, 11, 0), None);
+    }
+
+    #[test]
+    fn ruling_split_charges_the_loser() {
+        // 70/30 for the seller: the buyer loses and pays the fee from their share.
+        let split = ruling_split(1_000_000, 100_000, 50_000, 1_000, 0, 3_000).unwrap();
+        assert_eq!(split, RulingSplit { buyer: 344_000, seller: 805_000, arbitrator: 1_000, protocol_fee: 0 });
+
+        // 70/30 for the buyer: the seller pays it instead.
+        let split = ruling_split(1_000_000, 100_000, 50_000, 1_000, 0, 7_000).unwrap();
+        assert_eq!(split, RulingSplit { buyer: 805_000, seller: 344_000, arbitrator: 1_000, protocol_fee: 0 });
+    }
+
+    #[test]
+    fn ruling_split_full_rulings_match_winner_takes_all() {
+        let payout = ruling_payout(1_000_000, 100_000, 50_000, 1_000, 250).unwrap();
+        let buyer_wins = ruling_split(1_000_000, 100_000, 50_000, 1_000, 250, 10_000).unwrap();
+        assert_eq!((buyer_wins.buyer, buyer_wins.seller), (payout.winner, 0));
+        let seller_wins = ruling_split(1_000_000, 100_000, 50_000, 1_000, 250, 0).unwrap();
+        assert_eq!((seller_wins.buyer, seller_wins.seller), (0, payout.winner));
+    }
+
+    #[test]
+    fn ruling_split_falls_back_to_pool_without_loser_collateral() {
+        // The seller lost but posted no collateral, so the buyer's share pays.
+        let split = ruling_split(1_000_000, 100_000, 0, 1_000, 0, 7_000).unwrap();
+        assert_eq!(split, RulingSplit { buyer: 769_000, seller: 330_000, arbitrator: 1_000, protocol_fee: 0 });
+
+        // Collateral smaller than the fee covers what it can.
+        let split = ruling_split(1_000_000, 100_000, 400, 1_000, 0, 7_000).unwrap();
+        assert_eq!(split, RulingSplit { buyer: 769_680, seller: 329_720, arbitrator: 1_000, protocol_fee: 0 });
+
+        // An even split has no loser.
+        let split = ruling_split(1_000_000, 100_000, 50_000, 1_000, 0, 5_000).unwrap();
+        assert_eq!(split, RulingSplit { buyer: 574_500, seller: 574_500, arbitrator: 1_000, protocol_fee: 0 });
+    }
+
+    #[test]
+    fn zero_collateral_escrows_pay_nothing_extra() {
+        assert_eq!(ruling_payout(500, 0, 0, 0, 0).unwrap().winner, 500);
+        assert_eq!(approval_payout(500, 0, 0, 0).unwrap().buyer, 0);
+        assert_eq!(timeout_refund(500, 0, 0), Some(500));
+    }
+
+    #[test]
+    fn appeal_bond_is_share_of_pool() {
+        assert_eq!(appeal_bond(1_150_000, 500), Some(57_500));
+        assert_eq!(appeal_bond(1_150_000, 0), Some(0));
+    }
+
+    #[test]
+    fn vested_amount_is_linear() {
+        assert_eq!(vested_amount(1_000, 0, 100), 0);
+        assert_eq!(vested_amount(1_000, 25, 100), 250);
+        assert_eq!(vested_amount(1_000, 100, 100), 1_000);
+        assert_eq!(vested_amount(1_000, i64::MAX, 100), 1_000);
+        assert_eq!(vested_amount(1_000, -5, 100), 0);
+    }
+
+    #[test]
+    fn equal_shares_gives_dust_to_last() {
+        assert_eq!(equal_shares(1_000, 3), Some(vec![333, 333, 334]));
+        assert_eq!(equal_shares(1_000, 1), Some(vec![1_000]));
+        assert_eq!(equal_shares(0, 2), Some(vec![0, 0]));
+        assert_eq!(equal_shares(1_000, 0), None);
+    }
+
+    #[test]
+    fn weighted_shares_follow_weights() {
+        assert_eq!(weighted_shares(1_000_000, &[7_000, 3_000]), Some(vec![700_000, 300_000]));
+        assert_eq!(weighted_shares(10, &[3_333, 3_333, 3_334]), Some(vec![3, 3, 4]));
+        assert_eq!(weighted_shares(10, &[5_000, 4_000]), None);
+        assert_eq!(weighted_shares(10, &[]), None);
+    }
+
+    #[test]
+    fn pro_rata_rounds_down() {
+        assert_eq!(pro_rata(1_000, 1, 3), Some(333));
+        assert_eq!(pro_rata(1_000, 3, 3), Some(1_000));
+        assert_eq!(pro_rata(u64::MAX, u64::MAX - 1, u64::MAX), Some(u64::MAX - 1));
+        assert_eq!(pro_rata(1_000, 4, 3), None);
+        assert_eq!(pro_rata(1_000, 0, 0), None);
+    }
+
+    #[test]
+    fn usd_to_lamports_rounds_up() {
+        // 300 USD at 150 USD/SOL with Pyth's usual exponent.
+        assert_eq!(usd_to_lamports(300_000_000, 15_000_000_000, -8), Some(2_000_000_000));
+        // 1 USD at 3 USD/SOL is a third of a SOL, rounded up.
+        assert_eq!(usd_to_lamports(1_000_000, 3, 0), Some(333_333_334));
+        assert_eq!(usd_to_lamports(1_000_000, 0, -8), None);
+        assert_eq!(usd_to_lamports(u64::MAX, 1, -8), None);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_split_conserves(amount in any::<u64>(), bps in 0u16..=10_000) {
+            let (a, b) = split_bps(amount, bps).unwrap();
+            prop_assert_eq!(a as u128 + b as u128, amount as u128);
+        }
+
+        #[test]
+        fn prop_bps_never_exceeds_amount(amount in any::<u64>(), bps in 0u16..=10_000) {
+            prop_assert!(bps_of(amount, bps).unwrap() <= amount);
+        }
+
+        #[test]
+        fn prop_approval_conserves_vault(
+            payment in 0u64..=u64::MAX / 4,
+            buyer_col in 0u64..=u64::MAX / 4,
+            seller_col in 0u64..=u64::MAX / 4,
+            bps in 0u16..=10_000,
+        ) {
+            let payout = approval_payout(payment, buyer_col, seller_col, bps).unwrap();
+            prop_assert_eq!(payout.total(), total_pool(payment, buyer_col, seller_col));
+        }
+
+        #[test]
+        fn prop_crank_tip_conserves_vault(
+            payment in 0u64..=u64::MAX / 4,
+            buyer_col in 0u64..=u64::MAX / 4,
+            seller_col in 0u64..=u64::MAX / 4,
+            fee_bps in 0u16..=1_000,
+            tip_bps in 0u16..=100,
+        ) {
+            let payout = approval_payout(payment, buyer_col, seller_col, fee_bps).unwrap();
+            let (tipped, tip) = with_crank_tip(payout, payment, tip_bps).unwrap();
+            prop_assert_eq!(tipped.total().and_then(|t| t.checked_add(tip)), payout.total());
+        }
+
+        #[test]
+        fn prop_partial_approval_conserves_vault(
+            payment in 1u64..=u64::MAX / 4,
+            amount_frac in 0u64..=10_000,
+            seller_col in 0u64..=u64::MAX / 4,
+            bps in 0u16..=1_000,
+        ) {
+            let amount = bps_of(payment, amount_frac as u16).unwrap();
+            let (payout, collateral) = partial_approval(amount, payment, seller_col, bps).unwrap();
+            prop_assert!(collateral <= seller_col);
+            prop_assert_eq!(payout.total(), amount.checked_add(collateral));
+        }
+
+        #[test]
+        fn prop_ruling_conserves_vault(
+            payment in 0u64..=u64::MAX / 4,
+            buyer_col in 0u64..=u64::MAX / 4,
+            seller_col in 0u64..=u64::MAX / 4,
+            bps in 0u16..=1_000,
+        ) {
+            let arb_fee = arbitrator_base_fee(buyer_col);
+            let payout = ruling_payout(payment, buyer_col, seller_col, arb_fee, bps).unwrap();
+            prop_assert_eq!(payout.total(), total_pool(payment, buyer_col, seller_col));
+        }
+
+        #[test]
+        fn prop_ruling_split_conserves_vault(
+            payment in 0u64..=u64::MAX / 4,
+            buyer_col in 0u64..=u64::MAX / 4,
+            seller_col in 0u64..=u64::MAX / 4,
+            fee_bps in 0u16..=1_000,
+            buyer_bps in 0u16..=10_000,
+        ) {
+            let arb_fee = arbitrator_base_fee(buyer_col);
+            let split = ruling_split(payment, buyer_col, seller_col, arb_fee, fee_bps, buyer_bps).unwrap();
+            prop_assert_eq!(split.total(), total_pool(payment, buyer_col, seller_col));
+        }
+
+        #[test]
+        fn prop_vested_amount_is_monotonic(
+            total in any::<u64>(),
+            duration in 1i64..10_000_000,
+            t1 in 0i64..20_000_000,
+            dt in 0i64..20_000_000,
+        ) {
+            let earlier = vested_amount(total, t1, duration);
+            let later = vested_amount(total, t1 + dt, duration);
+            prop_assert!(earlier <= later);
+            prop_assert!(later <= total);
+        }
+
+        #[test]
+        fn prop_equal_shares_conserve(amount in any::<u64>(), n in 1usize..=8) {
+            let shares = equal_shares(amount, n).unwrap();
+            prop_assert_eq!(shares.len(), n);
+            prop_assert_eq!(shares.iter().map(|s| *s as u128).sum::<u128>(), amount as u128);
+        }
+
+        #[test]
+        fn prop_decayed_fee_is_monotonic(
+            fee in any::<u64>(),
+            sla in 0i64..1_000_000,
+            decay in 1i64..1_000_000,
+            t1 in 0i64..4_000_000,
+            dt in 0i64..4_000_000,
+        ) {
+            let earlier = decayed_fee(fee, t1, sla, decay);
+            let later = decayed_fee(fee, t1 + dt, sla, decay);
+            prop_assert!(later <= earlier);
+            prop_assert!(earlier <= fee);
+        }
+
+        #[test]
+        fn prop_milestones_conserve_vault(
+            amounts in proptest::collection::vec(1u64..1_000_000_000, 1..8),
+            buyer_col in 0u64..1_000_000_000,
+            seller_col in 0u64..1_000_000_000,
+            bps in 0u16..=1_000,
+        ) {
+            let last = amounts.len() - 1;
+            let mut paid: u128 = 0;
+            for (i, amount) in amounts.iter().enumerate() {
+                let payout = milestone_payout(*amount, i == last, buyer_col, seller_col, bps).unwrap();
+                paid += payout.total().unwrap() as u128;
+            }
+            let expected: u128 = amounts.iter().map(|a| *a as u128).sum::<u128>()
+                + buyer_col as u128
+                + seller_col as u128;
+            prop_assert_eq!(paid, expected);
+        }
+    }
+}
@@ -0,0 +1,134 @@
+//! Reads Pyth price updates for USD-pegged escrows.
+//!
+//! Pyth's pull oracle posts each price as a `PriceUpdateV2` account owned by
+//! its Solana receiver program. The Pyth SDK crates pin an older
+//! `solana-program` than Anchor 0.30 does, so we decode the account by hand:
+//! it is a plain Anchor account, Borsh-encoded behind its discriminator.
+
+use anchor_lang::prelude::*;
+
+use crate::ClawscrowError;
+
+/// Pyth Solana receiver, the owner of every `PriceUpdateV2` account.
+pub const PYTH_RECEIVER_ID: Pubkey = pubkey!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+/// Pyth feed id for SOL/USD.
+pub const SOL_USD_FEED_ID: [u8; 32] = [
+    0xef, 0x0d, 0x8b, 0x6f, 0xda, 0x2c, 0xeb, 0xa4, 0x1d, 0xa1, 0x5d, 0x40, 0x95, 0xd1, 0xda, 0x39,
+    0x2a, 0x0d, 0x2f, 0x8e, 0xd0, 0xc6, 0xc7, 0xbc, 0x0f, 0x4c, 0xfa, 0xc8, 0xc2, 0x80, 0xb5, 0x6d,
+];
+
+/// `sha256("account:PriceUpdateV2")[..8]`.
+const PRICE_UPDATE_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+/// Discriminator plus the write authority.
+const HEADER_LEN: usize = 8 + 32;
+/// Borsh tag of `VerificationLevel::Full`; `Partial` (0) carries a signature count.
+const VERIFICATION_FULL: u8 = 1;
+
+/// The fields of a Pyth price message we price escrows with. The value is
+/// `price * 10^exponent` USD, give or take `conf` in the same units.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Price {
+    pub price: i64,
+    pub conf: u64,
+    pub exponent: i32,
+    pub publish_time: i64,
+}
+
+/// Read the SOL/USD price from a `PriceUpdateV2` account, rejecting partially
+/// verified updates, prices older than `max_age_secs` at `now`, and prices
+/// whose confidence interval exceeds `max_conf_bps` of the price.
+pub fn read_sol_usd(info: &AccountInfo, now: i64, max_age_secs: i64, max_conf_bps: u64) -> Result<Price> {
+    require_keys_eq!(*info.owner, PYTH_RECEIVER_ID, ClawscrowError::InvalidPriceUpdate);
+    let data = info.try_borrow_data()?;
+    let (feed_id, price) = parse_price_update(&data).ok_or(ClawscrowError::InvalidPriceUpdate)?;
+    require!(feed_id == SOL_USD_FEED_ID, ClawscrowError::InvalidPriceUpdate);
+    check_price(&price, now, max_age_secs, max_conf_bps)?;
+    Ok(price)
+}
+
+/// Staleness and confidence checks, split out from the account decoding.
+pub fn check_price(price: &Price, now: i64, max_age_secs: i64, max_conf_bps: u64) -> Result<()> {
+    require!(price.price > 0, ClawscrowError::InvalidPriceUpdate);
+    require!(
+        now.saturating_sub(price.publish_time) <= max_age_secs,
+        ClawscrowError::StalePrice
+    );
+    let limit = (price.price as u128) * (max_conf_bps as u128) / (crate::BPS_DENOMINATOR as u128);
+    require!((price.conf as u128) <= limit, ClawscrowError::PriceTooUncertain);
+    Ok(())
+}
+
+/// Feed id and price of a fully verified `PriceUpdateV2`, or `None` for
+/// anything else.
+fn parse_price_update(data: &[u8]) -> Option<([u8; 32], Price)> {
+    if data.get(..8)? != PRICE_UPDATE_DISCRIMINATOR {
+        return None;
+    }
+    if *data.get(HEADER_LEN)? != VERIFICATION_FULL {
+        return None;
+    }
+    let mut rest = data.get(HEADER_LEN + 1..)?;
+    let mut take = |n: usize| -> Option<&[u8]> {
+        let (head, tail) = rest.split_at_checked(n)?;
+        rest = tail;
+        Some(head)
+    };
+    let feed_id: [u8; 32] = take(32)?.try_into().ok()?;
+    let price = i64::from_le_bytes(take(8)?.try_into().ok()?);
+    let conf = u64::from_le_bytes(take(8)?.try_into().ok()?);
+    let exponent = i32::from_le_bytes(take(4)?.try_into().ok()?);
+    let publish_time = i64::from_le_bytes(take(8)?.try_into().ok()?);
+    Some((feed_id, Price { price, conf, exponent, publish_time }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A `PriceUpdateV2` as the receiver writes it.
+    fn price_update(level: &[u8], feed_id: [u8; 32], price: Price) -> Vec<u8> {
+        let mut data = PRICE_UPDATE_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+        data.extend_from_slice(level);
+        data.extend_from_slice(&feed_id);
+        data.extend_from_slice(&price.price.to_le_bytes());
+        data.extend_from_slice(&price.conf.to_le_bytes());
+        data.extend_from_slice(&price.exponent.to_le_bytes());
+        data.extend_from_slice(&price.publish_time.to_le_bytes());
+        // prev_publish_time, ema_price, ema_conf, posted_slot
+        data.extend_from_slice(&[0; 32]);
+        data
+    }
+
+    const SOL_AT_150: Price = Price { price: 15_000_000_000, conf: 3_000_000, exponent: -8, publish_time: 1_000 };
+
+    #[test]
+    fn parses_fully_verified_updates() {
+        let data = price_update(&[VERIFICATION_FULL], SOL_USD_FEED_ID, SOL_AT_150);
+        assert_eq!(parse_price_update(&data), Some((SOL_USD_FEED_ID, SOL_AT_150)));
+    }
+
+    #[test]
+    fn rejects_partial_verification_and_foreign_accounts() {
+        let partial = price_update(&[0, 5], SOL_USD_FEED_ID, SOL_AT_150);
+        assert_eq!(parse_price_update(&partial), None);
+
+        let mut foreign = price_update(&[VERIFICATION_FULL], SOL_USD_FEED_ID, SOL_AT_150);
+        foreign[0] ^= 1;
+        assert_eq!(parse_price_update(&foreign), None);
+
+        let mut truncated = price_update(&[VERIFICATION_FULL], SOL_USD_FEED_ID, SOL_AT_150);
+        truncated.truncate(HEADER_LEN + 1 + 50);
+        assert_eq!(parse_price_update(&truncated), None);
+    }
+
+    #[test]
+    fn checks_staleness_and_confidence() {
+        assert!(check_price(&SOL_AT_150, 1_060, 60, 100).is_ok());
+        assert!(check_price(&SOL_AT_150, 1_061, 60, 100).is_err());
+        // 0.03 USD on 150 USD is 2 bps.
+        assert!(check_price(&SOL_AT_150, 1_000, 60, 2).is_ok());
+        assert!(check_price(&SOL_AT_150, 1_000, 60, 1).is_err());
+        assert!(check_price(&Price { price: 0, ..SOL_AT_150 }, 1_000, 60, 100).is_err());
+    }
+}
@@ -0,0 +1,151 @@
+//! Program-derived addresses with their bumps, one function per seed scheme
+//! in the README. Programs calling clawscrow through the `cpi` feature use
+//! these to fill in `cpi::accounts` without restating the seeds; off-chain,
+//! `clawscrow_client::pda` wraps them.
+
+use anchor_lang::prelude::Pubkey;
+
+use crate::ID;
+
+fn find(seeds: &[&[u8]]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(seeds, &ID)
+}
+
+pub fn config() -> (Pubkey, u8) {
+    find(&[b"config"])
+}
+
+pub fn stats() -> (Pubkey, u8) {
+    find(&[b"stats"])
+}
+
+/// Signs `emit_cpi!` event self-invocations; every `#[event_cpi]` instruction takes it.
+pub fn event_authority() -> (Pubkey, u8) {
+    find(&[b"__event_authority"])
+}
+
+pub fn receipt_authority() -> (Pubkey, u8) {
+    find(&[b"receipt_authority"])
+}
+
+/// The buyer's escrow ID counter; its `count` is the next escrow's ID.
+pub fn counter(buyer: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"counter", buyer.as_ref()])
+}
+
+/// Keyed by the creating buyer, which `transfer_buyer_role` does not change.
+pub fn escrow(original_buyer: &Pubkey, escrow_id: u64) -> (Pubkey, u8) {
+    find(&[b"escrow", original_buyer.as_ref(), &escrow_id.to_le_bytes()])
+}
+
+pub fn vault(escrow: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"vault", escrow.as_ref()])
+}
+
+pub fn asset_vault(escrow: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"asset_vault", escrow.as_ref()])
+}
+
+pub fn second_vault(escrow: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"second_vault", escrow.as_ref()])
+}
+
+pub fn bonus_vault(escrow: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"bonus_vault", escrow.as_ref()])
+}
+
+pub fn unwrap(escrow: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"unwrap", escrow.as_ref()])
+}
+
+pub fn payout_split(escrow: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"payout_split", escrow.as_ref()])
+}
+
+pub fn badge(escrow: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"badge", escrow.as_ref()])
+}
+
+pub fn badge_mint(escrow: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"badge_mint", escrow.as_ref()])
+}
+
+pub fn contribution(escrow: &Pubkey, contributor: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"contribution", escrow.as_ref(), contributor.as_ref()])
+}
+
+pub fn bid(escrow: &Pubkey, bidder: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"bid", escrow.as_ref(), bidder.as_ref()])
+}
+
+pub fn operator(owner: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"operator", owner.as_ref()])
+}
+
+pub fn offer(seller: &Pubkey, offer_id: u64) -> (Pubkey, u8) {
+    find(&[b"offer", seller.as_ref(), &offer_id.to_le_bytes()])
+}
+
+pub fn offer_vault(offer: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"offer_vault", offer.as_ref()])
+}
+
+pub fn recurring_agreement(buyer: &Pubkey, agreement_id: u64) -> (Pubkey, u8) {
+    find(&[b"recurring", buyer.as_ref(), &agreement_id.to_le_bytes()])
+}
+
+pub fn template(owner: &Pubkey, template_id: u64) -> (Pubkey, u8) {
+    find(&[b"template", owner.as_ref(), &template_id.to_le_bytes()])
+}
+
+pub fn user_index(wallet: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"user_index", wallet.as_ref()])
+}
+
+pub fn reputation(wallet: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"reputation", wallet.as_ref()])
+}
+
+pub fn arbitrator_index(arbitrator: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"arbitrator_index", arbitrator.as_ref()])
+}
+
+pub fn arbitrator_profile(arbitrator: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"arbitrator_profile", arbitrator.as_ref()])
+}
+
+pub fn case_queue(arbitrator: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"case_queue", arbitrator.as_ref()])
+}
+
+pub fn fee_override(platform: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"fee_override", platform.as_ref()])
+}
+
+pub fn listing_index(platform: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"listing_index", platform.as_ref()])
+}
+
+pub fn insurance_vault(mint: &Pubkey) -> (Pubkey, u8) {
+    find(&[b"insurance_vault", mint.as_ref()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escrows_are_keyed_by_buyer_and_little_endian_id() {
+        let buyer = Pubkey::new_unique();
+        let (address, bump) = escrow(&buyer, 258);
+        let expected = Pubkey::create_program_address(&[b"escrow", buyer.as_ref(), &[2, 1, 0, 0, 0, 0, 0, 0], &[bump]], &ID);
+        assert_eq!(expected.unwrap(), address);
+        assert_ne!(escrow(&buyer, 1).0, address);
+    }
+
+    #[test]
+    fn event_authority_matches_anchor_event_cpi() {
+        let (address, bump) = event_authority();
+        assert_eq!(Pubkey::create_program_address(&[b"__event_authority", &[bump]], &ID).unwrap(), address);
+    }
+}
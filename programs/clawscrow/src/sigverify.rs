@@ -0,0 +1,121 @@
+//! Checks for signatures verified by the native ed25519 program.
+//!
+//! Programs cannot verify ed25519 signatures cheaply themselves. Instead the
+//! transaction carries an ed25519 program instruction immediately before ours,
+//! the runtime rejects the whole transaction if that signature is invalid, and
+//! we read the instruction back through the instructions sysvar to confirm it
+//! covered the expected key and message.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::ed25519_program;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::sysvar::instructions::{load_current_index_checked, load_instruction_at_checked};
+
+use crate::ClawscrowError;
+
+/// `num_signatures` and padding, ahead of the offsets table.
+const HEADER_LEN: usize = 2;
+/// Seven little-endian u16 offsets per signature.
+const OFFSETS_LEN: usize = 14;
+const PUBKEY_LEN: usize = 32;
+const SIGNATURE_LEN: usize = 64;
+/// Instruction index meaning "this instruction's own data".
+const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+/// Require that the instruction just before the current one is an ed25519
+/// program check of `signer` over exactly `message`.
+pub fn verify_previous_ed25519(instructions: &AccountInfo, signer: &Pubkey, message: &[u8]) -> Result<()> {
+    let current = load_current_index_checked(instructions)?;
+    require!(current > 0, ClawscrowError::InvalidSignature);
+    let ix = load_instruction_at_checked(current as usize - 1, instructions)?;
+    verify_ed25519_instruction(&ix, signer, message)
+}
+
+/// Parse a single-signature ed25519 program instruction whose signature, key
+/// and message all live in its own data, and compare the key and message.
+pub fn verify_ed25519_instruction(ix: &Instruction, signer: &Pubkey, message: &[u8]) -> Result<()> {
+    require_keys_eq!(ix.program_id, ed25519_program::ID, ClawscrowError::InvalidSignature);
+    require!(ix.accounts.is_empty(), ClawscrowError::InvalidSignature);
+    let data = &ix.data;
+    require!(data.len() >= HEADER_LEN + OFFSETS_LEN && data[0] == 1, ClawscrowError::InvalidSignature);
+
+    let offset = |i: usize| u16::from_le_bytes([data[HEADER_LEN + 2 * i], data[HEADER_LEN + 2 * i + 1]]);
+    let (signature_offset, signature_ix) = (offset(0) as usize, offset(1));
+    let (pubkey_offset, pubkey_ix) = (offset(2) as usize, offset(3));
+    let (message_offset, message_len, message_ix) = (offset(4) as usize, offset(5) as usize, offset(6));
+    require!(
+        [signature_ix, pubkey_ix, message_ix].iter().all(|&i| i == CURRENT_INSTRUCTION),
+        ClawscrowError::InvalidSignature
+    );
+    require!(data.len() >= signature_offset + SIGNATURE_LEN, ClawscrowError::InvalidSignature);
+
+    let pubkey = data.get(pubkey_offset..pubkey_offset + PUBKEY_LEN).ok_or(ClawscrowError::InvalidSignature)?;
+    let signed = data.get(message_offset..message_offset + message_len).ok_or(ClawscrowError::InvalidSignature)?;
+    require!(pubkey == signer.as_ref(), ClawscrowError::InvalidSignature);
+    require!(signed == message, ClawscrowError::InvalidSignature);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Data laid out the way `Ed25519Program.createInstructionWithPublicKey` builds it.
+    fn ed25519_ix(signer: &Pubkey, message: &[u8]) -> Instruction {
+        let pubkey_offset = HEADER_LEN + OFFSETS_LEN;
+        let signature_offset = pubkey_offset + PUBKEY_LEN;
+        let message_offset = signature_offset + SIGNATURE_LEN;
+        let mut data = vec![1, 0];
+        for value in [
+            signature_offset as u16,
+            CURRENT_INSTRUCTION,
+            pubkey_offset as u16,
+            CURRENT_INSTRUCTION,
+            message_offset as u16,
+            message.len() as u16,
+            CURRENT_INSTRUCTION,
+        ] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(signer.as_ref());
+        data.extend_from_slice(&[7; SIGNATURE_LEN]);
+        data.extend_from_slice(message);
+        Instruction { program_id: ed25519_program::ID, accounts: vec![], data }
+    }
+
+    #[test]
+    fn accepts_matching_key_and_message() {
+        let signer = Pubkey::new_unique();
+        let ix = ed25519_ix(&signer, b"terms");
+        assert!(verify_ed25519_instruction(&ix, &signer, b"terms").is_ok());
+    }
+
+    #[test]
+    fn rejects_other_key_message_or_program() {
+        let signer = Pubkey::new_unique();
+        let ix = ed25519_ix(&signer, b"terms");
+        assert!(verify_ed25519_instruction(&ix, &Pubkey::new_unique(), b"terms").is_err());
+        assert!(verify_ed25519_instruction(&ix, &signer, b"other").is_err());
+
+        let mut foreign = ix.clone();
+        foreign.program_id = Pubkey::new_unique();
+        assert!(verify_ed25519_instruction(&foreign, &signer, b"terms").is_err());
+    }
+
+    #[test]
+    fn rejects_data_from_other_instructions() {
+        let signer = Pubkey::new_unique();
+        let mut ix = ed25519_ix(&signer, b"terms");
+        // Point the public key at instruction 0 instead of this one.
+        ix.data[HEADER_LEN + 6..HEADER_LEN + 8].copy_from_slice(&0u16.to_le_bytes());
+        assert!(verify_ed25519_instruction(&ix, &signer, b"terms").is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_data() {
+        let signer = Pubkey::new_unique();
+        let mut ix = ed25519_ix(&signer, b"terms");
+        ix.data.truncate(ix.data.len() - 1);
+        assert!(verify_ed25519_instruction(&ix, &signer, b"terms").is_err());
+    }
+}
@@ -0,0 +1,143 @@
+//! Canonical escrow terms for off-chain negotiation.
+//!
+//! Agents agree on a `TermsV1`, hash it, and pass the hash to `create_escrow`.
+//! The program rebuilds the same struct from the instruction arguments and
+//! rejects the escrow if the hashes differ, so both sides know exactly what
+//! was bound on-chain. The Borsh layout and domain tag are frozen; any change
+//! to either needs a new `TermsV2`.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+
+use crate::ArbitrationSla;
+
+/// Domain separator prepended to the encoded terms before hashing.
+pub const TERMS_V1_DOMAIN: &[u8] = b"clawscrow:terms:v1";
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct TermsV1 {
+    pub buyer: Pubkey,
+    /// `Pubkey::default()` when any seller may accept.
+    pub seller: Pubkey,
+    pub arbitrator: Pubkey,
+    pub mint: Pubkey,
+    pub payment_amount: u64,
+    pub buyer_collateral: u64,
+    pub seller_collateral: u64,
+    pub deadline_ts: i64,
+    /// SHA-256 of the UTF-8 description, so long briefs hash the same as they store.
+    pub description_hash: [u8; 32],
+    pub milestone_amounts: Vec<u64>,
+    pub arbitration_sla: Option<ArbitrationSla>,
+    pub platform: Option<Pubkey>,
+}
+
+impl TermsV1 {
+    /// SHA-256 over the domain tag followed by the Borsh encoding.
+    pub fn hash(&self) -> Result<[u8; 32]> {
+        let encoded = self.try_to_vec()?;
+        Ok(hashv(&[TERMS_V1_DOMAIN, &encoded]).to_bytes())
+    }
+}
+
+/// Domain separator for `AcceptanceV1` hashes.
+pub const ACCEPTANCE_V1_DOMAIN: &[u8] = b"clawscrow:accept:v1";
+
+/// What a seller signs off-chain to join an escrow through
+/// `accept_escrow_with_sig`. Binding the escrow address and its terms hash
+/// keeps the signature from being replayed against any other escrow.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct AcceptanceV1 {
+    pub escrow: Pubkey,
+    pub terms_hash: [u8; 32],
+    pub seller_payout_token: Pubkey,
+    pub seller_payout: Option<Pubkey>,
+}
+
+impl AcceptanceV1 {
+    /// SHA-256 over the domain tag followed by the Borsh encoding; this digest
+    /// is the ed25519-signed message.
+    pub fn hash(&self) -> Result<[u8; 32]> {
+        let encoded = self.try_to_vec()?;
+        Ok(hashv(&[ACCEPTANCE_V1_DOMAIN, &encoded]).to_bytes())
+    }
+}
+
+/// SHA-256 of a description, as committed to in `TermsV1::description_hash`.
+pub fn description_hash(description: &str) -> [u8; 32] {
+    hashv(&[description.as_bytes()]).to_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> TermsV1 {
+        TermsV1 {
+            buyer: Pubkey::new_from_array([1; 32]),
+            seller: Pubkey::default(),
+            arbitrator: Pubkey::new_from_array([2; 32]),
+            mint: Pubkey::new_from_array([3; 32]),
+            payment_amount: 1_000_000,
+            buyer_collateral: 100_000,
+            seller_collateral: 50_000,
+            deadline_ts: 1_700_000_000,
+            description_hash: description_hash("Write a haiku"),
+            milestone_amounts: vec![400_000, 600_000],
+            arbitration_sla: Some(ArbitrationSla { sla_secs: 3_600, decay_secs: 7_200 }),
+            platform: None,
+        }
+    }
+
+    #[test]
+    fn encoding_is_stable() {
+        let bytes = sample().try_to_vec().unwrap();
+        // 4 keys + 3 amounts + deadline + description hash
+        // + vec(len + 2 items) + Some(sla) + None.
+        assert_eq!(bytes.len(), 4 * 32 + 3 * 8 + 8 + 32 + (4 + 16) + (1 + 16) + 1);
+        assert!(TermsV1::try_from_slice(&bytes).unwrap() == sample());
+    }
+
+    #[test]
+    fn hash_commits_to_every_field() {
+        let base = sample().hash().unwrap();
+        assert_eq!(base, sample().hash().unwrap());
+
+        let mut changed = sample();
+        changed.payment_amount += 1;
+        assert_ne!(changed.hash().unwrap(), base);
+
+        let mut changed = sample();
+        changed.milestone_amounts.swap(0, 1);
+        assert_ne!(changed.hash().unwrap(), base);
+
+        let mut changed = sample();
+        changed.platform = Some(Pubkey::default());
+        assert_ne!(changed.hash().unwrap(), base);
+    }
+
+    #[test]
+    fn acceptance_hash_binds_the_escrow() {
+        let acceptance = AcceptanceV1 {
+            escrow: Pubkey::new_from_array([4; 32]),
+            terms_hash: sample().hash().unwrap(),
+            seller_payout_token: Pubkey::new_from_array([5; 32]),
+            seller_payout: None,
+        };
+        let base = acceptance.hash().unwrap();
+
+        let mut changed = acceptance.clone();
+        changed.escrow = Pubkey::new_from_array([6; 32]);
+        assert_ne!(changed.hash().unwrap(), base);
+
+        let mut changed = acceptance;
+        changed.seller_payout = Some(Pubkey::default());
+        assert_ne!(changed.hash().unwrap(), base);
+    }
+
+    #[test]
+    fn hash_is_domain_separated() {
+        let bytes = sample().try_to_vec().unwrap();
+        assert_ne!(sample().hash().unwrap(), hashv(&[&bytes]).to_bytes());
+    }
+}
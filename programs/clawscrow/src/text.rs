@@ -0,0 +1,84 @@
+//! Validation for user-supplied text stored on-chain.
+//!
+//! Strings arrive already UTF-8 checked by Borsh. On top of that the program
+//! requires NFC so the same text always hashes and renders identically, and
+//! limits length in grapheme clusters so scripts with multi-byte characters
+//! get the same budget as ASCII. Clients normalize before sending; the program
+//! only verifies.
+
+use anchor_lang::prelude::*;
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{ClawscrowError, MAX_DESCRIPTION_BYTES, MAX_DESCRIPTION_GRAPHEMES, MAX_URI_BYTES};
+
+pub fn is_nfc(text: &str) -> bool {
+    match is_nfc_quick(text.chars()) {
+        IsNormalized::Yes => true,
+        IsNormalized::No => false,
+        IsNormalized::Maybe => text.chars().eq(text.nfc()),
+    }
+}
+
+pub fn grapheme_len(text: &str) -> usize {
+    text.graphemes(true).count()
+}
+
+pub fn validate_description(description: &str) -> Result<()> {
+    require!(description.len() <= MAX_DESCRIPTION_BYTES, ClawscrowError::DescriptionTooLong);
+    require!(is_nfc(description), ClawscrowError::TextNotNormalized);
+    require!(
+        grapheme_len(description) <= MAX_DESCRIPTION_GRAPHEMES,
+        ClawscrowError::DescriptionTooLong
+    );
+    Ok(())
+}
+
+/// URIs (IPFS CIDs, Arweave IDs, links) are printable ASCII without spaces.
+pub fn validate_uri(uri: &str) -> Result<()> {
+    require!(!uri.is_empty() && uri.len() <= MAX_URI_BYTES, ClawscrowError::InvalidUri);
+    require!(uri.bytes().all(|b| b.is_ascii_graphic()), ClawscrowError::InvalidUri);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multi_byte_scripts_count_graphemes_not_bytes() {
+        let thai = "สวัสดี".repeat(80);
+        assert!(thai.len() > MAX_DESCRIPTION_GRAPHEMES);
+        assert!(validate_description(&thai).is_ok());
+
+        // Skin-tone modifiers make one grapheme out of two code points.
+        let emoji = "👍🏽".repeat(200);
+        assert_eq!(grapheme_len(&emoji), 200);
+        assert!(validate_description(&emoji).is_ok());
+    }
+
+    #[test]
+    fn rejects_too_many_graphemes_or_bytes() {
+        assert!(validate_description(&"a".repeat(MAX_DESCRIPTION_GRAPHEMES)).is_ok());
+        assert!(validate_description(&"a".repeat(MAX_DESCRIPTION_GRAPHEMES + 1)).is_err());
+        // Few graphemes, but each family emoji is 25 bytes.
+        assert!(validate_description(&"👨‍👩‍👧‍👦".repeat(100)).is_err());
+    }
+
+    #[test]
+    fn requires_nfc() {
+        let decomposed = "Cafe\u{301}";
+        assert!(!is_nfc(decomposed));
+        assert!(validate_description(decomposed).is_err());
+        assert!(validate_description("Caf\u{e9}").is_ok());
+    }
+
+    #[test]
+    fn uris_are_bounded_printable_ascii() {
+        assert!(validate_uri("ipfs://bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi").is_ok());
+        assert!(validate_uri("").is_err());
+        assert!(validate_uri("ipfs://a b").is_err());
+        assert!(validate_uri("ar://caf\u{e9}").is_err());
+        assert!(validate_uri(&"a".repeat(MAX_URI_BYTES + 1)).is_err());
+    }
+}
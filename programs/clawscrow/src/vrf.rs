@@ -0,0 +1,126 @@
+//! Requests and reads ORAO VRF randomness for random arbitrator draws.
+//!
+//! A request is a CPI into the ORAO program that creates a `Randomness`
+//! account at a PDA of the caller-chosen seed; ORAO's oracles fulfil it a few
+//! slots later by writing 64 random bytes into that account. The ORAO SDK
+//! pins an older `solana-program` than Anchor 0.30 does, so the instruction
+//! and the account are encoded by hand: both are plain Anchor types.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+
+use crate::ClawscrowError;
+
+/// ORAO VRF program, the owner of every `Randomness` account.
+pub const ORAO_VRF_ID: Pubkey = pubkey!("VRFzZoJdhFWL8rkvu87LpKM3RbcVezpMEc6X5GVDr7y");
+pub const NETWORK_STATE_SEED: &[u8] = b"orao-vrf-network-configuration";
+pub const RANDOMNESS_SEED: &[u8] = b"orao-vrf-randomness-request";
+
+/// `sha256("global:request")[..8]`.
+const REQUEST_DISCRIMINATOR: [u8; 8] = [46, 101, 67, 11, 76, 137, 12, 173];
+/// `sha256("account:Randomness")[..8]`.
+const RANDOMNESS_DISCRIMINATOR: [u8; 8] = [188, 96, 216, 248, 93, 94, 49, 112];
+/// Discriminator plus the request seed, ahead of the randomness itself.
+const RANDOMNESS_OFFSET: usize = 8 + 32;
+
+/// ORAO's network configuration, which names the treasury its fee goes to.
+pub fn network_state_address() -> Pubkey {
+    Pubkey::find_program_address(&[NETWORK_STATE_SEED], &ORAO_VRF_ID).0
+}
+
+/// Where ORAO writes the randomness requested with `seed`.
+pub fn randomness_address(seed: &[u8; 32]) -> Pubkey {
+    Pubkey::find_program_address(&[RANDOMNESS_SEED, seed], &ORAO_VRF_ID).0
+}
+
+/// ORAO's `request` instruction. `payer` covers the fee and the rent of the
+/// `Randomness` account at `randomness_address(seed)`.
+pub fn request_instruction(payer: Pubkey, treasury: Pubkey, seed: [u8; 32]) -> Instruction {
+    let mut data = REQUEST_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&seed);
+    Instruction {
+        program_id: ORAO_VRF_ID,
+        accounts: vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new(network_state_address(), false),
+            AccountMeta::new(treasury, false),
+            AccountMeta::new(randomness_address(&seed), false),
+            AccountMeta::new_readonly(anchor_lang::system_program::ID, false),
+        ],
+        data,
+    }
+}
+
+/// The fulfilled randomness for `seed`, or `RandomnessNotReady` while ORAO
+/// has not answered yet.
+pub fn read_randomness(info: &AccountInfo, seed: &[u8; 32]) -> Result<[u8; 64]> {
+    require_keys_eq!(*info.owner, ORAO_VRF_ID, ClawscrowError::InvalidRandomness);
+    require_keys_eq!(info.key(), randomness_address(seed), ClawscrowError::InvalidRandomness);
+    let data = info.try_borrow_data()?;
+    let randomness = parse_randomness(&data, seed).ok_or(ClawscrowError::InvalidRandomness)?;
+    require!(randomness != [0; 64], ClawscrowError::RandomnessNotReady);
+    Ok(randomness)
+}
+
+/// Index of the candidate `randomness` picks out of `len`.
+pub fn draw_index(randomness: &[u8; 64], len: usize) -> usize {
+    let mut word = [0u8; 8];
+    word.copy_from_slice(&randomness[..8]);
+    (u64::from_le_bytes(word) % len as u64) as usize
+}
+
+/// The randomness bytes of a `Randomness` account for `seed`, all zero
+/// until fulfilled, or `None` for anything else.
+fn parse_randomness(data: &[u8], seed: &[u8; 32]) -> Option<[u8; 64]> {
+    if data.get(..8)? != RANDOMNESS_DISCRIMINATOR || data.get(8..RANDOMNESS_OFFSET)? != seed {
+        return None;
+    }
+    data.get(RANDOMNESS_OFFSET..RANDOMNESS_OFFSET + 64)?.try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A `Randomness` account as ORAO writes it, with no per-node responses.
+    fn randomness_account(seed: [u8; 32], randomness: [u8; 64]) -> Vec<u8> {
+        let mut data = RANDOMNESS_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&seed);
+        data.extend_from_slice(&randomness);
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn parses_randomness_for_the_matching_seed() {
+        let data = randomness_account([7; 32], [9; 64]);
+        assert_eq!(parse_randomness(&data, &[7; 32]), Some([9; 64]));
+        assert_eq!(parse_randomness(&data, &[8; 32]), None);
+
+        let mut foreign = data.clone();
+        foreign[0] ^= 1;
+        assert_eq!(parse_randomness(&foreign, &[7; 32]), None);
+        assert_eq!(parse_randomness(&data[..RANDOMNESS_OFFSET + 63], &[7; 32]), None);
+    }
+
+    #[test]
+    fn draw_index_stays_in_range() {
+        let mut randomness = [0u8; 64];
+        randomness[..8].copy_from_slice(&41u64.to_le_bytes());
+        assert_eq!(draw_index(&randomness, 4), 1);
+        assert_eq!(draw_index(&[0xff; 64], 7), (u64::MAX % 7) as usize);
+        assert_eq!(draw_index(&[0xff; 64], 1), 0);
+    }
+
+    #[test]
+    fn request_targets_the_seeded_randomness_account() {
+        let payer = Pubkey::new_unique();
+        let ix = request_instruction(payer, Pubkey::new_unique(), [3; 32]);
+        assert_eq!(ix.program_id, ORAO_VRF_ID);
+        assert_eq!(ix.accounts[0].pubkey, payer);
+        assert!(ix.accounts[0].is_signer);
+        assert_eq!(ix.accounts[3].pubkey, randomness_address(&[3; 32]));
+        assert_eq!(&ix.data[..8], &REQUEST_DISCRIMINATOR);
+        assert_eq!(&ix.data[8..], &[3; 32]);
+    }
+}
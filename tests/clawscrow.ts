@@ -6,8 +6,11 @@ import {
   createAccount,
   mintTo,
   getAccount,
+  approve,
+  getAssociatedTokenAddressSync,
 } from "@solana/spl-token";
 import { assert } from "chai";
+import { createHash } from "crypto";
 
 describe("clawscrow", () => {
   const provider = anchor.AnchorProvider.env();
@@ -24,12 +27,41 @@ describe("clawscrow", () => {
   const seller = anchor.web3.Keypair.generate();
   const arbitrator = anchor.web3.Keypair.generate();
 
-  const ESCROW_ID = new anchor.BN(1);
+  const ESCROW_ID = new anchor.BN(0);
   const PAYMENT = new anchor.BN(1_000_000); // 1 USDC (6 decimals)
   const BUYER_COLLATERAL = new anchor.BN(100_000); // 0.1 USDC
   const SELLER_COLLATERAL = new anchor.BN(50_000); // 0.05 USDC
   const DEADLINE = new anchor.BN(Math.floor(Date.now() / 1000) + 86400);
 
+  // `create_escrow` parameters for a plain escrow; `overrides` sets any other term.
+  const createParams = (description: string, overrides: Record<string, unknown> = {}) => ({
+    description,
+    paymentAmount: PAYMENT,
+    buyerCollateral: BUYER_COLLATERAL,
+    sellerCollateral: SELLER_COLLATERAL,
+    deadlineTs: DEADLINE,
+    milestoneAmounts: [],
+    arbitrationSla: null,
+    platform: null,
+    termsHash: null,
+    acceptByTs: null,
+    intendedSeller: null,
+    buyerPayout: null,
+    panel: null,
+    vestingSecs: null,
+    reviewPeriodSecs: null,
+    contract: null,
+    deferredFunding: false,
+    backupArbitrator: null,
+    completionBadge: false,
+    compressedReceipts: false,
+    deliverable: null,
+    earlyBonus: null,
+    referral: null,
+    trustedPair: false,
+    ...overrides,
+  });
+
   let escrowPda: anchor.web3.PublicKey;
   let vaultPda: anchor.web3.PublicKey;
 
@@ -73,34 +105,53 @@ describe("clawscrow", () => {
     await mintTo(provider.connection, payer.payer, usdcMint, buyerToken, payer.payer, 10_000_000);
     await mintTo(provider.connection, payer.payer, usdcMint, sellerToken, payer.payer, 1_000_000);
 
+    // Only the program's upgrade authority may set up the config.
+    await expectError(
+      program.methods
+        .initializeConfig(new anchor.BN(30 * 86400), 3, new anchor.BN(86400))
+        .accounts({ admin: seller.publicKey })
+        .signers([seller])
+        .rpc(),
+      "Unauthorized"
+    );
+
+    // Protocol config: 30-day dispute window, 3 free disputes, 1-day cooldown step
+    await program.methods
+      .initializeConfig(new anchor.BN(30 * 86400), 3, new anchor.BN(86400))
+      .accounts({ admin: payer.publicKey })
+      .rpc();
+    // Zero protocol fee; the payer wallet doubles as treasury
+    await program.methods
+      .setProtocolFee(0, payer.publicKey)
+      .accounts({ admin: payer.publicKey })
+      .rpc();
+
     // Derive PDAs
     [escrowPda] = anchor.web3.PublicKey.findProgramAddressSync(
-      [Buffer.from("escrow"), ESCROW_ID.toArrayLike(Buffer, "le", 8)],
+      [Buffer.from("escrow"), payer.publicKey.toBuffer(), ESCROW_ID.toArrayLike(Buffer, "le", 8)],
       program.programId
     );
     [vaultPda] = anchor.web3.PublicKey.findProgramAddressSync(
-      [Buffer.from("vault"), ESCROW_ID.toArrayLike(Buffer, "le", 8)],
+      [Buffer.from("vault"), escrowPda.toBuffer()],
       program.programId
     );
   });
 
   it("Creates an escrow", async () => {
     await program.methods
-      .createEscrow(
-        ESCROW_ID,
-        "Write a haiku about lobsters",
-        PAYMENT,
-        BUYER_COLLATERAL,
-        SELLER_COLLATERAL,
-        DEADLINE
-      )
+      .createEscrow(createParams("Write a haiku about lobsters"))
       .accounts({
         buyer: payer.publicKey,
+        payer: payer.publicKey,
         escrow: escrowPda,
         vault: vaultPda,
         buyerToken,
-        usdcMint,
+        bonusVault: null,
+        mint: usdcMint,
         arbitrator: arbitrator.publicKey,
+        feeOverride: null,
+        arbitratorProfile: null,
+        buyerCredential: null,
         tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
         systemProgram: anchor.web3.SystemProgram.programId,
         rent: anchor.web3.SYSVAR_RENT_PUBKEY,
@@ -108,24 +159,87 @@ describe("clawscrow", () => {
       .rpc();
 
     const escrow = await program.account.escrow.fetch(escrowPda);
-    assert.equal(escrow.escrowId.toNumber(), 1);
+    assert.equal(escrow.escrowId.toNumber(), ESCROW_ID.toNumber());
     assert.equal(escrow.paymentAmount.toNumber(), 1_000_000);
     assert.deepEqual(escrow.state, { created: {} });
     assert.equal(escrow.description, "Write a haiku about lobsters");
+    assert.notDeepEqual(escrow.termsHash, new Array(32).fill(0));
+
+    const [arbitratorIndexPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("arbitrator_index"), arbitrator.publicKey.toBuffer()],
+      program.programId
+    );
+    const index = await program.account.arbitratorIndex.fetch(arbitratorIndexPda);
+    assert.deepEqual(index.escrows.map((key) => key.toBase58()), [escrowPda.toBase58()]);
+
+    const [counterPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("counter"), payer.publicKey.toBuffer()],
+      program.programId
+    );
+    const counter = await program.account.counter.fetch(counterPda);
+    assert.equal(counter.count.toNumber(), 1);
+
+    const [buyerIndexPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("user_index"), payer.publicKey.toBuffer()],
+      program.programId
+    );
+    const buyerIndex = await program.account.userIndex.fetch(buyerIndexPda);
+    assert.deepEqual(buyerIndex.escrows.map((key) => key.toBase58()), [escrowPda.toBase58()]);
 
     // Vault should have payment + buyer collateral
     const vault = await getAccount(provider.connection, vaultPda);
     assert.equal(Number(vault.amount), 1_100_000);
   });
 
+  it("Seller cannot accept before the arbitrator opts in", async () => {
+    try {
+      await program.methods
+        .acceptEscrow(ESCROW_ID, null)
+        .accounts({
+          seller: seller.publicKey,
+          payer: seller.publicKey,
+          escrow: escrowPda,
+          vault: vaultPda,
+          sellerToken,
+          sellerCredential: null,
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        })
+        .signers([seller])
+        .rpc();
+      assert.fail("accept should require the arbitrator's opt-in");
+    } catch (err: any) {
+      assert.include(err.toString(), "ArbitratorNotAccepted");
+    }
+  });
+
+  it("Arbitrator accepts the role", async () => {
+    await program.methods
+      .acceptArbitrationRole(ESCROW_ID)
+      .accounts({ arbitrator: arbitrator.publicKey, escrow: escrowPda })
+      .signers([arbitrator])
+      .rpc();
+
+    const escrow = await program.account.escrow.fetch(escrowPda);
+    assert.isTrue(escrow.arbitratorAccepted);
+
+    const [profilePda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("arbitrator_profile"), arbitrator.publicKey.toBuffer()],
+      program.programId
+    );
+    const profile = await program.account.arbitratorProfile.fetch(profilePda);
+    assert.equal(profile.rolesAccepted.toNumber(), 1);
+  });
+
   it("Seller accepts the escrow", async () => {
     await program.methods
-      .acceptEscrow(ESCROW_ID)
+      .acceptEscrow(ESCROW_ID, null)
       .accounts({
         seller: seller.publicKey,
+        payer: payer.publicKey, // marketplace sponsors the rent
         escrow: escrowPda,
         vault: vaultPda,
         sellerToken,
+        sellerCredential: null,
         tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
       })
       .signers([seller])
@@ -133,6 +247,8 @@ describe("clawscrow", () => {
 
     const escrow = await program.account.escrow.fetch(escrowPda);
     assert.deepEqual(escrow.state, { accepted: {} });
+    assert.isAbove(escrow.acceptedAt.toNumber(), 0);
+    assert.ok(escrow.sellerPayoutToken.equals(sellerToken));
     assert.ok(escrow.seller.equals(seller.publicKey));
 
     // Vault should now also have seller collateral
@@ -140,15 +256,30 @@ describe("clawscrow", () => {
     assert.equal(Number(vault.amount), 1_150_000);
   });
 
+  it("Seller sends a heartbeat", async () => {
+    await program.methods
+      .heartbeat(ESCROW_ID)
+      .accounts({
+        signer: seller.publicKey,
+        escrow: escrowPda,
+      })
+      .signers([seller])
+      .rpc();
+
+    const escrow = await program.account.escrow.fetch(escrowPda);
+    assert.ok(escrow.lastHeartbeatAt.toNumber() > 0);
+  });
+
   it("Seller delivers work", async () => {
     const hash = Buffer.alloc(32);
     Buffer.from("deadbeef", "hex").copy(hash);
 
     await program.methods
-      .deliver(Array.from(hash) as any)
+      .deliver(Array.from(hash) as any, "ipfs://bafkreihaiku")
       .accounts({
         seller: seller.publicKey,
         escrow: escrowPda,
+        operatorKey: null,
       })
       .signers([seller])
       .rpc();
@@ -156,26 +287,92 @@ describe("clawscrow", () => {
     const escrow = await program.account.escrow.fetch(escrowPda);
     assert.deepEqual(escrow.state, { delivered: {} });
     assert.ok(escrow.deliveredAt.toNumber() > 0);
+    assert.equal(escrow.deliveryUri, "ipfs://bafkreihaiku");
+  });
+
+  it("Buyer requests a revision and the seller redelivers", async () => {
+    const before = await program.account.escrow.fetch(escrowPda);
+
+    await program.methods
+      .requestRevision(ESCROW_ID, Array.from(Buffer.alloc(32, 1)) as any)
+      .accounts({ signer: payer.publicKey, escrow: escrowPda })
+      .rpc();
+
+    let escrow = await program.account.escrow.fetch(escrowPda);
+    assert.deepEqual(escrow.state, { accepted: {} });
+    assert.equal(escrow.revisions, 1);
+    assert.ok(escrow.deadlineTs.gt(before.deadlineTs));
+
+    const hash = Buffer.alloc(32);
+    Buffer.from("feedface", "hex").copy(hash);
+    await program.methods
+      .deliver(Array.from(hash) as any, null)
+      .accounts({ seller: seller.publicKey, escrow: escrowPda, operatorKey: null })
+      .signers([seller])
+      .rpc();
+
+    escrow = await program.account.escrow.fetch(escrowPda);
+    assert.deepEqual(escrow.state, { delivered: {} });
+    // Both deliveries stay on record for a possible dispute.
+    assert.equal(escrow.deliveries.length, 2);
+    assert.equal(Buffer.from(escrow.deliveries[0].deliveryHash).toString("hex").slice(0, 8), "deadbeef");
+    assert.equal(Buffer.from(escrow.deliveries[1].deliveryHash).toString("hex").slice(0, 8), "feedface");
+  });
+
+  it("Buyer acknowledges delivery", async () => {
+    await program.methods
+      .acknowledgeDelivery()
+      .accounts({ buyer: payer.publicKey, escrow: escrowPda })
+      .rpc();
+
+    const escrow = await program.account.escrow.fetch(escrowPda);
+    assert.ok(escrow.acknowledgedAt.toNumber() >= escrow.deliveredAt.toNumber());
   });
 
   it("Buyer approves delivery", async () => {
     const buyerBefore = await getAccount(provider.connection, buyerToken);
     const sellerBefore = await getAccount(provider.connection, sellerToken);
 
+    const feedbackHash = Array.from(Buffer.alloc(32, 7));
+
     await program.methods
-      .approve(ESCROW_ID)
+      .approve(ESCROW_ID, feedbackHash as any, 5)
       .accounts({
         signer: payer.publicKey,
         escrow: escrowPda,
         vault: vaultPda,
+        counter: counterAddress(payer.publicKey),
+        buyerPayoutOwner: payer.publicKey,
+        sellerPayoutOwner: seller.publicKey,
+        mint: usdcMint,
         buyerToken,
         sellerToken,
+        unwrapToken: null,
+        payoutSplit: null,
+        operatorKey: null,
+        assetVault: null,
+        buyerAssetToken: null,
+        priceUpdate: null,
+        tipSource: null,
+        treasuryToken: buyerToken,
+        crankToken: null,
+        referrerToken: null,
         tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
       })
       .rpc();
 
     const escrow = await program.account.escrow.fetch(escrowPda);
     assert.deepEqual(escrow.state, { approved: {} });
+    assert.isAtLeast(escrow.resolvedAt.toNumber(), escrow.acceptedAt.toNumber());
+    assert.equal(escrow.satisfaction, 5);
+
+    const [sellerRepPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("reputation"), seller.publicKey.toBuffer()],
+      program.programId
+    );
+    const sellerRep = await program.account.reputation.fetch(sellerRepPda);
+    assert.equal(sellerRep.escrowsCompleted, 1);
+    assert.equal(sellerRep.totalVolume.toNumber(), 1_000_000);
 
     // Seller gets payment + seller collateral
     const sellerAfter = await getAccount(provider.connection, sellerToken);
@@ -194,17 +391,17 @@ describe("clawscrow", () => {
 
   // --- Dispute flow ---
   describe("Dispute flow", () => {
-    const ESCROW_ID_2 = new anchor.BN(2);
+    const ESCROW_ID_2 = new anchor.BN(1);
     let escrowPda2: anchor.web3.PublicKey;
     let vaultPda2: anchor.web3.PublicKey;
 
     before(async () => {
       [escrowPda2] = anchor.web3.PublicKey.findProgramAddressSync(
-        [Buffer.from("escrow"), ESCROW_ID_2.toArrayLike(Buffer, "le", 8)],
+        [Buffer.from("escrow"), payer.publicKey.toBuffer(), ESCROW_ID_2.toArrayLike(Buffer, "le", 8)],
         program.programId
       );
       [vaultPda2] = anchor.web3.PublicKey.findProgramAddressSync(
-        [Buffer.from("vault"), ESCROW_ID_2.toArrayLike(Buffer, "le", 8)],
+        [Buffer.from("vault"), escrowPda2.toBuffer()],
         program.programId
       );
 
@@ -214,14 +411,19 @@ describe("clawscrow", () => {
 
       // Create, accept, deliver
       await program.methods
-        .createEscrow(ESCROW_ID_2, "Disputed task", PAYMENT, BUYER_COLLATERAL, SELLER_COLLATERAL, DEADLINE)
+        .createEscrow(createParams("Disputed task"))
         .accounts({
           buyer: payer.publicKey,
+          payer: payer.publicKey,
           escrow: escrowPda2,
           vault: vaultPda2,
           buyerToken,
-          usdcMint,
+          bonusVault: null,
+          mint: usdcMint,
           arbitrator: arbitrator.publicKey,
+          feeOverride: null,
+          arbitratorProfile: null,
+          buyerCredential: null,
           tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
           systemProgram: anchor.web3.SystemProgram.programId,
           rent: anchor.web3.SYSVAR_RENT_PUBKEY,
@@ -229,14 +431,20 @@ describe("clawscrow", () => {
         .rpc();
 
       await program.methods
-        .acceptEscrow(ESCROW_ID_2)
-        .accounts({ seller: seller.publicKey, escrow: escrowPda2, vault: vaultPda2, sellerToken, tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID })
+        .acceptArbitrationRole(ESCROW_ID_2)
+        .accounts({ arbitrator: arbitrator.publicKey, escrow: escrowPda2 })
+        .signers([arbitrator])
+        .rpc();
+
+      await program.methods
+        .acceptEscrow(ESCROW_ID_2, null)
+        .accounts({ seller: seller.publicKey, payer: seller.publicKey, escrow: escrowPda2, vault: vaultPda2, sellerToken, sellerCredential: null, tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID })
         .signers([seller])
         .rpc();
 
       await program.methods
-        .deliver(Array.from(Buffer.alloc(32)) as any)
-        .accounts({ seller: seller.publicKey, escrow: escrowPda2 })
+        .deliver(Array.from(Buffer.alloc(32)) as any, null)
+        .accounts({ seller: seller.publicKey, escrow: escrowPda2, operatorKey: null })
         .signers([seller])
         .rpc();
     });
@@ -244,44 +452,1921 @@ describe("clawscrow", () => {
     it("Buyer raises dispute", async () => {
       await program.methods
         .raiseDispute()
-        .accounts({ buyer: payer.publicKey, escrow: escrowPda2 })
+        .accounts({ buyer: payer.publicKey, escrow: escrowPda2, buyerToken, operatorKey: null })
         .rpc();
 
       const escrow = await program.account.escrow.fetch(escrowPda2);
       assert.deepEqual(escrow.state, { disputed: {} });
+      // Default dispute bond is 1% of the 1.15 USDC pool.
+      assert.equal(escrow.disputeBond.toNumber(), 11_500);
+      // Default arbitration timeout is 14 days from the dispute.
+      assert.equal(escrow.arbitrationDeadline.sub(escrow.disputedAt).toNumber(), 14 * 86400);
+
+      const [reputationPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("reputation"), payer.publicKey.toBuffer()],
+        program.programId
+      );
+      const reputation = await program.account.reputation.fetch(reputationPda);
+      assert.equal(reputation.disputesRaised, 1);
+      assert.equal(reputation.windowDisputes, 1);
+
+      const [caseQueuePda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("case_queue"), arbitrator.publicKey.toBuffer()],
+        program.programId
+      );
+      const queue = await program.account.caseQueue.fetch(caseQueuePda);
+      assert.deepEqual(queue.escrows.map((key) => key.toBase58()), [escrowPda2.toBase58()]);
     });
 
     it("Arbitrator rules in buyer's favor", async () => {
-      const buyerBefore = await getAccount(provider.connection, buyerToken);
-
       await program.methods
         .arbitrate(ESCROW_ID_2, { buyerWins: {} })
         .accounts({
           arbitrator: arbitrator.publicKey,
           escrow: escrowPda2,
+        })
+        .signers([arbitrator])
+        .rpc();
+
+      const escrow = await program.account.escrow.fetch(escrowPda2);
+      assert.deepEqual(escrow.state, { rulingPending: {} });
+      assert.deepEqual(escrow.pendingRuling, { buyerWins: {} });
+    });
+
+    it("Anyone executes the ruling after the challenge window", async () => {
+      const buyerBefore = await getAccount(provider.connection, buyerToken);
+
+      // Challenge window is zero in this config, so execution is immediate
+      await program.methods
+        .executeRuling(ESCROW_ID_2)
+        .accounts({
+          signer: seller.publicKey,
+          escrow: escrowPda2,
           vault: vaultPda2,
+          counter: counterAddress(payer.publicKey),
+          buyerPayoutOwner: payer.publicKey,
+          sellerPayoutOwner: seller.publicKey,
+          mint: usdcMint,
           buyerToken,
           sellerToken,
+          unwrapToken: null,
+          payoutSplit: null,
+          escalation: null,
+          governanceProposal: null,
+          referrerToken: null,
+          priceUpdate: null,
           arbitratorToken,
+          treasuryToken: buyerToken,
           tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
         })
-        .signers([arbitrator])
+        .signers([seller])
         .rpc();
 
       const escrow = await program.account.escrow.fetch(escrowPda2);
       assert.deepEqual(escrow.state, { resolvedBuyer: {} });
 
+      const [sellerRepPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("reputation"), seller.publicKey.toBuffer()],
+        program.programId
+      );
+      const sellerRep = await program.account.reputation.fetch(sellerRepPda);
+      assert.equal(sellerRep.disputesLost, 1);
+      assert.equal(sellerRep.escrowsCompleted, 2);
+
+      const [statsPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("stats")],
+        program.programId
+      );
+      const stats = await program.account.stats.fetch(statsPda);
+      assert.equal(stats.escrowsCreated.toNumber(), 2);
+      assert.equal(stats.volumeEscrowed.toNumber(), 2_000_000);
+      assert.equal(stats.volumeReleased.toNumber(), 1_000_000);
+      assert.equal(stats.disputesRaised.toNumber(), 1);
+      assert.equal(stats.rulingsBuyer.toNumber(), 1);
+
       // Arbitrator gets 1% of buyer collateral
       const arbAccount = await getAccount(provider.connection, arbitratorToken);
       assert.equal(Number(arbAccount.amount), 1000); // 1% of 100_000
 
-      // Buyer gets the rest (total pool - arb fee)
+      // Buyer gets the rest (total pool - arb fee) plus their dispute bond back
       const buyerAfter = await getAccount(provider.connection, buyerToken);
       const totalPool = 1_000_000 + 100_000 + 50_000;
       assert.equal(
         Number(buyerAfter.amount) - Number(buyerBefore.amount),
-        totalPool - 1000
+        totalPool - 1000 + 11_500
+      );
+    });
+  });
+
+  // --- Cancellation ---
+  describe("Cancellation", () => {
+    const ESCROW_ID_3 = new anchor.BN(2);
+    let escrowPda3: anchor.web3.PublicKey;
+    let vaultPda3: anchor.web3.PublicKey;
+
+    before(async () => {
+      [escrowPda3] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow"), payer.publicKey.toBuffer(), ESCROW_ID_3.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      [vaultPda3] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("vault"), escrowPda3.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .createEscrow(createParams("Cancelled task"))
+        .accounts({
+          buyer: payer.publicKey,
+          payer: payer.publicKey,
+          escrow: escrowPda3,
+          vault: vaultPda3,
+          buyerToken,
+          bonusVault: null,
+          mint: usdcMint,
+          arbitrator: arbitrator.publicKey,
+          feeOverride: null,
+          arbitratorProfile: null,
+          buyerCredential: null,
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc();
+    });
+
+    it("Buyer cancels an unaccepted escrow", async () => {
+      const buyerBefore = await getAccount(provider.connection, buyerToken);
+
+      await program.methods
+        .cancelEscrow(ESCROW_ID_3)
+        .accounts({
+          buyer: payer.publicKey,
+          escrow: escrowPda3,
+          vault: vaultPda3,
+          counter: anchor.web3.PublicKey.findProgramAddressSync(
+            [Buffer.from("counter"), payer.publicKey.toBuffer()],
+            program.programId
+          )[0],
+          buyerToken,
+          secondVault: null,
+          buyerSecondToken: null,
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+
+      const buyerAfter = await getAccount(provider.connection, buyerToken);
+      assert.equal(
+        Number(buyerAfter.amount) - Number(buyerBefore.amount),
+        1_100_000 // payment + buyer_collateral
+      );
+
+      // Escrow and vault are closed
+      assert.isNull(await provider.connection.getAccountInfo(escrowPda3));
+      assert.isNull(await provider.connection.getAccountInfo(vaultPda3));
+    });
+  });
+
+  // --- Feature tests, each with a fresh buyer and its own escrow counter ---
+  type Buyer = { keypair: anchor.web3.Keypair; token: anchor.web3.PublicKey };
+
+  const newBuyer = async (): Promise<Buyer> => {
+    const keypair = anchor.web3.Keypair.generate();
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(keypair.publicKey, 2e9)
+    );
+    const token = await createAccount(provider.connection, payer.payer, usdcMint, keypair.publicKey);
+    await mintTo(provider.connection, payer.payer, usdcMint, token, payer.payer, 10_000_000);
+    return { keypair, token };
+  };
+
+  const escrowAddresses = (buyer: anchor.web3.PublicKey, id: number) => {
+    const [escrow] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("escrow"), buyer.toBuffer(), new anchor.BN(id).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const [vault] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), escrow.toBuffer()],
+      program.programId
+    );
+    return { escrow, vault };
+  };
+
+  const counterAddress = (buyer: anchor.web3.PublicKey) =>
+    anchor.web3.PublicKey.findProgramAddressSync([Buffer.from("counter"), buyer.toBuffer()], program.programId)[0];
+
+  type CreateOptions = {
+    payment?: anchor.BN;
+    buyerCollateral?: anchor.BN;
+    sellerCollateral?: anchor.BN;
+    acceptBy?: anchor.BN | null;
+    intendedSeller?: anchor.web3.PublicKey | null;
+    arbitrator?: anchor.web3.PublicKey;
+    deferredFunding?: boolean;
+    buyerCredential?: anchor.web3.PublicKey | null;
+    arbitratorProfile?: anchor.web3.PublicKey | null;
+    trustedPair?: boolean;
+    deliverable?: { mint: anchor.web3.PublicKey; amount: anchor.BN } | null;
+    vestingSecs?: anchor.BN | null;
+  };
+
+  const createEscrowAs = (buyer: Buyer, id: number, opts: CreateOptions = {}) => {
+    const { escrow, vault } = escrowAddresses(buyer.keypair.publicKey, id);
+    return program.methods
+      .createEscrow(
+        createParams("Feature test", {
+          paymentAmount: opts.payment ?? PAYMENT,
+          buyerCollateral: opts.buyerCollateral ?? BUYER_COLLATERAL,
+          sellerCollateral: opts.sellerCollateral ?? SELLER_COLLATERAL,
+          acceptByTs: opts.acceptBy ?? null,
+          intendedSeller: opts.intendedSeller ?? null,
+          vestingSecs: opts.vestingSecs ?? null,
+          deferredFunding: opts.deferredFunding ?? false,
+          deliverable: opts.deliverable ?? null,
+          trustedPair: opts.trustedPair ?? false,
+        })
+      )
+      .accounts({
+        buyer: buyer.keypair.publicKey,
+        payer: buyer.keypair.publicKey,
+        escrow,
+        vault,
+        buyerToken: buyer.token,
+        mint: usdcMint,
+        arbitrator: opts.arbitrator ?? arbitrator.publicKey,
+        feeOverride: null,
+        tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        bonusVault: null,
+        buyerCredential: opts.buyerCredential ?? null,
+        arbitratorProfile: opts.arbitratorProfile ?? null,
+      })
+      .signers([buyer.keypair]);
+  };
+
+  // Arbitrator opt-in and seller acceptance of an escrow from `createEscrowAs`.
+  const acceptAs = async (buyer: Buyer, id: number) => {
+    const { escrow, vault } = escrowAddresses(buyer.keypair.publicKey, id);
+    await program.methods
+      .acceptArbitrationRole(new anchor.BN(id))
+      .accounts({ arbitrator: arbitrator.publicKey, escrow })
+      .signers([arbitrator])
+      .rpc();
+    await program.methods
+      .acceptEscrow(new anchor.BN(id), null)
+      .accounts({
+        seller: seller.publicKey,
+        payer: seller.publicKey,
+        escrow,
+        vault,
+        sellerToken,
+        tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        sellerCredential: null,
+      })
+      .signers([seller])
+      .rpc();
+  };
+
+  const deliverAs = (buyer: Buyer, id: number) =>
+    program.methods
+      .deliver(Array.from(Buffer.alloc(32, 2)) as any, null)
+      .accounts({ seller: seller.publicKey, escrow: escrowAddresses(buyer.keypair.publicKey, id).escrow, operatorKey: null })
+      .signers([seller])
+      .rpc();
+
+  const expectError = async (call: Promise<unknown>, code: string) => {
+    try {
+      await call;
+      assert.fail(`expected ${code}`);
+    } catch (err: any) {
+      assert.include(err.toString(), code);
+    }
+  };
+
+  describe("Partial approval", () => {
+    let buyer: Buyer;
+    let escrow: anchor.web3.PublicKey;
+    let vault: anchor.web3.PublicKey;
+
+    const approvePartial = (amount: anchor.BN) =>
+      program.methods
+        .approvePartial(new anchor.BN(0), amount)
+        .accounts({
+          buyer: buyer.keypair.publicKey,
+          escrow,
+          vault,
+          buyerToken: buyer.token,
+          sellerToken,
+          treasuryToken: buyerToken,
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          referrerToken: null,
+          payoutSplit: null,
+        })
+        .signers([buyer.keypair])
+        .rpc();
+
+    before(async () => {
+      buyer = await newBuyer();
+      ({ escrow, vault } = escrowAddresses(buyer.keypair.publicKey, 0));
+      await mintTo(provider.connection, payer.payer, usdcMint, sellerToken, payer.payer, 1_000_000);
+      await createEscrowAs(buyer, 0).rpc();
+      await acceptAs(buyer, 0);
+      await deliverAs(buyer, 0);
+    });
+
+    it("Rejects approving the whole payment", async () => {
+      await expectError(approvePartial(PAYMENT), "InvalidAmount");
+    });
+
+    it("Releases the approved share and disputes the rest", async () => {
+      const sellerBefore = await getAccount(provider.connection, sellerToken);
+
+      await approvePartial(new anchor.BN(400_000));
+
+      // 40% of the payment plus 40% of the seller's collateral, with no protocol fee.
+      const sellerAfter = await getAccount(provider.connection, sellerToken);
+      assert.equal(Number(sellerAfter.amount) - Number(sellerBefore.amount), 420_000);
+
+      const account = await program.account.escrow.fetch(escrow);
+      assert.deepEqual(account.state, { disputed: {} });
+      assert.equal(account.releasedAmount.toNumber(), 400_000);
+      assert.equal(account.sellerCollateral.toNumber(), 30_000);
+    });
+  });
+
+  describe("Seller offers", () => {
+    const OFFER_ID = new anchor.BN(0);
+    const ESCROW_ID_4 = new anchor.BN(3);
+    let offerPda: anchor.web3.PublicKey;
+    let escrowPda4: anchor.web3.PublicKey;
+
+    before(async () => {
+      [offerPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("offer"), seller.publicKey.toBuffer(), OFFER_ID.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      [escrowPda4] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow"), payer.publicKey.toBuffer(), ESCROW_ID_4.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+    });
+
+    it("Seller posts an offer with the arbitrator co-signing", async () => {
+      await program.methods
+        .postOffer(OFFER_ID, "Logo design", PAYMENT, BUYER_COLLATERAL, SELLER_COLLATERAL, new anchor.BN(3 * 86400), DEADLINE)
+        .accounts({
+          seller: seller.publicKey,
+          arbitrator: arbitrator.publicKey,
+          sellerToken,
+          mint: usdcMint,
+          sellerCredential: null,
+        })
+        .signers([seller, arbitrator])
+        .rpc();
+
+      const offer = await program.account.offer.fetch(offerPda);
+      assert.equal(offer.paymentAmount.toNumber(), PAYMENT.toNumber());
+      assert.ok(offer.sellerPayoutToken.equals(sellerToken));
+    });
+
+    it("Buyer takes the offer and gets an accepted escrow", async () => {
+      await program.methods
+        .acceptOffer(OFFER_ID)
+        .accounts({
+          buyer: payer.publicKey,
+          seller: seller.publicKey,
+          buyerToken,
+          mint: usdcMint,
+          buyerCredential: null,
+        })
+        .rpc();
+
+      const escrow = await program.account.escrow.fetch(escrowPda4);
+      assert.deepEqual(escrow.state, { accepted: {} });
+      assert.ok(escrow.seller.equals(seller.publicKey));
+      assert.isTrue(escrow.arbitratorAccepted);
+      assert.isNull(await provider.connection.getAccountInfo(offerPda));
+    });
+  });
+
+  describe("Bidding", () => {
+    let buyer: Buyer;
+    let escrow: anchor.web3.PublicKey;
+    let vault: anchor.web3.PublicKey;
+    let bid: anchor.web3.PublicKey;
+
+    const placeBid = (payment: number, deadline: anchor.BN) =>
+      program.methods
+        .placeBid(new anchor.BN(0), new anchor.BN(payment), deadline)
+        .accounts({ bidder: seller.publicKey, escrow, bid, systemProgram: anchor.web3.SystemProgram.programId, bidderCredential: null })
+        .signers([seller])
+        .rpc();
+
+    before(async () => {
+      buyer = await newBuyer();
+      ({ escrow, vault } = escrowAddresses(buyer.keypair.publicKey, 0));
+      [bid] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("bid"), escrow.toBuffer(), seller.publicKey.toBuffer()],
+        program.programId
       );
+      await createEscrowAs(buyer, 0).rpc();
+    });
+
+    it("Rejects a bid whose deadline has passed", async () => {
+      await expectError(placeBid(800_000, new anchor.BN(1)), "InvalidDeadline");
+    });
+
+    it("Selects a cheaper bid and refunds the difference", async () => {
+      await placeBid(800_000, DEADLINE);
+      const buyerBefore = await getAccount(provider.connection, buyer.token);
+
+      await program.methods
+        .selectBid(new anchor.BN(0))
+        .accounts({
+          buyer: buyer.keypair.publicKey,
+          escrow,
+          vault,
+          bid,
+          bidder: seller.publicKey,
+          buyerToken: buyer.token,
+          buyerPayoutToken: buyer.token,
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        })
+        .signers([buyer.keypair])
+        .rpc();
+
+      const buyerAfter = await getAccount(provider.connection, buyer.token);
+      assert.equal(Number(buyerAfter.amount) - Number(buyerBefore.amount), 200_000);
+
+      const account = await program.account.escrow.fetch(escrow);
+      assert.ok(account.seller.equals(seller.publicKey));
+      assert.equal(account.paymentAmount.toNumber(), 800_000);
+      assert.isNull(await provider.connection.getAccountInfo(bid));
+    });
+  });
+
+  describe("Arbitration fee on split rulings", () => {
+    let buyer: Buyer;
+    let escrow: anchor.web3.PublicKey;
+    let vault: anchor.web3.PublicKey;
+
+    const arbitrate = (buyerBps: number) =>
+      program.methods
+        .arbitrate(new anchor.BN(0), { split: { buyerBps } })
+        .accounts({ arbitrator: arbitrator.publicKey, escrow })
+        .signers([arbitrator])
+        .rpc();
+
+    before(async () => {
+      buyer = await newBuyer();
+      ({ escrow, vault } = escrowAddresses(buyer.keypair.publicKey, 0));
+      await mintTo(provider.connection, payer.payer, usdcMint, sellerToken, payer.payer, 1_000_000);
+      await createEscrowAs(buyer, 0).rpc();
+      await acceptAs(buyer, 0);
+      await deliverAs(buyer, 0);
+      await program.methods
+        .raiseDispute()
+        .accounts({ buyer: buyer.keypair.publicKey, escrow, buyerToken: buyer.token, operatorKey: null })
+        .signers([buyer.keypair])
+        .rpc();
+    });
+
+    it("Rejects a split above 100%", async () => {
+      await expectError(arbitrate(10_001), "InvalidRuling");
+    });
+
+    it("Charges the fee to the side that lost the larger share", async () => {
+      await arbitrate(3000);
+      const buyerBefore = await getAccount(provider.connection, buyer.token);
+      const sellerBefore = await getAccount(provider.connection, sellerToken);
+      const arbBefore = await getAccount(provider.connection, arbitratorToken);
+
+      await program.methods
+        .executeRuling(new anchor.BN(0))
+        .accounts({
+          signer: seller.publicKey,
+          escrow,
+          vault,
+          counter: counterAddress(buyer.keypair.publicKey),
+          buyerPayoutOwner: buyer.keypair.publicKey,
+          sellerPayoutOwner: seller.publicKey,
+          mint: usdcMint,
+          buyerToken: buyer.token,
+          sellerToken,
+          arbitratorToken,
+          treasuryToken: buyerToken,
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+          unwrapToken: null,
+          payoutSplit: null,
+          escalation: null,
+          governanceProposal: null,
+          priceUpdate: null,
+          referrerToken: null,
+        })
+        .signers([seller])
+        .rpc();
+
+      // 30/70 of the 1.15 USDC pool and of the 11_500 dispute bond; the
+      // buyer lost, so the 1_000 arbitrator fee comes out of their share.
+      const buyerAfter = await getAccount(provider.connection, buyer.token);
+      const sellerAfter = await getAccount(provider.connection, sellerToken);
+      const arbAfter = await getAccount(provider.connection, arbitratorToken);
+      assert.equal(Number(buyerAfter.amount) - Number(buyerBefore.amount), 345_000 - 1_000 + 3_450);
+      assert.equal(Number(sellerAfter.amount) - Number(sellerBefore.amount), 805_000 + 8_050);
+      assert.equal(Number(arbAfter.amount) - Number(arbBefore.amount), 1_000);
+    });
+  });
+
+  describe("Relayed acceptance", () => {
+    let buyer: Buyer;
+    let escrow: anchor.web3.PublicKey;
+    let vault: anchor.web3.PublicKey;
+    const relayedSeller = anchor.web3.Keypair.generate();
+    let relayedSellerToken: anchor.web3.PublicKey;
+
+    // sha256("clawscrow:accept:v1" || borsh(AcceptanceV1)) with no separate payout wallet.
+    const acceptanceMessage = (escrowKey: anchor.web3.PublicKey, termsHash: number[]) =>
+      createHash("sha256")
+        .update(Buffer.from("clawscrow:accept:v1"))
+        .update(escrowKey.toBuffer())
+        .update(Buffer.from(termsHash))
+        .update(relayedSellerToken.toBuffer())
+        .update(Buffer.from([0]))
+        .digest();
+
+    const acceptWithSig = (message: Buffer) =>
+      program.methods
+        .acceptEscrowWithSig(new anchor.BN(0), null)
+        .accounts({
+          payer: buyer.keypair.publicKey,
+          seller: relayedSeller.publicKey,
+          escrow,
+          vault,
+          sellerToken: relayedSellerToken,
+          instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+          sellerCredential: null,
+        })
+        .preInstructions([
+          anchor.web3.Ed25519Program.createInstructionWithPrivateKey({
+            privateKey: relayedSeller.secretKey,
+            message,
+          }),
+        ])
+        .signers([buyer.keypair])
+        .rpc();
+
+    before(async () => {
+      buyer = await newBuyer();
+      ({ escrow, vault } = escrowAddresses(buyer.keypair.publicKey, 0));
+      relayedSellerToken = await createAccount(provider.connection, payer.payer, usdcMint, relayedSeller.publicKey);
+      await mintTo(provider.connection, payer.payer, usdcMint, relayedSellerToken, payer.payer, 1_000_000);
+      await createEscrowAs(buyer, 0).rpc();
+      await program.methods
+        .acceptArbitrationRole(new anchor.BN(0))
+        .accounts({ arbitrator: arbitrator.publicKey, escrow })
+        .signers([arbitrator])
+        .rpc();
+      // The seller's only on-chain step: delegate the collateral to the escrow PDA.
+      await approve(
+        provider.connection,
+        payer.payer,
+        relayedSellerToken,
+        escrow,
+        relayedSeller,
+        SELLER_COLLATERAL.toNumber()
+      );
+    });
+
+    it("Rejects a signature over another escrow", async () => {
+      const { termsHash } = await program.account.escrow.fetch(escrow);
+      await expectError(acceptWithSig(acceptanceMessage(vault, termsHash)), "InvalidSignature");
+    });
+
+    it("Accepts on the seller's behalf and pulls the delegated collateral", async () => {
+      const { termsHash } = await program.account.escrow.fetch(escrow);
+      await acceptWithSig(acceptanceMessage(escrow, termsHash));
+
+      const account = await program.account.escrow.fetch(escrow);
+      assert.deepEqual(account.state, { accepted: {} });
+      assert.ok(account.seller.equals(relayedSeller.publicKey));
+      assert.ok(account.sellerPayoutToken.equals(relayedSellerToken));
+
+      const sellerAccount = await getAccount(provider.connection, relayedSellerToken);
+      assert.equal(Number(sellerAccount.amount), 1_000_000 - SELLER_COLLATERAL.toNumber());
+    });
+  });
+
+  describe("Draft escrows", () => {
+    let buyer: Buyer;
+    let escrow: anchor.web3.PublicKey;
+    let vault: anchor.web3.PublicKey;
+
+    before(async () => {
+      buyer = await newBuyer();
+      ({ escrow, vault } = escrowAddresses(buyer.keypair.publicKey, 0));
+      await createEscrowAs(buyer, 0, { deferredFunding: true }).rpc();
+    });
+
+    it("Creates an unfunded draft that cannot be accepted", async () => {
+      const account = await program.account.escrow.fetch(escrow);
+      assert.deepEqual(account.state, { draft: {} });
+      assert.equal(Number((await getAccount(provider.connection, vault)).amount), 0);
+
+      await expectError(acceptAs(buyer, 0), "InvalidState");
+    });
+
+    it("Funds the draft from the buyer's own signature", async () => {
+      await program.methods
+        .fundEscrow(new anchor.BN(0))
+        .accounts({
+          signer: buyer.keypair.publicKey,
+          escrow,
+          vault,
+          buyerToken: buyer.token,
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        })
+        .signers([buyer.keypair])
+        .rpc();
+
+      const account = await program.account.escrow.fetch(escrow);
+      assert.deepEqual(account.state, { created: {} });
+      assert.isNull(account.fundingSource);
+      assert.equal(
+        Number((await getAccount(provider.connection, vault)).amount),
+        PAYMENT.add(BUYER_COLLATERAL).toNumber()
+      );
+
+      await acceptAs(buyer, 0);
+      assert.deepEqual((await program.account.escrow.fetch(escrow)).state, { accepted: {} });
+    });
+  });
+
+  describe("Pooled escrows", () => {
+    let buyer: Buyer;
+    let backer: Buyer;
+    let escrow: anchor.web3.PublicKey;
+    let vault: anchor.web3.PublicKey;
+    let pool: anchor.web3.PublicKey;
+
+    const contribute = (from: Buyer, amount: number) =>
+      program.methods
+        .contribute(new anchor.BN(0), new anchor.BN(amount))
+        .accounts({
+          contributor: from.keypair.publicKey,
+          escrow,
+          vault,
+          contributorToken: from.token,
+        })
+        .signers([from.keypair])
+        .rpc();
+
+    const contributionOf = (from: Buyer) =>
+      program.account.contribution.fetch(
+        anchor.web3.PublicKey.findProgramAddressSync(
+          [Buffer.from("contribution"), escrow.toBuffer(), from.keypair.publicKey.toBuffer()],
+          program.programId
+        )[0]
+      );
+
+    before(async () => {
+      buyer = await newBuyer();
+      backer = await newBuyer();
+      ({ escrow, vault } = escrowAddresses(buyer.keypair.publicKey, 0));
+      pool = getAssociatedTokenAddressSync(usdcMint, escrow, true);
+      await createEscrowAs(buyer, 0, { deferredFunding: true, acceptBy: DEADLINE.subn(3600) }).rpc();
+      await program.methods
+        .openPool(new anchor.BN(0))
+        .accounts({ buyer: buyer.keypair.publicKey, escrow, mint: usdcMint, pool })
+        .signers([buyer.keypair])
+        .rpc();
+    });
+
+    it("Fills the deposit from several contributors, capping the last", async () => {
+      await contribute(buyer, 600_000);
+      await contribute(backer, 1_000_000);
+
+      const account = await program.account.escrow.fetch(escrow);
+      assert.deepEqual(account.state, { created: {} });
+      assert.ok(account.buyerPayoutToken.equals(pool));
+      assert.equal((await contributionOf(buyer)).amount.toNumber(), 600_000);
+      assert.equal((await contributionOf(backer)).amount.toNumber(), 500_000);
+    });
+
+    it("Rejects cancelling while contributions are outstanding", async () => {
+      await expectError(
+        program.methods
+          .cancelEscrow(new anchor.BN(0))
+          .accounts({ buyer: buyer.keypair.publicKey, escrow, vault, counter: counterAddress(buyer.keypair.publicKey), buyerToken: pool, secondVault: null, buyerSecondToken: null })
+          .signers([buyer.keypair])
+          .rpc(),
+        "ContributionsUnclaimed"
+      );
+    });
+  });
+
+  describe("Seller payout owner", () => {
+    let buyer: Buyer;
+    let escrow: anchor.web3.PublicKey;
+    const coldWallet = anchor.web3.Keypair.generate();
+    let coldToken: anchor.web3.PublicKey;
+
+    const setSellerPayout = (sellerPayout: anchor.web3.PublicKey | null, signer = seller) =>
+      program.methods
+        .setSellerPayout(new anchor.BN(0), sellerPayout)
+        .accounts({ seller: signer.publicKey, escrow })
+        .signers([signer])
+        .rpc();
+
+    before(async () => {
+      buyer = await newBuyer();
+      ({ escrow } = escrowAddresses(buyer.keypair.publicKey, 0));
+      coldToken = await createAccount(provider.connection, payer.payer, usdcMint, coldWallet.publicKey);
+      await mintTo(provider.connection, payer.payer, usdcMint, sellerToken, payer.payer, 1_000_000);
+      await createEscrowAs(buyer, 0).rpc();
+      await acceptAs(buyer, 0);
+    });
+
+    it("Rejects anyone but the seller", async () => {
+      await expectError(setSellerPayout(coldWallet.publicKey, buyer.keypair), "Unauthorized");
+    });
+
+    it("Moves payouts to the cold wallet's associated token account", async () => {
+      await setSellerPayout(coldWallet.publicKey);
+
+      const account = await program.account.escrow.fetch(escrow);
+      assert.ok(account.sellerPayout.equals(coldWallet.publicKey));
+      assert.ok(coldToken.equals(getAssociatedTokenAddressSync(usdcMint, coldWallet.publicKey)));
+      assert.ok(account.sellerPayoutToken.equals(coldToken));
+    });
+  });
+
+  describe("Random arbitrator", () => {
+    // ORAO VRF is not deployed on the local validator, so these tests stop
+    // short of the CPI; the randomness decoding is unit-tested in vrf.rs.
+    const ORAO_VRF_ID = new anchor.web3.PublicKey("VRFzZoJdhFWL8rkvu87LpKM3RbcVezpMEc6X5GVDr7y");
+    let buyer: Buyer;
+    let escrow: anchor.web3.PublicKey;
+    let randomArbitration: anchor.web3.PublicKey;
+    const [registry] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("arbitrator_registry")],
+      program.programId
+    );
+
+    before(async () => {
+      buyer = await newBuyer();
+      ({ escrow } = escrowAddresses(buyer.keypair.publicKey, 0));
+      [randomArbitration] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("random_arbitration"), escrow.toBuffer()],
+        program.programId
+      );
+      await createEscrowAs(buyer, 0).rpc();
+    });
+
+    it("Registers a staked arbitrator", async () => {
+      await program.methods
+        .registerArbitrator()
+        .accounts({ arbitrator: arbitrator.publicKey })
+        .signers([arbitrator])
+        .rpc();
+
+      const { arbitrators } = await program.account.arbitratorRegistry.fetch(registry);
+      assert.ok(arbitrators.some((key) => key.equals(arbitrator.publicKey)));
+    });
+
+    it("Hands the escrow to a draw and rejects drawing before a dispute", async () => {
+      await program.methods
+        .enableRandomArbitrator(new anchor.BN(0))
+        .accounts({ buyer: buyer.keypair.publicKey, escrow })
+        .signers([buyer.keypair])
+        .rpc();
+
+      const account = await program.account.escrow.fetch(escrow);
+      assert.ok(account.arbitrator.equals(randomArbitration));
+      assert.isTrue(account.arbitratorAccepted);
+      const draw = await program.account.randomArbitration.fetch(randomArbitration);
+      assert.deepEqual(draw.status, { idle: {} });
+
+      const seed = createHash("sha256").update("arbitrator_draw").update(escrow.toBuffer()).digest();
+      const [networkState] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("orao-vrf-network-configuration")],
+        ORAO_VRF_ID
+      );
+      const [randomness] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("orao-vrf-randomness-request"), seed],
+        ORAO_VRF_ID
+      );
+      await expectError(
+        program.methods
+          .requestRandomArbitrator(new anchor.BN(0))
+          .accounts({
+            payer: payer.publicKey,
+            escrow,
+            networkState,
+            treasury: payer.publicKey,
+            randomness,
+            vrfProgram: ORAO_VRF_ID,
+          })
+          .rpc(),
+        "InvalidState"
+      );
+    });
+  });
+
+  describe("DAO escalation", () => {
+    // SPL Governance is not deployed on the local validator, so these tests
+    // stop short of the CPI; proposal decoding is unit-tested in governance.rs.
+    const GOVERNANCE_ID = new anchor.web3.PublicKey("GovER5Lthms3bLBqWub97yVrMmEogzX7xNjdXpPPCVZw");
+    const realm = anchor.web3.Keypair.generate().publicKey;
+    const governance = anchor.web3.Keypair.generate().publicKey;
+    const governingTokenMint = anchor.web3.Keypair.generate().publicKey;
+    const proposalOwnerRecord = anchor.web3.Keypair.generate().publicKey;
+    let buyer: Buyer;
+    let escrow: anchor.web3.PublicKey;
+
+    const setDaoEscalation = (minPool: number | null) =>
+      program.methods
+        .setDaoEscalation(
+          minPool === null
+            ? null
+            : {
+                program: GOVERNANCE_ID,
+                realm,
+                governance,
+                governingTokenMint,
+                proposalOwnerRecord,
+                minPool: new anchor.BN(minPool),
+              }
+        )
+        .accounts({ admin: payer.publicKey })
+        .rpc();
+
+    const escalate = () => {
+      const [proposal] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("governance"), governance.toBuffer(), governingTokenMint.toBuffer(), escrow.toBuffer()],
+        GOVERNANCE_ID
+      );
+      const [realmConfig] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("realm-config"), realm.toBuffer()],
+        GOVERNANCE_ID
+      );
+      const [proposalDeposit] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("proposal-deposit"), proposal.toBuffer(), buyer.keypair.publicKey.toBuffer()],
+        GOVERNANCE_ID
+      );
+      return program.methods
+        .escalateToDao(new anchor.BN(0))
+        .accounts({
+          signer: buyer.keypair.publicKey,
+          escrow,
+          governanceProgram: GOVERNANCE_ID,
+          realm,
+          governance,
+          proposalOwnerRecord,
+          governingTokenMint,
+          proposal,
+          realmConfig,
+          proposalDeposit,
+        })
+        .signers([buyer.keypair])
+        .rpc();
+    };
+
+    before(async () => {
+      buyer = await newBuyer();
+      ({ escrow } = escrowAddresses(buyer.keypair.publicKey, 0));
+      await mintTo(provider.connection, payer.payer, usdcMint, sellerToken, payer.payer, 1_000_000);
+      await createEscrowAs(buyer, 0).rpc();
+      await acceptAs(buyer, 0);
+      await deliverAs(buyer, 0);
+      await program.methods
+        .raiseDispute()
+        .accounts({ buyer: buyer.keypair.publicKey, escrow, buyerToken: buyer.token, operatorKey: null })
+        .signers([buyer.keypair])
+        .rpc();
+    });
+
+    after(async () => {
+      await setDaoEscalation(null);
+    });
+
+    it("Rejects escalation while no realm is configured", async () => {
+      await expectError(escalate(), "DaoEscalationDisabled");
+    });
+
+    it("Rejects escalating a pool below the threshold", async () => {
+      await setDaoEscalation(1_000_000_000);
+      const config = await program.account.config.fetch(
+        anchor.web3.PublicKey.findProgramAddressSync([Buffer.from("config")], program.programId)[0]
+      );
+      assert.ok(config.daoEscalation.realm.equals(realm));
+
+      await expectError(escalate(), "BelowEscalationThreshold");
+      const account = await program.account.escrow.fetch(escrow);
+      assert.deepEqual(account.state, { disputed: {} });
+    });
+  });
+
+  describe("Second payment leg", () => {
+    let buyer: Buyer;
+    let escrow: anchor.web3.PublicKey;
+    let vault: anchor.web3.PublicKey;
+    let secondVault: anchor.web3.PublicKey;
+    let projectMint: anchor.web3.PublicKey;
+    let buyerProjectToken: anchor.web3.PublicKey;
+
+    const fundSecondLeg = (secondMint: anchor.web3.PublicKey, buyerSecondToken: anchor.web3.PublicKey) =>
+      program.methods
+        .fundSecondLeg(new anchor.BN(0), new anchor.BN(500_000))
+        .accounts({ buyer: buyer.keypair.publicKey, escrow, secondMint, buyerSecondToken })
+        .signers([buyer.keypair])
+        .rpc();
+
+    before(async () => {
+      buyer = await newBuyer();
+      ({ escrow, vault } = escrowAddresses(buyer.keypair.publicKey, 0));
+      [secondVault] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("second_vault"), escrow.toBuffer()],
+        program.programId
+      );
+      projectMint = await createMint(provider.connection, payer.payer, payer.publicKey, null, 6);
+      buyerProjectToken = await createAccount(provider.connection, payer.payer, projectMint, buyer.keypair.publicKey);
+      await mintTo(provider.connection, payer.payer, projectMint, buyerProjectToken, payer.payer, 1_000_000);
+      await createEscrowAs(buyer, 0).rpc();
+    });
+
+    it("Rejects a second leg in the escrow's own mint", async () => {
+      await expectError(fundSecondLeg(usdcMint, buyer.token), "InvalidSecondLeg");
+    });
+
+    it("Holds the second leg and refunds it when the escrow is cancelled", async () => {
+      await fundSecondLeg(projectMint, buyerProjectToken);
+
+      const account = await program.account.escrow.fetch(escrow);
+      assert.ok(account.secondLeg.mint.equals(projectMint));
+      assert.equal(account.secondLeg.amount.toNumber(), 500_000);
+      assert.equal(Number((await getAccount(provider.connection, secondVault)).amount), 500_000);
+
+      await program.methods
+        .cancelEscrow(new anchor.BN(0))
+        .accounts({
+          buyer: buyer.keypair.publicKey,
+          escrow,
+          vault,
+          counter: counterAddress(buyer.keypair.publicKey),
+          buyerToken: buyer.token,
+          secondVault,
+          buyerSecondToken: buyerProjectToken,
+        })
+        .signers([buyer.keypair])
+        .rpc();
+
+      assert.equal(Number((await getAccount(provider.connection, buyerProjectToken)).amount), 1_000_000);
+      assert.isNull(await provider.connection.getAccountInfo(secondVault));
+    });
+  });
+
+  describe("Yield on lent vaults", () => {
+    // No SPL token-lending program runs on the local validator, so these
+    // tests stop short of the deposit CPI; its encoding is unit-tested in lending.rs.
+    const lendingProgram = anchor.web3.Keypair.generate().publicKey;
+    const reserve = anchor.web3.Keypair.generate().publicKey;
+    const lendingMarket = anchor.web3.Keypair.generate().publicKey;
+    const liquiditySupply = anchor.web3.Keypair.generate().publicKey;
+    let collateralMint: anchor.web3.PublicKey;
+    let buyer: Buyer;
+    let escrow: anchor.web3.PublicKey;
+
+    const setLendingReserve = (buyerYieldBps: number) =>
+      program.methods
+        .setLendingReserve(
+          { program: lendingProgram, reserve, lendingMarket, liquiditySupply, collateralMint },
+          buyerYieldBps
+        )
+        .accounts({ admin: payer.publicKey, mint: usdcMint })
+        .rpc();
+
+    before(async () => {
+      collateralMint = await createMint(provider.connection, payer.payer, payer.publicKey, null, 6);
+      buyer = await newBuyer();
+      ({ escrow } = escrowAddresses(buyer.keypair.publicKey, 0));
+      await createEscrowAs(buyer, 0).rpc();
+    });
+
+    it("Rejects a buyer share above 100%", async () => {
+      await expectError(setLendingReserve(10_001), "InvalidConfig");
+    });
+
+    it("Whitelists a reserve but only lends accepted escrows", async () => {
+      await setLendingReserve(5_000);
+      const [lendingReserve] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("lending_reserve"), usdcMint.toBuffer()],
+        program.programId
+      );
+      const whitelisted = await program.account.lendingReserve.fetch(lendingReserve);
+      assert.ok(whitelisted.target.reserve.equals(reserve));
+      assert.equal(whitelisted.buyerYieldBps, 5_000);
+
+      const [marketAuthority] = anchor.web3.PublicKey.findProgramAddressSync(
+        [lendingMarket.toBuffer()],
+        lendingProgram
+      );
+      await expectError(
+        program.methods
+          .enableYield(new anchor.BN(0))
+          .accounts({
+            signer: buyer.keypair.publicKey,
+            escrow,
+            collateralMint,
+            lendingProgram,
+            reserve,
+            liquiditySupply,
+            lendingMarket,
+            marketAuthority,
+          })
+          .signers([buyer.keypair])
+          .rpc(),
+        "InvalidState"
+      );
+    });
+  });
+
+  describe("Discount offers", () => {
+    let buyer: Buyer;
+    let escrow: anchor.web3.PublicKey;
+    let vault: anchor.web3.PublicKey;
+
+    const offerDiscount = (refund: anchor.BN) =>
+      program.methods
+        .offerDiscount(new anchor.BN(0), refund)
+        .accounts({ seller: seller.publicKey, escrow })
+        .signers([seller])
+        .rpc();
+
+    const settleWithDiscount = () =>
+      program.methods
+        .settleWithDiscount(new anchor.BN(0), null, null)
+        .accounts({
+          signer: buyer.keypair.publicKey,
+          escrow,
+          vault,
+          counter: counterAddress(buyer.keypair.publicKey),
+          buyerPayoutOwner: buyer.keypair.publicKey,
+          sellerPayoutOwner: seller.publicKey,
+          mint: usdcMint,
+          buyerToken: buyer.token,
+          sellerToken,
+          unwrapToken: null,
+          payoutSplit: null,
+          operatorKey: null,
+          assetVault: null,
+          buyerAssetToken: null,
+          priceUpdate: null,
+          tipSource: null,
+          treasuryToken: buyerToken,
+          crankToken: null,
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+          referrerToken: null,
+        })
+        .signers([buyer.keypair])
+        .rpc();
+
+    before(async () => {
+      buyer = await newBuyer();
+      ({ escrow, vault } = escrowAddresses(buyer.keypair.publicKey, 0));
+      await mintTo(provider.connection, payer.payer, usdcMint, sellerToken, payer.payer, 1_000_000);
+      await createEscrowAs(buyer, 0).rpc();
+      await acceptAs(buyer, 0);
+      await deliverAs(buyer, 0);
+    });
+
+    it("Rejects settling without an offer, or an offer of the whole payment", async () => {
+      await expectError(settleWithDiscount(), "NoDiscountOffer");
+      await expectError(offerDiscount(PAYMENT), "InvalidDiscount");
+    });
+
+    it("Refunds the discount and releases the rest", async () => {
+      await offerDiscount(new anchor.BN(200_000));
+      const buyerBefore = await getAccount(provider.connection, buyer.token);
+
+      await settleWithDiscount();
+
+      // The discount plus the buyer's collateral come back.
+      const buyerAfter = await getAccount(provider.connection, buyer.token);
+      assert.equal(Number(buyerAfter.amount) - Number(buyerBefore.amount), 300_000);
+      const account = await program.account.escrow.fetch(escrow);
+      assert.deepEqual(account.state, { approved: {} });
+      assert.equal(account.paymentAmount.toNumber(), 800_000);
+      assert.isNull(account.discountOffer);
+    });
+  });
+
+  describe("Recurring agreements", () => {
+    const AGREEMENT_ID = new anchor.BN(0);
+    const PERIOD = 30 * 86400;
+    let buyer: Buyer;
+    let agreement: anchor.web3.PublicKey;
+
+    const createAgreement = (periodSecs: number) =>
+      program.methods
+        .createRecurringAgreement(
+          AGREEMENT_ID,
+          "Monthly retainer",
+          PAYMENT,
+          BUYER_COLLATERAL,
+          SELLER_COLLATERAL,
+          new anchor.BN(periodSecs),
+          new anchor.BN(Math.floor(Date.now() / 1000) - 60),
+          2
+        )
+        .accounts({
+          buyer: buyer.keypair.publicKey,
+          seller: seller.publicKey,
+          arbitrator: arbitrator.publicKey,
+          mint: usdcMint,
+          buyerCredential: null,
+          sellerCredential: null,
+          arbitratorProfile: null,
+        })
+        .signers([buyer.keypair])
+        .rpc();
+
+    const spawn = (id: number) => {
+      const { escrow, vault } = escrowAddresses(buyer.keypair.publicKey, id);
+      return program.methods
+        .spawnRecurringEscrow(AGREEMENT_ID)
+        .accounts({ buyer: buyer.keypair.publicKey, escrow, vault, buyerToken: buyer.token, mint: usdcMint })
+        .signers([buyer.keypair])
+        .rpc();
+    };
+
+    before(async () => {
+      buyer = await newBuyer();
+      [agreement] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("recurring"), buyer.keypair.publicKey.toBuffer(), AGREEMENT_ID.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+    });
+
+    it("Rejects a zero-length period", async () => {
+      await expectError(createAgreement(0), "InvalidRecurringAgreement");
+    });
+
+    it("Spawns the current period's escrow once", async () => {
+      await createAgreement(PERIOD);
+      await spawn(0);
+
+      const { escrow } = escrowAddresses(buyer.keypair.publicKey, 0);
+      const account = await program.account.escrow.fetch(escrow);
+      assert.deepEqual(account.state, { created: {} });
+      assert.ok(account.seller.equals(seller.publicKey));
+      assert.equal(account.paymentAmount.toNumber(), PAYMENT.toNumber());
+
+      const terms = await program.account.recurringAgreement.fetch(agreement);
+      assert.equal(terms.periodsSpawned, 1);
+      assert.equal(terms.lastEscrowId.toNumber(), 0);
+      assert.equal(terms.nextPeriodAt.toNumber(), account.deadlineTs.toNumber());
+
+      await expectError(spawn(1), "PeriodNotStarted");
+    });
+  });
+
+  describe("Escrow templates", () => {
+    const TEMPLATE_ID = new anchor.BN(0);
+    let buyer: Buyer;
+    let template: anchor.web3.PublicKey;
+
+    const createTemplate = (buyerCollateralBps: number) =>
+      program.methods
+        .createTemplate(TEMPLATE_ID, "Logo design", buyerCollateralBps, 500, new anchor.BN(3 * 86400), 1_000, null)
+        .accounts({ owner: buyer.keypair.publicKey, arbitrator: arbitrator.publicKey, mint: usdcMint })
+        .signers([buyer.keypair])
+        .rpc();
+
+    before(async () => {
+      buyer = await newBuyer();
+      [template] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("template"), buyer.keypair.publicKey.toBuffer(), TEMPLATE_ID.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+    });
+
+    it("Rejects collateral above 100% of the payment", async () => {
+      await expectError(createTemplate(10_001), "InvalidTemplate");
+    });
+
+    it("Creates an escrow with the template's terms", async () => {
+      await createTemplate(1_000);
+      const { escrow, vault } = escrowAddresses(buyer.keypair.publicKey, 0);
+
+      await program.methods
+        .createEscrowFromTemplate(PAYMENT, DEADLINE)
+        .accounts({
+          create: {
+            buyer: buyer.keypair.publicKey,
+            payer: buyer.keypair.publicKey,
+            escrow,
+            vault,
+            buyerToken: buyer.token,
+            mint: usdcMint,
+            arbitrator: arbitrator.publicKey,
+            feeOverride: null,
+            bonusVault: null,
+            tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          },
+          template,
+        })
+        .signers([buyer.keypair])
+        .rpc();
+
+      const account = await program.account.escrow.fetch(escrow);
+      assert.equal(account.description, "Logo design");
+      assert.equal(account.buyerCollateral.toNumber(), 100_000);
+      assert.equal(account.sellerCollateral.toNumber(), 50_000);
+      assert.equal(account.reviewPeriodSecs.toNumber(), 3 * 86400);
+    });
+  });
+
+  describe("Scheduled auto-approval", () => {
+    // Clockwork is not deployed on the local validator, so these tests stop
+    // short of the CPI; the thread encoding is unit-tested in automation.rs.
+    const CLOCKWORK_ID = new anchor.web3.PublicKey("CLoCKyJ6DXBJqqu2VWx9RLbgnwwR6BMHHuyasVmfMzBh");
+    let buyer: Buyer;
+    let escrow: anchor.web3.PublicKey;
+
+    const schedule = (signer: anchor.web3.Keypair) => {
+      const [threadAuthority] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("thread_authority")],
+        program.programId
+      );
+      const [thread] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("thread"), threadAuthority.toBuffer(), escrow.toBuffer()],
+        CLOCKWORK_ID
+      );
+      return program.methods
+        .scheduleAutoApprove(new anchor.BN(0), new anchor.BN(10_000_000))
+        .accounts({
+          seller: signer.publicKey,
+          escrow,
+          operatorKey: null,
+          payoutSplit: null,
+          priceUpdate: null,
+          threadAuthority,
+          thread,
+          clockworkProgram: CLOCKWORK_ID,
+        })
+        .signers([signer])
+        .rpc();
+    };
+
+    before(async () => {
+      buyer = await newBuyer();
+      ({ escrow } = escrowAddresses(buyer.keypair.publicKey, 0));
+      await createEscrowAs(buyer, 0).rpc();
+      await acceptAs(buyer, 0);
+    });
+
+    it("Waits for delivery", async () => {
+      await expectError(schedule(seller), "InvalidState");
+    });
+
+    it("Only the seller can schedule", async () => {
+      await deliverAs(buyer, 0);
+      await expectError(schedule(buyer.keypair), "Unauthorized");
+    });
+  });
+
+  describe("Compliance mode", () => {
+    let credentialMint: anchor.web3.PublicKey;
+    let holder: Buyer;
+    let holderCredential: anchor.web3.PublicKey;
+    let othersCredential: anchor.web3.PublicKey;
+
+    before(async () => {
+      credentialMint = await createMint(provider.connection, payer.payer, payer.publicKey, null, 0);
+      holder = await newBuyer();
+      holderCredential = await createAccount(provider.connection, payer.payer, credentialMint, holder.keypair.publicKey);
+      await mintTo(provider.connection, payer.payer, credentialMint, holderCredential, payer.payer, 1);
+      // A real credential, but held by the payer wallet rather than the buyer.
+      othersCredential = await createAccount(provider.connection, payer.payer, credentialMint, payer.publicKey);
+      await mintTo(provider.connection, payer.payer, credentialMint, othersCredential, payer.payer, 1);
+
+      await program.methods
+        .setCredentialMint(credentialMint)
+        .accounts({ admin: payer.publicKey })
+        .rpc();
+    });
+
+    after(async () => {
+      await program.methods
+        .setCredentialMint(anchor.web3.PublicKey.default)
+        .accounts({ admin: payer.publicKey })
+        .rpc();
+    });
+
+    it("Rejects a buyer without a credential", async () => {
+      await expectError(createEscrowAs(holder, 0).rpc(), "CredentialRequired");
+    });
+
+    it("Rejects a credential held by someone else", async () => {
+      await expectError(createEscrowAs(holder, 0, { buyerCredential: othersCredential }).rpc(), "CredentialRequired");
+    });
+
+    it("Accepts the buyer's own credential", async () => {
+      await createEscrowAs(holder, 0, { buyerCredential: holderCredential }).rpc();
+
+      const escrow = await program.account.escrow.fetch(escrowAddresses(holder.keypair.publicKey, 0).escrow);
+      assert.deepEqual(escrow.state, { created: {} });
+    });
+
+    it("Requires the seller's credential to accept", async () => {
+      const { escrow, vault } = escrowAddresses(holder.keypair.publicKey, 0);
+      await program.methods
+        .acceptArbitrationRole(new anchor.BN(0))
+        .accounts({ arbitrator: arbitrator.publicKey, escrow })
+        .signers([arbitrator])
+        .rpc();
+
+      const accept = (sellerCredential: anchor.web3.PublicKey | null) =>
+        program.methods
+          .acceptEscrow(new anchor.BN(0), null)
+          .accounts({
+            seller: seller.publicKey,
+            payer: seller.publicKey,
+            escrow,
+            vault,
+            sellerToken,
+            sellerCredential,
+            tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+          })
+          .signers([seller])
+          .rpc();
+
+      await expectError(accept(null), "CredentialRequired");
+      await expectError(accept(othersCredential), "CredentialRequired");
+
+      const sellerCredential = await createAccount(provider.connection, payer.payer, credentialMint, seller.publicKey);
+      await mintTo(provider.connection, payer.payer, credentialMint, sellerCredential, payer.payer, 1);
+      await accept(sellerCredential);
+
+      const accepted = await program.account.escrow.fetch(escrow);
+      assert.deepEqual(accepted.state, { accepted: {} });
+    });
+  });
+
+  describe("Risk limits", () => {
+    let buyer: Buyer;
+    let acceptBy: anchor.BN;
+    const unvetted = anchor.web3.Keypair.generate();
+    const LARGE_PAYMENT = PAYMENT.muln(2);
+    const [arbitratorProfile] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("arbitrator_profile"), arbitrator.publicKey.toBuffer()],
+      program.programId
+    );
+
+    before(async () => {
+      buyer = await newBuyer();
+      // One open escrow per buyer; above PAYMENT the arbitrator needs a profile.
+      await program.methods
+        .setRiskLimits(1, PAYMENT)
+        .accounts({ admin: payer.publicKey })
+        .rpc();
+    });
+
+    after(async () => {
+      await program.methods
+        .setRiskLimits(0, new anchor.BN(0))
+        .accounts({ admin: payer.publicKey })
+        .rpc();
+    });
+
+    it("Rejects a large payment to an arbitrator without a profile", async () => {
+      await expectError(
+        createEscrowAs(buyer, 0, { payment: LARGE_PAYMENT, arbitrator: unvetted.publicKey }).rpc(),
+        "ArbitratorNotVetted"
+      );
+      // Naming a vetted arbitrator is not enough without passing the profile.
+      await expectError(createEscrowAs(buyer, 0, { payment: LARGE_PAYMENT }).rpc(), "ArbitratorNotVetted");
+    });
+
+    it("Allows a large payment with a vetted arbitrator's profile", async () => {
+      const slot = await provider.connection.getSlot();
+      acceptBy = new anchor.BN((await provider.connection.getBlockTime(slot))! + 5);
+      await createEscrowAs(buyer, 0, { payment: LARGE_PAYMENT, arbitratorProfile, acceptBy }).rpc();
+
+      const counter = await program.account.counter.fetch(counterAddress(buyer.keypair.publicKey));
+      assert.equal(counter.openEscrows, 1);
+    });
+
+    it("Rejects an escrow over the open-escrow cap", async () => {
+      await expectError(createEscrowAs(buyer, 1).rpc(), "OpenEscrowLimit");
+    });
+
+    it("Frees a slot once when an escrow expires", async () => {
+      const { escrow, vault } = escrowAddresses(buyer.keypair.publicKey, 0);
+      const [arbitratorIndex] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("arbitrator_index"), arbitrator.publicKey.toBuffer()],
+        program.programId
+      );
+      // Let the acceptance window lapse so the escrow can be expired.
+      while ((await provider.connection.getBlockTime(await provider.connection.getSlot()))! <= acceptBy.toNumber()) {
+        await new Promise((resolve) => setTimeout(resolve, 500));
+      }
+      const expire = (counter: anchor.web3.PublicKey | null) =>
+        program.methods
+          .expireUnaccepted(new anchor.BN(0))
+          .accounts({
+            signer: payer.publicKey,
+            escrow,
+            vault,
+            counter,
+            buyerToken: buyer.token,
+            arbitratorIndex,
+            tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+          })
+          .rpc();
+      await expectError(expire(null), "CounterMissing");
+      await expire(counterAddress(buyer.keypair.publicKey));
+
+      let counter = await program.account.counter.fetch(counterAddress(buyer.keypair.publicKey));
+      assert.equal(counter.openEscrows, 0);
+      assert.isFalse((await program.account.escrow.fetch(escrow)).holdsOpenSlot);
+
+      // Closing the settled escrow does not free the slot a second time.
+      await program.methods
+        .closeEscrow(new anchor.BN(0))
+        .accounts({
+          signer: payer.publicKey,
+          buyer: buyer.keypair.publicKey,
+          escrow,
+          vault,
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+          assetVault: null,
+        })
+        .rpc();
+      counter = await program.account.counter.fetch(counterAddress(buyer.keypair.publicKey));
+      assert.equal(counter.openEscrows, 0);
+
+      await createEscrowAs(buyer, 1).rpc();
+    });
+  });
+
+  describe("Minimum collateral", () => {
+    let buyer: Buyer;
+
+    before(async () => {
+      buyer = await newBuyer();
+      // Buyer 10% and seller 5% of the payment: exactly the default test collateral.
+      await program.methods
+        .setCollateralRatios(1_000, 500)
+        .accounts({ admin: payer.publicKey })
+        .rpc();
+    });
+
+    after(async () => {
+      await program.methods
+        .setCollateralRatios(0, 0)
+        .accounts({ admin: payer.publicKey })
+        .rpc();
+    });
+
+    it("Rejects collateral below the configured share", async () => {
+      await expectError(
+        createEscrowAs(buyer, 0, { buyerCollateral: BUYER_COLLATERAL.subn(1) }).rpc(),
+        "CollateralBelowRatio"
+      );
+      await expectError(
+        createEscrowAs(buyer, 0, { sellerCollateral: SELLER_COLLATERAL.subn(1) }).rpc(),
+        "CollateralBelowRatio"
+      );
+    });
+
+    it("Rejects a trusted pair that does not name its seller", async () => {
+      await expectError(
+        createEscrowAs(buyer, 0, {
+          buyerCollateral: new anchor.BN(0),
+          sellerCollateral: new anchor.BN(0),
+          trustedPair: true,
+        }).rpc(),
+        "InvalidTrustedPair"
+      );
+    });
+
+    it("Lets a trusted pair skip collateral", async () => {
+      await createEscrowAs(buyer, 0, {
+        buyerCollateral: new anchor.BN(0),
+        sellerCollateral: new anchor.BN(0),
+        intendedSeller: seller.publicKey,
+        trustedPair: true,
+      }).rpc();
+
+      const escrow = await program.account.escrow.fetch(escrowAddresses(buyer.keypair.publicKey, 0).escrow);
+      assert.isTrue(escrow.trustedPair);
+      assert.equal(escrow.buyerCollateral.toNumber(), 0);
+    });
+  });
+
+  describe("Asset deliverables", () => {
+    let buyer: Buyer;
+    let escrow: anchor.web3.PublicKey;
+    let vault: anchor.web3.PublicKey;
+    let assetMint: anchor.web3.PublicKey;
+    let assetVault: anchor.web3.PublicKey;
+    let sellerAsset: anchor.web3.PublicKey;
+
+    const deliverAsset = () =>
+      program.methods
+        .deliverAsset(new anchor.BN(0), Array.from(Buffer.alloc(32, 4)) as any, null)
+        .accounts({ seller: seller.publicKey, escrow, assetMint, sellerAssetToken: sellerAsset, assetVault })
+        .signers([seller])
+        .rpc();
+
+    const close = () =>
+      program.methods
+        .closeEscrow(new anchor.BN(0))
+        .accounts({
+          signer: payer.publicKey,
+          buyer: buyer.keypair.publicKey,
+          escrow,
+          vault,
+          assetVault,
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+
+    before(async () => {
+      buyer = await newBuyer();
+      ({ escrow, vault } = escrowAddresses(buyer.keypair.publicKey, 0));
+      [assetVault] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("asset_vault"), escrow.toBuffer()],
+        program.programId
+      );
+      assetMint = await createMint(provider.connection, payer.payer, payer.publicKey, null, 0);
+      sellerAsset = await createAccount(provider.connection, payer.payer, assetMint, seller.publicKey);
+      await mintTo(provider.connection, payer.payer, assetMint, sellerAsset, payer.payer, 1);
+      await createEscrowAs(buyer, 0, { deliverable: { mint: assetMint, amount: new anchor.BN(1) } }).rpc();
+      await acceptAs(buyer, 0);
+      await deliverAsset();
+    });
+
+    it("Keeps the asset through a revision and redelivery", async () => {
+      await program.methods
+        .requestRevision(new anchor.BN(0), Array.from(Buffer.alloc(32, 5)) as any)
+        .accounts({ signer: buyer.keypair.publicKey, escrow })
+        .signers([buyer.keypair])
+        .rpc();
+      assert.deepEqual((await program.account.escrow.fetch(escrow)).state, { accepted: {} });
+
+      await deliverAsset();
+
+      const account = await program.account.escrow.fetch(escrow);
+      assert.deepEqual(account.state, { delivered: {} });
+      assert.equal(account.revisions, 1);
+      assert.equal(Number((await getAccount(provider.connection, assetVault)).amount), 1);
+      assert.equal(Number((await getAccount(provider.connection, sellerAsset)).amount), 0);
+    });
+
+    it("Won't close until the asset is released", async () => {
+      await program.methods
+        .raiseDispute()
+        .accounts({ buyer: buyer.keypair.publicKey, escrow, buyerToken: buyer.token, operatorKey: null })
+        .signers([buyer.keypair])
+        .rpc();
+      await program.methods
+        .arbitrate(new anchor.BN(0), { buyerWins: {} })
+        .accounts({ arbitrator: arbitrator.publicKey, escrow })
+        .signers([arbitrator])
+        .rpc();
+      await program.methods
+        .executeRuling(new anchor.BN(0))
+        .accounts({
+          signer: seller.publicKey,
+          escrow,
+          vault,
+          counter: counterAddress(buyer.keypair.publicKey),
+          buyerPayoutOwner: buyer.keypair.publicKey,
+          sellerPayoutOwner: seller.publicKey,
+          mint: usdcMint,
+          buyerToken: buyer.token,
+          sellerToken,
+          arbitratorToken,
+          treasuryToken: buyerToken,
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+          unwrapToken: null,
+          payoutSplit: null,
+          escalation: null,
+          governanceProposal: null,
+          referrerToken: null,
+          priceUpdate: null,
+        })
+        .signers([seller])
+        .rpc();
+
+      await expectError(close(), "DeliverableUnreleased");
+
+      await program.methods
+        .releaseDeliverable(new anchor.BN(0))
+        .accounts({
+          signer: payer.publicKey,
+          escrow,
+          assetVault,
+          recipientToken: sellerAsset,
+          sellerPayoutOwner: seller.publicKey,
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+      assert.equal(Number((await getAccount(provider.connection, sellerAsset)).amount), 1);
+
+      await close();
+      assert.isNull(await provider.connection.getAccountInfo(escrow));
+    });
+  });
+
+  describe("Payout splits", () => {
+    const agency = anchor.web3.Keypair.generate();
+    const member = anchor.web3.Keypair.generate();
+    let agencyToken: anchor.web3.PublicKey;
+    let memberToken: anchor.web3.PublicKey;
+
+    const splitAccounts = () => [
+      { pubkey: agencyToken, isSigner: false, isWritable: true },
+      { pubkey: memberToken, isSigner: false, isWritable: true },
+    ];
+    const payoutSplitAddress = (escrow: anchor.web3.PublicKey) =>
+      anchor.web3.PublicKey.findProgramAddressSync([Buffer.from("payout_split"), escrow.toBuffer()], program.programId)[0];
+
+    const setPayoutSplit = (escrow: anchor.web3.PublicKey) =>
+      program.methods
+        .setPayoutSplit(new anchor.BN(0), [
+          { wallet: agency.publicKey, bps: 6000 },
+          { wallet: member.publicKey, bps: 4000 },
+        ])
+        .accounts({ seller: seller.publicKey, escrow, payoutSplit: payoutSplitAddress(escrow) })
+        .signers([seller])
+        .rpc();
+
+    const balances = async () =>
+      Promise.all([agencyToken, memberToken, sellerToken].map(async (token) =>
+        Number((await getAccount(provider.connection, token)).amount)
+      ));
+
+    before(async () => {
+      agencyToken = await createAccount(provider.connection, payer.payer, usdcMint, agency.publicKey);
+      memberToken = await createAccount(provider.connection, payer.payer, usdcMint, member.publicKey);
+      await mintTo(provider.connection, payer.payer, usdcMint, sellerToken, payer.payer, 1_000_000);
+    });
+
+    describe("on a vesting escrow", () => {
+      let buyer: Buyer;
+      let escrow: anchor.web3.PublicKey;
+      let vault: anchor.web3.PublicKey;
+
+      const claimVested = (payoutSplit: anchor.web3.PublicKey | null) =>
+        program.methods
+          .claimVested(new anchor.BN(0))
+          .accounts({ signer: payer.publicKey, escrow, vault, counter: counterAddress(buyer.keypair.publicKey), sellerToken, payoutSplit })
+          .remainingAccounts(payoutSplit ? splitAccounts() : [])
+          .rpc();
+
+      before(async () => {
+        buyer = await newBuyer();
+        ({ escrow, vault } = escrowAddresses(buyer.keypair.publicKey, 0));
+        await createEscrowAs(buyer, 0, { vestingSecs: new anchor.BN(1) }).rpc();
+        await acceptAs(buyer, 0);
+        await setPayoutSplit(escrow);
+        await deliverAs(buyer, 0);
+        await program.methods
+          .approve(new anchor.BN(0), Array.from(Buffer.alloc(32, 7)) as any, 5)
+          .accounts({
+            signer: buyer.keypair.publicKey,
+            escrow,
+            vault,
+            counter: counterAddress(buyer.keypair.publicKey),
+            buyerPayoutOwner: buyer.keypair.publicKey,
+            sellerPayoutOwner: seller.publicKey,
+            mint: usdcMint,
+            buyerToken: buyer.token,
+            sellerToken,
+            unwrapToken: null,
+            payoutSplit: payoutSplitAddress(escrow),
+            operatorKey: null,
+            assetVault: null,
+            buyerAssetToken: null,
+            priceUpdate: null,
+            tipSource: null,
+            treasuryToken: buyerToken,
+            crankToken: null,
+            referrerToken: null,
+            tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+          })
+          .remainingAccounts(splitAccounts())
+          .signers([buyer.keypair])
+          .rpc();
+        await new Promise((resolve) => setTimeout(resolve, 2000));
+      });
+
+      it("Rejects a claim without the split", async () => {
+        await expectError(claimVested(null), "PayoutSplitMissing");
+      });
+
+      it("Streams the vested payment to the split recipients", async () => {
+        const [agencyBefore, memberBefore, sellerBefore] = await balances();
+
+        await claimVested(payoutSplitAddress(escrow));
+
+        const [agencyAfter, memberAfter, sellerAfter] = await balances();
+        assert.equal(agencyAfter - agencyBefore, 600_000);
+        assert.equal(memberAfter - memberBefore, 400_000);
+        assert.equal(sellerAfter, sellerBefore);
+        const account = await program.account.escrow.fetch(escrow);
+        assert.deepEqual(account.state, { approved: {} });
+      });
+    });
+
+    describe("on a partial approval", () => {
+      let buyer: Buyer;
+      let escrow: anchor.web3.PublicKey;
+      let vault: anchor.web3.PublicKey;
+
+      const approvePartial = (payoutSplit: anchor.web3.PublicKey | null) =>
+        program.methods
+          .approvePartial(new anchor.BN(0), new anchor.BN(500_000))
+          .accounts({
+            buyer: buyer.keypair.publicKey,
+            escrow,
+            vault,
+            buyerToken: buyer.token,
+            sellerToken,
+            payoutSplit,
+            treasuryToken: buyerToken,
+            tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            referrerToken: null,
+          })
+          .remainingAccounts(payoutSplit ? splitAccounts() : [])
+          .signers([buyer.keypair])
+          .rpc();
+
+      before(async () => {
+        buyer = await newBuyer();
+        ({ escrow, vault } = escrowAddresses(buyer.keypair.publicKey, 0));
+        await createEscrowAs(buyer, 0).rpc();
+        await acceptAs(buyer, 0);
+        await setPayoutSplit(escrow);
+        await deliverAs(buyer, 0);
+      });
+
+      it("Rejects releasing without the split", async () => {
+        await expectError(approvePartial(null), "PayoutSplitMissing");
+      });
+
+      it("Splits the released payment and returns the collateral share to the seller", async () => {
+        const [agencyBefore, memberBefore, sellerBefore] = await balances();
+
+        await approvePartial(payoutSplitAddress(escrow));
+
+        const [agencyAfter, memberAfter, sellerAfter] = await balances();
+        assert.equal(agencyAfter - agencyBefore, 300_000);
+        assert.equal(memberAfter - memberBefore, 200_000);
+        // Half of the seller's collateral comes back with the released half.
+        assert.equal(sellerAfter - sellerBefore, 25_000);
+      });
+    });
+  });
+
+  describe("Marketplace listings", () => {
+    let buyer: Buyer;
+    const platform = anchor.web3.Keypair.generate().publicKey;
+
+    const listingAddress = (key: anchor.web3.PublicKey) =>
+      anchor.web3.PublicKey.findProgramAddressSync([Buffer.from("listing_index"), key.toBuffer()], program.programId)[0];
+
+    const createAndList = (listedOn: anchor.web3.PublicKey | null) => {
+      const { escrow, vault } = escrowAddresses(buyer.keypair.publicKey, 0);
+      return program.methods
+        .createAndList(createParams("Listed job", { platform: listedOn }))
+        .accounts({
+          create: {
+            buyer: buyer.keypair.publicKey,
+            payer: buyer.keypair.publicKey,
+            escrow,
+            vault,
+            buyerToken: buyer.token,
+            bonusVault: null,
+            mint: usdcMint,
+            arbitrator: arbitrator.publicKey,
+            feeOverride: null,
+            arbitratorProfile: null,
+            buyerCredential: null,
+            tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          },
+          listingIndex: listingAddress(listedOn ?? anchor.web3.PublicKey.default),
+        })
+        .signers([buyer.keypair])
+        .rpc();
+    };
+
+    before(async () => {
+      buyer = await newBuyer();
+    });
+
+    it("Rejects a listing without a platform", async () => {
+      await expectError(createAndList(null), "InvalidListing");
+    });
+
+    it("Lists the escrow under its platform", async () => {
+      await createAndList(platform);
+
+      const { escrow } = escrowAddresses(buyer.keypair.publicKey, 0);
+      const index = await program.account.listingIndex.fetch(listingAddress(platform));
+      assert.deepEqual(index.escrows.map((key) => key.toBase58()), [escrow.toBase58()]);
+      assert.equal((await program.account.escrow.fetch(escrow)).platform!.toBase58(), platform.toBase58());
+    });
+  });
+
+  describe("Renewals", () => {
+    let buyer: Buyer;
+
+    const renew = () => {
+      const predecessor = escrowAddresses(buyer.keypair.publicKey, 0).escrow;
+      const { escrow, vault } = escrowAddresses(buyer.keypair.publicKey, 1);
+      return program.methods
+        .renewEscrow(PAYMENT, DEADLINE)
+        .accounts({
+          buyer: buyer.keypair.publicKey,
+          predecessor,
+          escrow,
+          vault,
+          buyerToken: buyer.token,
+          mint: usdcMint,
+          buyerCredential: null,
+          arbitratorProfile: null,
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .signers([buyer.keypair])
+        .rpc();
+    };
+
+    const setCollateralBounds = (min: anchor.BN, max: anchor.BN) =>
+      program.methods.setCollateralBounds(min, max).accounts({ admin: payer.publicKey }).rpc();
+
+    before(async () => {
+      buyer = await newBuyer();
+      const { escrow, vault } = escrowAddresses(buyer.keypair.publicKey, 0);
+      await createEscrowAs(buyer, 0).rpc();
+      await acceptAs(buyer, 0);
+      await deliverAs(buyer, 0);
+      await program.methods
+        .approve(new anchor.BN(0), Array.from(Buffer.alloc(32, 7)) as any, 5)
+        .accounts({
+          signer: buyer.keypair.publicKey,
+          escrow,
+          vault,
+          counter: counterAddress(buyer.keypair.publicKey),
+          buyerPayoutOwner: buyer.keypair.publicKey,
+          sellerPayoutOwner: seller.publicKey,
+          mint: usdcMint,
+          buyerToken: buyer.token,
+          sellerToken,
+          unwrapToken: null,
+          payoutSplit: null,
+          operatorKey: null,
+          assetVault: null,
+          buyerAssetToken: null,
+          priceUpdate: null,
+          tipSource: null,
+          treasuryToken: buyerToken,
+          crankToken: null,
+          referrerToken: null,
+          tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        })
+        .signers([buyer.keypair])
+        .rpc();
+    });
+
+    after(async () => {
+      await setCollateralBounds(new anchor.BN(0), new anchor.BN("18446744073709551615"));
+    });
+
+    it("Rejects a renewal whose collateral is now out of bounds", async () => {
+      await setCollateralBounds(SELLER_COLLATERAL.addn(1), new anchor.BN("18446744073709551615"));
+      await expectError(renew(), "CollateralOutOfBounds");
+    });
+
+    it("Renews an approved escrow within the bounds", async () => {
+      await setCollateralBounds(new anchor.BN(0), new anchor.BN("18446744073709551615"));
+      await renew();
+
+      const successor = await program.account.escrow.fetch(escrowAddresses(buyer.keypair.publicKey, 1).escrow);
+      assert.equal(successor.predecessorId!.toNumber(), 0);
+      assert.equal(successor.sellerCollateral.toNumber(), SELLER_COLLATERAL.toNumber());
     });
   });
 });